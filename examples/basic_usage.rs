@@ -72,6 +72,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         from: alice_address.clone(),
         to: bob_address.clone(),
         amount: transfer_amount,
+        memo: None,
     };
     
     let transfer_tx = Transaction::new(
@@ -136,7 +137,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("✅ Added transactions to pool");
     println!("  Pending transactions: {}", tx_pool.pending_count());
     
-    // Get transactions for block (sorted by priority and fee)
+    // Get transactions for block (by priority, then fee; each sender's nonces stay in order)
     let block_txs = tx_pool.get_transactions_for_block(10);
     println!("  Transactions for next block: {}", block_txs.len());
     
@@ -5,11 +5,14 @@ use qoranet::{
     TransactionType, 
     Transaction,
     qrc20::QRC20Transaction,
-    evm::EVMTransaction,
-    BridgeTransaction,
+    evm::{self, EVMTransaction},
+    BridgeRequest,
     wallet,
+    MAX_QOR_SUPPLY,
 };
+use k256::ecdsa::SigningKey;
 use primitive_types::{H160, H256, U256};
+use rand::rngs::OsRng;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🌊 QoraNet ERC-20 Compatibility Demo");
@@ -80,17 +83,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         U256::from(500_000) * U256::from(10).pow(6.into())
     );
     
+    // EVM transactions are signed with a secp256k1 key; the sender is recovered from the signature
+    let deployer_key = SigningKey::random(&mut OsRng);
+    let deployer = evm::evm_address(deployer_key.verifying_key());
+    qoranet.fund_account(deployer, U256::from(100) * U256::from(10).pow(9.into())); // 100 QOR
+    
     let evm_tx = EVMTransaction {
-        from: alice,
+        from: deployer,
         to: None, // Contract deployment
         value: U256::zero(),
         gas_limit: U256::from(2_000_000),
         gas_price: qoranet.gas_price,
         data: erc20_bytecode,
         nonce: U256::zero(),
-    };
+        transaction_type: evm::EVMTransactionType::Legacy,
+        signature: None,
+    }.sign(&deployer_key, qoranet.evm_pool().evm().read().unwrap().chain_id().as_u64())?;
     
-    let receipt = qoranet.process_evm_transaction(alice, evm_tx, 2_000_000)?;
+    let receipt = qoranet.process_evm_transaction(deployer, evm_tx)?;
     let erc20_contract = receipt.contract_address.unwrap();
     
     println!("✅ Deployed ERC-20 contract at: 0x{:x}", erc20_contract);
@@ -101,7 +111,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🌉 Demo 4: Cross-chain Bridge");
     println!("-----------------------------");
     
-    let bridge_tx = BridgeTransaction::FromEthereum {
+    let bridge_tx = BridgeRequest::FromEthereum {
         eth_token: H160::from_low_u64_be(0xa0b86a33e6ba), // Mock Ethereum USDC
         amount: U256::from(5000) * U256::from(10).pow(6.into()), // 5000 USDC
         token_name: "USD Coin".to_string(),
@@ -196,7 +206,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("-----------------------------");
     
     let total_tokens = qoranet.qrc20_registry.list_tokens().len();
-    let total_qor_supply = U256::from(21_000_000) * U256::from(10).pow(9.into()); // 21M QOR
+    let total_qor_supply = U256::from(MAX_QOR_SUPPLY); // 21M QOR cap
     
     println!("QoraNet Network Stats:");
     println!("  Block Number: {}", qoranet.current_block);
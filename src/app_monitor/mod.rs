@@ -1,4 +1,9 @@
-use crate::{Result, QoraNetError, AppMetrics, Address};
+pub mod sink;
+
+pub use sink::{CsvMetricsSink, MetricsRecord, MetricsSink, NoopMetricsSink, PrometheusMetricsSink};
+
+use crate::{Result, QoraNetError, AppMetrics, Address, Timestamp};
+use crate::transaction::TransactionData;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::process::{Command, Stdio};
@@ -19,6 +24,9 @@ pub struct AppMonitor {
     
     /// Monitoring interval
     monitor_interval: Duration,
+    
+    /// Where accepted metrics reports are persisted
+    sink: Box<dyn MetricsSink>,
 }
 
 /// Information about a monitored application
@@ -81,7 +89,33 @@ impl AppMonitor {
             system: System::new_all(),
             owner,
             monitor_interval: Duration::from_secs(30), // Monitor every 30 seconds
+            sink: Box::new(NoopMetricsSink),
+        }
+    }
+    
+    /// Persist every metrics report to `sink`
+    pub fn with_metrics_sink(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.sink = Box::new(sink);
+        self
+    }
+    
+    /// Record a `ReportMetrics` transaction, passing it to the metrics sink.
+    ///
+    /// Reports are keyed by the owner-namespaced app id; a locally monitored app
+    /// with that key also has its in-memory metrics replaced.
+    pub fn record_report(&mut self, report: &TransactionData, timestamp: Timestamp) -> Result<MetricsRecord> {
+        let (app_key, metrics) = match report {
+            TransactionData::ReportMetrics { app_owner, app_id, metrics, .. } => (app_id.namespaced(app_owner), metrics),
+            _ => return Err(QoraNetError::AppMonitorError("Not a metrics report".to_string())),
+        };
+        
+        if let Some(app) = self.monitored_apps.get_mut(&app_key) {
+            app.metrics = metrics.clone();
         }
+        
+        let record = MetricsRecord::new(timestamp, app_key, metrics, metrics.performance_score());
+        self.sink.record(&record)?;
+        Ok(record)
     }
     
     /// Register a new application for monitoring
@@ -364,3 +398,67 @@ pub struct SystemStats {
     pub active_apps: usize,
     pub running_apps: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::AppId;
+    use std::sync::{Arc, Mutex};
+
+    /// Sink that keeps every record it is given
+    #[derive(Debug, Clone, Default)]
+    struct RecordingSink {
+        records: Arc<Mutex<Vec<MetricsRecord>>>,
+    }
+
+    impl MetricsSink for RecordingSink {
+        fn record(&mut self, record: &MetricsRecord) -> Result<()> {
+            self.records.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+    }
+
+    fn report(uptime: u64) -> TransactionData {
+        let mut metrics = AppMetrics::new();
+        metrics.cpu_usage = 40.0;
+        metrics.memory_usage = 512 * 1024 * 1024;
+        metrics.uptime = uptime;
+        metrics.requests_served = 1_000;
+        TransactionData::ReportMetrics {
+            validator: Address([1u8; 32]),
+            app_owner: Address([2u8; 32]),
+            app_id: AppId::new("indexer").unwrap(),
+            metrics,
+        }
+    }
+
+    #[test]
+    fn test_sink_receives_one_record_per_report() {
+        let sink = RecordingSink::default();
+        let mut monitor = AppMonitor::new(Address([1u8; 32])).with_metrics_sink(sink.clone());
+
+        monitor.record_report(&report(3_600), 1_000).unwrap();
+        monitor.record_report(&report(7_200), 1_300).unwrap();
+        assert!(monitor.record_report(&TransactionData::ClaimRewards {
+            claimant: Address([1u8; 32]),
+            lp_rewards: 1,
+            app_rewards: 0,
+        }, 1_400).is_err());
+
+        let records = sink.records.lock().unwrap();
+        assert_eq!(records.len(), 2);
+
+        let app_key = AppId::new("indexer").unwrap().namespaced(&Address([2u8; 32]));
+        let first = &records[0];
+        assert_eq!(first.timestamp, 1_000);
+        assert_eq!(first.app_id, app_key);
+        assert_eq!(first.cpu_usage, 40.0);
+        assert_eq!(first.memory_usage, 512 * 1024 * 1024);
+        assert_eq!(first.uptime, 3_600);
+        assert_eq!(first.requests_served, 1_000);
+        if let TransactionData::ReportMetrics { metrics, .. } = report(3_600) {
+            assert_eq!(first.score, metrics.performance_score());
+        }
+        assert_eq!((records[1].timestamp, records[1].uptime), (1_300, 7_200));
+    }
+}
@@ -0,0 +1,210 @@
+//! Pluggable persistence for app metrics reports
+//!
+//! `AppMonitor` hands every accepted `ReportMetrics` to a `MetricsSink`, so
+//! historical app performance survives restarts and can be audited when
+//! rewards are questioned or scraped by external dashboards.
+
+use crate::{AppMetrics, QoraNetError, Result, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Header line of the CSV written by `CsvMetricsSink`
+pub const CSV_HEADER: &str = "timestamp,app_id,cpu_usage,memory_usage,uptime,requests_served,score";
+
+/// One metrics report as persisted by a sink
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsRecord {
+    pub timestamp: Timestamp,
+    pub app_id: String,
+    pub cpu_usage: f64,
+    pub memory_usage: u64,
+    pub uptime: u64,
+    pub requests_served: u64,
+    /// Performance score the report earns toward rewards
+    pub score: f64,
+}
+
+impl MetricsRecord {
+    pub fn new(timestamp: Timestamp, app_id: String, metrics: &AppMetrics, score: f64) -> Self {
+        Self {
+            timestamp,
+            app_id,
+            cpu_usage: metrics.cpu_usage,
+            memory_usage: metrics.memory_usage,
+            uptime: metrics.uptime,
+            requests_served: metrics.requests_served,
+            score,
+        }
+    }
+}
+
+/// Destination for metrics records; `Sync` so a monitor can be shared across tasks
+pub trait MetricsSink: Send + Sync + std::fmt::Debug {
+    fn record(&mut self, record: &MetricsRecord) -> Result<()>;
+}
+
+/// Sink that drops every record (the default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetricsSink;
+
+impl MetricsSink for NoopMetricsSink {
+    fn record(&mut self, _record: &MetricsRecord) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Appends records to a CSV file, one line per report
+#[derive(Debug)]
+pub struct CsvMetricsSink {
+    file: File,
+}
+
+impl CsvMetricsSink {
+    /// Open `path` for appending, writing the header if the file is new or empty
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| QoraNetError::AppMonitorError(format!("Failed to open metrics file {}: {}", path.display(), e)))?;
+
+        let empty = file.metadata()
+            .map_err(|e| QoraNetError::AppMonitorError(format!("Failed to stat metrics file {}: {}", path.display(), e)))?
+            .len() == 0;
+        if empty {
+            writeln!(file, "{}", CSV_HEADER)
+                .map_err(|e| QoraNetError::AppMonitorError(format!("Failed to write metrics header: {}", e)))?;
+        }
+
+        Ok(Self { file })
+    }
+}
+
+impl MetricsSink for CsvMetricsSink {
+    fn record(&mut self, record: &MetricsRecord) -> Result<()> {
+        // App ids are validated identifiers, but quote them so a comma can never shift columns
+        writeln!(
+            self.file,
+            "{},\"{}\",{},{},{},{},{}",
+            record.timestamp,
+            record.app_id.replace('"', "\"\""),
+            record.cpu_usage,
+            record.memory_usage,
+            record.uptime,
+            record.requests_served,
+            record.score,
+        ).map_err(|e| QoraNetError::AppMonitorError(format!("Failed to write metrics record: {}", e)))
+    }
+}
+
+/// Keeps the latest record per app for a Prometheus scrape endpoint.
+///
+/// Clones share state: hand one to the monitor and render from another.
+#[derive(Debug, Clone, Default)]
+pub struct PrometheusMetricsSink {
+    latest: Arc<Mutex<BTreeMap<String, MetricsRecord>>>,
+}
+
+impl PrometheusMetricsSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Text exposition format, one gauge family per metric, apps in id order
+    pub fn render(&self) -> String {
+        let latest = self.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let families: [(&str, &str, fn(&MetricsRecord) -> f64); 6] = [
+            ("qoranet_app_cpu_usage", "CPU usage percentage", |r| r.cpu_usage),
+            ("qoranet_app_memory_bytes", "Memory usage in bytes", |r| r.memory_usage as f64),
+            ("qoranet_app_uptime_seconds", "Uptime in seconds", |r| r.uptime as f64),
+            ("qoranet_app_requests_served", "Requests served", |r| r.requests_served as f64),
+            ("qoranet_app_score", "Performance score", |r| r.score),
+            ("qoranet_app_last_report_timestamp", "Timestamp of the last report", |r| r.timestamp as f64),
+        ];
+
+        let mut output = String::new();
+        for (name, help, value) in families {
+            output.push_str(&format!("# HELP {} {}\n# TYPE {} gauge\n", name, help, name));
+            for record in latest.values() {
+                output.push_str(&format!("{}{{app_id=\"{}\"}} {}\n", name, escape_label(&record.app_id), value(record)));
+            }
+        }
+        output
+    }
+}
+
+impl MetricsSink for PrometheusMetricsSink {
+    fn record(&mut self, record: &MetricsRecord) -> Result<()> {
+        self.latest.lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(record.app_id.clone(), record.clone());
+        Ok(())
+    }
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(app_id: &str, uptime: u64) -> MetricsRecord {
+        MetricsRecord {
+            timestamp: 1_700_000_000,
+            app_id: app_id.to_string(),
+            cpu_usage: 12.5,
+            memory_usage: 1_048_576,
+            uptime,
+            requests_served: 42,
+            score: 0.75,
+        }
+    }
+
+    #[test]
+    fn test_csv_sink_appends_one_line_per_record() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("metrics.csv");
+
+        let mut sink = CsvMetricsSink::open(&path).unwrap();
+        sink.record(&record("app-1", 100)).unwrap();
+        drop(sink);
+
+        // Reopening appends without repeating the header
+        let mut sink = CsvMetricsSink::open(&path).unwrap();
+        sink.record(&record("app-2", 200)).unwrap();
+        drop(sink);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines, vec![
+            CSV_HEADER,
+            "1700000000,\"app-1\",12.5,1048576,100,42,0.75",
+            "1700000000,\"app-2\",12.5,1048576,200,42,0.75",
+        ]);
+    }
+
+    #[test]
+    fn test_prometheus_sink_renders_latest_per_app() {
+        let exporter = PrometheusMetricsSink::new();
+        let mut sink = exporter.clone();
+        sink.record(&record("app-1", 100)).unwrap();
+        sink.record(&record("app-1", 160)).unwrap();
+        sink.record(&record("a\"b", 5)).unwrap();
+
+        let output = exporter.render();
+        assert!(output.contains("# TYPE qoranet_app_uptime_seconds gauge\n"));
+        assert!(output.contains("qoranet_app_uptime_seconds{app_id=\"app-1\"} 160\n"));
+        assert!(!output.contains("qoranet_app_uptime_seconds{app_id=\"app-1\"} 100\n"));
+        assert!(output.contains("qoranet_app_uptime_seconds{app_id=\"a\\\"b\"} 5\n"));
+        assert!(output.contains("qoranet_app_score{app_id=\"app-1\"} 0.75\n"));
+    }
+}
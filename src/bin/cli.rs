@@ -1,20 +1,67 @@
 use qoranet::{
     transaction::{Transaction, TransactionData},
+    wallet::{format_token_balance, format_token_balance_rounded, group_thousands},
     fee_oracle::{GlobalFeeOracle, FeePriority, TransactionType},
     storage::BlockchainStorage,
+    consensus::GenesisConfig,
     Address, Balance, LPToken, Result, QoraNetError,
 };
-use clap::{Arg, Command, ArgMatches, SubCommand};
+use clap::{Arg, ArgAction, Command, ArgMatches, SubCommand};
+use serde::Serialize;
 use ed25519_dalek::Keypair;
 use rand::rngs::OsRng;
+use primitive_types::U256;
+use std::io::Write;
 use std::path::PathBuf;
 use std::fs;
 
+/// Decimal places of a raw QOR amount
+const QOR_DECIMALS: u8 = 9;
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let matches = Command::new("qoranet-cli")
+    let matches = build_cli().get_matches();
+    let mut out = Output::new(std::io::stdout(), OutputMode::from_matches(&matches));
+
+    let result = run(&matches, &mut out).await;
+    if result.is_err() && out.mode == OutputMode::Json {
+        std::process::exit(1);
+    }
+    result
+}
+
+/// Dispatch a command; a failure is reported on `out` as well as returned
+async fn run<W: Write>(matches: &ArgMatches, out: &mut Output<W>) -> Result<()> {
+    let result = match matches.subcommand() {
+        Some(("wallet", wallet_matches)) => handle_wallet_commands(wallet_matches, out).await,
+        Some(("transaction", tx_matches)) => handle_transaction_commands(tx_matches, out).await,
+        Some(("network", network_matches)) => handle_network_commands(network_matches, out).await,
+        Some(("price", _)) => handle_price_command(out).await,
+        Some(("storage", storage_matches)) => handle_storage_commands(storage_matches, out).await,
+        Some(("verify-chain", verify_matches)) => handle_verify_chain(verify_matches, out).await,
+        _ => {
+            out.text("Use --help for available commands");
+            Ok(())
+        }
+    };
+
+    if let Err(e) = &result {
+        out.error(e);
+    }
+    result
+}
+
+fn build_cli() -> Command {
+    Command::new("qoranet-cli")
         .version(qoranet::VERSION)
         .about("QoraNet Command Line Interface")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print results as JSON instead of text")
+                .action(ArgAction::SetTrue)
+                .global(true)
+        )
         .subcommand(
             Command::new("wallet")
                 .about("Wallet operations")
@@ -46,6 +93,12 @@ async fn main() -> Result<()> {
                                 .help("Data directory")
                                 .default_value("./qoranet-data")
                         )
+                        .arg(
+                            Arg::new("display-decimals")
+                                .long("display-decimals")
+                                .help("Round the balance to this many decimals and group thousands")
+                                .value_parser(clap::value_parser!(u8))
+                        )
                 )
         )
         .subcommand(
@@ -109,26 +162,364 @@ async fn main() -> Result<()> {
             Command::new("price")
                 .about("QOR price information")
         )
-        .get_matches();
+        .subcommand(
+            Command::new("storage")
+                .about("Local storage maintenance")
+                .subcommand(
+                    Command::new("verify")
+                        .about("Check the stored chain for inconsistencies")
+                        .arg(
+                            Arg::new("data-dir")
+                                .short('d')
+                                .long("data-dir")
+                                .help("Data directory")
+                                .default_value("./qoranet-data")
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("verify-chain")
+                .about("Re-execute every block from genesis and report the first divergence")
+                .arg(
+                    Arg::new("data-dir")
+                        .short('d')
+                        .long("data-dir")
+                        .help("Data directory")
+                        .default_value("./qoranet-data")
+                )
+                .arg(
+                    Arg::new("genesis")
+                        .long("genesis")
+                        .help("Genesis config JSON file (defaults to the built-in config)")
+                )
+        )
+}
+
+/// How command results are printed, selected by the global `--json` flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputMode {
+    Text,
+    Json,
+}
+
+impl OutputMode {
+    /// `--json` is global, so this works on any subcommand's matches
+    fn from_matches(matches: &ArgMatches) -> Self {
+        if matches.get_flag("json") {
+            OutputMode::Json
+        } else {
+            OutputMode::Text
+        }
+    }
+}
+
+/// Error reported to scripts, with a stable code per error kind
+#[derive(Debug, Serialize)]
+struct CliError {
+    code: &'static str,
+    message: String,
+}
+
+impl From<&QoraNetError> for CliError {
+    fn from(error: &QoraNetError) -> Self {
+        let code = match error {
+            QoraNetError::InvalidTransaction(_) => "invalid_transaction",
+            QoraNetError::InsufficientLiquidity { .. } => "insufficient_liquidity",
+            QoraNetError::AppMonitorError(_) => "app_monitor",
+            QoraNetError::NetworkError(_) => "network",
+            QoraNetError::StorageError(_) => "storage",
+            QoraNetError::ConsensusError(_) => "consensus",
+            QoraNetError::TokenError(_) => "token",
+            QoraNetError::BridgeError(_) => "bridge",
+            QoraNetError::PoolFull { .. } => "pool_full",
+            QoraNetError::ReplacementUnderpriced { .. } => "replacement_underpriced",
+            QoraNetError::SpendingLimitExceeded { .. } => "spending_limit_exceeded",
+        };
+        Self { code, message: error.to_string() }
+    }
+}
 
+fn error_json(error: &QoraNetError) -> serde_json::Value {
+    serde_json::json!({ "error": CliError::from(error) })
+}
+
+/// `document` with the command's failure attached, so a failing run still prints one document
+fn with_error<T: Serialize>(document: &T, error: &QoraNetError) -> serde_json::Value {
+    let mut document = serde_json::to_value(document).expect("CLI output serializes");
+    if let Some(fields) = document.as_object_mut() {
+        fields.insert("error".to_string(), serde_json::json!(CliError::from(error)));
+    }
+    document
+}
+
+/// Where command results are printed.
+///
+/// Text lines are dropped in JSON mode, and at most one JSON document is
+/// written per run so scripts can always parse stdout as a single value.
+struct Output<W: Write> {
+    writer: W,
+    mode: OutputMode,
+    document_written: bool,
+}
+
+impl<W: Write> Output<W> {
+    fn new(writer: W, mode: OutputMode) -> Self {
+        Self { writer, mode, document_written: false }
+    }
+
+    /// Print a line for people; ignored in JSON mode
+    fn text(&mut self, line: impl std::fmt::Display) {
+        if self.mode == OutputMode::Text {
+            writeln!(self.writer, "{}", line).expect("CLI output is writable");
+        }
+    }
+
+    /// Print the run's JSON document; ignored in text mode or once a document was written
+    fn json<T: Serialize>(&mut self, value: &T) {
+        if self.mode != OutputMode::Json || self.document_written {
+            return;
+        }
+        serde_json::to_writer_pretty(&mut self.writer, value).expect("CLI output serializes");
+        writeln!(self.writer).expect("CLI output is writable");
+        self.document_written = true;
+    }
+
+    /// Report a failed run as an error document, unless the command already wrote its own
+    fn error(&mut self, error: &QoraNetError) {
+        self.json(&error_json(error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn subcommand_mode(args: &[&str]) -> OutputMode {
+        let matches = build_cli().try_get_matches_from(args).unwrap();
+        let (_, group) = matches.subcommand().unwrap();
+        let (_, command) = group.subcommand().unwrap();
+        OutputMode::from_matches(command)
+    }
+
+    #[test]
+    fn test_json_flag_accepted_anywhere() {
+        assert_eq!(subcommand_mode(&["qoranet-cli", "--json", "transaction", "fee-estimate"]), OutputMode::Json);
+        assert_eq!(subcommand_mode(&["qoranet-cli", "network", "status", "--json"]), OutputMode::Json);
+        assert_eq!(subcommand_mode(&["qoranet-cli", "wallet", "balance", "-a", "00"]), OutputMode::Text);
+    }
+
+    #[test]
+    fn test_balance_display_decimals_parsed() {
+        let matches = build_cli()
+            .try_get_matches_from(["qoranet-cli", "wallet", "balance", "-a", "00", "--display-decimals", "2"])
+            .unwrap();
+        let balance = matches.subcommand_matches("wallet").unwrap().subcommand_matches("balance").unwrap();
+        assert_eq!(balance.get_one::<u8>("display-decimals"), Some(&2));
+        assert!(build_cli()
+            .try_get_matches_from(["qoranet-cli", "wallet", "balance", "-a", "00", "--display-decimals", "x"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_error_json_carries_code() {
+        let error = QoraNetError::InsufficientLiquidity { required: 10, available: 3 };
+        let parsed: serde_json::Value = serde_json::from_str(&error_json(&error).to_string()).unwrap();
+        assert_eq!(parsed["error"]["code"], "insufficient_liquidity");
+        assert_eq!(parsed["error"]["message"], "Insufficient liquidity: required 10, have 3");
+    }
+
+    /// Run the CLI on `args` and return its result along with what it printed
+    async fn run_cli(args: &[&str]) -> (Result<()>, String) {
+        let matches = build_cli().try_get_matches_from(args).unwrap();
+        let mut out = Output::new(Vec::new(), OutputMode::from_matches(&matches));
+        let result = run(&matches, &mut out).await;
+        (result, String::from_utf8(out.writer).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_storage_verify_json() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().to_str().unwrap();
+
+        let (result, stdout) = run_cli(&["qoranet-cli", "--json", "storage", "verify", "-d", data_dir]).await;
+        assert!(result.is_ok());
+
+        // Exactly one document, with no text around it
+        let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(report["latest_height"], 0);
+        assert!(report["issues"].as_array().unwrap().is_empty());
+        assert!(report.get("error").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_verify_chain_reports_missing_genesis() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().to_str().unwrap();
+
+        // An empty database has nothing to re-execute, so it can't be trusted
+        let (result, stdout) = run_cli(&["qoranet-cli", "--json", "verify-chain", "-d", data_dir]).await;
+        assert!(matches!(result, Err(QoraNetError::StorageError(message)) if message.contains("height 0")));
+        assert!(!dir.path().join("verify-chain").exists());
+
+        let document: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(document["error"]["code"], "storage");
+    }
+
+    #[tokio::test]
+    async fn test_wallet_balance_json_and_text() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().to_str().unwrap();
+        let address = "0x".to_string() + &"07".repeat(32);
+
+        let (result, stdout) = run_cli(&["qoranet-cli", "wallet", "balance", "-a", &address, "-d", data_dir, "--json"]).await;
+        assert!(result.is_ok());
+        let document: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(document["amount"], 0);
+
+        let (result, stdout) = run_cli(&["qoranet-cli", "wallet", "balance", "-a", &address, "-d", data_dir]).await;
+        assert!(result.is_ok());
+        assert!(stdout.starts_with("💰 Balance:"));
+        assert!(serde_json::from_str::<serde_json::Value>(&stdout).is_err());
+    }
+
+    #[test]
+    fn test_failed_command_prints_one_document() {
+        let mut out = Output::new(Vec::new(), OutputMode::Json);
+        let failure = QoraNetError::StorageError("broken".to_string());
+        out.json(&with_error(&serde_json::json!({ "latest_height": 3 }), &failure));
+        out.text("not for scripts");
+        out.error(&failure);
+
+        let document: serde_json::Value = serde_json::from_slice(&out.writer).unwrap();
+        assert_eq!(document["latest_height"], 3);
+        assert_eq!(document["error"]["code"], "storage");
+    }
+}
+
+async fn handle_storage_commands<W: Write>(matches: &ArgMatches, out: &mut Output<W>) -> Result<()> {
     match matches.subcommand() {
-        Some(("wallet", wallet_matches)) => handle_wallet_commands(wallet_matches).await,
-        Some(("transaction", tx_matches)) => handle_transaction_commands(tx_matches).await,
-        Some(("network", network_matches)) => handle_network_commands(network_matches).await,
-        Some(("price", _)) => handle_price_command().await,
+        Some(("verify", verify_matches)) => {
+            let data_dir = verify_matches.get_one::<String>("data-dir").unwrap();
+            verify_storage(data_dir, out).await
+        },
         _ => {
-            println!("Use --help for available commands");
+            out.text("Use 'storage --help' for available commands");
             Ok(())
         }
     }
 }
 
-async fn handle_wallet_commands(matches: &ArgMatches) -> Result<()> {
+async fn verify_storage<W: Write>(data_dir: &str, out: &mut Output<W>) -> Result<()> {
+    let storage_path = PathBuf::from(data_dir).join("blockchain");
+    out.text(format!("🔍 Verifying storage at {}", storage_path.display()));
+    
+    let storage = BlockchainStorage::new(storage_path)?;
+    let report = storage.verify_integrity()?;
+    
+    if report.is_clean() {
+        out.json(&report);
+        out.text(format!("Latest height: {}", report.latest_height));
+        out.text(format!("Blocks checked: {}", report.blocks_checked));
+        out.text("✅ No inconsistencies found");
+        return Ok(());
+    }
+    
+    let failure = QoraNetError::StorageError(format!(
+        "Storage integrity check failed with {} issue(s)",
+        report.issues.len()
+    ));
+    out.json(&with_error(&report, &failure));
+    out.text(format!("Latest height: {}", report.latest_height));
+    out.text(format!("Blocks checked: {}", report.blocks_checked));
+    out.text(format!("❌ Found {} issue(s):", report.issues.len()));
+    for issue in &report.issues {
+        out.text(format!("  height {}: {}", issue.height, issue.description));
+    }
+    
+    Err(failure)
+}
+
+async fn handle_verify_chain<W: Write>(matches: &ArgMatches, out: &mut Output<W>) -> Result<()> {
+    let data_dir = matches.get_one::<String>("data-dir").unwrap();
+    let genesis = match matches.get_one::<String>("genesis") {
+        Some(path) => {
+            let contents = fs::read_to_string(path)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to read genesis config {}: {}", path, e)))?;
+            serde_json::from_str(&contents)
+                .map_err(|e| QoraNetError::StorageError(format!("Invalid genesis config {}: {}", path, e)))?
+        },
+        None => GenesisConfig::default(),
+    };
+    verify_chain(data_dir, &genesis, out).await
+}
+
+async fn verify_chain<W: Write>(data_dir: &str, genesis: &GenesisConfig, out: &mut Output<W>) -> Result<()> {
+    let storage_path = PathBuf::from(data_dir).join("blockchain");
+    // Re-executed state goes to a scratch database next to the real one
+    let replay_path = PathBuf::from(data_dir).join("verify-chain");
+    out.text(format!("🔍 Re-executing chain at {} from genesis", storage_path.display()));
+    
+    let storage = BlockchainStorage::new(storage_path)?;
+    let _ = fs::remove_dir_all(&replay_path);
+    let verification = {
+        let mut replay = BlockchainStorage::new(&replay_path)?;
+        storage.verify_chain(genesis, &mut replay)
+    };
+    let _ = fs::remove_dir_all(&replay_path);
+    let verification = verification?;
+    
+    let failure = verification.divergence.as_ref().map(|divergence| QoraNetError::StorageError(format!(
+        "Chain diverges at height {}: {}",
+        divergence.height, divergence.reason
+    )));
+    
+    match &failure {
+        Some(failure) => out.json(&with_error(&verification, failure)),
+        None => out.json(&verification),
+    }
+    out.text(format!("Latest height: {}", verification.latest_height));
+    out.text(format!("Blocks verified: {}", verification.blocks_verified));
+    match &verification.divergence {
+        None => out.text("✅ Chain re-executes identically from genesis to tip"),
+        Some(divergence) => out.text(format!("❌ First divergence at height {}: {}", divergence.height, divergence.reason)),
+    }
+    
+    failure.map_or(Ok(()), Err)
+}
+
+async fn handle_wallet_commands<W: Write>(matches: &ArgMatches, out: &mut Output<W>) -> Result<()> {
     match matches.subcommand() {
         Some(("generate", gen_matches)) => {
             let output_file = gen_matches.get_one::<String>("output").unwrap();
-            generate_wallet(output_file).await
+            generate_wallet(output_file, out).await
         },
         Some(("balance", balance_matches)) => {
             let address_str = balance_matches.get_one::<String>("address").unwrap();
-            let data_dir = balance
+            let data_dir = balance_matches.get_one::<String>("data-dir").unwrap();
+            let display_decimals = balance_matches.get_one::<u8>("display-decimals").copied();
+            show_balance(address_str, data_dir, display_decimals, out).await
+        },
+        _ => {
+            out.text("Use 'wallet --help' for available commands");
+            Ok(())
+        }
+    }
+}
+
+async fn show_balance<W: Write>(address_str: &str, data_dir: &str, display_decimals: Option<u8>, out: &mut Output<W>) -> Result<()> {
+    let address = Address::from_hex(address_str)?;
+    let storage = BlockchainStorage::new(PathBuf::from(data_dir).join("blockchain"))?;
+    let amount = storage.get_account(&address)?.map_or(0, |account| account.balance.amount);
+    let exact = format_token_balance(U256::from(amount), QOR_DECIMALS);
+    
+    out.json(&serde_json::json!({ "address": address_str, "amount": amount, "balance": exact }));
+    match display_decimals {
+        Some(places) => {
+            let rounded = group_thousands(&format_token_balance_rounded(U256::from(amount), QOR_DECIMALS, places));
+            out.text(format!("💰 Balance: {} QOR (exact: {})", rounded, exact));
+        },
+        None => out.text(format!("💰 Balance: {} QOR", exact)),
+    }
+    Ok(())
+}
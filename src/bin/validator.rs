@@ -1,17 +1,17 @@
 use qoranet::{
-    consensus::{ConsensusState, ValidatorInfo, Block},
-    transaction::TransactionPool,
-    storage::BlockchainStorage,
-    app_monitor::AppMonitor,
-    fee_oracle::GlobalFeeOracle,
-    Address, Result, QoraNetError, Balance,
+    consensus::{ConsensusState, ValidatorInfo, Block, GenesisConfig, ProductionWatchdog, DEFAULT_MAX_STORAGE_FAILURES},
+    transaction::{Transaction, TransactionData, TransactionPool, DEFAULT_MAX_READMITTED},
+    storage::{BlockchainStorage, HeadEvent, PruningConfig},
+    app_monitor::{AppMonitor, CsvMetricsSink},
+    fee_oracle::{FeeOracle, GlobalFeeOracle},
+    Address, Result, QoraNetError, Balance, QoraNet,
 };
 use clap::{Arg, Command};
 use ed25519_dalek::Keypair;
 use rand::rngs::OsRng;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{info, error, warn};
 use tracing_subscriber;
 
@@ -39,6 +39,12 @@ struct ValidatorNode {
     /// Fee oracle
     fee_oracle: Arc<GlobalFeeOracle>,
     
+    /// Token, bridge and EVM facade; its bridge follows this chain's finality
+    chain: Arc<RwLock<QoraNet>>,
+    
+    /// Halts production after repeated storage failures
+    watchdog: Arc<ProductionWatchdog>,
+    
     /// Configuration
     config: ValidatorConfig,
 }
@@ -51,6 +57,14 @@ struct ValidatorConfig {
     pub block_time_seconds: u64,
     pub max_block_size: usize,
     pub max_transactions_per_block: usize,
+    pub ws_port: u16,
+    pub health_port: u16,
+    pub max_storage_failures: u32,
+    /// CSV file applied metrics reports are appended to; defaults to one in `data_dir`
+    pub metrics_csv: Option<PathBuf>,
+    /// Adds CoinMarketCap to the QOR price sources
+    pub cmc_api_key: Option<String>,
+    pub genesis: GenesisConfig,
 }
 
 impl ValidatorConfig {
@@ -62,6 +76,12 @@ impl ValidatorConfig {
             block_time_seconds: 10, // 10 second blocks
             max_block_size: 1024 * 1024, // 1MB max block size
             max_transactions_per_block: 1000,
+            ws_port: 8546, // WebSocket subscriptions
+            health_port: 8547, // /health and /ready probes
+            max_storage_failures: DEFAULT_MAX_STORAGE_FAILURES,
+            metrics_csv: None,
+            cmc_api_key: None,
+            genesis: GenesisConfig::default(),
         }
     }
 }
@@ -79,30 +99,54 @@ impl ValidatorNode {
         // Initialize storage
         let storage_path = config.data_dir.join("blockchain");
         std::fs::create_dir_all(&storage_path)?;
-        let storage = BlockchainStorage::new(storage_path)?;
+        let mut storage = BlockchainStorage::new(storage_path)?;
+        // Finality, and so what may be pruned, is this chain's
+        storage.set_pruning_config(PruningConfig::for_genesis(&config.genesis));
         let storage = Arc::new(RwLock::new(storage));
         
-        // Initialize transaction pool
-        let tx_pool = Arc::new(RwLock::new(TransactionPool::new()));
+        // Initialize transaction pool, validating for this chain from its current tip
+        let mut tx_pool = TransactionPool::new().with_chain(config.genesis.clone());
+        tx_pool.set_chain_height(storage.read().await.get_latest_block_info().1);
+        let tx_pool = Arc::new(RwLock::new(tx_pool));
         
         // Initialize consensus
-        let consensus = ConsensusState::new(
+        let mut consensus = ConsensusState::new(
             config.min_liquidity_requirement,
             config.min_apps_requirement,
         );
+        consensus.set_signed_blocks_height(config.genesis.signed_blocks_height);
+        consensus.set_domain_separated_root_height(config.genesis.domain_separated_root_height);
+        // Slashings are chain state; restore them before producing anything
+        for (offender, height) in storage.read().await.slashed_validators()? {
+            consensus.record_slashing(&offender, height);
+        }
         let consensus = Arc::new(RwLock::new(consensus));
         
-        // Initialize application monitor
-        let app_monitor = AppMonitor::new(address.clone());
+        // Initialize application monitor, persisting metrics reports as blocks apply them
+        let metrics_csv = config.metrics_csv.clone()
+            .unwrap_or_else(|| config.data_dir.join("app-metrics.csv"));
+        info!("📈 Recording app metrics reports to {}", metrics_csv.display());
+        let app_monitor = AppMonitor::new(address.clone())
+            .with_metrics_sink(CsvMetricsSink::open(&metrics_csv)?);
         let app_monitor = Arc::new(RwLock::new(app_monitor));
         
         // Initialize fee oracle
-        let fee_oracle = Arc::new(GlobalFeeOracle::new());
+        let mut oracle = FeeOracle::new();
+        if let Some(api_key) = &config.cmc_api_key {
+            oracle = oracle.with_coinmarketcap_api_key(api_key);
+        }
+        // Receipts itemize fees with the same discounts the pool admits them under
+        storage.write().await.set_fee_discount_policy(oracle.discount_policy().clone());
+        let fee_oracle = Arc::new(GlobalFeeOracle::from_oracle(oracle));
+        
+        let chain = Arc::new(RwLock::new(QoraNet::new()));
         
         // Register self as validator
         let validator_info = ValidatorInfo::new(address.clone());
         consensus.write().await.update_validator(validator_info)?;
         
+        let watchdog = Arc::new(ProductionWatchdog::new(config.max_storage_failures));
+        
         Ok(Self {
             keypair,
             address,
@@ -111,6 +155,8 @@ impl ValidatorNode {
             consensus,
             app_monitor,
             fee_oracle,
+            chain,
+            watchdog,
             config,
         })
     }
@@ -130,19 +176,80 @@ impl ValidatorNode {
         let consensus = Arc::clone(&self.consensus);
         let storage = Arc::clone(&self.storage);
         let tx_pool = Arc::clone(&self.tx_pool);
+        let app_monitor = Arc::clone(&self.app_monitor);
+        let chain = Arc::clone(&self.chain);
         let block_time = self.config.block_time_seconds;
         let max_txs = self.config.max_transactions_per_block;
+        let genesis = self.config.genesis.clone();
         let validator_address = self.address.clone();
         let keypair = self.keypair.clone();
+        let watchdog = Arc::clone(&self.watchdog);
         
-        // Fee oracle update task
+        // Readiness probes
+        let health_listener = tokio::net::TcpListener::bind(("0.0.0.0", self.config.health_port)).await
+            .map_err(|e| QoraNetError::NetworkError(format!("Failed to bind health port: {}", e)))?;
+        info!("🩺 Health probes on port {}", self.config.health_port);
+        let probe_watchdog = Arc::clone(&watchdog);
+        tokio::spawn(async move {
+            if let Err(e) = qoranet::rpc::health::serve_health(health_listener, probe_watchdog).await {
+                error!("Health server stopped: {}", e);
+            }
+        });
+        
+        // WebSocket head subscriptions
+        let head_feed = self.storage.read().await.head_feed();
+        let ws_listener = tokio::net::TcpListener::bind(("0.0.0.0", self.config.ws_port)).await
+            .map_err(|e| QoraNetError::NetworkError(format!("Failed to bind WebSocket port: {}", e)))?;
+        info!("🔌 WebSocket subscriptions on port {}", self.config.ws_port);
+        tokio::spawn(async move {
+            if let Err(e) = qoranet::rpc::ws::serve_ws(ws_listener, head_feed).await {
+                error!("WebSocket server stopped: {}", e);
+            }
+        });
+        
+        // Transactions of heads rewound by a reorg go back into the pool
+        let mut heads = self.storage.read().await.subscribe_heads();
+        let reorg_storage = Arc::clone(&self.storage);
+        let reorg_pool = Arc::clone(&self.tx_pool);
+        let reorg_oracle = Arc::clone(&self.fee_oracle);
+        tokio::spawn(async move {
+            loop {
+                let head = match heads.recv().await {
+                    Ok(HeadEvent::Removed(head)) => head,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Missed {} head events; reverted transactions may not be re-admitted", missed);
+                        continue;
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                
+                let storage = reorg_storage.read().await;
+                let reverted = match storage.removed_head_transactions(&head) {
+                    Ok(reverted) => reverted,
+                    Err(e) => {
+                        warn!("Failed to load transactions of rewound block #{}: {}", head.height, e);
+                        continue;
+                    }
+                };
+                // Fees are checked against the discount each signer's holdings earn; a read error forgoes it
+                let holdings = |signer: &Address| storage.account_holdings(signer).unwrap_or_default();
+                let readmitted = reorg_pool.write().await
+                    .readmit_reverted(reverted, &reorg_oracle, DEFAULT_MAX_READMITTED, holdings).await;
+                drop(storage);
+                if readmitted > 0 {
+                    info!("♻️  Re-admitted {} transactions from rewound block #{}", readmitted, head.height);
+                }
+            }
+        });
+        
+        // Fee oracle update task; jittered so validators don't query the price APIs in lockstep
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
             loop {
-                interval.tick().await;
                 if let Err(e) = fee_oracle.update_price().await {
                     warn!("Failed to update QOR price: {}", e);
                 }
+                tokio::time::sleep(fee_oracle.next_update_delay().await).await;
             }
         });
         
@@ -152,13 +259,20 @@ impl ValidatorNode {
             loop {
                 interval.tick().await;
                 
-                match Self::try_produce_block(
+                let outcome = Self::try_produce_block(
                     &consensus,
                     &storage,
                     &tx_pool,
+                    &app_monitor,
+                    &chain,
                     &validator_address,
+                    &keypair,
                     max_txs,
-                ).await {
+                    &genesis,
+                ).await;
+                let halted = watchdog.observe(&outcome);
+                
+                match outcome {
                     Ok(Some(block)) => {
                         info!("📦 Produced block #{} with {} transactions", 
                             block.header.height, 
@@ -172,6 +286,14 @@ impl ValidatorNode {
                         error!("Failed to produce block: {}", e);
                     }
                 }
+                
+                if halted {
+                    error!(
+                        "FATAL: {} consecutive storage write failures, halting block production; node is no longer ready",
+                        watchdog.consecutive_failures()
+                    );
+                    break;
+                }
             }
         });
         
@@ -193,12 +315,13 @@ impl ValidatorNode {
             drop(storage); // Release read lock
             
             info!("🌱 Creating genesis block...");
-            let genesis_block = Block::genesis(self.address.clone());
+            let genesis_block = self.config.genesis.genesis_block(self.address.clone());
             
             let mut storage = self.storage.write().await;
             storage.store_block(&genesis_block)?;
+            storage.apply_genesis_allocations(&self.config.genesis)?;
             
-            info!("✅ Genesis block created: {}", genesis_block.hash());
+            info!("✅ Genesis block created: {} (chain config {})", genesis_block.hash(), self.config.genesis.genesis_hash());
         }
         
         Ok(())
@@ -209,8 +332,12 @@ impl ValidatorNode {
         consensus: &Arc<RwLock<ConsensusState>>,
         storage: &Arc<RwLock<BlockchainStorage>>,
         tx_pool: &Arc<RwLock<TransactionPool>>,
+        app_monitor: &Arc<RwLock<AppMonitor>>,
+        chain: &Arc<RwLock<QoraNet>>,
         validator_address: &Address,
+        keypair: &Keypair,
         max_transactions: usize,
+        genesis: &GenesisConfig,
     ) -> Result<Option<Block>> {
         let consensus_state = consensus.read().await;
         let (latest_hash, latest_height) = {
@@ -222,55 +349,119 @@ impl ValidatorNode {
         let new_height = latest_height + 1;
         
         // Check if this validator is selected to produce the block
-        let selected_validator = consensus_state.select_block_producer(previous_hash.as_bytes())?;
+        let selected_validator = consensus_state.select_block_producer(previous_hash.as_bytes(), new_height)?;
         if selected_validator != *validator_address {
             return Ok(None); // Not selected
         }
         
-        // Get transactions from pool
-        let transactions = {
+        // Get transactions from pool, starting each signer at its committed nonce (0 for a new account)
+        let (transactions, evidence, state_root) = {
+            let storage = storage.read().await;
             let pool = tx_pool.read().await;
-            pool.get_transactions_for_block(max_transactions)
+            let transactions = pool.get_transactions_for_block_from(max_transactions, new_height, |signer| {
+                // A read error falls back to the nonce the pool last recorded
+                storage.get_account(signer).ok().map(|account| account.map_or(0, |account| account.nonce))
+            });
+            
+            // Equivocation reports follow our own pooled transactions in nonce order
+            let mut nonce = storage.get_account(validator_address)?.map_or(0, |account| account.nonce)
+                + transactions.iter().filter(|tx| tx.signer == *validator_address).count() as u64;
+            let evidence: Vec<Transaction> = consensus_state.pending_evidence().into_iter().map(|proof| {
+                let report = Transaction::equivocation_report(proof, nonce, keypair);
+                nonce += 1;
+                report
+            }).collect();
+            // The block commits to the state it is built on
+            (transactions, evidence, storage.state_root()?)
         };
         
         // Get network stats
+        let epoch = consensus_state.epoch_of(new_height);
         let total_liquidity = consensus_state.total_network_liquidity();
         let active_apps = consensus_state.total_active_apps() as u32;
         
         drop(consensus_state);
         
-        // Create new block
-        let block = Block::new(
+        // Create and sign new block
+        let mut block = Block::new(
             previous_hash,
             new_height,
             validator_address.clone(),
-            transactions.clone(),
+            transactions.iter().cloned().chain(evidence).collect(),
             total_liquidity,
             active_apps,
-        );
+        )
+        .with_domain_separated_root_height(genesis.domain_separated_root_height)
+        .with_state_root(state_root);
+        block.sign(keypair);
         
-        // Validate and store block
-        block.validate(new_height, &previous_hash)?;
+        // Validate (including that we were the selected producer) and store block
+        block.validate_against_state(new_height, &previous_hash, &*consensus.read().await)?;
         
-        {
+        let (next_nonces, failed, finalized_height) = {
             let mut storage = storage.write().await;
             storage.store_block(&block)?;
-        }
+            // Advances each signer's nonce; transactions that no longer apply are left without effect
+            let failed = storage.apply_block_transactions(&block)?;
+            // Part of the collected fees funds the epoch reward pool; minting stops once the supply cap is reached
+            storage.reward_block(validator_address, block.collected_fees(&failed), epoch, genesis)?;
+            
+            let mut next_nonces = Vec::new();
+            for tx in &transactions {
+                let nonce = storage.get_account(&tx.signer)?.map_or(0, |account| account.nonce);
+                next_nonces.push((tx.signer.clone(), nonce));
+            }
+            // Old bodies go once final; the block is already committed, so a failure only delays it
+            if let Err(e) = storage.prune_to_retention() {
+                warn!("Failed to prune old blocks: {}", e);
+            }
+            (next_nonces, failed, storage.finalized_height())
+        };
         
-        // Remove transactions from pool
+        // Bridge releases wait for the chain's finality, not the facade's
+        chain.write().await.set_chain_heights(new_height, finalized_height);
+        
+        // Remove included transactions and any others their nonces superseded
         {
             let mut pool = tx_pool.write().await;
             for tx in &transactions {
                 pool.remove_transaction(&tx.hash());
             }
+            for (signer, nonce) in &next_nonces {
+                pool.set_next_nonce(signer, *nonce);
+            }
+            // Nothing that can no longer be included should wait in the pool
+            pool.evict_expired(new_height);
+            pool.set_chain_height(new_height);
         }
         
         // Update consensus height
         {
             let mut consensus_state = consensus.write().await;
+            consensus_state.record_produced_block(&block);
+            // Applied evidence takes effect from the next epoch's producer set
+            for tx in &block.transactions {
+                if let TransactionData::ReportEquivocation { proof, .. } = &tx.data {
+                    if !failed.contains(&tx.hash()) {
+                        consensus_state.record_slashing(proof.offender(), new_height);
+                    }
+                }
+            }
             consensus_state.update_height(new_height);
         }
         
+        // The block is committed, so a sink failure only costs the persisted record
+        {
+            let mut app_monitor = app_monitor.write().await;
+            for tx in &block.transactions {
+                if matches!(tx.data, TransactionData::ReportMetrics { .. }) && !failed.contains(&tx.hash()) {
+                    if let Err(e) = app_monitor.record_report(&tx.data, block.header.timestamp) {
+                        warn!("Failed to record metrics report {}: {}", tx.hash(), e);
+                    }
+                }
+            }
+        }
+        
         Ok(Some(block))
     }
     
@@ -339,6 +530,34 @@ async fn main() -> Result<()> {
                 .help("Minimum number of apps required")
                 .default_value("1")
         )
+        .arg(
+            Arg::new("ws-port")
+                .long("ws-port")
+                .help("Port for WebSocket subscriptions")
+                .default_value("8546")
+        )
+        .arg(
+            Arg::new("health-port")
+                .long("health-port")
+                .help("Port for /health and /ready probes")
+                .default_value("8547")
+        )
+        .arg(
+            Arg::new("max-storage-failures")
+                .long("max-storage-failures")
+                .help("Consecutive storage write failures before block production halts")
+                .default_value("3")
+        )
+        .arg(
+            Arg::new("metrics-csv")
+                .long("metrics-csv")
+                .help("CSV file app metrics reports are appended to (default: app-metrics.csv in the data directory)")
+        )
+        .arg(
+            Arg::new("cmc-api-key")
+                .long("cmc-api-key")
+                .help("CoinMarketCap API key; adds CoinMarketCap to the QOR price sources")
+        )
         .arg(
             Arg::new("block-time")
                 .long("block-time")
@@ -362,9 +581,29 @@ async fn main() -> Result<()> {
             .map_err(|_| QoraNetError::InvalidTransaction("Invalid min-apps value".to_string()))?;
     }
     
+    if let Some(ws_port) = matches.get_one::<String>("ws-port") {
+        config.ws_port = ws_port.parse()
+            .map_err(|_| QoraNetError::InvalidTransaction("Invalid ws-port value".to_string()))?;
+    }
+    
+    if let Some(health_port) = matches.get_one::<String>("health-port") {
+        config.health_port = health_port.parse()
+            .map_err(|_| QoraNetError::InvalidTransaction("Invalid health-port value".to_string()))?;
+    }
+    
+    if let Some(max_failures) = matches.get_one::<String>("max-storage-failures") {
+        config.max_storage_failures = max_failures.parse()
+            .map_err(|_| QoraNetError::InvalidTransaction("Invalid max-storage-failures value".to_string()))?;
+    }
+    
+    config.metrics_csv = matches.get_one::<String>("metrics-csv").map(PathBuf::from);
+    config.cmc_api_key = matches.get_one::<String>("cmc-api-key").cloned();
+    
     if let Some(block_time) = matches.get_one::<String>("block-time") {
         config.block_time_seconds = block_time.parse()
             .map_err(|_| QoraNetError::InvalidTransaction("Invalid block-time value".to_string()))?;
+        // Block time is a chain parameter, so it is part of the genesis hash
+        config.genesis.block_time_secs = config.block_time_seconds;
     }
     
     // Create and start validator
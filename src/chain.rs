@@ -0,0 +1,422 @@
+//! In-memory QoraNet facade
+//!
+//! Ties the QRC-20 registry, the Ethereum bridge and the EVM to QOR account
+//! balances so token, bridge and gas accounting can be driven from one place
+//! (used by the token RPC handlers, the examples and the token CLI).
+
+use primitive_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::qrc20::bridge::{BridgeRequest, ERC20Bridge};
+use crate::qrc20::evm_pool::DEFAULT_MAX_CONCURRENT_EXECUTIONS;
+use crate::qrc20::{EVMExecutionPool, EVMTransaction, QoraNetEVM, QRC20Event, QRC20Registry, QRC20Transaction};
+
+/// Default gas price in QOR base units (9 decimals)
+pub const DEFAULT_GAS_PRICE: u64 = 1_000;
+
+/// Gas charged for a QRC-20 operation
+pub const QRC20_TX_GAS: u64 = 30_000;
+
+/// Gas charged for a bridge request
+pub const BRIDGE_TX_GAS: u64 = 150_000;
+
+/// Result of an executed transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionReceipt {
+    pub tx_hash: H256,
+    pub gas_used: u64,
+    pub fee_paid: U256,
+    /// Token contract created or touched by the transaction
+    pub contract_address: Option<H160>,
+}
+
+/// Token holding as shown in an account overview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenBalanceInfo {
+    pub symbol: String,
+    pub balance: U256,
+    pub decimals: u8,
+}
+
+/// QOR and token balances of an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountInfo {
+    pub address: H160,
+    pub qor_balance: U256,
+    pub token_balances: HashMap<H160, TokenBalanceInfo>,
+}
+
+/// QoraNet chain facade
+pub struct QoraNet {
+    pub qrc20_registry: QRC20Registry,
+    pub bridge: ERC20Bridge,
+    evm_pool: EVMExecutionPool,
+    /// Gas price in QOR base units
+    pub gas_price: U256,
+    /// Transactions executed through the facade
+    pub current_block: u64,
+    /// Latest committed chain height, as last reported by `set_chain_heights`
+    chain_height: u64,
+    qor_balances: HashMap<H160, U256>,
+    next_account: u64,
+}
+
+impl QoraNet {
+    pub fn new() -> Self {
+        Self {
+            qrc20_registry: QRC20Registry::new(),
+            bridge: ERC20Bridge::new(),
+            evm_pool: EVMExecutionPool::new(QoraNetEVM::new(), DEFAULT_MAX_CONCURRENT_EXECUTIONS),
+            gas_price: U256::from(DEFAULT_GAS_PRICE),
+            current_block: 0,
+            chain_height: 0,
+            qor_balances: HashMap::new(),
+            next_account: 1,
+        }
+    }
+
+    /// Set how many EVM executions may run at once (resets EVM state)
+    pub fn with_max_concurrent_evm(mut self, max_concurrent: usize) -> Self {
+        self.evm_pool = EVMExecutionPool::new(QoraNetEVM::new(), max_concurrent);
+        self
+    }
+
+    /// Shared handle to the bounded EVM execution pool
+    pub fn evm_pool(&self) -> EVMExecutionPool {
+        self.evm_pool.clone()
+    }
+
+    /// Create a fresh account funded with `initial_qor` base units
+    pub fn create_account(&mut self, initial_qor: U256) -> H160 {
+        let address = H160::from_low_u64_be(self.next_account);
+        self.next_account += 1;
+        self.qor_balances.insert(address, initial_qor);
+        address
+    }
+
+    /// Credit `amount` QOR base units to an address, e.g. one controlled by an EVM key
+    pub fn fund_account(&mut self, account: H160, amount: U256) {
+        let balance = self.qor_balances.entry(account).or_default();
+        *balance = balance.saturating_add(amount);
+    }
+
+    /// Execute a QRC-20 transaction, charging gas in QOR
+    pub fn process_qrc20_transaction(
+        &mut self,
+        caller: H160,
+        tx: QRC20Transaction,
+        gas_limit: u64,
+    ) -> Result<QRC20Event, String> {
+        let fee = self.check_gas(caller, QRC20_TX_GAS, gas_limit)?;
+
+        let event = self.qrc20_registry.execute_transaction(caller, tx)
+            .map_err(|e| e.to_string())?;
+
+        self.charge(caller, fee);
+        self.advance_block();
+        Ok(event)
+    }
+
+    /// Execute a bridge request, charging gas in QOR
+    pub fn process_bridge_transaction(
+        &mut self,
+        caller: H160,
+        request: BridgeRequest,
+        gas_limit: u64,
+    ) -> Result<ExecutionReceipt, String> {
+        let fee = self.check_gas(caller, BRIDGE_TX_GAS, gas_limit)?;
+
+        // The request lands in the next chain block; burns are recorded at that height
+        self.bridge.set_qora_heights(self.chain_height + 1, None);
+        let record = self.bridge.process_request(&mut self.qrc20_registry, caller, request)
+            .map_err(|e| e.to_string())?;
+
+        self.charge(caller, fee);
+        self.advance_block();
+
+        Ok(ExecutionReceipt {
+            tx_hash: record.id,
+            gas_used: BRIDGE_TX_GAS,
+            fee_paid: fee,
+            contract_address: Some(record.qora_token),
+        })
+    }
+
+    /// Execute a signed EVM transaction through the bounded pool, charging gas in QOR.
+    ///
+    /// The sender is recovered from the signature, which must be for this chain's id.
+    /// The signed nonce must be the sender's next one, and the signed gas limit
+    /// and price are the ones used.
+    pub fn process_evm_transaction(
+        &mut self,
+        caller: H160,
+        tx: EVMTransaction,
+    ) -> Result<ExecutionReceipt, String> {
+        let sender = self.evm_pool.evm().read()
+            .map_err(|_| "EVM state lock poisoned".to_string())?
+            .verify_transaction(&tx)?;
+        if sender != caller {
+            return Err("Transaction sender does not match caller".to_string());
+        }
+        if tx.gas_price < self.gas_price {
+            return Err(format!("Gas price {} below the minimum {}", tx.gas_price, self.gas_price));
+        }
+        if tx.gas_limit > U256::from(u64::MAX) {
+            return Err(format!("Gas limit {} out of range", tx.gas_limit));
+        }
+        let gas_limit = tx.gas_limit.as_u64();
+        // Reserve the full limit up front; only gas actually used is charged
+        self.check_gas_at(caller, gas_limit, gas_limit, tx.gas_price)?;
+
+        let tx_hash = tx.hash();
+        let result = self.evm_pool.execute_at_nonce(caller, tx.nonce, tx.to, tx.data, tx.value, gas_limit)?;
+        if !result.succeeded() {
+            return Err(format!("EVM execution failed: {:?}", result.exit_reason));
+        }
+
+        let fee = tx.gas_price * U256::from(result.gas_used);
+        self.charge(caller, fee);
+        self.advance_block();
+
+        Ok(ExecutionReceipt {
+            tx_hash,
+            gas_used: result.gas_used,
+            fee_paid: fee,
+            contract_address: result.contract_address.or(tx.to),
+        })
+    }
+
+    /// QOR balance (`None`) or balance of a QRC-20 token
+    pub fn get_token_balance(&self, account: H160, contract: Option<H160>) -> U256 {
+        match contract {
+            None => self.qor_balance(account),
+            Some(contract) => self.qrc20_registry.get_token(contract)
+                .map(|token| token.balance_of(account))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// QOR balance of an account
+    pub fn qor_balance(&self, account: H160) -> U256 {
+        self.qor_balances.get(&account).copied().unwrap_or_default()
+    }
+
+    /// QOR balance plus every non-zero token holding
+    pub fn get_account_info(&self, account: H160) -> AccountInfo {
+        let token_balances = self.qrc20_registry.list_tokens()
+            .into_iter()
+            .filter_map(|token| {
+                let balance = token.balance_of(account);
+                if balance.is_zero() {
+                    return None;
+                }
+                Some((token.contract_address, TokenBalanceInfo {
+                    symbol: token.symbol.clone(),
+                    balance,
+                    decimals: token.decimals,
+                }))
+            })
+            .collect();
+
+        AccountInfo {
+            address: account,
+            qor_balance: self.qor_balance(account),
+            token_balances,
+        }
+    }
+
+    /// Gas price in QOR base units per gas
+    pub fn get_gas_price_in_qor(&self) -> U256 {
+        self.gas_price
+    }
+
+    /// Check the gas limit covers `gas_used` and the caller can pay; returns the fee
+    fn check_gas(&self, caller: H160, gas_used: u64, gas_limit: u64) -> Result<U256, String> {
+        self.check_gas_at(caller, gas_used, gas_limit, self.gas_price)
+    }
+
+    /// As `check_gas`, at a caller-chosen gas price
+    fn check_gas_at(&self, caller: H160, gas_used: u64, gas_limit: u64, gas_price: U256) -> Result<U256, String> {
+        if gas_limit < gas_used {
+            return Err(format!("Out of gas: required {}, limit {}", gas_used, gas_limit));
+        }
+
+        let fee = gas_price.saturating_mul(U256::from(gas_used));
+        let balance = self.qor_balance(caller);
+        if balance < fee {
+            return Err(format!("Insufficient QOR for gas: required {}, available {}", fee, balance));
+        }
+        Ok(fee)
+    }
+
+    /// Record a committed block and the chain's finalized height.
+    ///
+    /// This is the bridge's only source of QoraNet finality: burns are released
+    /// once the chain, not the facade, has finalized the block they landed in.
+    pub fn set_chain_heights(&mut self, height: u64, finalized_height: Option<u64>) {
+        self.chain_height = self.chain_height.max(height);
+        self.bridge.set_qora_heights(height, finalized_height);
+    }
+
+    fn advance_block(&mut self) {
+        self.current_block += 1;
+    }
+
+    fn charge(&mut self, caller: H160, fee: U256) {
+        let balance = self.qor_balances.entry(caller).or_default();
+        *balance = balance.saturating_sub(fee);
+    }
+}
+
+impl Default for QoraNet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrc20::bridge::DepositAttestation;
+    use crate::qrc20::evm_integration::evm_address;
+    use k256::ecdsa::SigningKey;
+
+    fn qor(amount: u64) -> U256 {
+        U256::from(amount) * U256::from(1_000_000_000u64)
+    }
+
+    fn bridge_operator() -> SigningKey {
+        SigningKey::from_slice(&[0x46u8; 32]).unwrap()
+    }
+
+    /// Facade whose bridge accepts attestations from `bridge_operator`
+    fn attested_qoranet() -> QoraNet {
+        let mut qoranet = QoraNet::new();
+        qoranet.bridge.bridge_operators.push(evm_address(bridge_operator().verifying_key()));
+        qoranet
+    }
+
+    fn attested_deposit(eth_token: H160, user: H160, amount: u64) -> DepositAttestation {
+        DepositAttestation::new(H256::random(), eth_token, user, U256::from(amount), 12)
+            .sign(&bridge_operator())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bridge_requests_through_facade() {
+        let mut qoranet = attested_qoranet();
+        let user = qoranet.create_account(qor(10));
+        let eth_token = H160::from_low_u64_be(0xa0b86a33e6ba);
+        let deposit = || BridgeRequest::FromEthereum {
+            attestation: attested_deposit(eth_token, user, 5_000_000),
+            token_name: "USD Coin".to_string(),
+            token_symbol: "USDC".to_string(),
+            decimals: 6,
+        };
+
+        let receipt = qoranet.process_bridge_transaction(user, deposit(), BRIDGE_TX_GAS).unwrap();
+
+        let bridged = receipt.contract_address.unwrap();
+        let minted = qoranet.get_token_balance(user, Some(bridged));
+        assert!(!minted.is_zero());
+        assert_eq!(qoranet.get_token_balance(user, None), qor(10) - receipt.fee_paid);
+        qoranet.set_chain_heights(1, None);
+
+        let receipt = qoranet.process_bridge_transaction(user, BridgeRequest::ToEthereum {
+            qora_token: bridged,
+            amount: U256::from(1_000_000),
+        }, BRIDGE_TX_GAS).unwrap();
+
+        assert_eq!(receipt.contract_address, Some(bridged));
+        assert_eq!(qoranet.get_token_balance(user, Some(bridged)), minted - U256::from(1_000_000));
+        assert!(matches!(
+            qoranet.bridge.get_transaction(receipt.tx_hash).unwrap().direction,
+            crate::qrc20::bridge::BridgeDirection::QoraNetToEthereum
+        ));
+
+        // The burn landed in block 2 and is final only once the chain finalizes block 2
+        let burn = qoranet.bridge.get_transaction(receipt.tx_hash).unwrap();
+        assert_eq!(burn.qora_block_height, Some(2));
+        assert!(!burn.qora_finalized);
+        qoranet.process_bridge_transaction(user, deposit(), BRIDGE_TX_GAS).unwrap();
+        qoranet.set_chain_heights(3, Some(1));
+        assert!(!qoranet.bridge.get_transaction(receipt.tx_hash).unwrap().qora_finalized);
+        qoranet.set_chain_heights(4, Some(2));
+        assert!(qoranet.bridge.get_transaction(receipt.tx_hash).unwrap().qora_finalized);
+    }
+
+    #[test]
+    fn test_gas_limit_and_balance_enforced() {
+        let mut qoranet = attested_qoranet();
+        let poor = qoranet.create_account(U256::zero());
+        let rich = qoranet.create_account(qor(1));
+        let eth_token = H160::from_low_u64_be(42);
+        let request = BridgeRequest::FromEthereum {
+            attestation: attested_deposit(eth_token, rich, 1_000),
+            token_name: "Dai".to_string(),
+            token_symbol: "DAI".to_string(),
+            decimals: 18,
+        };
+
+        assert!(qoranet.process_bridge_transaction(poor, request.clone(), BRIDGE_TX_GAS).is_err());
+        assert!(qoranet.process_bridge_transaction(rich, request, BRIDGE_TX_GAS - 1).is_err());
+        assert_eq!(qoranet.get_token_balance(rich, None), qor(1));
+        assert_eq!(qoranet.qrc20_registry.token_count(), 0);
+    }
+
+    #[test]
+    fn test_unattested_deposit_mints_nothing() {
+        let mut qoranet = attested_qoranet();
+        let user = qoranet.create_account(qor(1));
+        let eth_token = H160::from_low_u64_be(42);
+        let self_signed = DepositAttestation::new(H256::random(), eth_token, user, U256::from(1_000), 1_000)
+            .sign(&SigningKey::from_slice(&[0x47u8; 32]).unwrap())
+            .unwrap();
+
+        let result = qoranet.process_bridge_transaction(user, BridgeRequest::FromEthereum {
+            attestation: self_signed,
+            token_name: "Dai".to_string(),
+            token_symbol: "DAI".to_string(),
+            decimals: 18,
+        }, BRIDGE_TX_GAS);
+
+        assert!(result.is_err());
+        assert_eq!(qoranet.qrc20_registry.token_count(), 0);
+        assert_eq!(qoranet.get_token_balance(user, None), qor(1));
+    }
+
+    #[test]
+    fn test_signed_evm_transaction_runs_once_at_its_gas_price() {
+        let mut qoranet = QoraNet::new();
+        let key = SigningKey::from_slice(&[0x48u8; 32]).unwrap();
+        let sender = evm_address(key.verifying_key());
+        qoranet.fund_account(sender, qor(1));
+        let chain_id = qoranet.evm_pool().evm().read().unwrap().chain_id().as_u64();
+        let call = |nonce: u64, gas_price: U256| EVMTransaction {
+            from: sender,
+            to: Some(H160::from_low_u64_be(0xbeef)),
+            value: U256::zero(),
+            gas_limit: U256::from(50_000),
+            gas_price,
+            data: Vec::new(),
+            nonce: U256::from(nonce),
+            transaction_type: crate::qrc20::evm_integration::EVMTransactionType::Legacy,
+            signature: None,
+        }.sign(&key, chain_id).unwrap();
+
+        let price = qoranet.gas_price * 2;
+        let tx = call(0, price);
+        let receipt = qoranet.process_evm_transaction(sender, tx.clone()).unwrap();
+        assert_eq!(receipt.fee_paid, price * U256::from(receipt.gas_used));
+
+        // The same signed transaction can't be replayed
+        let balance = qoranet.qor_balance(sender);
+        assert!(qoranet.process_evm_transaction(sender, tx).is_err());
+        assert!(qoranet.process_evm_transaction(sender, call(5, price)).is_err());
+        assert!(qoranet.process_evm_transaction(sender, call(1, qoranet.gas_price - 1)).is_err());
+        assert_eq!(qoranet.qor_balance(sender), balance);
+
+        assert!(qoranet.process_evm_transaction(sender, call(1, qoranet.gas_price)).is_ok());
+    }
+}
@@ -0,0 +1,119 @@
+//! Incremental tracking of active hosted applications
+//!
+//! Each known app gets a slot in a bitset; the active count is updated on
+//! registration, reports and staleness pruning so reads are O(1).
+
+use crate::{Address, Timestamp};
+use std::collections::HashMap;
+
+/// Apps silent for longer than this are considered inactive (seconds)
+pub const DEFAULT_APP_STALENESS_SECS: u64 = 900;
+
+#[derive(Debug, Clone)]
+struct AppActivity {
+    slot: usize,
+    last_seen: Timestamp,
+}
+
+/// Bitset-backed set of active (validator, app_id) pairs
+#[derive(Debug, Clone)]
+pub struct ActiveAppTracker {
+    apps: HashMap<(Address, String), AppActivity>,
+    active_bits: Vec<u64>,
+    active_count: usize,
+    staleness_secs: u64,
+}
+
+impl ActiveAppTracker {
+    pub fn new(staleness_secs: u64) -> Self {
+        Self {
+            apps: HashMap::new(),
+            active_bits: Vec::new(),
+            active_count: 0,
+            staleness_secs,
+        }
+    }
+
+    /// Set how long an app may go without reporting before it is pruned
+    pub fn set_staleness(&mut self, secs: u64) {
+        self.staleness_secs = secs;
+    }
+
+    /// Mark an app as active at `timestamp`, registering it if unknown
+    pub fn touch(&mut self, validator: &Address, app_id: &str, timestamp: Timestamp) {
+        let key = (validator.clone(), app_id.to_string());
+        let slot = match self.apps.get_mut(&key) {
+            Some(activity) => {
+                activity.last_seen = activity.last_seen.max(timestamp);
+                activity.slot
+            }
+            None => {
+                let slot = self.apps.len();
+                self.apps.insert(key, AppActivity { slot, last_seen: timestamp });
+                if slot / 64 >= self.active_bits.len() {
+                    self.active_bits.push(0);
+                }
+                slot
+            }
+        };
+
+        self.set_active(slot, true);
+    }
+
+    /// Mark apps not seen within the staleness window as inactive.
+    ///
+    /// Returns the number of apps that became inactive.
+    pub fn prune_stale(&mut self, now: Timestamp) -> usize {
+        let cutoff = now.saturating_sub(self.staleness_secs);
+        let stale: Vec<usize> = self.apps.values()
+            .filter(|activity| activity.last_seen < cutoff)
+            .map(|activity| activity.slot)
+            .collect();
+
+        stale.into_iter()
+            .filter(|slot| self.set_active(*slot, false))
+            .count()
+    }
+
+    /// Check if an app is currently active
+    pub fn is_active(&self, validator: &Address, app_id: &str) -> bool {
+        self.apps.get(&(validator.clone(), app_id.to_string()))
+            .map(|activity| self.bit(activity.slot))
+            .unwrap_or(false)
+    }
+
+    /// Number of active apps (O(1))
+    pub fn active_count(&self) -> usize {
+        self.active_count
+    }
+
+    /// Number of apps ever registered
+    pub fn known_count(&self) -> usize {
+        self.apps.len()
+    }
+
+    fn bit(&self, slot: usize) -> bool {
+        self.active_bits[slot / 64] & (1u64 << (slot % 64)) != 0
+    }
+
+    /// Set a slot's bit, returning true if it changed
+    fn set_active(&mut self, slot: usize, active: bool) -> bool {
+        if self.bit(slot) == active {
+            return false;
+        }
+
+        self.active_bits[slot / 64] ^= 1u64 << (slot % 64);
+        if active {
+            self.active_count += 1;
+        } else {
+            self.active_count -= 1;
+        }
+        true
+    }
+}
+
+impl Default for ActiveAppTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_APP_STALENESS_SECS)
+    }
+}
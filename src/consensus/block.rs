@@ -1,9 +1,29 @@
-use crate::{Hash, Address, BlockHeight, Timestamp, transaction::Transaction, Result, QoraNetError};
-use serde::{Deserialize, Serialize};
+use crate::{Hash, Address, BlockHeight, Timestamp, transaction::{Transaction, TransactionData}, Result, QoraNetError, QoraSignature};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use chrono::Utc;
+use ed25519_dalek::{Keypair, Signer};
+use std::fmt;
 
-/// Block header containing metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::ConsensusState;
+
+/// Prefix for hashing a transaction into a merkle leaf
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+
+/// Prefix for hashing two child hashes into a merkle node
+const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+/// Domain separator so a producer's block signature can't be replayed as another message
+const BLOCK_SIGNING_DOMAIN: &[u8] = b"QORANET_BLOCK_V1";
+
+/// First header version committing to a state root
+pub const STATE_ROOT_HEADER_VERSION: u32 = 2;
+
+/// Block header containing metadata.
+///
+/// Headers before `STATE_ROOT_HEADER_VERSION` have no state root on the wire,
+/// so stored blocks keep their layout and their hashes.
+#[derive(Debug, Clone)]
 pub struct BlockHeader {
     /// Previous block hash
     pub previous_hash: Hash,
@@ -34,6 +54,79 @@ pub struct BlockHeader {
     
     /// Nonce for additional entropy
     pub nonce: u64,
+    
+    /// Root of the account state the block was built on, i.e. after its parent;
+    /// zero for genesis and for headers before `STATE_ROOT_HEADER_VERSION`
+    pub state_root: Hash,
+}
+
+/// Header fields every header version has, in wire order
+#[derive(Serialize, Deserialize)]
+struct HeaderFields {
+    previous_hash: Hash,
+    transactions_root: Hash,
+    height: BlockHeight,
+    timestamp: Timestamp,
+    validator: Address,
+    total_liquidity: u64,
+    active_apps: u32,
+    total_fees: u64,
+    version: u32,
+    nonce: u64,
+}
+
+/// Header in self-describing formats such as JSON, where the root is a named field
+#[derive(Serialize, Deserialize)]
+struct ReadableHeader {
+    #[serde(flatten)]
+    fields: HeaderFields,
+    #[serde(default = "Hash::zero")]
+    state_root: Hash,
+}
+
+impl Serialize for BlockHeader {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return ReadableHeader { fields: self.fields(), state_root: self.state_root.clone() }.serialize(serializer);
+        }
+        if self.commits_state_root() {
+            (self.fields(), &self.state_root).serialize(serializer)
+        } else {
+            self.fields().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockHeader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let readable = ReadableHeader::deserialize(deserializer)?;
+            return Ok(Self::from_fields(readable.fields, readable.state_root));
+        }
+        deserializer.deserialize_tuple(2, BinaryHeaderVisitor)
+    }
+}
+
+/// Reads the state root only when the header's version puts one on the wire
+struct BinaryHeaderVisitor;
+
+impl<'de> Visitor<'de> for BinaryHeaderVisitor {
+    type Value = BlockHeader;
+    
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a block header")
+    }
+    
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<BlockHeader, A::Error> {
+        let fields: HeaderFields = seq.next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let state_root = if fields.version >= STATE_ROOT_HEADER_VERSION {
+            seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?
+        } else {
+            Hash::zero()
+        };
+        Ok(BlockHeader::from_fields(fields, state_root))
+    }
 }
 
 impl BlockHeader {
@@ -55,8 +148,45 @@ impl BlockHeader {
             total_liquidity,
             active_apps,
             total_fees,
-            version: 1,
+            version: STATE_ROOT_HEADER_VERSION,
             nonce: 0,
+            state_root: Hash::zero(),
+        }
+    }
+    
+    /// Whether the header commits to `state_root`
+    pub fn commits_state_root(&self) -> bool {
+        self.version >= STATE_ROOT_HEADER_VERSION
+    }
+    
+    fn fields(&self) -> HeaderFields {
+        HeaderFields {
+            previous_hash: self.previous_hash.clone(),
+            transactions_root: self.transactions_root.clone(),
+            height: self.height,
+            timestamp: self.timestamp,
+            validator: self.validator.clone(),
+            total_liquidity: self.total_liquidity,
+            active_apps: self.active_apps,
+            total_fees: self.total_fees,
+            version: self.version,
+            nonce: self.nonce,
+        }
+    }
+    
+    fn from_fields(fields: HeaderFields, state_root: Hash) -> Self {
+        Self {
+            previous_hash: fields.previous_hash,
+            transactions_root: fields.transactions_root,
+            height: fields.height,
+            timestamp: fields.timestamp,
+            validator: fields.validator,
+            total_liquidity: fields.total_liquidity,
+            active_apps: fields.active_apps,
+            total_fees: fields.total_fees,
+            version: fields.version,
+            nonce: fields.nonce,
+            state_root,
         }
     }
     
@@ -66,6 +196,23 @@ impl BlockHeader {
         Hash::new(&serialized)
     }
     
+    /// Bytes the producer signs: the domain separator and the header hash
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut message = BLOCK_SIGNING_DOMAIN.to_vec();
+        message.extend_from_slice(self.hash().as_bytes());
+        message
+    }
+    
+    /// Check `signature` is the producer's signature over this header
+    pub fn verify_signature(&self, signature: &QoraSignature) -> Result<()> {
+        use ed25519_dalek::PublicKey;
+        
+        let pubkey = PublicKey::from_bytes(&self.validator.0)
+            .map_err(|e| QoraNetError::ConsensusError(format!("Invalid producer key: {}", e)))?;
+        pubkey.verify_strict(&self.signing_message(), signature)
+            .map_err(|e| QoraNetError::ConsensusError(format!("Invalid block signature: {}", e)))
+    }
+    
     /// Validate block header
     pub fn validate(&self, expected_height: BlockHeight, expected_previous: &Hash) -> Result<()> {
         if self.height != expected_height {
@@ -92,11 +239,69 @@ impl BlockHeader {
     }
 }
 
+/// A block header with its producer's signature, enough to prove what the producer signed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedHeader {
+    pub header: BlockHeader,
+    pub signature: QoraSignature,
+}
+
+impl SignedHeader {
+    pub fn verify(&self) -> Result<()> {
+        self.header.verify_signature(&self.signature)
+    }
+}
+
+/// Two different headers one producer signed for the same height
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquivocationProof {
+    pub first: SignedHeader,
+    pub second: SignedHeader,
+}
+
+impl EquivocationProof {
+    /// Validator the proof accuses
+    pub fn offender(&self) -> &Address {
+        &self.first.header.validator
+    }
+    
+    /// Height both headers were signed for
+    pub fn height(&self) -> BlockHeight {
+        self.first.header.height
+    }
+    
+    /// Check both headers come from the same producer at the same height,
+    /// differ, and carry valid producer signatures
+    pub fn verify(&self) -> Result<()> {
+        let (first, second) = (&self.first.header, &self.second.header);
+        if first.validator != second.validator {
+            return Err(QoraNetError::ConsensusError(
+                "Equivocation proof headers have different producers".to_string()
+            ));
+        }
+        if first.height != second.height {
+            return Err(QoraNetError::ConsensusError(
+                "Equivocation proof headers are at different heights".to_string()
+            ));
+        }
+        if first.hash() == second.hash() {
+            return Err(QoraNetError::ConsensusError(
+                "Equivocation proof headers are the same header".to_string()
+            ));
+        }
+        self.first.verify()?;
+        self.second.verify()
+    }
+}
+
 /// Complete block with header and transactions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub header: BlockHeader,
     pub transactions: Vec<Transaction>,
+    /// Producer's signature over the header hash; `None` until signed (and for genesis).
+    /// Not part of the block hash.
+    pub signature: Option<QoraSignature>,
 }
 
 impl Block {
@@ -127,16 +332,68 @@ impl Block {
         Self {
             header,
             transactions,
+            signature: None,
         }
     }
     
-    /// Calculate merkle root of transactions
-    fn calculate_transactions_root(transactions: &[Transaction]) -> Hash {
+    /// Commit to the transactions root scheme in force at the block's height; set before signing
+    pub fn with_domain_separated_root_height(mut self, domain_separated_root_height: BlockHeight) -> Self {
+        self.header.transactions_root = Self::transactions_root_at(
+            &self.transactions,
+            self.header.height,
+            domain_separated_root_height,
+        );
+        self
+    }
+    
+    /// Commit the block to the account state it was built on; set before signing
+    pub fn with_state_root(mut self, state_root: Hash) -> Self {
+        self.header.state_root = state_root;
+        self
+    }
+    
+    /// Bytes the producer signs: the domain separator and the header hash
+    pub fn signing_message(&self) -> Vec<u8> {
+        self.header.signing_message()
+    }
+    
+    /// Sign the block as its producer
+    pub fn sign(&mut self, keypair: &Keypair) {
+        self.signature = Some(keypair.sign(&self.signing_message()));
+    }
+    
+    /// Check the block carries a valid signature from `header.validator`
+    pub fn verify_producer_signature(&self) -> Result<()> {
+        let signature = self.signature.as_ref().ok_or_else(|| QoraNetError::ConsensusError(
+            format!("Block #{} is not signed by its producer", self.header.height)
+        ))?;
+        self.header.verify_signature(signature)
+    }
+    
+    /// Header and producer signature, if the block is signed
+    pub fn signed_header(&self) -> Option<SignedHeader> {
+        self.signature.map(|signature| SignedHeader {
+            header: self.header.clone(),
+            signature,
+        })
+    }
+    
+    /// Calculate merkle root of transactions.
+    ///
+    /// Leaves and internal nodes are hashed under different prefixes, and an odd
+    /// node is carried up unchanged rather than paired with itself, so no other
+    /// transaction list (including one with the last entry repeated) yields the
+    /// same root. An empty list, as in the genesis block, has the zero root.
+    pub(crate) fn calculate_transactions_root(transactions: &[Transaction]) -> Hash {
         if transactions.is_empty() {
             return Hash::zero();
         }
         
-        let mut hashes: Vec<Hash> = transactions.iter().map(|tx| tx.hash()).collect();
+        let mut hashes: Vec<Hash> = transactions.iter().map(|tx| {
+            let mut leaf_data = vec![MERKLE_LEAF_PREFIX];
+            leaf_data.extend_from_slice(tx.hash().as_bytes());
+            Hash::new(&leaf_data)
+        }).collect();
         
         // Build merkle tree
         while hashes.len() > 1 {
@@ -144,16 +401,13 @@ impl Block {
             
             for chunk in hashes.chunks(2) {
                 let combined = if chunk.len() == 2 {
-                    let mut combined_data = Vec::new();
+                    let mut combined_data = vec![MERKLE_NODE_PREFIX];
                     combined_data.extend_from_slice(chunk[0].as_bytes());
                     combined_data.extend_from_slice(chunk[1].as_bytes());
                     Hash::new(&combined_data)
                 } else {
-                    // Odd number, hash with itself
-                    let mut combined_data = Vec::new();
-                    combined_data.extend_from_slice(chunk[0].as_bytes());
-                    combined_data.extend_from_slice(chunk[0].as_bytes());
-                    Hash::new(&combined_data)
+                    // Odd number, promote to the next level as is
+                    chunk[0].clone()
                 };
                 next_level.push(combined);
             }
@@ -164,6 +418,40 @@ impl Block {
         hashes[0].clone()
     }
     
+    /// Merkle root as computed before the prefixed scheme, pairing an odd node with itself
+    pub(crate) fn legacy_transactions_root(transactions: &[Transaction]) -> Hash {
+        if transactions.is_empty() {
+            return Hash::zero();
+        }
+        
+        let mut hashes: Vec<Hash> = transactions.iter().map(|tx| tx.hash()).collect();
+        while hashes.len() > 1 {
+            hashes = hashes.chunks(2).map(|chunk| {
+                let right = chunk.get(1).unwrap_or(&chunk[0]);
+                let mut combined_data = chunk[0].as_bytes().to_vec();
+                combined_data.extend_from_slice(right.as_bytes());
+                Hash::new(&combined_data)
+            }).collect();
+        }
+        hashes[0].clone()
+    }
+    
+    /// Merkle root a block at `height` commits to.
+    ///
+    /// Blocks below `domain_separated_root_height` predate the prefixed scheme
+    /// and keep the root they were produced with.
+    pub fn transactions_root_at(
+        transactions: &[Transaction],
+        height: BlockHeight,
+        domain_separated_root_height: BlockHeight,
+    ) -> Hash {
+        if height < domain_separated_root_height {
+            Self::legacy_transactions_root(transactions)
+        } else {
+            Self::calculate_transactions_root(transactions)
+        }
+    }
+    
     /// Get block hash
     pub fn hash(&self) -> Hash {
         self.header.hash()
@@ -174,13 +462,27 @@ impl Block {
         bincode::serialize(self).unwrap().len()
     }
     
-    /// Validate entire block
+    /// Validate entire block, for chains whose every block has a prefixed transactions root
     pub fn validate(&self, expected_height: BlockHeight, expected_previous: &Hash) -> Result<()> {
+        self.validate_with_root_height(expected_height, expected_previous, 0)
+    }
+    
+    /// Validate entire block, accepting the legacy transactions root below `domain_separated_root_height`
+    pub fn validate_with_root_height(
+        &self,
+        expected_height: BlockHeight,
+        expected_previous: &Hash,
+        domain_separated_root_height: BlockHeight,
+    ) -> Result<()> {
         // Validate header
         self.header.validate(expected_height, expected_previous)?;
         
         // Validate transactions root
-        let calculated_root = Self::calculate_transactions_root(&self.transactions);
+        let calculated_root = Self::transactions_root_at(
+            &self.transactions,
+            self.header.height,
+            domain_separated_root_height,
+        );
         if calculated_root != self.header.transactions_root {
             return Err(QoraNetError::ConsensusError(
                 "Invalid transactions root".to_string()
@@ -198,11 +500,43 @@ impl Block {
         // Validate individual transactions
         for tx in &self.transactions {
             tx.verify_signature()?;
+            // The signature only proves who signed; they must also be the account acting
+            if Self::authorizing_address(tx) != &tx.signer {
+                return Err(QoraNetError::ConsensusError(format!(
+                    "Transaction {} is not signed by its sender",
+                    tx.hash()
+                )));
+            }
+            tx.check_not_expired(expected_height)?;
         }
         
         Ok(())
     }
     
+    /// Validate the block and that its producer is the validator `consensus` selected
+    pub fn validate_against_state(
+        &self,
+        expected_height: BlockHeight,
+        expected_previous: &Hash,
+        consensus: &ConsensusState,
+    ) -> Result<()> {
+        self.validate_with_root_height(expected_height, expected_previous, consensus.domain_separated_root_height())?;
+        consensus.validate_block_producer(self)
+    }
+    
+    /// Account whose funds or identity a transaction acts on
+    fn authorizing_address(tx: &Transaction) -> &Address {
+        match &tx.data {
+            TransactionData::Transfer { from, .. } => from,
+            TransactionData::ProvideLiquidity { provider, .. } => provider,
+            TransactionData::RegisterApp { owner, .. } => owner,
+            TransactionData::ReportMetrics { validator, .. } => validator,
+            TransactionData::ClaimRewards { claimant, .. } => claimant,
+            TransactionData::ReportEquivocation { reporter, .. } => reporter,
+            TransactionData::SetSendLimit { authority, .. } => authority,
+        }
+    }
+    
     /// Get transaction by hash
     pub fn get_transaction(&self, tx_hash: &Hash) -> Option<&Transaction> {
         self.transactions.iter().find(|tx| &tx.hash() == tx_hash)
@@ -212,6 +546,14 @@ impl Block {
     pub fn transaction_hashes(&self) -> Vec<Hash> {
         self.transactions.iter().map(|tx| tx.hash()).collect()
     }
+    
+    /// Fees actually paid: `total_fees` less those of the `failed` transactions, which paid none
+    pub fn collected_fees(&self, failed: &[Hash]) -> u64 {
+        self.transactions.iter()
+            .filter(|tx| !failed.contains(&tx.hash()))
+            .map(|tx| tx.fee_qor)
+            .sum()
+    }
 }
 
 /// Genesis block creation
@@ -259,3 +601,144 @@ impl BlockStats {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FeePriority;
+    use rand::rngs::OsRng;
+
+    fn signed_transfer(keypair: &Keypair, from: Address, nonce: u64) -> Transaction {
+        let mut tx = Transaction {
+            data: TransactionData::Transfer {
+                from,
+                to: Address([7u8; 32]),
+                amount: 1,
+                memo: None,
+            },
+            nonce,
+            fee_qor: 1_000,
+            fee_usd: 0.0001,
+            priority: FeePriority::Low,
+            signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+            signer: Address::from_pubkey(&keypair.public),
+            valid_until_height: None,
+        };
+        tx.signature = keypair.sign(&tx.signing_message());
+        tx
+    }
+
+    fn transfers(count: usize) -> Vec<Transaction> {
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        (0..count as u64).map(|nonce| signed_transfer(&keypair, signer.clone(), nonce)).collect()
+    }
+
+    #[test]
+    fn test_genesis_has_zero_root_and_validates() {
+        let genesis = Block::genesis(Address([1u8; 32]));
+        assert_eq!(genesis.header.transactions_root, Hash::zero());
+        assert!(genesis.validate(0, &Hash::zero()).is_ok());
+    }
+
+    #[test]
+    fn test_legacy_root_accepted_only_below_activation() {
+        let txs = transfers(3);
+        let mut block = Block::new(Hash::zero(), 5, Address([1u8; 32]), txs.clone(), 0, 0);
+        block.header.transactions_root = Block::legacy_transactions_root(&txs);
+
+        // Blocks stored before the upgrade keep validating against their original root
+        assert!(block.validate_with_root_height(5, &Hash::zero(), 6).is_ok());
+        let err = block.validate_with_root_height(5, &Hash::zero(), 5).unwrap_err();
+        assert!(err.to_string().contains("transactions root"), "{}", err);
+        assert!(block.validate(5, &Hash::zero()).is_err());
+    }
+
+    #[test]
+    fn test_leaf_hash_is_prefixed() {
+        let txs = transfers(1);
+        assert_ne!(Block::calculate_transactions_root(&txs), txs[0].hash());
+    }
+
+    #[test]
+    fn test_duplicated_tail_rejected_for_odd_and_even_counts() {
+        for count in 1..=6 {
+            let txs = transfers(count);
+            let block = Block::new(Hash::zero(), 1, Address([1u8; 32]), txs.clone(), 0, 0);
+            assert!(block.validate(1, &Hash::zero()).is_ok(), "{} transactions", count);
+
+            // Repeating the last transaction pads the odd level the old way
+            let mut padded = txs.clone();
+            padded.push(txs[count - 1].clone());
+            if count % 2 == 1 && count > 1 {
+                assert_eq!(Block::legacy_transactions_root(&padded), Block::legacy_transactions_root(&txs), "{} transactions", count);
+            }
+            assert_ne!(Block::calculate_transactions_root(&padded), block.header.transactions_root);
+
+            let mut swapped = block.clone();
+            swapped.transactions = padded;
+            let err = swapped.validate(1, &Hash::zero()).unwrap_err();
+            assert!(err.to_string().contains("transactions root"), "{} transactions: {}", count, err);
+        }
+    }
+
+    #[test]
+    fn test_reordered_transactions_rejected() {
+        let txs = transfers(4);
+        let mut block = Block::new(Hash::zero(), 1, Address([1u8; 32]), txs, 0, 0);
+        block.transactions.swap(0, 1);
+        assert!(block.validate(1, &Hash::zero()).is_err());
+    }
+
+    #[test]
+    fn test_signer_must_be_sender() {
+        let keypair = Keypair::generate(&mut OsRng);
+        // Validly signed, but spending someone else's balance
+        let tx = signed_transfer(&keypair, Address([9u8; 32]), 0);
+        assert!(tx.verify_signature().is_ok());
+
+        let block = Block::new(Hash::zero(), 1, Address([1u8; 32]), vec![tx], 0, 0);
+        let err = block.validate(1, &Hash::zero()).unwrap_err();
+        assert!(err.to_string().contains("not signed by its sender"), "{}", err);
+    }
+
+    #[test]
+    fn test_producer_signature_covers_header() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let producer = Address::from_pubkey(&keypair.public);
+        let mut block = Block::new(Hash::zero(), 1, producer, Vec::new(), 0, 0);
+        assert!(block.verify_producer_signature().is_err());
+
+        let unsigned_hash = block.hash();
+        block.sign(&keypair);
+        assert_eq!(block.hash(), unsigned_hash);
+        assert!(block.verify_producer_signature().is_ok());
+
+        block.header.total_fees += 1;
+        assert!(block.verify_producer_signature().is_err());
+    }
+
+    #[test]
+    fn test_state_root_committed_only_by_new_headers() {
+        let block = Block::new(Hash::zero(), 1, Address([1u8; 32]), Vec::new(), 0, 0)
+            .with_state_root(Hash::new(b"state"));
+        let decoded: Block = bincode::deserialize(&bincode::serialize(&block).unwrap()).unwrap();
+        assert_eq!(decoded.header.state_root, Hash::new(b"state"));
+        assert_eq!(decoded.hash(), block.hash());
+        assert_ne!(block.clone().with_state_root(Hash::zero()).hash(), block.hash());
+
+        // Older headers keep their layout and hash, with no root on the wire
+        let mut legacy = block.header.clone();
+        legacy.version = 1;
+        let encoded = bincode::serialize(&legacy).unwrap();
+        assert_eq!(encoded, bincode::serialize(&legacy.fields()).unwrap());
+        let decoded: BlockHeader = bincode::deserialize(&encoded).unwrap();
+        assert!(!decoded.commits_state_root());
+        assert_eq!(decoded.state_root, Hash::zero());
+        assert_eq!(decoded.hash(), legacy.hash());
+
+        let json = serde_json::to_string(&block.header).unwrap();
+        let decoded: BlockHeader = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.hash(), block.hash());
+    }
+}
@@ -0,0 +1,244 @@
+//! Genesis chain parameters
+//!
+//! Everything nodes must agree on before the first block is folded into a
+//! deterministic `genesis_hash`. The hash is embedded in the genesis block
+//! (as its previous hash) and exchanged in the peer handshake, so nodes on
+//! different chain configs refuse to connect.
+
+use crate::rewards::{RewardPoolConfig, DEFAULT_MAX_REWARDED_APPS};
+use crate::transaction::TransactionKind;
+use crate::{Address, BlockHeight, Hash, QoraNetError, Result, Timestamp, MAX_FEE_USD, MAX_QOR_SUPPLY, MIN_FEE_USD};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::Block;
+
+/// Domain separator for the genesis hash
+const GENESIS_DOMAIN: &[u8] = b"QORANET_GENESIS_V1";
+
+/// QOR allocated to an account at genesis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisAllocation {
+    pub address: Address,
+    pub amount: u64,
+}
+
+/// Chain parameters fixed at genesis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenesisConfig {
+    pub chain_id: u64,
+    pub block_time_secs: u64,
+    pub min_fee_usd: f64,
+    pub max_fee_usd: f64,
+    /// Blocks after which a block is considered final
+    pub finality_depth: u64,
+    pub genesis_timestamp: Timestamp,
+    /// Cap on native QOR ever minted (genesis allocations plus block rewards)
+    pub max_qor_supply: u64,
+    /// QOR minted to the producer of each block, until the cap is reached
+    pub block_reward: u64,
+    /// Transaction types accepted from genesis
+    pub enabled_tx_types: HashSet<TransactionKind>,
+    /// Types that become enabled once the chain reaches the given height
+    pub tx_type_activation_heights: HashMap<TransactionKind, BlockHeight>,
+    pub allocations: Vec<GenesisAllocation>,
+    /// Custody key allowed to set per-account send limits; `None` disables them
+    #[serde(default)]
+    pub spending_limit_authority: Option<Address>,
+    /// Height from which blocks must carry their producer's signature; blocks
+    /// below it predate producer signatures and are accepted unsigned
+    #[serde(default)]
+    pub signed_blocks_height: BlockHeight,
+    /// Height from which transactions roots hash leaves and nodes under
+    /// distinct prefixes; blocks below it keep their original root. An upgrade
+    /// schedule rather than part of the chain's identity, so it is left out of
+    /// the genesis hash and can be set on a running chain.
+    #[serde(default)]
+    pub domain_separated_root_height: BlockHeight,
+    /// Share of each block routed into the epoch reward pool
+    #[serde(default)]
+    pub reward_pool: RewardPoolConfig,
+    /// Most apps, best smoothed score first, that share an epoch's reward pool
+    #[serde(default = "default_max_rewarded_apps")]
+    pub max_rewarded_apps: usize,
+}
+
+fn default_max_rewarded_apps() -> usize {
+    DEFAULT_MAX_REWARDED_APPS
+}
+
+impl Default for GenesisConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: 2024, // QoraNet chain ID
+            block_time_secs: 10,
+            min_fee_usd: MIN_FEE_USD,
+            max_fee_usd: MAX_FEE_USD,
+            finality_depth: 32,
+            genesis_timestamp: 1_704_067_200, // 2024-01-01T00:00:00Z
+            max_qor_supply: MAX_QOR_SUPPLY,
+            block_reward: 2_000_000_000, // 2 QOR
+            enabled_tx_types: TransactionKind::ALL.into_iter().collect(),
+            tx_type_activation_heights: HashMap::new(),
+            allocations: Vec::new(),
+            spending_limit_authority: None,
+            signed_blocks_height: 0,
+            domain_separated_root_height: 0,
+            reward_pool: RewardPoolConfig::default(),
+            max_rewarded_apps: DEFAULT_MAX_REWARDED_APPS,
+        }
+    }
+}
+
+impl GenesisConfig {
+    /// Hash of the genesis allocations, independent of their listed order
+    pub fn allocations_hash(&self) -> Hash {
+        let mut allocations = self.allocations.clone();
+        allocations.sort_by(|a, b| a.address.0.cmp(&b.address.0).then(a.amount.cmp(&b.amount)));
+
+        let mut data = Vec::with_capacity(allocations.len() * 40);
+        for allocation in &allocations {
+            data.extend_from_slice(allocation.address.as_bytes());
+            data.extend_from_slice(&allocation.amount.to_le_bytes());
+        }
+        Hash::new(&data)
+    }
+
+    /// Whether transactions of `kind` may be included at `height`
+    pub fn tx_type_enabled(&self, kind: TransactionKind, height: BlockHeight) -> bool {
+        if kind == TransactionKind::SetSendLimit {
+            return self.spending_limit_authority.is_some();
+        }
+        self.enabled_tx_types.contains(&kind)
+            || self.tx_type_activation_heights.get(&kind).map_or(false, |activation| height >= *activation)
+    }
+    
+    /// Hash of the transaction type allowlist, independent of set order
+    fn tx_types_hash(&self) -> Hash {
+        let mut enabled: Vec<TransactionKind> = self.enabled_tx_types.iter().copied().collect();
+        enabled.sort();
+        let mut activations: Vec<(TransactionKind, BlockHeight)> = self.tx_type_activation_heights
+            .iter()
+            .map(|(kind, height)| (*kind, *height))
+            .collect();
+        activations.sort();
+        Hash::new(&bincode::serialize(&(enabled, activations)).unwrap())
+    }
+    
+    /// Sum of genesis allocations; errors if it exceeds the supply cap
+    pub fn total_allocated(&self) -> Result<u64> {
+        let total = self.allocations.iter()
+            .try_fold(0u64, |total, allocation| total.checked_add(allocation.amount))
+            .ok_or_else(|| QoraNetError::ConsensusError("Genesis allocations overflow".to_string()))?;
+
+        if total > self.max_qor_supply {
+            return Err(QoraNetError::ConsensusError(format!(
+                "Genesis allocations {} exceed max QOR supply {}",
+                total, self.max_qor_supply
+            )));
+        }
+        Ok(total)
+    }
+
+    /// Deterministic commitment to all chain parameters
+    pub fn genesis_hash(&self) -> Hash {
+        let mut data = Vec::with_capacity(GENESIS_DOMAIN.len() + 8 * 8 + 64);
+        data.extend_from_slice(GENESIS_DOMAIN);
+        data.extend_from_slice(&self.chain_id.to_le_bytes());
+        data.extend_from_slice(&self.block_time_secs.to_le_bytes());
+        // Fee bounds are hashed by bit pattern so the encoding is exact
+        data.extend_from_slice(&self.min_fee_usd.to_bits().to_le_bytes());
+        data.extend_from_slice(&self.max_fee_usd.to_bits().to_le_bytes());
+        data.extend_from_slice(&self.finality_depth.to_le_bytes());
+        data.extend_from_slice(&self.genesis_timestamp.to_le_bytes());
+        data.extend_from_slice(&self.max_qor_supply.to_le_bytes());
+        data.extend_from_slice(&self.block_reward.to_le_bytes());
+        data.extend_from_slice(self.tx_types_hash().as_bytes());
+        data.extend_from_slice(self.allocations_hash().as_bytes());
+        // Appended only when set, so chains without an authority keep their hash
+        if let Some(authority) = &self.spending_limit_authority {
+            data.extend_from_slice(authority.as_bytes());
+        }
+        if self.signed_blocks_height > 0 {
+            data.extend_from_slice(&self.signed_blocks_height.to_le_bytes());
+        }
+        if self.reward_pool != RewardPoolConfig::default() {
+            data.extend_from_slice(&self.reward_pool.fee_share_bps.to_le_bytes());
+            data.extend_from_slice(&self.reward_pool.block_reward_share_bps.to_le_bytes());
+        }
+        if self.max_rewarded_apps != DEFAULT_MAX_REWARDED_APPS {
+            data.extend_from_slice(&(self.max_rewarded_apps as u64).to_le_bytes());
+        }
+        Hash::new(&data)
+    }
+
+    /// Build the genesis block for this config
+    pub fn genesis_block(&self, genesis_validator: Address) -> Block {
+        let mut block = Block::genesis(genesis_validator);
+        // Height 0 has no parent; its previous hash carries the config commitment
+        block.header.previous_hash = self.genesis_hash();
+        block.header.timestamp = self.genesis_timestamp;
+        block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_allocations() -> GenesisConfig {
+        GenesisConfig {
+            allocations: vec![
+                GenesisAllocation { address: Address([1u8; 32]), amount: 1_000 },
+                GenesisAllocation { address: Address([2u8; 32]), amount: 2_000 },
+            ],
+            ..GenesisConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_identical_configs_share_genesis_hash() {
+        let a = config_with_allocations();
+        let mut b = config_with_allocations();
+        b.allocations.reverse();
+
+        assert_eq!(a.genesis_hash(), b.genesis_hash());
+
+        let validator = Address([9u8; 32]);
+        assert_eq!(a.genesis_block(validator.clone()).hash(), b.genesis_block(validator).hash());
+    }
+
+    #[test]
+    fn test_differing_configs_differ() {
+        let base = config_with_allocations();
+
+        let mut chain_id = base.clone();
+        chain_id.chain_id += 1;
+        let mut finality = base.clone();
+        finality.finality_depth += 1;
+        let mut fees = base.clone();
+        fees.min_fee_usd *= 2.0;
+        let mut allocations = base.clone();
+        allocations.allocations[0].amount += 1;
+        let mut supply = base.clone();
+        supply.max_qor_supply -= 1;
+        let mut tx_types = base.clone();
+        tx_types.enabled_tx_types.remove(&TransactionKind::SmartContract);
+        let mut signed_blocks = base.clone();
+        signed_blocks.signed_blocks_height = 1_000;
+
+        for other in [chain_id, finality, fees, allocations, supply, tx_types, signed_blocks] {
+            assert_ne!(base.genesis_hash(), other.genesis_hash());
+        }
+        assert_eq!(base.genesis_block(Address([9u8; 32])).header.previous_hash, base.genesis_hash());
+    }
+
+    #[test]
+    fn test_allocations_over_supply_cap_rejected() {
+        let mut config = config_with_allocations();
+        assert_eq!(config.total_allocated().unwrap(), 3_000);
+
+        config.max_qor_supply = 2_999;
+        assert!(config.total_allocated().is_err());
+    }
+}
@@ -0,0 +1,910 @@
+//! Proof of Liquidity consensus
+//!
+//! Tracks the validator set, hosted application reports and block producer
+//! selection for QoraNet.
+
+pub mod block;
+pub mod activity;
+pub mod genesis;
+pub mod watchdog;
+
+pub use block::{Block, BlockHeader, BlockStats, EquivocationProof, SignedHeader};
+pub use activity::{ActiveAppTracker, DEFAULT_APP_STALENESS_SECS};
+pub use genesis::{GenesisAllocation, GenesisConfig};
+pub use watchdog::{ProductionWatchdog, DEFAULT_MAX_STORAGE_FAILURES};
+
+use crate::{Address, AppMetrics, BlockHeight, Hash, QoraNetError, Result, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{info, warn};
+
+/// Default minimum interval between two metrics reports for the same app (seconds)
+pub const DEFAULT_MIN_REPORT_INTERVAL_SECS: u64 = 300;
+
+/// Default number of blocks per epoch
+pub const DEFAULT_EPOCH_LENGTH: u64 = 100;
+
+/// How many blocks back produced blocks are remembered for equivocation checks
+pub const EQUIVOCATION_WINDOW: u64 = 1_000;
+
+/// Validator information tracked by consensus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorInfo {
+    pub address: Address,
+    pub liquidity: u64,      // Verified LP liquidity in QOR units
+    pub active_apps: usize,  // Number of apps currently hosted
+    pub last_active: Timestamp,
+}
+
+impl ValidatorInfo {
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            liquidity: 0,
+            active_apps: 0,
+            last_active: chrono::Utc::now().timestamp() as u64,
+        }
+    }
+}
+
+/// A validator's standing in the current set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorStatus {
+    pub info: ValidatorInfo,
+    /// Meets the liquidity and app requirements
+    pub eligible: bool,
+    /// Among the validators the next block's producer is drawn from; an eligible
+    /// validator that joined mid-epoch is not until the next epoch starts
+    pub in_selection_set: bool,
+}
+
+/// Last accepted metrics report for a hosted application
+#[derive(Debug, Clone)]
+struct AppReport {
+    last_report: Timestamp,
+    metrics: AppMetrics,
+}
+
+/// Consensus state shared by the validator node
+#[derive(Debug)]
+pub struct ConsensusState {
+    validators: HashMap<Address, ValidatorInfo>,
+    app_reports: HashMap<(Address, String), AppReport>,
+    app_activity: ActiveAppTracker,
+    min_liquidity_requirement: u64,
+    min_apps_requirement: usize,
+    min_report_interval_secs: u64,
+    current_height: BlockHeight,
+    epoch_length: u64,
+    /// Eligible validators with their selection weights, frozen at the start of each epoch
+    epoch_snapshots: BTreeMap<u64, Vec<(Address, u64)>>,
+    /// Reject blocks whose producer isn't the validator selected for their height
+    enforce_block_producer: bool,
+    /// First height whose blocks must be signed by their producer
+    signed_blocks_height: BlockHeight,
+    /// First height whose transactions root uses the prefixed merkle scheme
+    domain_separated_root_height: BlockHeight,
+    /// Header each validator signed at recent heights
+    produced_blocks: HashMap<(Address, BlockHeight), SignedHeader>,
+    /// Equivocation proofs waiting for a block to include them, by offender
+    pending_evidence: HashMap<Address, EquivocationProof>,
+    /// Validators slashed for equivocation, with the height of the block that
+    /// included the evidence; never eligible again
+    slashed: HashMap<Address, BlockHeight>,
+}
+
+impl ConsensusState {
+    pub fn new(min_liquidity_requirement: u64, min_apps_requirement: usize) -> Self {
+        Self {
+            validators: HashMap::new(),
+            app_reports: HashMap::new(),
+            app_activity: ActiveAppTracker::default(),
+            min_liquidity_requirement,
+            min_apps_requirement,
+            min_report_interval_secs: DEFAULT_MIN_REPORT_INTERVAL_SECS,
+            current_height: 0,
+            epoch_length: DEFAULT_EPOCH_LENGTH,
+            epoch_snapshots: BTreeMap::new(),
+            enforce_block_producer: true,
+            signed_blocks_height: 0,
+            domain_separated_root_height: 0,
+            produced_blocks: HashMap::new(),
+            pending_evidence: HashMap::new(),
+            slashed: HashMap::new(),
+        }
+    }
+
+    /// Turn producer authorization in `validate_block_producer` on or off
+    pub fn set_enforce_block_producer(&mut self, enforce: bool) {
+        self.enforce_block_producer = enforce;
+    }
+
+    /// Accept unsigned blocks below `height`, for chains that predate producer signatures
+    pub fn set_signed_blocks_height(&mut self, height: BlockHeight) {
+        self.signed_blocks_height = height;
+    }
+
+    /// Accept the legacy transactions root below `height`, for chains that predate the prefixed scheme
+    pub fn set_domain_separated_root_height(&mut self, height: BlockHeight) {
+        self.domain_separated_root_height = height;
+    }
+
+    /// First height whose blocks must carry a prefixed transactions root
+    pub fn domain_separated_root_height(&self) -> BlockHeight {
+        self.domain_separated_root_height
+    }
+
+    /// Set the number of blocks per epoch (at least 1)
+    pub fn set_epoch_length(&mut self, blocks: u64) {
+        self.epoch_length = blocks.max(1);
+    }
+
+    /// Epoch containing `height`
+    pub fn epoch_of(&self, height: BlockHeight) -> u64 {
+        height / self.epoch_length
+    }
+
+    /// Set the minimum interval between metrics reports for the same app
+    pub fn set_min_report_interval(&mut self, secs: u64) {
+        self.min_report_interval_secs = secs;
+    }
+
+    /// Set how long an app may go without reporting before it stops counting as active
+    pub fn set_app_staleness(&mut self, secs: u64) {
+        self.app_activity.set_staleness(secs);
+    }
+
+    /// Register a hosted app as active.
+    ///
+    /// Apps are keyed by `AppId::namespaced`, so owners reusing an id don't collide.
+    pub fn register_app(&mut self, validator: &Address, app_key: &str, timestamp: Timestamp) {
+        self.app_activity.touch(validator, app_key, timestamp);
+    }
+
+    /// Deactivate apps that have stopped reporting; returns how many were pruned
+    pub fn prune_stale_apps(&mut self, now: Timestamp) -> usize {
+        self.app_activity.prune_stale(now)
+    }
+
+    /// Check if an app is currently counted as active
+    pub fn is_app_active(&self, validator: &Address, app_key: &str) -> bool {
+        self.app_activity.is_active(validator, app_key)
+    }
+
+    /// Add or replace a validator
+    pub fn update_validator(&mut self, validator: ValidatorInfo) -> Result<()> {
+        self.validators.insert(validator.address.clone(), validator);
+        Ok(())
+    }
+
+    /// Set a known validator's verified liquidity, re-checking its eligibility
+    pub fn update_validator_liquidity(&mut self, address: &Address, liquidity: u64) -> Result<()> {
+        self.modify_validator(address, |validator| validator.liquidity = liquidity)
+    }
+
+    /// Set a known validator's hosted app count, re-checking its eligibility
+    pub fn update_validator_apps(&mut self, address: &Address, active_apps: usize) -> Result<()> {
+        self.modify_validator(address, |validator| validator.active_apps = active_apps)
+    }
+
+    /// Apply `change` to a known validator, logging if it gains or loses eligibility
+    fn modify_validator(&mut self, address: &Address, change: impl FnOnce(&mut ValidatorInfo)) -> Result<()> {
+        let mut validator = self.validators.get(address).cloned()
+            .ok_or_else(|| QoraNetError::ConsensusError(format!("Unknown validator: {}", address)))?;
+
+        let was_eligible = self.is_eligible(&validator);
+        change(&mut validator);
+        let is_eligible = self.is_eligible(&validator);
+
+        if !was_eligible && is_eligible {
+            info!(
+                "✅ Validator {} is now eligible ({} liquidity, {} apps)",
+                address, validator.liquidity, validator.active_apps
+            );
+        } else if was_eligible && !is_eligible {
+            warn!(
+                "Validator {} is no longer eligible ({} liquidity, {} apps; requires {} and {})",
+                address, validator.liquidity, validator.active_apps,
+                self.min_liquidity_requirement, self.min_apps_requirement
+            );
+        }
+
+        self.validators.insert(address.clone(), validator);
+        Ok(())
+    }
+
+    /// Get validator information
+    pub fn get_validator(&self, address: &Address) -> Option<&ValidatorInfo> {
+        self.validators.get(address)
+    }
+
+    /// Check if a validator meets the liquidity and app requirements and hasn't been slashed
+    fn is_eligible(&self, validator: &ValidatorInfo) -> bool {
+        validator.liquidity >= self.min_liquidity_requirement
+            && validator.active_apps >= self.min_apps_requirement
+            && !self.slashed.contains_key(&validator.address)
+    }
+
+    /// Eligible validators in deterministic (address) order
+    fn eligible_validators(&self) -> Vec<&ValidatorInfo> {
+        let mut eligible: Vec<&ValidatorInfo> = self.validators.values()
+            .filter(|v| self.is_eligible(v))
+            .collect();
+        eligible.sort_by(|a, b| a.address.0.cmp(&b.address.0));
+        eligible
+    }
+
+    /// Freeze the current eligible set for `epoch`, forgetting sets no recent height needs
+    fn snapshot_epoch(&mut self, epoch: u64) {
+        let validators = self.eligible_validators().into_iter()
+            .map(|v| (v.address.clone(), v.liquidity))
+            .collect();
+        self.epoch_snapshots.insert(epoch, validators);
+
+        // Keep the set in force at the equivocation horizon and everything after it
+        let horizon = self.epoch_of(self.current_height.saturating_sub(EQUIVOCATION_WINDOW));
+        let in_force = self.epoch_snapshots.range(..=horizon).next_back().map(|(epoch, _)| *epoch);
+        if let Some(in_force) = in_force {
+            self.epoch_snapshots = self.epoch_snapshots.split_off(&in_force);
+        }
+    }
+
+    /// Validators selection draws from at `height`, with their liquidity weights.
+    ///
+    /// A height uses the set frozen for its epoch, or for the latest epoch before
+    /// it if none was frozen yet, so validators joining mid-epoch only take part
+    /// from the next epoch and every node that recorded the same heights draws
+    /// from the same set. Until a first height is recorded, the registered
+    /// validators are the genesis set.
+    fn selection_set(&self, height: BlockHeight) -> Vec<(Address, u64)> {
+        if self.epoch_snapshots.is_empty() {
+            return self.eligible_validators().into_iter()
+                .map(|v| (v.address.clone(), v.liquidity))
+                .collect();
+        }
+        self.epoch_snapshots.range(..=self.epoch_of(height)).next_back()
+            .map(|(_, validators)| validators.clone())
+            .unwrap_or_default()
+    }
+
+    /// Select the producer of the block at `height`, seeded by the previous block hash.
+    ///
+    /// Each eligible validator is picked with probability proportional to its
+    /// liquidity. The draw only depends on the seed and the (address-ordered)
+    /// selection set, so every node agrees on the result. If no candidate has
+    /// any liquidity (a zero minimum requirement), they are weighted equally.
+    pub fn select_block_producer(&self, seed: &[u8; 32], height: BlockHeight) -> Result<Address> {
+        let eligible = self.selection_set(height);
+        if eligible.is_empty() {
+            return Err(QoraNetError::ConsensusError("No eligible validators".to_string()));
+        }
+
+        // 128 bits of seed keep the modulo bias negligible for any total stake
+        let mut seed_bytes = [0u8; 16];
+        seed_bytes.copy_from_slice(&seed[..16]);
+        let draw = u128::from_le_bytes(seed_bytes);
+
+        let total: u128 = eligible.iter().map(|(_, liquidity)| *liquidity as u128).sum();
+        if total == 0 {
+            let index = (draw % eligible.len() as u128) as usize;
+            return Ok(eligible[index].0.clone());
+        }
+
+        let mut target = draw % total;
+        for (address, liquidity) in &eligible {
+            let weight = *liquidity as u128;
+            if target < weight {
+                return Ok(address.clone());
+            }
+            target -= weight;
+        }
+
+        unreachable!("weighted draw is below the total stake")
+    }
+
+    /// Get the producer selected for `height` on top of `prev_hash`
+    pub fn producer_for(&self, prev_hash: &Hash, height: BlockHeight) -> Result<Address> {
+        self.select_block_producer(prev_hash.as_bytes(), height)
+    }
+
+    /// Dry-run selection: would `validator` produce the block at `height`?
+    pub fn would_produce(&self, validator: &Address, prev_hash: &Hash, height: BlockHeight) -> bool {
+        self.producer_for(prev_hash, height)
+            .map(|producer| producer == *validator)
+            .unwrap_or(false)
+    }
+
+    /// Check `block` was produced by the validator selected for its height on top of its parent.
+    ///
+    /// The header only names the producer; from `signed_blocks_height` on, the
+    /// producer's signature proves it. Genesis has no selection and is exempt;
+    /// nothing is checked when enforcement is off.
+    pub fn validate_block_producer(&self, block: &Block) -> Result<()> {
+        if !self.enforce_block_producer || block.header.height == 0 {
+            return Ok(());
+        }
+
+        let expected = self.producer_for(&block.header.previous_hash, block.header.height)?;
+        if block.header.validator != expected {
+            return Err(QoraNetError::ConsensusError(format!(
+                "Block #{} produced by {}, but {} was selected",
+                block.header.height, block.header.validator, expected
+            )));
+        }
+        if block.header.height >= self.signed_blocks_height {
+            block.verify_producer_signature()?;
+        }
+        Ok(())
+    }
+
+    /// Remember the header a validator signed at its height.
+    ///
+    /// A second, different header signed by the same validator at that height
+    /// is equivocation: the proof is returned and kept in `pending_evidence`
+    /// until a block includes it. Unsigned or badly signed blocks prove nothing
+    /// and are ignored.
+    pub fn record_produced_block(&mut self, block: &Block) -> Option<EquivocationProof> {
+        let signed = block.signed_header()?;
+        if signed.verify().is_err() {
+            return None;
+        }
+
+        let key = (block.header.validator.clone(), block.header.height);
+        let seen = match self.produced_blocks.get(&key) {
+            Some(seen) => seen,
+            None => {
+                self.produced_blocks.insert(key, signed);
+                return None;
+            }
+        };
+        if seen.header.hash() == signed.header.hash() {
+            return None;
+        }
+
+        let proof = EquivocationProof { first: seen.clone(), second: signed };
+        let offender = proof.offender().clone();
+        if !self.slashed.contains_key(&offender) && !self.pending_evidence.contains_key(&offender) {
+            warn!(
+                "⚔️ Validator {} signed blocks {} and {} at height {}",
+                offender, proof.first.header.hash(), proof.second.header.hash(), proof.height()
+            );
+            self.pending_evidence.insert(offender, proof.clone());
+        }
+        Some(proof)
+    }
+
+    /// Equivocation proofs not yet included in a block, in offender order
+    pub fn pending_evidence(&self) -> Vec<EquivocationProof> {
+        let mut evidence: Vec<EquivocationProof> = self.pending_evidence.values().cloned().collect();
+        evidence.sort_by(|a, b| a.offender().0.cmp(&b.offender().0));
+        evidence
+    }
+
+    /// Slash `validator` for evidence included in the block at `height`.
+    ///
+    /// Called for every applied `ReportEquivocation`, so all nodes slash at the
+    /// same block. The current epoch's snapshot is left alone; the validator is
+    /// no longer eligible and is left out of every later snapshot.
+    pub fn record_slashing(&mut self, validator: &Address, height: BlockHeight) {
+        self.pending_evidence.remove(validator);
+        if !self.slashed.contains_key(validator) {
+            warn!("⚔️ Slashing validator {} for equivocation (evidence in block #{})", validator, height);
+            self.slashed.insert(validator.clone(), height);
+        }
+    }
+
+    /// Whether a validator has been slashed for equivocation
+    pub fn is_slashed(&self, validator: &Address) -> bool {
+        self.slashed.contains_key(validator)
+    }
+
+    /// Validate and record a `ReportMetrics` submission for (validator, app key).
+    ///
+    /// Reports arriving sooner than the configured interval are rejected and do
+    /// not replace the last accepted metrics, so they never count toward uptime.
+    pub fn record_metrics_report(
+        &mut self,
+        validator: &Address,
+        app_key: &str,
+        metrics: &AppMetrics,
+        timestamp: Timestamp,
+    ) -> Result<()> {
+        let key = (validator.clone(), app_key.to_string());
+
+        if let Some(previous) = self.app_reports.get(&key) {
+            let elapsed = timestamp.saturating_sub(previous.last_report);
+            if elapsed < self.min_report_interval_secs {
+                return Err(QoraNetError::AppMonitorError(format!(
+                    "Metrics for app {} reported too frequently: {}s since last report, minimum {}s",
+                    app_key, elapsed, self.min_report_interval_secs
+                )));
+            }
+        }
+
+        self.app_reports.insert(key, AppReport {
+            last_report: timestamp,
+            metrics: metrics.clone(),
+        });
+        // An accepted report (re)activates the app
+        self.app_activity.touch(validator, app_key, timestamp);
+
+        Ok(())
+    }
+
+    /// Get the last accepted metrics for an app
+    pub fn reported_metrics(&self, validator: &Address, app_key: &str) -> Option<&AppMetrics> {
+        self.app_reports
+            .get(&(validator.clone(), app_key.to_string()))
+            .map(|report| &report.metrics)
+    }
+
+    /// Update current block height.
+    ///
+    /// Snapshots the validator set when the next block starts a new epoch.
+    pub fn update_height(&mut self, height: BlockHeight) {
+        self.current_height = height;
+        let horizon = height.saturating_sub(EQUIVOCATION_WINDOW);
+        self.produced_blocks.retain(|(_, produced_at), _| *produced_at >= horizon);
+        let next_epoch = self.epoch_of(height + 1);
+        if !self.epoch_snapshots.contains_key(&next_epoch) {
+            self.snapshot_epoch(next_epoch);
+        }
+    }
+
+    /// Get current block height
+    pub fn current_height(&self) -> BlockHeight {
+        self.current_height
+    }
+
+    /// Total liquidity provided by all validators
+    pub fn total_network_liquidity(&self) -> u64 {
+        self.validators.values().map(|v| v.liquidity).sum()
+    }
+
+    /// Total number of active apps across validators (O(1))
+    pub fn total_active_apps(&self) -> usize {
+        self.app_activity.active_count()
+    }
+
+    /// Every known validator, largest liquidity first (ties by address)
+    pub fn validator_set(&self) -> Vec<ValidatorStatus> {
+        let selection_set = self.selection_set(self.current_height + 1);
+        let mut validators: Vec<ValidatorStatus> = self.validators.values()
+            .map(|v| ValidatorStatus {
+                info: v.clone(),
+                eligible: self.is_eligible(v),
+                in_selection_set: selection_set.iter().any(|(address, _)| *address == v.address),
+            })
+            .collect();
+        validators.sort_by(|a, b| b.info.liquidity.cmp(&a.info.liquidity)
+            .then_with(|| a.info.address.0.cmp(&b.info.address.0)));
+        validators
+    }
+
+    /// Number of known validators
+    pub fn validator_count(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Number of validators eligible to produce blocks
+    pub fn eligible_validator_count(&self) -> usize {
+        self.validators.values().filter(|v| self.is_eligible(v)).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(uptime: u64) -> AppMetrics {
+        let mut metrics = AppMetrics::new();
+        metrics.uptime = uptime;
+        metrics
+    }
+
+    #[test]
+    fn test_report_within_interval_rejected() {
+        let mut state = ConsensusState::new(0, 0);
+        state.set_min_report_interval(60);
+        let validator = Address([1u8; 32]);
+
+        assert!(state.record_metrics_report(&validator, "app-1", &report(100), 1_000).is_ok());
+
+        let result = state.record_metrics_report(&validator, "app-1", &report(5_000), 1_030);
+        assert!(matches!(result, Err(QoraNetError::AppMonitorError(_))));
+
+        // Rejected report must not count toward uptime
+        assert_eq!(state.reported_metrics(&validator, "app-1").unwrap().uptime, 100);
+    }
+
+    #[test]
+    fn test_reports_spaced_correctly_accepted() {
+        let mut state = ConsensusState::new(0, 0);
+        state.set_min_report_interval(60);
+        let validator = Address([1u8; 32]);
+
+        assert!(state.record_metrics_report(&validator, "app-1", &report(100), 1_000).is_ok());
+        assert!(state.record_metrics_report(&validator, "app-1", &report(160), 1_060).is_ok());
+        assert_eq!(state.reported_metrics(&validator, "app-1").unwrap().uptime, 160);
+
+        // Interval is tracked per (validator, app_id)
+        assert!(state.record_metrics_report(&validator, "app-2", &report(10), 1_061).is_ok());
+    }
+
+    #[test]
+    fn test_same_app_id_under_different_owners_does_not_collide() {
+        let mut state = ConsensusState::new(0, 0);
+        state.set_min_report_interval(60);
+        let validator = Address([1u8; 32]);
+        let app_id = crate::transaction::AppId::new("indexer").unwrap();
+        let (alice_app, bob_app) = (app_id.namespaced(&Address([2u8; 32])), app_id.namespaced(&Address([3u8; 32])));
+
+        assert!(state.record_metrics_report(&validator, &alice_app, &report(100), 1_000).is_ok());
+        assert!(state.record_metrics_report(&validator, &bob_app, &report(7), 1_001).is_ok());
+        assert_eq!(state.reported_metrics(&validator, &alice_app).unwrap().uptime, 100);
+        assert_eq!(state.reported_metrics(&validator, &bob_app).unwrap().uptime, 7);
+    }
+
+    #[test]
+    fn test_active_app_counter_matches_brute_force() {
+        let mut state = ConsensusState::new(0, 0);
+        state.set_min_report_interval(10);
+        state.set_app_staleness(100);
+
+        // Reference model: last time each app was seen
+        let mut last_seen: HashMap<(Address, String), Timestamp> = HashMap::new();
+        let brute_force = |last_seen: &HashMap<(Address, String), Timestamp>, pruned_at: Timestamp| {
+            last_seen.values().filter(|seen| **seen + 100 >= pruned_at).count()
+        };
+
+        let mut now = 1_000;
+        let mut pruned_at = 0;
+        for step in 0..300u64 {
+            let validator = Address([(step % 7) as u8; 32]);
+            let app_id = format!("app-{}", step % 13);
+            now += 5 + step % 17;
+
+            if step % 3 == 0 {
+                state.register_app(&validator, &app_id, now);
+                let seen = last_seen.entry((validator.clone(), app_id.clone())).or_insert(now);
+                *seen = (*seen).max(now);
+            } else if state.record_metrics_report(&validator, &app_id, &report(step), now).is_ok() {
+                last_seen.insert((validator.clone(), app_id.clone()), now);
+            }
+
+            if step % 11 == 0 {
+                state.prune_stale_apps(now);
+                pruned_at = now;
+            }
+
+            // Between prunes, anything seen since the last prune stays active
+            assert_eq!(state.total_active_apps(), brute_force(&last_seen, pruned_at), "step {}", step);
+        }
+
+        state.prune_stale_apps(now + 1_000);
+        assert_eq!(state.total_active_apps(), brute_force(&last_seen, now + 1_000));
+        assert_eq!(state.total_active_apps(), 0);
+
+        // Reactivation after expiry
+        let validator = Address([1u8; 32]);
+        state.register_app(&validator, "app-1", now + 2_000);
+        assert!(state.is_app_active(&validator, "app-1"));
+        assert_eq!(state.total_active_apps(), 1);
+    }
+
+    #[test]
+    fn test_would_produce_matches_selection() {
+        let mut state = ConsensusState::new(100, 1);
+        for i in 1..=5u8 {
+            let mut validator = ValidatorInfo::new(Address([i; 32]));
+            validator.liquidity = 100 * i as u64;
+            validator.active_apps = 1;
+            state.update_validator(validator).unwrap();
+        }
+
+        for height in 1..200u64 {
+            let prev_hash = Hash::new(&height.to_le_bytes());
+            let selected = state.select_block_producer(prev_hash.as_bytes(), height).unwrap();
+
+            for i in 1..=5u8 {
+                let candidate = Address([i; 32]);
+                assert_eq!(
+                    state.would_produce(&candidate, &prev_hash, height),
+                    candidate == selected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_from_selected_producer_accepted() {
+        let mut state = ConsensusState::new(100, 1);
+        let keypairs: HashMap<Address, ed25519_dalek::Keypair> = (0..5).map(|_| {
+            let keypair = ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng);
+            (Address::from_pubkey(&keypair.public), keypair)
+        }).collect();
+        for address in keypairs.keys() {
+            let mut validator = ValidatorInfo::new(address.clone());
+            validator.liquidity = 100;
+            validator.active_apps = 1;
+            state.update_validator(validator).unwrap();
+        }
+
+        for height in 1..50u64 {
+            let prev_hash = Hash::new(&height.to_le_bytes());
+            let selected = state.producer_for(&prev_hash, height).unwrap();
+            let mut block = Block::new(prev_hash.clone(), height, selected.clone(), Vec::new(), 0, 0);
+            block.sign(&keypairs[&selected]);
+            assert!(block.validate_against_state(height, &prev_hash, &state).is_ok());
+
+            // Any other validator forging the same block is refused
+            for (forger, keypair) in &keypairs {
+                if *forger == selected {
+                    continue;
+                }
+                let mut forged = Block::new(prev_hash.clone(), height, forger.clone(), Vec::new(), 0, 0);
+                forged.sign(keypair);
+                let result = forged.validate_against_state(height, &prev_hash, &state);
+                assert!(matches!(result, Err(QoraNetError::ConsensusError(_))));
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_naming_selected_producer_needs_its_signature() {
+        let keypair = ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng);
+        let producer = Address::from_pubkey(&keypair.public);
+        let mut state = ConsensusState::new(100, 1);
+        let mut validator = ValidatorInfo::new(producer.clone());
+        validator.liquidity = 100;
+        validator.active_apps = 1;
+        state.update_validator(validator).unwrap();
+        let prev_hash = Hash::new(b"parent");
+
+        // Naming the only validator in the header is not enough
+        let unsigned = Block::new(prev_hash.clone(), 5, producer.clone(), Vec::new(), 0, 0);
+        assert!(state.validate_block_producer(&unsigned).is_err());
+        let mut forged = unsigned.clone();
+        forged.sign(&ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng));
+        assert!(state.validate_block_producer(&forged).is_err());
+        let mut signed = unsigned.clone();
+        signed.sign(&keypair);
+        assert!(state.validate_block_producer(&signed).is_ok());
+
+        // Blocks from before producer signatures stay valid
+        state.set_signed_blocks_height(6);
+        assert!(state.validate_block_producer(&unsigned).is_ok());
+    }
+
+    #[test]
+    fn test_producer_check_configurable_and_skips_genesis() {
+        let mut state = ConsensusState::new(100, 1);
+        state.update_validator(eligible(1)).unwrap();
+        let outsider = Address([9u8; 32]);
+
+        let genesis = Block::genesis(outsider.clone());
+        assert!(state.validate_block_producer(&genesis).is_ok());
+
+        let forged = Block::new(genesis.hash(), 1, outsider, Vec::new(), 0, 0);
+        assert!(state.validate_block_producer(&forged).is_err());
+        state.set_enforce_block_producer(false);
+        assert!(forged.validate_against_state(1, &genesis.hash(), &state).is_ok());
+    }
+
+    #[test]
+    fn test_liquidity_and_app_updates_move_eligibility() {
+        let mut state = ConsensusState::new(100, 2);
+        let address = Address([1u8; 32]);
+        state.update_validator(ValidatorInfo::new(address.clone())).unwrap();
+        state.update_validator(eligible(2)).unwrap();
+        // `eligible` hosts one app; give it the second the requirement asks for
+        state.update_validator_apps(&Address([2u8; 32]), 2).unwrap();
+        assert_eq!(state.eligible_validator_count(), 1);
+
+        // Liquidity alone isn't enough
+        state.update_validator_liquidity(&address, 150).unwrap();
+        assert_eq!(state.eligible_validator_count(), 1);
+
+        state.update_validator_apps(&address, 2).unwrap();
+        assert_eq!(state.eligible_validator_count(), 2);
+
+        // Dropping below either threshold removes it again
+        state.update_validator_liquidity(&address, 99).unwrap();
+        assert_eq!(state.eligible_validator_count(), 1);
+        state.update_validator_liquidity(&address, 100).unwrap();
+        assert_eq!(state.eligible_validator_count(), 2);
+        state.update_validator_apps(&address, 1).unwrap();
+        assert_eq!(state.eligible_validator_count(), 1);
+
+        let info = state.get_validator(&address).unwrap();
+        assert_eq!((info.liquidity, info.active_apps), (100, 1));
+
+        let unknown = state.update_validator_liquidity(&Address([9u8; 32]), 1_000);
+        assert!(matches!(unknown, Err(QoraNetError::ConsensusError(_))));
+        assert!(state.get_validator(&Address([9u8; 32])).is_none());
+    }
+
+    fn signed_block(keypair: &ed25519_dalek::Keypair, height: BlockHeight, fees: u64) -> Block {
+        let mut block = Block::new(Hash::zero(), height, Address::from_pubkey(&keypair.public), Vec::new(), 0, 0);
+        // Fees only serve to make otherwise identical blocks differ
+        block.header.total_fees = fees;
+        block.sign(keypair);
+        block
+    }
+
+    #[test]
+    fn test_conflicting_signed_blocks_slash_validator_from_next_epoch() {
+        let keypair = ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng);
+        let cheater = Address::from_pubkey(&keypair.public);
+        let mut state = ConsensusState::new(100, 1);
+        state.set_epoch_length(10);
+        let mut validator = ValidatorInfo::new(cheater.clone());
+        validator.liquidity = 100;
+        validator.active_apps = 1;
+        state.update_validator(validator).unwrap();
+        state.update_validator(eligible(1)).unwrap();
+        state.update_height(0);
+
+        let first = signed_block(&keypair, 7, 0);
+        let second = signed_block(&keypair, 7, 1);
+        assert!(state.record_produced_block(&first).is_none());
+        let proof = state.record_produced_block(&second).unwrap();
+        proof.verify().unwrap();
+        assert_eq!(proof.offender(), &cheater);
+        assert_eq!(state.pending_evidence().len(), 1);
+
+        // Detection alone changes nothing until a block includes the evidence
+        assert!(!state.is_slashed(&cheater));
+        assert_eq!(state.eligible_validator_count(), 2);
+
+        // Included at height 4: the running epoch keeps its producer set
+        state.record_slashing(&cheater, 4);
+        assert!(state.is_slashed(&cheater));
+        assert!(state.pending_evidence().is_empty());
+        assert_eq!(state.eligible_validator_count(), 1);
+        let draws = |state: &ConsensusState, height| (0..50u64)
+            .map(|i| state.producer_for(&Hash::new(&i.to_le_bytes()), height).unwrap())
+            .collect::<Vec<_>>();
+        assert!(draws(&state, 5).contains(&cheater));
+
+        // From the next epoch's snapshot on it is never selected
+        state.update_height(9);
+        assert!(!draws(&state, 10).contains(&cheater));
+    }
+
+    #[test]
+    fn test_honest_validator_never_slashed() {
+        let keypair = ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng);
+        let honest = Address::from_pubkey(&keypair.public);
+        let mut state = ConsensusState::new(0, 0);
+
+        // One block per height, some seen twice (e.g. relayed by several peers)
+        for height in 1..20u64 {
+            let block = signed_block(&keypair, height, 0);
+            assert!(state.record_produced_block(&block).is_none());
+            assert!(state.record_produced_block(&block).is_none());
+
+            let header = block.signed_header().unwrap();
+            let proof = EquivocationProof { first: header.clone(), second: header };
+            assert!(matches!(proof.verify(), Err(QoraNetError::ConsensusError(_))));
+        }
+
+        // Different heights are not equivocation either
+        let proof = EquivocationProof {
+            first: signed_block(&keypair, 1, 0).signed_header().unwrap(),
+            second: signed_block(&keypair, 2, 0).signed_header().unwrap(),
+        };
+        assert!(proof.verify().is_err());
+
+        // Nor are forged blocks it never signed
+        let mut forged = Block::new(Hash::zero(), 3, honest.clone(), Vec::new(), 0, 0);
+        forged.header.total_fees = 5;
+        forged.sign(&ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng));
+        assert!(state.record_produced_block(&forged).is_none());
+        let proof = EquivocationProof {
+            first: signed_block(&keypair, 3, 0).signed_header().unwrap(),
+            second: forged.signed_header().unwrap(),
+        };
+        assert!(proof.verify().is_err());
+
+        assert!(state.pending_evidence().is_empty());
+        assert!(!state.is_slashed(&honest));
+    }
+
+    fn eligible(address: u8) -> ValidatorInfo {
+        let mut validator = ValidatorInfo::new(Address([address; 32]));
+        validator.liquidity = 100;
+        validator.active_apps = 1;
+        validator
+    }
+
+    #[test]
+    fn test_same_seed_selects_same_producer_across_instances() {
+        let build = |order: &[u8]| {
+            let mut state = ConsensusState::new(100, 1);
+            for &i in order {
+                let mut validator = eligible(i);
+                validator.liquidity = 100 * i as u64;
+                state.update_validator(validator).unwrap();
+            }
+            state
+        };
+        // Insertion order (and so HashMap iteration order) must not matter
+        let a = build(&[1, 2, 3, 4, 5]);
+        let b = build(&[5, 3, 1, 4, 2]);
+
+        for i in 0..200u64 {
+            let seed = Hash::new(&i.to_le_bytes());
+            assert_eq!(a.producer_for(&seed, 1).unwrap(), b.producer_for(&seed, 1).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_selection_tracks_liquidity_and_skips_ineligible() {
+        let mut state = ConsensusState::new(100, 1);
+        for (i, liquidity) in [(1u8, 100u64), (2, 300), (3, 600)] {
+            let mut validator = eligible(i);
+            validator.liquidity = liquidity;
+            state.update_validator(validator).unwrap();
+        }
+        // Plenty of stake but no hosted apps
+        let mut idle = eligible(4);
+        idle.liquidity = 10_000;
+        idle.active_apps = 0;
+        state.update_validator(idle).unwrap();
+
+        let rounds = 10_000u64;
+        let mut counts: HashMap<Address, u64> = HashMap::new();
+        for i in 0..rounds {
+            let seed = Hash::new(&i.to_le_bytes());
+            *counts.entry(state.producer_for(&seed, 1).unwrap()).or_default() += 1;
+        }
+
+        assert!(!counts.contains_key(&Address([4u8; 32])));
+        for (i, share) in [(1u8, 0.1), (2, 0.3), (3, 0.6)] {
+            let observed = counts[&Address([i; 32])] as f64 / rounds as f64;
+            assert!((observed - share).abs() < 0.03, "validator {} got {}", i, observed);
+        }
+    }
+
+    #[test]
+    fn test_validator_joining_mid_epoch_waits_for_next_epoch() {
+        let mut state = ConsensusState::new(100, 1);
+        state.set_epoch_length(10);
+        for i in 1..=3u8 {
+            state.update_validator(eligible(i)).unwrap();
+        }
+        state.update_height(0);
+
+        let seeds: Vec<Hash> = (0..100u64).map(|i| Hash::new(&i.to_le_bytes())).collect();
+        let before: Vec<Address> = seeds.iter()
+            .map(|seed| state.producer_for(seed, 5).unwrap())
+            .collect();
+
+        // Joins during epoch 0 (heights 0..10)
+        state.update_height(4);
+        state.update_validator(eligible(4)).unwrap();
+        let newcomer = Address([4u8; 32]);
+
+        for height in 5..10 {
+            state.update_height(height - 1);
+            let during: Vec<Address> = seeds.iter()
+                .map(|seed| state.producer_for(seed, height).unwrap())
+                .collect();
+            assert_eq!(during, before);
+        }
+
+        // Not yet frozen, epoch 1 draws from epoch 0's set rather than the live one
+        assert!(seeds.iter().all(|seed| state.producer_for(seed, 10).unwrap() != newcomer));
+
+        // The last block of epoch 0 snapshots the set for epoch 1
+        state.update_height(9);
+        assert_eq!(state.epoch_of(10), 1);
+        assert!(seeds.iter().any(|seed| state.producer_for(seed, 10).unwrap() == newcomer));
+        // Epoch 0's heights still resolve to the set they were produced under
+        let replayed: Vec<Address> = seeds.iter()
+            .map(|seed| state.producer_for(seed, 5).unwrap())
+            .collect();
+        assert_eq!(replayed, before);
+    }
+}
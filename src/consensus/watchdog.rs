@@ -0,0 +1,114 @@
+//! Block production watchdog
+//!
+//! Counts consecutive storage write failures while producing blocks. Once the
+//! limit is reached production halts for good and the node reports itself
+//! unready, so orchestration can restart or replace it instead of leaving a
+//! validator that "produces" blocks which never persist.
+
+use crate::{QoraNetError, Result};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Default number of consecutive storage failures before production halts
+pub const DEFAULT_MAX_STORAGE_FAILURES: u32 = 3;
+
+/// Shared between the production loop and the readiness endpoint
+#[derive(Debug)]
+pub struct ProductionWatchdog {
+    max_failures: u32,
+    consecutive_failures: AtomicU32,
+    halted: AtomicBool,
+}
+
+impl ProductionWatchdog {
+    pub fn new(max_failures: u32) -> Self {
+        Self {
+            max_failures: max_failures.max(1),
+            consecutive_failures: AtomicU32::new(0),
+            halted: AtomicBool::new(false),
+        }
+    }
+
+    /// Record the outcome of one production round; returns true if this round halted production
+    pub fn observe<T>(&self, outcome: &Result<T>) -> bool {
+        match outcome {
+            Ok(_) => {
+                self.consecutive_failures.store(0, Ordering::SeqCst);
+                false
+            }
+            Err(QoraNetError::StorageError(_)) => {
+                let failures = self.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1;
+                failures >= self.max_failures && !self.halted.swap(true, Ordering::SeqCst)
+            }
+            // Not a write failure; doesn't say anything about the disk
+            Err(_) => false,
+        }
+    }
+
+    /// Whether block production has stopped
+    pub fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::SeqCst)
+    }
+
+    /// Whether the node should pass readiness checks
+    pub fn is_ready(&self) -> bool {
+        !self.is_halted()
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for ProductionWatchdog {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_STORAGE_FAILURES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_failure() -> Result<()> {
+        Err(QoraNetError::StorageError("Failed to store block: No space left on device".to_string()))
+    }
+
+    #[test]
+    fn test_halts_after_consecutive_storage_failures() {
+        let watchdog = ProductionWatchdog::new(3);
+
+        assert!(!watchdog.observe(&write_failure()));
+        assert!(!watchdog.observe(&write_failure()));
+        assert!(watchdog.is_ready());
+
+        assert!(watchdog.observe(&write_failure()));
+        assert!(watchdog.is_halted());
+        assert!(!watchdog.is_ready());
+
+        // Halting is reported once and is not undone by a later success
+        assert!(!watchdog.observe(&write_failure()));
+        watchdog.observe(&Ok(()));
+        assert!(watchdog.is_halted());
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let watchdog = ProductionWatchdog::new(3);
+        watchdog.observe(&write_failure());
+        watchdog.observe(&write_failure());
+        watchdog.observe(&Ok(()));
+        assert_eq!(watchdog.consecutive_failures(), 0);
+
+        watchdog.observe(&write_failure());
+        watchdog.observe(&write_failure());
+        assert!(watchdog.is_ready());
+    }
+
+    #[test]
+    fn test_other_errors_do_not_count() {
+        let watchdog = ProductionWatchdog::new(1);
+        let rejected: Result<()> = Err(QoraNetError::ConsensusError("Invalid transactions root".to_string()));
+        assert!(!watchdog.observe(&rejected));
+        assert!(watchdog.is_ready());
+    }
+}
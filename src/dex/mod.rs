@@ -0,0 +1,239 @@
+//! Constant-product DEX pools
+//!
+//! Tracks pool reserves and LP supply so LP holders can see what their
+//! `LPToken.amount` is currently worth.
+
+use crate::{Address, LPToken, PoolType, QoraNetError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// LP units permanently locked by the first deposit so the supply can never return to zero
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/// Constant-product liquidity pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pool {
+    pub address: Address,
+    pub token_a: Address,
+    pub token_b: Address,
+    pub pool_type: PoolType,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub total_supply: u64, // Includes MINIMUM_LIQUIDITY once initialized
+}
+
+/// Current worth of an LP position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LpPosition {
+    pub lp_amount: u64,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub share_of_pool: f64,
+    pub impermanent_loss: Option<f64>, // Only known if an entry price is given
+}
+
+impl Pool {
+    pub fn new(address: Address, token_a: Address, token_b: Address, pool_type: PoolType) -> Self {
+        Self {
+            address,
+            token_a,
+            token_b,
+            pool_type,
+            reserve_a: 0,
+            reserve_b: 0,
+            total_supply: 0,
+        }
+    }
+
+    /// Deposit liquidity and return the LP amount minted to the provider.
+    ///
+    /// The first deposit mints `sqrt(a * b)` LP units, of which
+    /// `MINIMUM_LIQUIDITY` are locked and not returned to the provider.
+    pub fn add_liquidity(&mut self, amount_a: u64, amount_b: u64) -> Result<u64> {
+        if amount_a == 0 || amount_b == 0 {
+            return Err(QoraNetError::TokenError("Liquidity amounts must be non-zero".to_string()));
+        }
+
+        let minted = if self.total_supply == 0 {
+            let liquidity = integer_sqrt(amount_a as u128 * amount_b as u128) as u64;
+            if liquidity <= MINIMUM_LIQUIDITY {
+                return Err(QoraNetError::InsufficientLiquidity {
+                    required: MINIMUM_LIQUIDITY + 1,
+                    available: liquidity,
+                });
+            }
+            self.total_supply = MINIMUM_LIQUIDITY;
+            liquidity - MINIMUM_LIQUIDITY
+        } else {
+            let by_a = amount_a as u128 * self.total_supply as u128 / self.reserve_a as u128;
+            let by_b = amount_b as u128 * self.total_supply as u128 / self.reserve_b as u128;
+            u64::try_from(by_a.min(by_b))
+                .map_err(|_| QoraNetError::TokenError("LP supply overflow".to_string()))?
+        };
+
+        if minted == 0 {
+            return Err(QoraNetError::TokenError("Deposit too small to mint LP tokens".to_string()));
+        }
+
+        self.reserve_a = self.reserve_a.checked_add(amount_a)
+            .ok_or_else(|| QoraNetError::TokenError("Reserve overflow".to_string()))?;
+        self.reserve_b = self.reserve_b.checked_add(amount_b)
+            .ok_or_else(|| QoraNetError::TokenError("Reserve overflow".to_string()))?;
+        self.total_supply = self.total_supply.checked_add(minted)
+            .ok_or_else(|| QoraNetError::TokenError("LP supply overflow".to_string()))?;
+
+        Ok(minted)
+    }
+
+    /// Underlying token amounts redeemable for `lp_amount` at current reserves
+    pub fn lp_value(&self, lp_amount: u64) -> (u64, u64) {
+        if self.total_supply == 0 {
+            return (0, 0);
+        }
+
+        let lp_amount = lp_amount.min(self.total_supply) as u128;
+        let total_supply = self.total_supply as u128;
+        (
+            (lp_amount * self.reserve_a as u128 / total_supply) as u64,
+            (lp_amount * self.reserve_b as u128 / total_supply) as u64,
+        )
+    }
+
+    /// Price of token A in units of token B
+    pub fn price(&self) -> Option<f64> {
+        if self.reserve_a == 0 {
+            return None;
+        }
+        Some(self.reserve_b as f64 / self.reserve_a as f64)
+    }
+
+    /// Value and impermanent loss of an LP position
+    pub fn position(&self, lp_amount: u64, entry_price: Option<f64>) -> LpPosition {
+        let (amount_a, amount_b) = self.lp_value(lp_amount);
+        let share_of_pool = if self.total_supply == 0 {
+            0.0
+        } else {
+            lp_amount.min(self.total_supply) as f64 / self.total_supply as f64
+        };
+
+        let impermanent_loss = match (entry_price, self.price()) {
+            (Some(entry), Some(current)) => impermanent_loss(entry, current),
+            _ => None,
+        };
+
+        LpPosition {
+            lp_amount,
+            amount_a,
+            amount_b,
+            share_of_pool,
+            impermanent_loss,
+        }
+    }
+
+    /// Check if an LP token belongs to this pool
+    pub fn matches(&self, lp_token: &LPToken) -> bool {
+        lp_token.pool_address == self.address
+    }
+}
+
+/// Impermanent loss versus holding, as a fraction (e.g. -0.057 for 5.7% loss).
+///
+/// Prices are token A in units of token B at entry and now.
+pub fn impermanent_loss(entry_price: f64, current_price: f64) -> Option<f64> {
+    if !(entry_price > 0.0 && current_price > 0.0) {
+        return None;
+    }
+
+    let ratio = current_price / entry_price;
+    Some(2.0 * ratio.sqrt() / (1.0 + ratio) - 1.0)
+}
+
+/// Known pools by address
+#[derive(Debug, Default)]
+pub struct PoolRegistry {
+    pools: HashMap<Address, Pool>,
+}
+
+impl PoolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a pool
+    pub fn insert(&mut self, pool: Pool) {
+        self.pools.insert(pool.address.clone(), pool);
+    }
+
+    pub fn get(&self, address: &Address) -> Option<&Pool> {
+        self.pools.get(address)
+    }
+
+    pub fn get_mut(&mut self, address: &Address) -> Option<&mut Pool> {
+        self.pools.get_mut(address)
+    }
+}
+
+/// Integer square root (floor)
+fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    let mut x = value;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> Pool {
+        Pool::new(Address([9u8; 32]), Address::native_qor(), Address([2u8; 32]), PoolType::QorErc20)
+    }
+
+    #[test]
+    fn test_lp_value_of_total_supply_equals_reserves() {
+        let mut pool = pool();
+        pool.add_liquidity(1_000_000, 4_000_000).unwrap();
+        pool.add_liquidity(333_333, 1_333_332).unwrap();
+
+        assert_eq!(pool.lp_value(pool.total_supply), (pool.reserve_a, pool.reserve_b));
+    }
+
+    #[test]
+    fn test_first_deposit_locks_minimum_liquidity() {
+        let mut pool = pool();
+        let minted = pool.add_liquidity(1_000_000, 1_000_000).unwrap();
+
+        assert_eq!(minted, 1_000_000 - MINIMUM_LIQUIDITY);
+        assert_eq!(pool.total_supply, 1_000_000);
+
+        // The provider can redeem everything except the locked share
+        let (a, b) = pool.lp_value(minted);
+        assert_eq!((a, b), (999_000, 999_000));
+
+        // Too-small first deposit is rejected
+        let mut empty = self::pool();
+        assert!(empty.add_liquidity(10, 10).is_err());
+        assert_eq!(empty.lp_value(100), (0, 0));
+    }
+
+    #[test]
+    fn test_impermanent_loss_zero_when_price_unchanged() {
+        assert_eq!(impermanent_loss(2.5, 2.5), Some(0.0));
+
+        // Price 4x -> ~20% loss
+        let il = impermanent_loss(1.0, 4.0).unwrap();
+        assert!((il + 0.2).abs() < 1e-9);
+
+        let mut pool = pool();
+        pool.add_liquidity(1_000_000, 2_000_000).unwrap();
+        let position = pool.position(500_000, Some(2.0));
+        assert_eq!(position.impermanent_loss, Some(0.0));
+    }
+}
@@ -1,15 +1,189 @@
-use crate::{Result, QoraNetError, MIN_FEE_USD, MAX_FEE_USD, DEFAULT_FEE_USD, usd_to_qor, qor_to_usd};
+use crate::{Address, Result, QoraNetError, Transaction, MIN_FEE_USD, MAX_FEE_USD, DEFAULT_FEE_USD, usd_to_qor, qor_to_usd};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use tokio::time::{Duration, Instant};
 
+/// Default distance from the median, in percent, beyond which a source's quote is discarded
+pub const DEFAULT_MAX_DEVIATION_PCT: f64 = 20.0;
+
+/// Default age past which a sourced price is too old to validate fees against
+pub const DEFAULT_MAX_PRICE_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Default time allowed for a single price source to answer
+pub const DEFAULT_SOURCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default time between price updates
+pub const DEFAULT_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default upper bound on the random delay added to each update interval
+pub const DEFAULT_UPDATE_JITTER: Duration = Duration::from_secs(15);
+
+/// Default time a source's quote is reused instead of asking the source again
+pub const DEFAULT_QUOTE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Backoff after a rate-limited response that carries no usable `Retry-After`
+pub const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Longest a rate-limited source is left alone, whatever it asks for
+pub const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
 /// Price oracle for QOR token and fee calculation
 #[derive(Debug, Clone)]
 pub struct FeeOracle {
     qor_price_usd: f64,
+    has_sourced_price: bool, // True once any price source has succeeded
+    fallback_price: Option<f64>, // Used only until a source succeeds
     last_update: Instant,
     update_interval: Duration,
+    update_jitter: Duration, // Spreads a fleet's updates so they don't hit the APIs together
+    quote_cache_ttl: Duration,
+    source_states: HashMap<String, SourceState>, // source name -> cached quote and backoff
     price_sources: Vec<PriceSource>,
+    discount_policy: FeeDiscountPolicy,
+    token_prices: HashMap<String, f64>, // symbol -> USD price, for ERC-20 fee tokens
+    http: reqwest::Client,
+    source_timeout: Duration, // Per source, so one hung API can't stall the update
+    deviation_guard: PriceDeviationGuard,
+    pending_jump: Option<PendingPriceJump>, // Deviating price held back until confirmed
+    max_deviation_pct: f64, // Quotes this far from the weighted median are outliers
+    max_price_age: Duration, // Fees aren't validated against a sourced price older than this
+}
+
+/// Limits on how far one price update may move the accepted price.
+///
+/// A jump past `max_deviation_bps` is held back (the old price stays in force)
+/// until it is seen again on `confirmations` consecutive updates or an
+/// operator acknowledges it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceDeviationGuard {
+    pub max_deviation_bps: u32,
+    pub confirmations: u32,
+}
+
+impl Default for PriceDeviationGuard {
+    fn default() -> Self {
+        Self {
+            max_deviation_bps: 5_000, // 50%
+            confirmations: 3,
+        }
+    }
+}
+
+/// Per-source cached quote and rate-limit backoff
+#[derive(Debug, Clone, Default)]
+struct SourceState {
+    quote: Option<(f64, Instant)>,
+    backoff_until: Option<Instant>,
+    rate_limited: u32, // Consecutive rate-limited responses
+}
+
+/// Why a price source produced no quote
+#[derive(Debug)]
+enum SourceFailure {
+    /// HTTP 429/503; `retry_after` is the server's `Retry-After`, if usable
+    RateLimited { retry_after: Option<Duration> },
+    /// Still inside a previous rate-limit backoff, so not asked at all
+    BackingOff(Duration),
+    TimedOut,
+    Failed(QoraNetError),
+}
+
+#[derive(Debug, Clone)]
+struct PendingPriceJump {
+    price: f64,
+    observations: u32,
+}
+
+/// Price jump awaiting confirmation, as reported over RPC
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceAlarm {
+    pub accepted_price: f64,
+    pub pending_price: f64,
+    pub deviation_bps: u64,
+    pub observations: u32,
+    pub confirmations_required: u32,
+}
+
+/// USD price from a CoinGecko or CoinMarketCap quote body.
+///
+/// CoinGecko: `{"qor": {"usd": 0.05}}`.
+/// CoinMarketCap: `{"data": {"QOR": {"quote": {"USD": {"price": 0.05}}}}}`, where
+/// the entry under the symbol may also be a list.
+fn parse_usd_price(body: &Value) -> Option<f64> {
+    if let Some(data) = body.get("data") {
+        let entries = data.as_object()?;
+        let entry = entries.values().next()?;
+        let entry = entry.as_array().and_then(|list| list.first()).unwrap_or(entry);
+        return entry.pointer("/quote/USD/price")?.as_f64();
+    }
+    
+    let coins = body.as_object()?;
+    if coins.len() != 1 {
+        return None;
+    }
+    coins.values().next()?.get("usd")?.as_f64()
+}
+
+/// Weighted mean of the `(price, weight)` quotes within `max_deviation_pct` of their weighted median.
+///
+/// Errors, so the last accepted price stays in force, when more than half of
+/// the quotes are outliers.
+fn aggregate_quotes(quotes: &[(f64, f64)], max_deviation_pct: f64) -> Result<f64> {
+    let mut sorted: Vec<(f64, f64)> = quotes.iter().copied().filter(|(_, weight)| *weight > 0.0).collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    
+    let total_weight: f64 = sorted.iter().map(|(_, weight)| weight).sum();
+    let mut cumulative = 0.0;
+    let median = sorted.iter().find(|(_, weight)| {
+        cumulative += weight;
+        cumulative >= total_weight / 2.0
+    }).map(|(price, _)| *price)
+        .ok_or_else(|| QoraNetError::NetworkError("No weighted QOR price quotes".to_string()))?;
+    
+    let inliers: Vec<(f64, f64)> = sorted.iter().copied()
+        .filter(|(price, _)| (price - median).abs() / median * 100.0 <= max_deviation_pct)
+        .collect();
+    
+    let rejected = sorted.len() - inliers.len();
+    if rejected * 2 > sorted.len() {
+        tracing::warn!(
+            "{} of {} QOR price quotes deviate more than {}% from the median ${}; keeping the last price",
+            rejected, sorted.len(), max_deviation_pct, median
+        );
+        return Err(QoraNetError::NetworkError(format!(
+            "QOR price sources disagree: {} of {} quotes are outliers",
+            rejected, sorted.len()
+        )));
+    }
+    if rejected > 0 {
+        tracing::warn!("Discarded {} outlying QOR price quote(s) around median ${}", rejected, median);
+    }
+    
+    let inlier_weight: f64 = inliers.iter().map(|(_, weight)| weight).sum();
+    Ok(inliers.iter().map(|(price, weight)| price * weight).sum::<f64>() / inlier_weight)
+}
+
+/// `Retry-After` in its delta-seconds form; HTTP dates fall back to the default backoff
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// Backoff after the `attempt`-th consecutive rate-limited response.
+///
+/// Honours the server's `Retry-After` when given, otherwise doubles from
+/// `DEFAULT_RATE_LIMIT_BACKOFF`; either way capped at `MAX_RATE_LIMIT_BACKOFF`.
+fn rate_limit_backoff(retry_after: Option<Duration>, attempt: u32) -> Duration {
+    let backoff = retry_after.unwrap_or_else(|| {
+        DEFAULT_RATE_LIMIT_BACKOFF.saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+    });
+    backoff.min(MAX_RATE_LIMIT_BACKOFF)
+}
+
+/// Relative difference between two prices in basis points
+fn deviation_bps(from: f64, to: f64) -> u64 {
+    ((to - from).abs() / from * 10_000.0).round() as u64
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +191,90 @@ pub struct PriceSource {
     pub name: String,
     pub url: String,
     pub weight: f64, // Weight for price aggregation
+    /// Sent with every request, e.g. an API key
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+}
+
+/// CoinMarketCap quotes endpoint; every request needs an API key
+const COINMARKETCAP_QUOTES_URL: &str = "https://pro-api.coinmarketcap.com/v1/cryptocurrency/quotes/latest";
+
+impl PriceSource {
+    /// CoinMarketCap quotes for QOR from `endpoint`, authenticated with `api_key`
+    fn coinmarketcap(endpoint: &str, api_key: &str) -> Self {
+        Self {
+            name: "CoinMarketCap".to_string(),
+            url: format!("{}?symbol=QOR&convert=USD", endpoint),
+            weight: 0.4,
+            headers: vec![("X-CMC_PRO_API_KEY".to_string(), api_key.to_string())],
+        }
+    }
+}
+
+/// Holdings of a fee payer relevant to fee discounts (QOR base units)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountHoldings {
+    pub qor_balance: u64,
+    pub lp_value: u64,
+}
+
+/// Balance threshold and the discount (basis points) it unlocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscountTier {
+    pub min_amount: u64,
+    pub discount_bps: u16,
+}
+
+/// Fee discounts for QOR holders and liquidity providers.
+///
+/// Only integer thresholds and basis points are involved, so every validator
+/// derives the same discount from the same on-chain holdings. The best
+/// matching QOR or LP tier applies; discounted fees never go below `MIN_FEE_USD`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeDiscountPolicy {
+    qor_tiers: Vec<DiscountTier>,
+    lp_tiers: Vec<DiscountTier>,
+}
+
+/// Upper bound on any discount (100%)
+const MAX_DISCOUNT_BPS: u16 = 10_000;
+
+impl FeeDiscountPolicy {
+    /// Policy with no discounts
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Add a discount for accounts holding at least `min_qor` QOR base units
+    pub fn with_qor_tier(mut self, min_qor: u64, discount_bps: u16) -> Self {
+        self.qor_tiers.push(DiscountTier { min_amount: min_qor, discount_bps: discount_bps.min(MAX_DISCOUNT_BPS) });
+        self
+    }
+
+    /// Add a discount for accounts with at least `min_lp_value` in LP positions
+    pub fn with_lp_tier(mut self, min_lp_value: u64, discount_bps: u16) -> Self {
+        self.lp_tiers.push(DiscountTier { min_amount: min_lp_value, discount_bps: discount_bps.min(MAX_DISCOUNT_BPS) });
+        self
+    }
+
+    /// Discount in basis points for the given holdings
+    pub fn discount_bps(&self, holdings: &AccountHoldings) -> u16 {
+        let best = |tiers: &[DiscountTier], amount: u64| {
+            tiers.iter()
+                .filter(|tier| amount >= tier.min_amount)
+                .map(|tier| tier.discount_bps)
+                .max()
+                .unwrap_or(0)
+        };
+
+        best(&self.qor_tiers, holdings.qor_balance).max(best(&self.lp_tiers, holdings.lp_value))
+    }
+
+    /// Apply the discount to a USD fee, floored at `MIN_FEE_USD`
+    pub fn apply(&self, fee_usd: f64, holdings: &AccountHoldings) -> f64 {
+        let remaining_bps = (MAX_DISCOUNT_BPS - self.discount_bps(holdings)) as f64;
+        (fee_usd * remaining_bps / MAX_DISCOUNT_BPS as f64).max(MIN_FEE_USD)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +285,8 @@ pub enum TransactionType {
     ReportMetrics,
     ClaimRewards,
     SmartContract { complexity: ContractComplexity },
+    ReportEquivocation,
+    SetSendLimit,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,137 +300,435 @@ impl FeeOracle {
     pub fn new() -> Self {
         Self {
             qor_price_usd: 1.0, // Default price, will be updated
+            has_sourced_price: false,
+            fallback_price: None,
             last_update: Instant::now(),
-            update_interval: Duration::from_secs(60), // Update every minute
+            update_interval: DEFAULT_UPDATE_INTERVAL,
+            update_jitter: DEFAULT_UPDATE_JITTER,
+            quote_cache_ttl: DEFAULT_QUOTE_CACHE_TTL,
+            source_states: HashMap::new(),
             price_sources: vec![
                 PriceSource {
                     name: "CoinGecko".to_string(),
                     url: "https://api.coingecko.com/api/v3/simple/price?ids=qor&vs_currencies=usd".to_string(),
                     weight: 0.4,
-                },
-                PriceSource {
-                    name: "CoinMarketCap".to_string(),
-                    url: "https://pro-api.coinmarketcap.com/v1/cryptocurrency/quotes/latest".to_string(),
-                    weight: 0.4,
+                    headers: Vec::new(),
                 },
                 PriceSource {
                     name: "DEX Price".to_string(),
                     url: "internal://dex-price".to_string(),
                     weight: 0.2,
+                    headers: Vec::new(),
                 },
             ],
+            discount_policy: FeeDiscountPolicy::none(),
+            token_prices: HashMap::new(),
+            http: reqwest::Client::new(),
+            source_timeout: DEFAULT_SOURCE_TIMEOUT,
+            deviation_guard: PriceDeviationGuard::default(),
+            pending_jump: None,
+            max_deviation_pct: DEFAULT_MAX_DEVIATION_PCT,
+            max_price_age: DEFAULT_MAX_PRICE_AGE,
+        }
+    }
+    
+    /// Set how far a single update may move the price before it needs confirming
+    pub fn with_deviation_guard(mut self, deviation_guard: PriceDeviationGuard) -> Self {
+        self.deviation_guard = deviation_guard;
+        self
+    }
+    
+    /// Set the price used until a source has succeeded at least once
+    pub fn with_fallback_price(mut self, price_usd: f64) -> Self {
+        self.fallback_price = Some(price_usd).filter(|p| p.is_finite() && *p > 0.0);
+        self
+    }
+    
+    /// Replace the configured price sources
+    pub fn with_price_sources(mut self, price_sources: Vec<PriceSource>) -> Self {
+        self.price_sources = price_sources;
+        self
+    }
+    
+    /// Also quote CoinMarketCap, which only answers requests carrying an API key
+    pub fn with_coinmarketcap_api_key(mut self, api_key: &str) -> Self {
+        self.price_sources.retain(|source| source.name != "CoinMarketCap");
+        self.price_sources.push(PriceSource::coinmarketcap(COINMARKETCAP_QUOTES_URL, api_key));
+        self
+    }
+    
+    /// Set the fee discount policy for QOR holders and LPs
+    pub fn with_discount_policy(mut self, discount_policy: FeeDiscountPolicy) -> Self {
+        self.discount_policy = discount_policy;
+        self
+    }
+    
+    /// Set how far (in percent) a quote may sit from the median before it is discarded
+    pub fn set_outlier_threshold(&mut self, max_deviation_pct: f64) {
+        if max_deviation_pct.is_finite() && max_deviation_pct >= 0.0 {
+            self.max_deviation_pct = max_deviation_pct;
+        }
+    }
+    
+    /// Set how old a sourced price may get before fee validation refuses it
+    pub fn with_max_price_age(mut self, max_price_age: Duration) -> Self {
+        self.max_price_age = max_price_age;
+        self
+    }
+    
+    /// Set the minimum time between price updates
+    pub fn with_update_interval(mut self, update_interval: Duration) -> Self {
+        self.update_interval = update_interval;
+        self
+    }
+    
+    /// Set the upper bound on the random delay added to each update interval
+    pub fn with_update_jitter(mut self, update_jitter: Duration) -> Self {
+        self.update_jitter = update_jitter;
+        self
+    }
+    
+    /// Set how long a source's quote is reused before the source is asked again
+    pub fn with_quote_cache_ttl(mut self, quote_cache_ttl: Duration) -> Self {
+        self.quote_cache_ttl = quote_cache_ttl;
+        self
+    }
+    
+    /// Delay before the next scheduled update: the interval plus a random share of the jitter
+    pub fn next_update_delay(&self) -> Duration {
+        let jitter_ms = self.update_jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            return self.update_interval;
+        }
+        self.update_interval + Duration::from_millis(rand::thread_rng().gen_range(0..=jitter_ms))
+    }
+    
+    /// Remaining rate-limit backoff for a source, if it is backing off
+    pub fn source_backoff(&self, source_name: &str) -> Option<Duration> {
+        let until = self.source_states.get(source_name)?.backoff_until?;
+        let now = Instant::now();
+        (until > now).then(|| until - now)
+    }
+    
+    /// Set how long each price source may take before it counts as failed
+    pub fn with_source_timeout(mut self, source_timeout: Duration) -> Self {
+        self.source_timeout = source_timeout;
+        self
+    }
+    
+    /// Set the USD price of an ERC-20 fee token
+    pub fn with_token_price(mut self, symbol: &str, price_usd: f64) -> Self {
+        self.set_token_price(symbol, price_usd);
+        self
+    }
+    
+    /// Update the USD price of an ERC-20 fee token
+    pub fn set_token_price(&mut self, symbol: &str, price_usd: f64) {
+        if price_usd.is_finite() && price_usd > 0.0 {
+            self.token_prices.insert(symbol.to_string(), price_usd);
         }
     }
     
-    /// Get current QOR price in USD
+    /// USD price of an ERC-20 fee token
+    pub fn get_token_price(&self, symbol: &str) -> Result<f64> {
+        self.token_prices.get(symbol).copied().ok_or_else(|| QoraNetError::TokenError(
+            format!("No price available for fee token {}", symbol)
+        ))
+    }
+    
+    /// Current fee discount policy
+    pub fn discount_policy(&self) -> &FeeDiscountPolicy {
+        &self.discount_policy
+    }
+    
+    /// Check if a price has ever been sourced successfully
+    pub fn has_sourced_price(&self) -> bool {
+        self.has_sourced_price
+    }
+    
+    /// Price to use for fee calculation, if one is known.
+    ///
+    /// Errors if no source has ever succeeded and no fallback is configured.
+    pub fn effective_price(&self) -> Result<f64> {
+        if self.has_sourced_price {
+            Ok(self.qor_price_usd)
+        } else {
+            self.fallback_price.ok_or_else(|| QoraNetError::InvalidTransaction(
+                "QOR price unavailable: no price source has succeeded and no fallback price is set".to_string()
+            ))
+        }
+    }
+    
+    /// Time since the price was last updated
+    pub fn price_age(&self) -> Duration {
+        self.last_update.elapsed()
+    }
+    
+    /// Whether the sourced price is older than `max_price_age`.
+    ///
+    /// A configured fallback price never goes stale; it is only used until the
+    /// first source succeeds.
+    pub fn is_price_stale(&self) -> bool {
+        self.has_sourced_price && self.price_age() > self.max_price_age
+    }
+    
+    /// Get current QOR price in USD (always positive)
     pub fn get_qor_price(&self) -> f64 {
-        self.qor_price_usd
+        self.effective_price().unwrap_or(self.qor_price_usd)
     }
     
     /// Update QOR price from external sources
     pub async fn update_price(&mut self) -> Result<()> {
-        if self.last_update.elapsed() < self.update_interval {
+        // Only throttle once we have a real price
+        if self.has_sourced_price && self.last_update.elapsed() < self.update_interval {
             return Ok(()); // Too soon to update
         }
         
-        let mut total_weighted_price = 0.0;
-        let mut total_weight = 0.0;
+        let mut accepted_quotes = Vec::new();
+        
+        // Query all sources at once; a slow one costs at most `source_timeout`.
+        // Recent quotes are reused and rate-limited sources are left alone.
+        let quotes = futures_util::future::join_all(self.price_sources.iter().map(|source| async move {
+            let state = self.source_states.get(&source.name);
+            let now = Instant::now();
+            if let Some((price, fetched_at)) = state.and_then(|state| state.quote) {
+                if now.duration_since(fetched_at) < self.quote_cache_ttl {
+                    return (source, Ok(price), false);
+                }
+            }
+            if let Some(until) = state.and_then(|state| state.backoff_until).filter(|until| *until > now) {
+                return (source, Err(SourceFailure::BackingOff(until - now)), false);
+            }
+            let quote = tokio::time::timeout(self.source_timeout, self.fetch_price_from_source(source)).await
+                .unwrap_or(Err(SourceFailure::TimedOut));
+            (source, quote, true)
+        })).await;
+        
+        let mut updates = Vec::new();
+        for (source, quote, fetched) in quotes {
+            match quote {
+                // Ignore nonsensical quotes rather than letting them zero out fees
+                Ok(price) if price.is_finite() && price > 0.0 => {
+                    accepted_quotes.push((price, source.weight));
+                    if fetched {
+                        updates.push((source.name.clone(), Ok(price)));
+                    }
+                },
+                Ok(price) => tracing::warn!("Ignoring QOR price {} from {}", price, source.name),
+                Err(SourceFailure::RateLimited { retry_after }) => updates.push((source.name.clone(), Err(retry_after))),
+                Err(SourceFailure::BackingOff(remaining)) => {
+                    tracing::debug!("QOR price source {} rate limited; retrying in {:?}", source.name, remaining);
+                },
+                Err(SourceFailure::TimedOut) => {
+                    tracing::warn!("QOR price source {} timed out after {:?}", source.name, self.source_timeout);
+                },
+                Err(SourceFailure::Failed(e)) => tracing::warn!("QOR price source {} failed: {}", source.name, e),
+            }
+        }
         
-        for source in &self.price_sources {
-            if let Ok(price) = self.fetch_price_from_source(source).await {
-                total_weighted_price += price * source.weight;
-                total_weight += source.weight;
+        let now = Instant::now();
+        for (name, outcome) in updates {
+            let state = self.source_states.entry(name.clone()).or_default();
+            match outcome {
+                Ok(price) => *state = SourceState { quote: Some((price, now)), ..SourceState::default() },
+                Err(retry_after) => {
+                    state.rate_limited += 1;
+                    let backoff = rate_limit_backoff(retry_after, state.rate_limited);
+                    state.backoff_until = Some(now + backoff);
+                    tracing::warn!("QOR price source {} rate limited; backing off for {:?}", name, backoff);
+                },
             }
         }
         
-        if total_weight > 0.0 {
-            self.qor_price_usd = total_weighted_price / total_weight;
-            self.last_update = Instant::now();
+        // The previously accepted price stays in force
+        if accepted_quotes.is_empty() {
+            return Err(QoraNetError::NetworkError("All QOR price sources failed".to_string()));
         }
         
-        Ok(())
+        let price = aggregate_quotes(&accepted_quotes, self.max_deviation_pct)?;
+        self.submit_price(price)
+    }
+    
+    /// Offer a newly aggregated price.
+    ///
+    /// Prices within the deviation guard are accepted. A larger jump is rejected
+    /// and raises the price alarm; it is accepted once the same level is seen on
+    /// enough consecutive updates, or when acknowledged.
+    pub fn submit_price(&mut self, price: f64) -> Result<()> {
+        if !price.is_finite() || price <= 0.0 {
+            return Err(QoraNetError::NetworkError(format!("Invalid QOR price {}", price)));
+        }
+        
+        let max_bps = self.deviation_guard.max_deviation_bps as u64;
+        if !self.has_sourced_price || deviation_bps(self.qor_price_usd, price) <= max_bps {
+            self.accept_price(price);
+            return Ok(());
+        }
+        
+        // Count consecutive sightings of (roughly) the same new level
+        let observations = match &self.pending_jump {
+            Some(pending) if deviation_bps(pending.price, price) <= max_bps => pending.observations + 1,
+            _ => 1,
+        };
+        if observations >= self.deviation_guard.confirmations {
+            tracing::warn!("QOR price jump to ${} confirmed after {} updates", price, observations);
+            self.accept_price(price);
+            return Ok(());
+        }
+        
+        self.pending_jump = Some(PendingPriceJump { price, observations });
+        tracing::warn!(
+            "QOR price jump from ${} to ${} held for confirmation ({}/{})",
+            self.qor_price_usd, price, observations, self.deviation_guard.confirmations
+        );
+        Err(QoraNetError::NetworkError(format!(
+            "QOR price ${} deviates {} bps from ${}; held for confirmation",
+            price, deviation_bps(self.qor_price_usd, price), self.qor_price_usd
+        )))
+    }
+    
+    /// Operator acknowledgement: accept the held price jump; returns the new price
+    pub fn acknowledge_price_jump(&mut self) -> Result<f64> {
+        let pending = self.pending_jump.take().ok_or_else(|| QoraNetError::NetworkError(
+            "No QOR price jump awaiting confirmation".to_string()
+        ))?;
+        self.accept_price(pending.price);
+        Ok(pending.price)
+    }
+    
+    /// Held price jump, if any
+    pub fn price_alarm(&self) -> Option<PriceAlarm> {
+        self.pending_jump.as_ref().map(|pending| PriceAlarm {
+            accepted_price: self.qor_price_usd,
+            pending_price: pending.price,
+            deviation_bps: deviation_bps(self.qor_price_usd, pending.price),
+            observations: pending.observations,
+            confirmations_required: self.deviation_guard.confirmations,
+        })
+    }
+    
+    /// Only an accepted price refreshes the price age; a held jump leaves it to grow stale
+    fn accept_price(&mut self, price: f64) {
+        self.qor_price_usd = price;
+        self.has_sourced_price = true;
+        self.pending_jump = None;
+        self.last_update = Instant::now();
     }
     
     /// Fetch price from a specific source
-    async fn fetch_price_from_source(&self, source: &PriceSource) -> Result<f64> {
+    async fn fetch_price_from_source(&self, source: &PriceSource) -> std::result::Result<f64, SourceFailure> {
         match source.url.as_str() {
             url if url.starts_with("internal://dex-price") => {
                 // Get price from internal DEX pools
-                self.get_dex_price().await
+                self.get_dex_price().await.map_err(SourceFailure::Failed)
             },
             _ => {
                 // Fetch from external API
-                self.fetch_external_price(&source.url).await
+                self.fetch_external_price(source).await
             }
         }
     }
     
-    /// Get price from internal DEX pools
+    /// Get price from internal DEX pools.
+    ///
+    /// Fails until the pools can be queried: echoing our own price back would
+    /// count as a live source and keep the price fresh through an outage.
     async fn get_dex_price(&self) -> Result<f64> {
-        // In a real implementation, this would query the DEX pools
-        // For now, return a mock price
-        Ok(self.qor_price_usd) // Placeholder
+        Err(QoraNetError::NetworkError("DEX price source is not available yet".to_string()))
     }
     
-    /// Fetch price from external API
-    async fn fetch_external_price(&self, url: &str) -> Result<f64> {
-        // In a real implementation, this would make HTTP requests
-        // For now, return a mock price with some variation
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        let variation = rng.gen_range(-0.05..0.05); // ±5% variation
-        Ok(self.qor_price_usd * (1.0 + variation))
+    /// Fetch price from external API, telling rate limiting apart from other failures
+    async fn fetch_external_price(&self, source: &PriceSource) -> std::result::Result<f64, SourceFailure> {
+        let url = &source.url;
+        let mut request = self.http.get(url).timeout(self.source_timeout);
+        for (name, value) in &source.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| SourceFailure::Failed(QoraNetError::NetworkError(format!("Price request to {} failed: {}", url, e))))?;
+        
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            let retry_after = response.headers().get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(SourceFailure::RateLimited { retry_after });
+        }
+        
+        let response = response.error_for_status()
+            .map_err(|e| SourceFailure::Failed(QoraNetError::NetworkError(format!("Price request to {} failed: {}", url, e))))?;
+        
+        let body: Value = response.json().await
+            .map_err(|e| SourceFailure::Failed(QoraNetError::NetworkError(format!("Invalid price response from {}: {}", url, e))))?;
+        
+        parse_usd_price(&body)
+            .ok_or_else(|| SourceFailure::Failed(QoraNetError::NetworkError(format!("Unrecognized price response from {}", url))))
     }
     
     /// Calculate transaction fee in QOR tokens
     pub fn calculate_fee(&self, tx_type: &TransactionType, priority: FeePriority) -> u64 {
+        self.calculate_fee_for(tx_type, priority, &AccountHoldings::default())
+    }
+    
+    /// Calculate transaction fee in QOR tokens, discounted for the payer's holdings
+    pub fn calculate_fee_for(&self, tx_type: &TransactionType, priority: FeePriority, holdings: &AccountHoldings) -> u64 {
+        usd_to_qor(self.fee_usd_for(tx_type, priority, holdings), self.get_qor_price())
+    }
+    
+    /// Fee in USD before conversion to QOR, discounted for the payer's holdings
+    pub(crate) fn fee_usd_for(&self, tx_type: &TransactionType, priority: FeePriority, holdings: &AccountHoldings) -> f64 {
         let base_fee_usd = self.get_base_fee_usd(tx_type);
         let priority_multiplier = self.get_priority_multiplier(priority);
         let final_fee_usd = (base_fee_usd * priority_multiplier).clamp(MIN_FEE_USD, MAX_FEE_USD);
-        
-        usd_to_qor(final_fee_usd, self.qor_price_usd)
+        self.discount_policy.apply(final_fee_usd, holdings)
+    }
+    
+    /// How `calculate_fee_for` arrives at its total, for receipts
+    pub fn fee_breakdown_for(&self, tx_type: &TransactionType, priority: FeePriority, holdings: &AccountHoldings) -> FeeBreakdown {
+        let total_fee_qor = self.calculate_fee_for(tx_type, priority, holdings);
+        FeeBreakdown::split(total_fee_qor, tx_type, holdings, &self.discount_policy, self.get_qor_price())
     }
     
     /// Get base fee in USD for transaction type
     fn get_base_fee_usd(&self, tx_type: &TransactionType) -> f64 {
-        match tx_type {
-            TransactionType::Transfer => DEFAULT_FEE_USD,
-            TransactionType::ProvideLiquidity => DEFAULT_FEE_USD * 2.0,
-            TransactionType::RegisterApp => DEFAULT_FEE_USD * 5.0,
-            TransactionType::ReportMetrics => DEFAULT_FEE_USD * 0.5,
-            TransactionType::ClaimRewards => DEFAULT_FEE_USD * 1.5,
-            TransactionType::SmartContract { complexity } => {
-                match complexity {
-                    ContractComplexity::Simple => DEFAULT_FEE_USD * 3.0,
-                    ContractComplexity::Medium => DEFAULT_FEE_USD * 10.0,
-                    ContractComplexity::Complex => DEFAULT_FEE_USD * 50.0,
-                }
-            }
-        }
+        scheduled_fee_usd(tx_type)
     }
     
     /// Get priority multiplier
     fn get_priority_multiplier(&self, priority: FeePriority) -> f64 {
-        match priority {
-            FeePriority::Low => 1.0,
-            FeePriority::Medium => 1.5,
-            FeePriority::High => 2.0,
-            FeePriority::Urgent => 5.0,
-        }
+        priority_multiplier(&priority)
     }
     
     /// Validate fee amount
     pub fn validate_fee(&self, fee_qor: u64, tx_type: &TransactionType) -> Result<()> {
-        let fee_usd = qor_to_usd(fee_qor, self.qor_price_usd);
-        let min_required_usd = self.get_base_fee_usd(tx_type);
+        self.validate_fee_for(fee_qor, tx_type, &AccountHoldings::default())
+    }
+    
+    /// Validate fee amount, allowing the payer's holdings discount.
+    ///
+    /// Bounds are converted to QOR with the same rounding the builder uses, so a
+    /// fee from `calculate_fee_for` at the same price always validates.
+    pub fn validate_fee_for(&self, fee_qor: u64, tx_type: &TransactionType, holdings: &AccountHoldings) -> Result<()> {
+        // During an oracle outage the old price may badly underprice fees
+        if self.is_price_stale() {
+            return Err(QoraNetError::InvalidTransaction(format!(
+                "QOR price is stale: last updated {}s ago, maximum age {}s",
+                self.price_age().as_secs(), self.max_price_age.as_secs()
+            )));
+        }
+        let price = self.effective_price()?;
+        let fee_usd = qor_to_usd(fee_qor, price);
+        let min_required_usd = self.discount_policy.apply(self.get_base_fee_usd(tx_type), holdings);
         
-        if fee_usd < min_required_usd {
+        if fee_qor < usd_to_qor(min_required_usd, price) {
             return Err(QoraNetError::InvalidTransaction(
                 format!("Fee too low: ${:.6} provided, ${:.6} required", fee_usd, min_required_usd)
             ));
         }
         
-        if fee_usd > MAX_FEE_USD {
+        if fee_qor > usd_to_qor(MAX_FEE_USD, price) {
             return Err(QoraNetError::InvalidTransaction(
                 format!("Fee too high: ${:.6} provided, ${:.6} maximum", fee_usd, MAX_FEE_USD)
             ));
@@ -186,7 +744,7 @@ impl FeeOracle {
             medium: self.calculate_fee(tx_type, FeePriority::Medium),
             high: self.calculate_fee(tx_type, FeePriority::High),
             urgent: self.calculate_fee(tx_type, FeePriority::Urgent),
-            qor_price_usd: self.qor_price_usd,
+            qor_price_usd: self.get_qor_price(),
         }
     }
 }
@@ -199,6 +757,130 @@ pub enum FeePriority {
     Urgent,  // 5x multiplier
 }
 
+impl FeePriority {
+    /// All priorities, cheapest first
+    pub const ALL: [FeePriority; 4] = [FeePriority::Low, FeePriority::Medium, FeePriority::High, FeePriority::Urgent];
+}
+
+/// Itemized fee shown on a transaction receipt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeBreakdown {
+    /// Fee at `Low` priority, after any holdings discount
+    pub base_fee_qor: u64,
+    /// Extra paid for the chosen priority
+    pub priority_tip_qor: u64,
+    /// Always `base_fee_qor + priority_tip_qor`
+    pub total_fee_qor: u64,
+    /// ERC-20 token the fee was paid in, if not QOR
+    pub fee_token: Option<Address>,
+    /// Amount charged in `fee_token` units
+    pub fee_token_amount: Option<u64>,
+    /// USD price used for the conversion: QOR's, or the fee token's when paid in a token
+    pub price_used: f64,
+}
+
+impl FeeBreakdown {
+    /// Split `total_fee_qor` into the base fee and the priority tip at `price_used`.
+    ///
+    /// The base is the type's scheduled fee, clamped and discounted for the
+    /// payer's `holdings`. Quotes and receipts are both itemized here, so a
+    /// receipt breaks a fee down the way it was quoted.
+    pub fn split(
+        total_fee_qor: u64,
+        tx_type: &TransactionType,
+        holdings: &AccountHoldings,
+        discount_policy: &FeeDiscountPolicy,
+        price_used: f64,
+    ) -> Self {
+        let base_fee_usd = discount_policy.apply(scheduled_fee_usd(tx_type).clamp(MIN_FEE_USD, MAX_FEE_USD), holdings);
+        // Clamping can squeeze the total below the base; the tip never goes negative
+        let base_fee_qor = usd_to_qor(base_fee_usd, price_used).min(total_fee_qor);
+        
+        Self {
+            base_fee_qor,
+            priority_tip_qor: total_fee_qor - base_fee_qor,
+            total_fee_qor,
+            fee_token: None,
+            fee_token_amount: None,
+            price_used,
+        }
+    }
+    
+    /// Breakdown of the QOR fee `transaction` signed for, as charged when its block is applied.
+    ///
+    /// The price is the one implied by the quoted USD fee, so every node derives
+    /// the same breakdown without consulting its oracle.
+    pub fn charged(transaction: &Transaction, holdings: &AccountHoldings, discount_policy: &FeeDiscountPolicy) -> Self {
+        let fee_qor = transaction.fee_qor;
+        let price_used = if fee_qor == 0 { 0.0 } else { transaction.fee_usd * 1_000_000_000.0 / fee_qor as f64 };
+        Self::split(fee_qor, &transaction.data.transaction_type(), holdings, discount_policy, price_used)
+    }
+}
+
+/// Scheduled fee in USD for a transaction type, before priority, clamping and discounts
+fn scheduled_fee_usd(tx_type: &TransactionType) -> f64 {
+    match tx_type {
+        TransactionType::Transfer => DEFAULT_FEE_USD,
+        TransactionType::ProvideLiquidity => DEFAULT_FEE_USD * 2.0,
+        TransactionType::RegisterApp => DEFAULT_FEE_USD * 5.0,
+        TransactionType::ReportMetrics => DEFAULT_FEE_USD * 0.5,
+        TransactionType::ClaimRewards => DEFAULT_FEE_USD * 1.5,
+        // Producers include evidence fee-free; the schedule only matters for estimates
+        TransactionType::ReportEquivocation => DEFAULT_FEE_USD * 0.5,
+        TransactionType::SetSendLimit => DEFAULT_FEE_USD,
+        TransactionType::SmartContract { complexity } => {
+            match complexity {
+                ContractComplexity::Simple => DEFAULT_FEE_USD * 3.0,
+                ContractComplexity::Medium => DEFAULT_FEE_USD * 10.0,
+                ContractComplexity::Complex => DEFAULT_FEE_USD * 50.0,
+            }
+        }
+    }
+}
+
+/// Multiplier a priority applies to the base fee
+fn priority_multiplier(priority: &FeePriority) -> f64 {
+    match priority {
+        FeePriority::Low => 1.0,
+        FeePriority::Medium => 1.5,
+        FeePriority::High => 2.0,
+        FeePriority::Urgent => 5.0,
+    }
+}
+
+/// Snapshot of mempool congestion used to pick a fee priority
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MempoolStats {
+    pub pending_low: usize,
+    pub pending_medium: usize,
+    pub pending_high: usize,
+    pub pending_urgent: usize,
+    /// Transactions included per block
+    pub max_block_transactions: usize,
+    pub block_time_secs: u64,
+}
+
+impl MempoolStats {
+    /// Pending transactions a new one at `priority` would queue behind.
+    ///
+    /// Blocks take higher priorities first, so only equal or higher ones count.
+    pub fn queued_ahead(&self, priority: &FeePriority) -> usize {
+        match priority {
+            FeePriority::Urgent => self.pending_urgent,
+            FeePriority::High => self.pending_urgent + self.pending_high,
+            FeePriority::Medium => self.pending_urgent + self.pending_high + self.pending_medium,
+            FeePriority::Low => self.pending_urgent + self.pending_high + self.pending_medium + self.pending_low,
+        }
+    }
+
+    /// Expected seconds until a new transaction at `priority` is included
+    pub fn expected_wait_secs(&self, priority: &FeePriority) -> u64 {
+        let per_block = self.max_block_transactions.max(1);
+        let blocks = (self.queued_ahead(priority) + 1).div_ceil(per_block) as u64;
+        blocks.saturating_mul(self.block_time_secs)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeeEstimate {
     pub low: u64,      // QOR amount for low priority
@@ -229,11 +911,26 @@ pub struct GlobalFeeOracle {
 
 impl GlobalFeeOracle {
     pub fn new() -> Self {
+        Self::from_oracle(FeeOracle::new())
+    }
+    
+    /// Wrap a pre-configured oracle (e.g. with a fallback price)
+    pub fn from_oracle(oracle: FeeOracle) -> Self {
         Self {
-            oracle: tokio::sync::RwLock::new(FeeOracle::new()),
+            oracle: tokio::sync::RwLock::new(oracle),
         }
     }
     
+    /// Cheapest priority expected to confirm within `target_secs`.
+    ///
+    /// If even `Urgent` can't make the target the mempool is too congested for
+    /// the priority to matter much, so `High` is used.
+    pub fn priority_for_target(target_secs: u64, mempool_stats: &MempoolStats) -> FeePriority {
+        FeePriority::ALL.into_iter()
+            .find(|priority| mempool_stats.expected_wait_secs(priority) <= target_secs)
+            .unwrap_or(FeePriority::High)
+    }
+    
     pub async fn get_fee_estimate(&self, tx_type: &TransactionType) -> FeeEstimate {
         let oracle = self.oracle.read().await;
         oracle.get_fee_estimate(tx_type)
@@ -244,18 +941,533 @@ impl GlobalFeeOracle {
         oracle.calculate_fee(tx_type, priority)
     }
     
+    /// Fee a transaction would be charged, as `(qor, usd)`, from one consistent price snapshot
+    pub async fn preview_fee(&self, tx_type: &TransactionType, priority: FeePriority) -> (u64, f64) {
+        let oracle = self.oracle.read().await;
+        let fee_qor = oracle.calculate_fee(tx_type, priority.clone());
+        let fee_usd = oracle.get_fee_estimate(tx_type).get_usd_fee(priority);
+        (fee_qor, fee_usd)
+    }
+    
     pub async fn validate_fee(&self, fee_qor: u64, tx_type: &TransactionType) -> Result<()> {
         let oracle = self.oracle.read().await;
         oracle.validate_fee(fee_qor, tx_type)
     }
     
+    pub async fn calculate_fee_for(&self, tx_type: &TransactionType, priority: FeePriority, holdings: &AccountHoldings) -> u64 {
+        let oracle = self.oracle.read().await;
+        oracle.calculate_fee_for(tx_type, priority, holdings)
+    }
+    
+    pub async fn validate_fee_for(&self, fee_qor: u64, tx_type: &TransactionType, holdings: &AccountHoldings) -> Result<()> {
+        let oracle = self.oracle.read().await;
+        oracle.validate_fee_for(fee_qor, tx_type, holdings)
+    }
+    
     pub async fn update_price(&self) -> Result<()> {
         let mut oracle = self.oracle.write().await;
         oracle.update_price().await
     }
     
+    pub async fn next_update_delay(&self) -> Duration {
+        let oracle = self.oracle.read().await;
+        oracle.next_update_delay()
+    }
+    
     pub async fn get_qor_price(&self) -> f64 {
         let oracle = self.oracle.read().await;
         oracle.get_qor_price()
     }
+    
+    pub async fn price_age(&self) -> Duration {
+        let oracle = self.oracle.read().await;
+        oracle.price_age()
+    }
+    
+    pub async fn is_price_stale(&self) -> bool {
+        let oracle = self.oracle.read().await;
+        oracle.is_price_stale()
+    }
+    
+    pub async fn price_alarm(&self) -> Option<PriceAlarm> {
+        let oracle = self.oracle.read().await;
+        oracle.price_alarm()
+    }
+    
+    pub async fn acknowledge_price_jump(&self) -> Result<f64> {
+        let mut oracle = self.oracle.write().await;
+        oracle.acknowledge_price_jump()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_all_sources_fail_without_fallback_rejects_fees() {
+        let mut oracle = FeeOracle::new().with_price_sources(Vec::new());
+
+        assert!(oracle.update_price().await.is_err());
+        assert!(!oracle.has_sourced_price());
+        assert!(oracle.get_qor_price() > 0.0);
+
+        let fee = oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Medium);
+        assert!(fee > 0);
+        assert!(oracle.validate_fee(fee, &TransactionType::Transfer).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_all_sources_fail_uses_fallback_price() {
+        let mut oracle = FeeOracle::new()
+            .with_price_sources(Vec::new())
+            .with_fallback_price(0.5);
+
+        assert!(oracle.update_price().await.is_err());
+        assert_eq!(oracle.get_qor_price(), 0.5);
+
+        let fee = oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Low);
+        assert_eq!(fee, usd_to_qor(DEFAULT_FEE_USD, 0.5));
+        assert!(oracle.validate_fee(fee, &TransactionType::Transfer).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_dex_source_does_not_echo_own_price() {
+        let mut oracle = FeeOracle::new().with_price_sources(vec![PriceSource {
+            name: "DEX Price".to_string(),
+            url: "internal://dex-price".to_string(),
+            weight: 1.0,
+            headers: Vec::new(),
+        }]);
+
+        assert!(oracle.update_price().await.is_err());
+        assert!(!oracle.has_sourced_price());
+    }
+
+    #[tokio::test]
+    async fn test_non_positive_fallback_ignored() {
+        let oracle = FeeOracle::new().with_fallback_price(0.0);
+        assert!(oracle.effective_price().is_err());
+        assert!(oracle.get_qor_price() > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_first_update_not_throttled() {
+        let body = serde_json::json!({ "qor": { "usd": 0.05 } });
+        let (_server, source) = mock_source("CoinGecko", ResponseTemplate::new(200).set_body_json(body)).await;
+        let mut oracle = FeeOracle::new().with_price_sources(vec![source]);
+
+        // A fresh oracle was never updated, so the interval doesn't hold the first fetch back
+        oracle.update_price().await.unwrap();
+        assert!(oracle.has_sourced_price());
+        assert!(oracle.validate_fee(
+            oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Medium),
+            &TransactionType::Transfer,
+        ).is_ok());
+    }
+
+    fn discounted_oracle() -> FeeOracle {
+        FeeOracle::new()
+            .with_price_sources(Vec::new())
+            .with_fallback_price(1.0)
+            .with_discount_policy(
+                FeeDiscountPolicy::none()
+                    .with_qor_tier(1_000_000_000_000, 2_000) // 1,000 QOR -> 20%
+                    .with_lp_tier(500_000_000_000, 1_000),  // 500 QOR of LP -> 10%
+            )
+    }
+
+    #[test]
+    fn test_high_balance_gets_discount() {
+        let oracle = discounted_oracle();
+        let whale = AccountHoldings { qor_balance: 5_000_000_000_000, lp_value: 0 };
+        let tx_type = TransactionType::RegisterApp;
+
+        let full = oracle.calculate_fee(&tx_type, FeePriority::Low);
+        let discounted = oracle.calculate_fee_for(&tx_type, FeePriority::Low, &whale);
+        assert_eq!(oracle.discount_policy().discount_bps(&whale), 2_000);
+        assert!((discounted as f64 - full as f64 * 0.8).abs() <= 1.0);
+        assert!(discounted >= usd_to_qor(MIN_FEE_USD, 1.0));
+
+        // Discounted fee validates for the holder but not for anyone else
+        assert!(oracle.validate_fee_for(discounted, &tx_type, &whale).is_ok());
+        assert!(oracle.validate_fee(discounted, &tx_type).is_err());
+    }
+
+    #[test]
+    fn test_low_balance_pays_full_fee() {
+        let oracle = discounted_oracle();
+        let minnow = AccountHoldings { qor_balance: 10, lp_value: 10 };
+        let tx_type = TransactionType::RegisterApp;
+
+        assert_eq!(oracle.discount_policy().discount_bps(&minnow), 0);
+        let fee = oracle.calculate_fee_for(&tx_type, FeePriority::Low, &minnow);
+        assert_eq!(fee, oracle.calculate_fee(&tx_type, FeePriority::Low));
+        assert!(fee >= usd_to_qor(MIN_FEE_USD, 1.0));
+    }
+
+    #[test]
+    fn test_discount_floored_at_minimum_fee() {
+        let oracle = discounted_oracle();
+        let lp = AccountHoldings { qor_balance: 0, lp_value: 600_000_000_000 };
+
+        assert_eq!(oracle.discount_policy().discount_bps(&lp), 1_000);
+        let fee = oracle.calculate_fee_for(&TransactionType::Transfer, FeePriority::Low, &lp);
+        assert_eq!(fee, usd_to_qor(MIN_FEE_USD, 1.0));
+    }
+
+    async fn mock_source(name: &str, response: ResponseTemplate) -> (MockServer, PriceSource) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/price")).respond_with(response).mount(&server).await;
+        let source = PriceSource { name: name.to_string(), url: format!("{}/price", server.uri()), weight: 1.0, headers: Vec::new() };
+        (server, source)
+    }
+
+    #[tokio::test]
+    async fn test_coingecko_price_is_fetched() {
+        let body = serde_json::json!({ "qor": { "usd": 0.0523 } });
+        let (_server, source) = mock_source("CoinGecko", ResponseTemplate::new(200).set_body_json(body)).await;
+        let mut oracle = FeeOracle::new().with_price_sources(vec![source]);
+
+        oracle.update_price().await.unwrap();
+        assert!(oracle.has_sourced_price());
+        assert_eq!(oracle.get_qor_price(), 0.0523);
+    }
+
+    #[tokio::test]
+    async fn test_coinmarketcap_request_carries_key_and_symbol() {
+        use wiremock::matchers::{header, query_param};
+
+        let server = MockServer::start().await;
+        let body = serde_json::json!({ "data": { "QOR": { "quote": { "USD": { "price": 0.061 } } } } });
+        Mock::given(method("GET")).and(path("/quotes"))
+            .and(header("X-CMC_PRO_API_KEY", "secret"))
+            .and(query_param("symbol", "QOR"))
+            .and(query_param("convert", "USD"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let source = PriceSource::coinmarketcap(&format!("{}/quotes", server.uri()), "secret");
+        let mut oracle = FeeOracle::new().with_price_sources(vec![source]);
+        oracle.update_price().await.unwrap();
+        assert_eq!(oracle.get_qor_price(), 0.061);
+
+        let keyed = FeeOracle::new().with_coinmarketcap_api_key("secret").with_coinmarketcap_api_key("rotated");
+        let cmc: Vec<_> = keyed.price_sources.iter().filter(|source| source.name == "CoinMarketCap").collect();
+        assert_eq!(cmc.len(), 1);
+        assert_eq!(cmc[0].headers, vec![("X-CMC_PRO_API_KEY".to_string(), "rotated".to_string())]);
+    }
+
+    #[test]
+    fn test_coinmarketcap_shapes_parse() {
+        let object = serde_json::json!({ "data": { "QOR": { "quote": { "USD": { "price": 0.061 } } } } });
+        assert_eq!(parse_usd_price(&object), Some(0.061));
+        let list = serde_json::json!({ "data": { "QOR": [{ "quote": { "USD": { "price": 0.062 } } }] } });
+        assert_eq!(parse_usd_price(&list), Some(0.062));
+        assert_eq!(parse_usd_price(&serde_json::json!({ "price": 0.05 })), None);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_body_keeps_previous_price() {
+        let (_server, source) = mock_source("Broken", ResponseTemplate::new(200).set_body_string("<html>rate limited</html>")).await;
+        let mut oracle = FeeOracle::new().with_price_sources(vec![source.clone()]);
+        assert!(oracle.fetch_external_price(&source).await.is_err());
+
+        oracle.submit_price(0.05).unwrap();
+        oracle.update_interval = Duration::ZERO;
+        assert!(oracle.update_price().await.is_err());
+        assert_eq!(oracle.get_qor_price(), 0.05);
+
+        let unknown = serde_json::json!({ "result": "ok" });
+        let (_server, source) = mock_source("Unknown", ResponseTemplate::new(200).set_body_json(unknown)).await;
+        match oracle.fetch_external_price(&source).await {
+            Err(SourceFailure::Failed(QoraNetError::NetworkError(msg))) => assert!(msg.contains("Unrecognized")),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stale_price_rejects_fees() {
+        let oracle = GlobalFeeOracle::from_oracle(FeeOracle::new().with_price_sources(Vec::new()));
+        oracle.oracle.write().await.submit_price(0.05).unwrap();
+        let fee = oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Medium).await;
+        assert!(oracle.validate_fee(fee, &TransactionType::Transfer).await.is_ok());
+
+        tokio::time::advance(DEFAULT_MAX_PRICE_AGE).await;
+        assert_eq!(oracle.price_age().await, DEFAULT_MAX_PRICE_AGE);
+        assert!(!oracle.is_price_stale().await);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(oracle.is_price_stale().await);
+        assert!(oracle.validate_fee(fee, &TransactionType::Transfer).await.is_err());
+
+        // A fresh price clears it
+        oracle.oracle.write().await.submit_price(0.05).unwrap();
+        assert!(oracle.validate_fee(fee, &TransactionType::Transfer).await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fallback_price_never_stale() {
+        let oracle = FeeOracle::new()
+            .with_price_sources(Vec::new())
+            .with_fallback_price(0.05)
+            .with_max_price_age(Duration::from_secs(60));
+        tokio::time::advance(Duration::from_secs(3_600)).await;
+
+        assert!(!oracle.is_price_stale());
+        let fee = oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Low);
+        assert!(oracle.validate_fee(fee, &TransactionType::Transfer).is_ok());
+    }
+
+    #[test]
+    fn test_single_outlier_is_discarded() {
+        let quotes = [(0.050, 0.4), (0.052, 0.4), (5.0, 0.2)];
+        let price = aggregate_quotes(&quotes, DEFAULT_MAX_DEVIATION_PCT).unwrap();
+        assert!((price - 0.051).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_agreeing_sources_are_averaged() {
+        let quotes = [(0.050, 1.0), (0.051, 1.0), (0.052, 2.0)];
+        let price = aggregate_quotes(&quotes, DEFAULT_MAX_DEVIATION_PCT).unwrap();
+        assert!((price - 0.05125).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_majority_disagreement_keeps_last_price() {
+        // Median 0.05; both other quotes are 50% away
+        let quotes = [(0.025, 1.0), (0.05, 1.0), (0.075, 1.0)];
+        assert!(aggregate_quotes(&quotes, DEFAULT_MAX_DEVIATION_PCT).is_err());
+
+        let mut sources = Vec::new();
+        let mut servers = Vec::new();
+        for (name, usd) in [("Low", 0.025), ("Mid", 0.05), ("High", 0.075)] {
+            let body = serde_json::json!({ "qor": { "usd": usd } });
+            let (server, source) = mock_source(name, ResponseTemplate::new(200).set_body_json(body)).await;
+            servers.push(server);
+            sources.push(source);
+        }
+        let mut oracle = FeeOracle::new().with_price_sources(sources);
+        oracle.submit_price(0.04).unwrap();
+        oracle.update_interval = Duration::ZERO;
+
+        assert!(oracle.update_price().await.is_err());
+        assert_eq!(oracle.get_qor_price(), 0.04);
+
+        // Loosening the threshold lets them all through
+        oracle.set_outlier_threshold(60.0);
+        oracle.update_price().await.unwrap();
+        assert!((oracle.get_qor_price() - 0.05).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_hung_source_times_out_without_blocking_others() {
+        let slow = ResponseTemplate::new(200)
+            .set_body_json(serde_json::json!({ "qor": { "usd": 9.0 } }))
+            .set_delay(Duration::from_secs(10));
+        let (_slow_server, hung) = mock_source("Hung", slow).await;
+        let fast = ResponseTemplate::new(200).set_body_json(serde_json::json!({ "qor": { "usd": 0.05 } }));
+        let (_fast_server, healthy) = mock_source("Healthy", fast).await;
+
+        let mut oracle = FeeOracle::new()
+            .with_price_sources(vec![hung, healthy])
+            .with_source_timeout(Duration::from_millis(200));
+
+        let started = std::time::Instant::now();
+        oracle.update_price().await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(oracle.get_qor_price(), 0.05);
+    }
+
+    async fn counted_source(name: &str, response: ResponseTemplate, expected_requests: u64) -> (MockServer, PriceSource) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/price"))
+            .respond_with(response)
+            .expect(expected_requests)
+            .mount(&server)
+            .await;
+        let source = PriceSource { name: name.to_string(), url: format!("{}/price", server.uri()), weight: 1.0, headers: Vec::new() };
+        (server, source)
+    }
+
+    #[tokio::test]
+    async fn test_updates_within_interval_are_coalesced() {
+        let body = serde_json::json!({ "qor": { "usd": 0.05 } });
+        let (server, source) = counted_source("CoinGecko", ResponseTemplate::new(200).set_body_json(body), 1).await;
+        let oracle = GlobalFeeOracle::from_oracle(FeeOracle::new().with_price_sources(vec![source]));
+
+        let (a, b, c) = tokio::join!(oracle.update_price(), oracle.update_price(), oracle.update_price());
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(oracle.get_qor_price().await, 0.05);
+
+        // Even with the interval forced open, the cached quote is reused
+        oracle.oracle.write().await.update_interval = Duration::ZERO;
+        oracle.update_price().await.unwrap();
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_source_backs_off() {
+        let limited = ResponseTemplate::new(429).insert_header("Retry-After", "120");
+        let (server, source) = counted_source("Limited", limited, 1).await;
+        let mut oracle = FeeOracle::new().with_price_sources(vec![source]);
+
+        assert!(oracle.update_price().await.is_err());
+        let backoff = oracle.source_backoff("Limited").unwrap();
+        assert!(backoff > Duration::from_secs(110) && backoff <= Duration::from_secs(120));
+
+        // Retrying straight away doesn't touch the source
+        assert!(oracle.update_price().await.is_err());
+        server.verify().await;
+    }
+
+    #[test]
+    fn test_rate_limit_backoff_without_retry_after_doubles() {
+        assert_eq!(parse_retry_after(" 7 "), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+
+        assert_eq!(rate_limit_backoff(None, 1), DEFAULT_RATE_LIMIT_BACKOFF);
+        assert_eq!(rate_limit_backoff(None, 3), DEFAULT_RATE_LIMIT_BACKOFF * 4);
+        assert_eq!(rate_limit_backoff(None, 40), MAX_RATE_LIMIT_BACKOFF);
+        assert_eq!(rate_limit_backoff(Some(Duration::from_secs(5)), 3), Duration::from_secs(5));
+        assert_eq!(rate_limit_backoff(Some(Duration::from_secs(86_400)), 1), MAX_RATE_LIMIT_BACKOFF);
+    }
+
+    #[test]
+    fn test_update_delay_stays_within_jitter() {
+        let oracle = FeeOracle::new()
+            .with_update_interval(Duration::from_secs(60))
+            .with_update_jitter(Duration::from_secs(10));
+        for _ in 0..100 {
+            let delay = oracle.next_update_delay();
+            assert!(delay >= Duration::from_secs(60) && delay <= Duration::from_secs(70));
+        }
+
+        let fixed = oracle.with_update_jitter(Duration::ZERO);
+        assert_eq!(fixed.next_update_delay(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_builder_and_validator_agree_at_rounding_boundaries() {
+        // Prices where the fee lands on or near a half unit
+        let prices = [0.04, 0.4, 0.3, 0.7, 1.0 / 3.0, 40_000.0, 66_666.666_666_7, 80_000.0];
+        let tx_types = [TransactionType::Transfer, TransactionType::ClaimRewards, TransactionType::RegisterApp];
+
+        for price in prices {
+            let oracle = FeeOracle::new().with_price_sources(Vec::new()).with_fallback_price(price);
+            for tx_type in &tx_types {
+                let fee = oracle.calculate_fee(tx_type, FeePriority::Low);
+                let required = usd_to_qor(oracle.get_base_fee_usd(tx_type), price);
+
+                assert_eq!(fee, required, "price {} {:?}", price, tx_type);
+                assert!(oracle.validate_fee(fee, tx_type).is_ok(), "price {} {:?}", price, tx_type);
+                if fee > 0 {
+                    assert!(oracle.validate_fee(fee - 1, tx_type).is_err(), "price {} {:?}", price, tx_type);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sudden_price_jump_held_until_confirmed() {
+        let mut oracle = FeeOracle::new().with_price_sources(Vec::new());
+        oracle.submit_price(1.0).unwrap();
+        let fee_before = oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Low);
+        std::thread::sleep(Duration::from_millis(20));
+
+        // A 10x jump is rejected and the old price kept, without counting as fresh
+        assert!(oracle.submit_price(10.0).is_err());
+        assert_eq!(oracle.get_qor_price(), 1.0);
+        assert!(oracle.price_age() >= Duration::from_millis(20));
+        assert_eq!(oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Low), fee_before);
+        let alarm = oracle.price_alarm().unwrap();
+        assert_eq!(alarm.pending_price, 10.0);
+        assert_eq!(alarm.deviation_bps, 90_000);
+
+        // Still held on the second sighting, accepted on the third
+        assert!(oracle.submit_price(10.2).is_err());
+        assert_eq!(oracle.get_qor_price(), 1.0);
+        assert_eq!(oracle.price_alarm().unwrap().observations, 2);
+        oracle.submit_price(10.1).unwrap();
+        assert_eq!(oracle.get_qor_price(), 10.1);
+        assert!(oracle.price_alarm().is_none());
+    }
+
+    #[test]
+    fn test_price_jump_accepted_on_acknowledgement() {
+        let mut oracle = FeeOracle::new().with_price_sources(Vec::new());
+        oracle.submit_price(1.0).unwrap();
+        assert!(oracle.acknowledge_price_jump().is_err());
+
+        assert!(oracle.submit_price(10.0).is_err());
+        // Returning to normal clears the alarm
+        oracle.submit_price(1.05).unwrap();
+        assert!(oracle.price_alarm().is_none());
+
+        assert!(oracle.submit_price(10.0).is_err());
+        assert_eq!(oracle.acknowledge_price_jump().unwrap(), 10.0);
+        assert_eq!(oracle.get_qor_price(), 10.0);
+        assert!(oracle.price_alarm().is_none());
+    }
+
+    fn congested(pending_per_priority: usize) -> MempoolStats {
+        MempoolStats {
+            pending_low: pending_per_priority,
+            pending_medium: pending_per_priority,
+            pending_high: pending_per_priority,
+            pending_urgent: pending_per_priority,
+            max_block_transactions: 100,
+            block_time_secs: 10,
+        }
+    }
+
+    #[test]
+    fn test_priority_for_target() {
+        // Quiet mempool: everything confirms next block
+        let quiet = congested(5);
+        assert!(matches!(GlobalFeeOracle::priority_for_target(30, &quiet), FeePriority::Low));
+
+        // 250 queued at Urgent and High each: Urgent confirms in 3 blocks, High in 6
+        let busy = congested(250);
+        assert!(matches!(GlobalFeeOracle::priority_for_target(30, &busy), FeePriority::Urgent));
+        assert!(matches!(GlobalFeeOracle::priority_for_target(60, &busy), FeePriority::High));
+        assert!(matches!(GlobalFeeOracle::priority_for_target(100, &busy), FeePriority::Medium));
+
+        // Nothing can make a target shorter than one block
+        assert!(matches!(GlobalFeeOracle::priority_for_target(5, &quiet), FeePriority::High));
+    }
+
+    #[test]
+    fn test_charged_breakdown_matches_quoted_fee() {
+        let policy = FeeDiscountPolicy::none().with_qor_tier(1_000_000_000_000, 2_000);
+        let oracle = FeeOracle::new().with_fallback_price(0.05).with_discount_policy(policy.clone());
+        let whale = AccountHoldings { qor_balance: 1_000_000_000_000, lp_value: 0 };
+        let quoted = oracle.fee_breakdown_for(&TransactionType::Transfer, FeePriority::Medium, &whale);
+        let mut tx = crate::Transaction {
+            data: crate::TransactionData::Transfer { from: Address([1u8; 32]), to: Address([2u8; 32]), amount: 1, memo: None },
+            nonce: 0,
+            fee_qor: quoted.total_fee_qor,
+            fee_usd: crate::qor_to_usd(quoted.total_fee_qor, 0.05),
+            priority: FeePriority::Medium,
+            signature: crate::QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+            signer: Address([1u8; 32]),
+            valid_until_height: None,
+        };
+
+        // The discounted base hits the fee floor, so it isn't the total over the multiplier
+        let charged = FeeBreakdown::charged(&tx, &whale, &policy);
+        assert_eq!(charged.base_fee_qor, quoted.base_fee_qor);
+        assert_eq!(charged.priority_tip_qor, quoted.priority_tip_qor);
+        assert_eq!(charged.total_fee_qor, quoted.total_fee_qor);
+        assert_eq!(charged.base_fee_qor, usd_to_qor(MIN_FEE_USD, 0.05));
+        assert!((charged.price_used - 0.05).abs() < 1e-12);
+
+        // Fee-free transactions have no implied price
+        tx.fee_qor = 0;
+        tx.fee_usd = 0.0;
+        assert_eq!(FeeBreakdown::charged(&tx, &whale, &policy).price_used, 0.0);
+    }
 }
@@ -7,6 +7,10 @@ pub mod rpc;
 pub mod app_monitor;
 pub mod rewards;
 pub mod fee_oracle;
+pub mod dex;
+pub mod qrc20;
+pub mod wallet;
+pub mod chain;
 
 use ed25519_dalek::{Keypair, PublicKey, Signature};
 use serde::{Deserialize, Serialize};
@@ -15,6 +19,14 @@ use std::collections::HashMap;
 
 pub use fee_oracle::*;
 
+// Curated top-level API. `TransactionType` above is the fee category of a
+// transaction (from `fee_oracle`); the transaction payload is `TransactionData`.
+pub use transaction::{AppId, Transaction, TransactionData, TransactionKind};
+pub use qrc20::evm_integration as evm;
+pub use qrc20::bridge::{BridgeRequest, BridgeTransaction};
+pub use qrc20::{QRC20Event, QRC20Error, QRC20Registry, QRC20Token, QRC20Transaction};
+pub use chain::{AccountInfo, ExecutionReceipt, QoraNet, TokenBalanceInfo};
+
 /// QoraNet version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -26,6 +38,22 @@ pub const MIN_FEE_USD: f64 = 0.0001;  // $0.0001 minimum fee
 pub const MAX_FEE_USD: f64 = 0.01;    // $0.01 maximum fee
 pub const DEFAULT_FEE_USD: f64 = 0.0001; // Default fee for simple transactions
 
+/// Maximum native QOR supply in smallest units (21M QOR)
+pub const MAX_QOR_SUPPLY: u64 = 21_000_000 * 1_000_000_000;
+
+/// Canonical rounding of a fractional amount of smallest token units.
+///
+/// Every USD conversion and fee computation goes through this, so a transaction
+/// builder and a validator always land on the same integer amount. Ties round
+/// to the even unit; negative or non-finite amounts become 0 and amounts past
+/// `u64::MAX` saturate.
+pub fn round_to_smallest_unit(units: f64) -> u64 {
+    if !units.is_finite() || units <= 0.0 {
+        return 0;
+    }
+    units.round_ties_even() as u64
+}
+
 /// Convert USD to QOR tokens using current price
 pub fn usd_to_qor(usd_amount: f64, qor_price_usd: f64) -> u64 {
     if qor_price_usd <= 0.0 {
@@ -34,7 +62,7 @@ pub fn usd_to_qor(usd_amount: f64, qor_price_usd: f64) -> u64 {
     
     let qor_amount = usd_amount / qor_price_usd;
     // Convert to smallest unit (assuming 9 decimals like SOL)
-    (qor_amount * 1_000_000_000.0) as u64
+    round_to_smallest_unit(qor_amount * 1_000_000_000.0)
 }
 
 /// Convert QOR tokens to USD using current price
@@ -51,7 +79,7 @@ pub fn usd_to_token(usd_amount: f64, token_price_usd: f64, decimals: u8) -> u64
     
     let token_amount = usd_amount / token_price_usd;
     let decimal_multiplier = 10_u64.pow(decimals as u32);
-    (token_amount * decimal_multiplier as f64) as u64
+    round_to_smallest_unit(token_amount * decimal_multiplier as f64)
 }
 
 /// Convert token amount to USD using current price and decimals
@@ -87,6 +115,15 @@ pub enum QoraNetError {
     
     #[error("Bridge error: {0}")]
     BridgeError(String),
+    
+    #[error("Transaction pool full: fee {offered} does not beat the lowest pending fee {lowest}")]
+    PoolFull { offered: u64, lowest: u64 },
+    
+    #[error("Replacement for nonce {nonce} underpriced: fee {offered}, at least {required} required")]
+    ReplacementUnderpriced { nonce: u64, offered: u64, required: u64 },
+    
+    #[error("Spending limit exceeded: sending {requested} would exceed the limit of {limit} per window ({spent} already sent)")]
+    SpendingLimitExceeded { limit: u64, spent: u64, requested: u64 },
 }
 
 /// QoraNet result type
@@ -125,7 +162,7 @@ impl std::fmt::Display for Hash {
 }
 
 /// Account address
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Address(pub [u8; 32]);
 
 impl Address {
@@ -211,15 +248,17 @@ impl ERC20TokenInfo {
 }
 
 /// Multi-token balance supporting QOR + ERC-20s
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Ordered by token address so the encoding, and any hash over it, is deterministic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TokenBalance {
-    pub balances: HashMap<Address, u64>, // token_address -> amount
+    pub balances: std::collections::BTreeMap<Address, u64>, // token_address -> amount
 }
 
 impl TokenBalance {
     pub fn new() -> Self {
         Self {
-            balances: HashMap::new(),
+            balances: std::collections::BTreeMap::new(),
         }
     }
     
@@ -380,7 +419,7 @@ impl FeePayment {
     /// Calculate fee in specified token
     pub fn calculate_fee(fee_usd: f64, token: &Address, token_registry: &TokenRegistry, oracle: &FeeOracle) -> Result<Self> {
         if token.is_native_qor() {
-            let qor_price = oracle.get_qor_price()?;
+            let qor_price = oracle.effective_price()?;
             let fee_amount = usd_to_qor(fee_usd, qor_price);
             Ok(FeePayment::QOR(fee_amount))
         } else {
@@ -394,12 +433,54 @@ impl FeePayment {
             let token_price = oracle.get_token_price(&token_info.symbol)?;
             let fee_amount = usd_to_token(fee_usd, token_price, token_info.decimals);
             
+            // Rounding to whole token units must not push the fee to zero or below the floor
+            if fee_amount == 0 {
+                return Err(QoraNetError::TokenError(format!(
+                    "Fee rounds to zero {} units; token price or decimals too low to pay fees",
+                    token_info.symbol
+                )));
+            }
+            // Small tolerance for float error in the round trip through token units
+            if token_to_usd(fee_amount, token_price, token_info.decimals) < MIN_FEE_USD * (1.0 - 1e-9) {
+                return Err(QoraNetError::TokenError(format!(
+                    "Fee of {} {} units is worth less than the ${} minimum",
+                    fee_amount, token_info.symbol, MIN_FEE_USD
+                )));
+            }
+            
             Ok(FeePayment::ERC20 { 
                 token: token.clone(), 
                 amount: fee_amount 
             })
         }
     }
+
+    /// Charge for a transaction in `token`, with the itemized breakdown for its receipt
+    pub fn calculate_with_breakdown(
+        tx_type: &TransactionType,
+        priority: FeePriority,
+        holdings: &AccountHoldings,
+        token: &Address,
+        token_registry: &TokenRegistry,
+        oracle: &FeeOracle,
+    ) -> Result<(Self, FeeBreakdown)> {
+        let mut breakdown = oracle.fee_breakdown_for(tx_type, priority, holdings);
+        if token.is_native_qor() {
+            return Ok((FeePayment::QOR(breakdown.total_fee_qor), breakdown));
+        }
+
+        let fee_usd = oracle.fee_usd_for(tx_type, priority, holdings);
+        let payment = Self::calculate_fee(fee_usd, token, token_registry, oracle)?;
+        if let FeePayment::ERC20 { token, amount } = &payment {
+            let symbol = &token_registry.get_token_info(token)
+                .ok_or_else(|| QoraNetError::TokenError("Token not found".to_string()))?
+                .symbol;
+            breakdown.fee_token = Some(token.clone());
+            breakdown.fee_token_amount = Some(*amount);
+            breakdown.price_used = oracle.get_token_price(symbol)?;
+        }
+        Ok((payment, breakdown))
+    }
 }
 
 /// Token registry to manage supported ERC-20 tokens
@@ -460,6 +541,12 @@ impl Default for TokenRegistry {
     }
 }
 
+/// Longest uptime a report can plausibly claim (10 years)
+pub const MAX_PLAUSIBLE_UPTIME_SECS: u64 = 10 * 365 * 24 * 3600;
+
+/// Most requests a report can plausibly claim
+pub const MAX_PLAUSIBLE_REQUESTS: u64 = 1_000_000_000_000;
+
 /// Application performance metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppMetrics {
@@ -481,14 +568,81 @@ impl AppMetrics {
         }
     }
     
-    /// Calculate performance score for rewards
+    /// Calculate performance score for rewards with the default weights
     pub fn performance_score(&self) -> f64 {
-        let cpu_score = (self.cpu_usage / 100.0).min(1.0);
-        let uptime_hours = self.uptime as f64 / 3600.0;
-        let uptime_score = (uptime_hours / 24.0).min(1.0); // Max score at 24h uptime
-        let request_score = (self.requests_served as f64 / 1000.0).min(1.0);
+        self.score_with(&ScoreWeights::DEFAULT)
+    }
+
+    /// Calculate performance score weighted for the kind of app
+    pub fn score_for_type(&self, app_type: &transaction::AppType) -> f64 {
+        self.score_with(&ScoreWeights::for_app_type(app_type))
+    }
+
+    /// Calculate performance score with custom weights.
+    ///
+    /// Inputs are clamped before scoring and any non-finite term counts as
+    /// zero, so the score is always in `[0, 1]` however absurd the report.
+    pub fn score_with(&self, weights: &ScoreWeights) -> f64 {
+        let cpu_score = unit_interval(self.cpu_usage / 100.0);
+        let uptime_hours = self.uptime.min(MAX_PLAUSIBLE_UPTIME_SECS) as f64 / 3600.0;
+        let uptime_score = unit_interval(uptime_hours / 24.0); // Max score at 24h uptime
+        let request_score = unit_interval(self.requests_served.min(MAX_PLAUSIBLE_REQUESTS) as f64 / 1000.0);
         
-        (cpu_score * 0.4 + uptime_score * 0.3 + request_score * 0.3)
+        unit_interval(
+            cpu_score * unit_interval(weights.cpu)
+                + uptime_score * unit_interval(weights.uptime)
+                + request_score * unit_interval(weights.requests)
+        )
+    }
+
+    /// Whether the report contains values no real app could produce
+    pub fn is_suspicious(&self) -> bool {
+        !self.cpu_usage.is_finite()
+            || !(0.0..=100.0).contains(&self.cpu_usage)
+            || self.uptime > MAX_PLAUSIBLE_UPTIME_SECS
+            || self.requests_served > MAX_PLAUSIBLE_REQUESTS
+    }
+}
+
+/// Clamp to `[0, 1]`, mapping NaN and infinities to 0
+fn unit_interval(value: f64) -> f64 {
+    if value.is_finite() {
+        value.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// How much each metric counts toward an app's performance score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreWeights {
+    pub cpu: f64,
+    pub uptime: f64,
+    pub requests: f64,
+}
+
+impl ScoreWeights {
+    /// Profile used when the app type is unknown
+    pub const DEFAULT: ScoreWeights = ScoreWeights { cpu: 0.4, uptime: 0.3, requests: 0.3 };
+
+    /// Weight profile for an app type
+    pub fn for_app_type(app_type: &transaction::AppType) -> Self {
+        use transaction::AppType;
+        match app_type {
+            // Storage is judged on availability
+            AppType::StorageNode => ScoreWeights { cpu: 0.1, uptime: 0.6, requests: 0.3 },
+            AppType::OracleService => ScoreWeights { cpu: 0.2, uptime: 0.5, requests: 0.3 },
+            // Compute is judged on work done
+            AppType::ComputeNode => ScoreWeights { cpu: 0.6, uptime: 0.2, requests: 0.2 },
+            AppType::IndexingService => ScoreWeights { cpu: 0.3, uptime: 0.3, requests: 0.4 },
+            AppType::RelayNode => ScoreWeights { cpu: 0.1, uptime: 0.4, requests: 0.5 },
+        }
+    }
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self::DEFAULT
     }
 }
 
@@ -503,3 +657,138 @@ pub type BlockHeight = u64;
 
 /// Timestamp type  
 pub type Timestamp = u64;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry_with(symbol: &str, decimals: u8) -> (TokenRegistry, Address) {
+        let token = Address([7u8; 32]);
+        let mut registry = TokenRegistry::new();
+        registry.register_erc20(ERC20TokenInfo {
+            ethereum_address: "0xdac17f958d2ee523a2206206994597c13d831ec7".to_string(),
+            qoranet_address: token.clone(),
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            decimals,
+            total_supply: 0,
+            is_fee_token: true,
+        }).unwrap();
+        (registry, token)
+    }
+
+    #[test]
+    fn test_fee_breakdown_for_qor_and_token_fees() {
+        let (registry, token) = registry_with("USDT", 6);
+        let oracle = FeeOracle::new().with_fallback_price(0.05).with_token_price("USDT", 1.0);
+        let holdings = AccountHoldings::default();
+
+        let (payment, qor) = FeePayment::calculate_with_breakdown(
+            &TransactionType::Transfer, FeePriority::High, &holdings, &Address::native_qor(), &registry, &oracle,
+        ).unwrap();
+        assert!(matches!(payment, FeePayment::QOR(amount) if amount == qor.total_fee_qor));
+        assert_eq!(qor.total_fee_qor, oracle.calculate_fee(&TransactionType::Transfer, FeePriority::High));
+        assert_eq!(qor.base_fee_qor, oracle.calculate_fee(&TransactionType::Transfer, FeePriority::Low));
+        assert_eq!(qor.base_fee_qor + qor.priority_tip_qor, qor.total_fee_qor);
+        assert!(qor.priority_tip_qor > 0);
+        assert_eq!(qor.price_used, 0.05);
+        assert!(qor.fee_token.is_none());
+
+        let (payment, erc20) = FeePayment::calculate_with_breakdown(
+            &TransactionType::Transfer, FeePriority::High, &holdings, &token, &registry, &oracle,
+        ).unwrap();
+        assert_eq!(erc20.total_fee_qor, qor.total_fee_qor);
+        assert_eq!(erc20.base_fee_qor + erc20.priority_tip_qor, erc20.total_fee_qor);
+        assert_eq!(erc20.fee_token, Some(token.clone()));
+        assert_eq!(erc20.price_used, 1.0);
+        // $0.0002 at $1 per USDT with 6 decimals
+        assert_eq!(erc20.fee_token_amount, Some(200));
+        assert!(matches!(payment, FeePayment::ERC20 { amount: 200, .. }));
+    }
+
+    #[test]
+    fn test_erc20_fee_accepted() {
+        let (registry, token) = registry_with("USDT", 6);
+        let oracle = FeeOracle::new().with_token_price("USDT", 1.0);
+
+        match FeePayment::calculate_fee(MIN_FEE_USD, &token, &registry, &oracle).unwrap() {
+            FeePayment::ERC20 { amount, .. } => {
+                assert_eq!(amount, 100);
+                assert!(token_to_usd(amount, 1.0, 6) >= MIN_FEE_USD * (1.0 - 1e-9));
+            },
+            other => panic!("unexpected payment {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_erc20_fee_rounding_to_zero_rejected() {
+        // One whole unit is worth far more than the fee
+        let (registry, token) = registry_with("WBTC", 0);
+        let oracle = FeeOracle::new().with_token_price("WBTC", 60_000.0);
+        assert!(FeePayment::calculate_fee(MIN_FEE_USD, &token, &registry, &oracle).is_err());
+
+        // Fee truncates to 3 units worth $0.00009, below the floor
+        let (registry, token) = registry_with("DUST", 0);
+        let oracle = FeeOracle::new().with_token_price("DUST", 0.00003);
+        assert!(FeePayment::calculate_fee(MIN_FEE_USD, &token, &registry, &oracle).is_err());
+    }
+
+    #[test]
+    fn test_score_depends_on_app_type() {
+        let mut metrics = AppMetrics::new();
+        metrics.cpu_usage = 90.0;
+        metrics.uptime = 2 * 3600;
+        metrics.requests_served = 500;
+
+        let storage = metrics.score_for_type(&transaction::AppType::StorageNode);
+        let compute = metrics.score_for_type(&transaction::AppType::ComputeNode);
+
+        // Busy CPU with little uptime favours compute
+        assert!((storage - (0.09 + 0.05 + 0.15)).abs() < 1e-9);
+        assert!((compute - (0.54 + 2.0 / 120.0 + 0.1)).abs() < 1e-9);
+        assert!(compute > storage);
+        assert_eq!(metrics.performance_score(), metrics.score_with(&ScoreWeights::DEFAULT));
+    }
+
+    #[test]
+    fn test_extreme_metrics_score_in_range() {
+        let reports = [
+            AppMetrics { cpu_usage: f64::NAN, uptime: u64::MAX, requests_served: u64::MAX, ..AppMetrics::new() },
+            AppMetrics { cpu_usage: f64::INFINITY, ..AppMetrics::new() },
+            AppMetrics { cpu_usage: -50.0, uptime: MAX_PLAUSIBLE_UPTIME_SECS + 1, ..AppMetrics::new() },
+            AppMetrics { cpu_usage: 1e308, requests_served: MAX_PLAUSIBLE_REQUESTS + 1, ..AppMetrics::new() },
+        ];
+        let hostile = ScoreWeights { cpu: f64::INFINITY, uptime: -1.0, requests: f64::NAN };
+
+        for metrics in &reports {
+            assert!(metrics.is_suspicious());
+            for score in [metrics.performance_score(), metrics.score_with(&hostile)] {
+                assert!(score.is_finite());
+                assert!((0.0..=1.0).contains(&score));
+            }
+        }
+        assert_eq!(reports[1].performance_score(), 0.0);
+
+        let mut honest = AppMetrics::new();
+        honest.cpu_usage = 100.0;
+        honest.uptime = 48 * 3600;
+        honest.requests_served = 5_000;
+        assert!(!honest.is_suspicious());
+        assert!((honest.performance_score() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rounding_ties_to_even() {
+        assert_eq!(round_to_smallest_unit(2.5), 2);
+        assert_eq!(round_to_smallest_unit(3.5), 4);
+        assert_eq!(round_to_smallest_unit(2.4999), 2);
+        assert_eq!(round_to_smallest_unit(2.5001), 3);
+        assert_eq!(round_to_smallest_unit(-1.0), 0);
+        assert_eq!(round_to_smallest_unit(f64::NAN), 0);
+        assert_eq!(round_to_smallest_unit(f64::INFINITY), 0);
+
+        // Token conversions share the policy: 2.5 and 3.5 units at $1 with 0 decimals
+        assert_eq!(usd_to_token(2.5, 1.0, 0), 2);
+        assert_eq!(usd_to_token(3.5, 1.0, 0), 4);
+    }
+}
@@ -0,0 +1,187 @@
+//! Validator-signed checkpoints for light clients
+//!
+//! A checkpoint commits to `(height, block_hash, state_root)`. Light clients
+//! trust it once a quorum of known validators has signed that tuple.
+
+use crate::{Address, BlockHeight, Hash, QoraNetError, QoraSignature, Result};
+use ed25519_dalek::{Keypair, PublicKey, Signer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::NetworkMessage;
+
+/// Domain separator so checkpoint signatures can't be replayed as other messages
+const CHECKPOINT_DOMAIN: &[u8] = b"QORANET_CHECKPOINT_V1";
+
+/// A validator's signature over a checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorSignature {
+    pub validator: Address,
+    pub signature: QoraSignature,
+}
+
+/// Bytes validators sign for a checkpoint
+pub fn checkpoint_signing_message(height: BlockHeight, block_hash: &Hash, state_root: &Hash) -> Vec<u8> {
+    let mut message = Vec::with_capacity(CHECKPOINT_DOMAIN.len() + 8 + 64);
+    message.extend_from_slice(CHECKPOINT_DOMAIN);
+    message.extend_from_slice(&height.to_le_bytes());
+    message.extend_from_slice(block_hash.as_bytes());
+    message.extend_from_slice(state_root.as_bytes());
+    message
+}
+
+/// Co-sign a checkpoint as a validator
+pub fn sign_checkpoint(
+    keypair: &Keypair,
+    height: BlockHeight,
+    block_hash: &Hash,
+    state_root: &Hash,
+) -> ValidatorSignature {
+    let message = checkpoint_signing_message(height, block_hash, state_root);
+    ValidatorSignature {
+        validator: Address::from_pubkey(&keypair.public),
+        signature: keypair.sign(&message),
+    }
+}
+
+/// Verifies checkpoints against a known validator set
+#[derive(Debug, Clone)]
+pub struct CheckpointVerifier {
+    validators: HashSet<Address>,
+    quorum: usize,
+}
+
+impl CheckpointVerifier {
+    /// Verifier requiring `quorum` distinct validator signatures
+    pub fn new(validators: impl IntoIterator<Item = Address>, quorum: usize) -> Self {
+        Self {
+            validators: validators.into_iter().collect(),
+            quorum: quorum.max(1),
+        }
+    }
+
+    /// Verifier requiring more than two thirds of the validator set
+    pub fn with_bft_quorum(validators: impl IntoIterator<Item = Address>) -> Self {
+        let validators: HashSet<Address> = validators.into_iter().collect();
+        let quorum = validators.len() * 2 / 3 + 1;
+        Self { validators, quorum }
+    }
+
+    /// Required number of signatures
+    pub fn quorum(&self) -> usize {
+        self.quorum
+    }
+
+    /// Count valid signatures from distinct known validators; errors below quorum
+    pub fn verify(
+        &self,
+        height: BlockHeight,
+        block_hash: &Hash,
+        state_root: &Hash,
+        validator_signatures: &[ValidatorSignature],
+    ) -> Result<usize> {
+        let message = checkpoint_signing_message(height, block_hash, state_root);
+        let mut signers = HashSet::new();
+
+        for entry in validator_signatures {
+            if !self.validators.contains(&entry.validator) || signers.contains(&entry.validator) {
+                continue;
+            }
+            let valid = PublicKey::from_bytes(&entry.validator.0)
+                .map(|pubkey| pubkey.verify_strict(&message, &entry.signature).is_ok())
+                .unwrap_or(false);
+            if valid {
+                signers.insert(entry.validator.clone());
+            }
+        }
+
+        if signers.len() < self.quorum {
+            return Err(QoraNetError::ConsensusError(format!(
+                "Checkpoint at height {} has {} valid signatures, quorum is {}",
+                height, signers.len(), self.quorum
+            )));
+        }
+
+        Ok(signers.len())
+    }
+
+    /// Light-client acceptance of a gossiped checkpoint
+    pub fn accept(&self, message: &NetworkMessage) -> Result<(BlockHeight, Hash, Hash)> {
+        match message {
+            NetworkMessage::SignedCheckpoint { height, block_hash, state_root, validator_signatures } => {
+                self.verify(*height, block_hash, state_root, validator_signatures)?;
+                Ok((*height, block_hash.clone(), state_root.clone()))
+            }
+            _ => Err(QoraNetError::NetworkError("Not a checkpoint message".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    fn validators(count: usize) -> Vec<Keypair> {
+        (0..count).map(|_| Keypair::generate(&mut OsRng)).collect()
+    }
+
+    fn checkpoint(keypairs: &[Keypair], signers: usize) -> NetworkMessage {
+        let block_hash = Hash::new(b"block-100");
+        let state_root = Hash::new(b"state-100");
+        NetworkMessage::SignedCheckpoint {
+            height: 100,
+            validator_signatures: keypairs[..signers]
+                .iter()
+                .map(|keypair| sign_checkpoint(keypair, 100, &block_hash, &state_root))
+                .collect(),
+            block_hash,
+            state_root,
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_with_quorum_accepted() {
+        let keypairs = validators(4);
+        let verifier = CheckpointVerifier::with_bft_quorum(
+            keypairs.iter().map(|k| Address::from_pubkey(&k.public)),
+        );
+        assert_eq!(verifier.quorum(), 3);
+
+        let (height, block_hash, _) = verifier.accept(&checkpoint(&keypairs, 3)).unwrap();
+        assert_eq!(height, 100);
+        assert_eq!(block_hash, Hash::new(b"block-100"));
+    }
+
+    #[test]
+    fn test_checkpoint_below_quorum_rejected() {
+        let keypairs = validators(4);
+        let verifier = CheckpointVerifier::with_bft_quorum(
+            keypairs.iter().map(|k| Address::from_pubkey(&k.public)),
+        );
+
+        assert!(verifier.accept(&checkpoint(&keypairs, 2)).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_and_foreign_signatures_not_counted() {
+        let keypairs = validators(4);
+        let outsider = validators(1);
+        let verifier = CheckpointVerifier::with_bft_quorum(
+            keypairs.iter().map(|k| Address::from_pubkey(&k.public)),
+        );
+
+        let block_hash = Hash::new(b"block-100");
+        let state_root = Hash::new(b"state-100");
+        let signature = sign_checkpoint(&keypairs[0], 100, &block_hash, &state_root);
+        let signatures = vec![
+            signature.clone(),
+            signature,
+            sign_checkpoint(&outsider[0], 100, &block_hash, &state_root),
+            // Signed over a different state root
+            sign_checkpoint(&keypairs[1], 100, &block_hash, &Hash::new(b"other")),
+        ];
+
+        assert!(verifier.verify(100, &block_hash, &state_root, &signatures).is_err());
+    }
+}
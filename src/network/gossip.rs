@@ -0,0 +1,108 @@
+//! Gossip deduplication
+//!
+//! Every node re-broadcasts new transactions and blocks to its peers, so the
+//! same item reaches a node along many paths. The seen cache remembers the
+//! content hashes of recently relayed items and drops repeats, which stops an
+//! item from circulating through the mesh forever. Entries expire after a TTL
+//! and the least recently seen are evicted once the cache is full.
+//!
+//! Only items that passed validation are recorded: a hash need not cover
+//! everything that makes an item valid (a block's hash leaves out its
+//! signature), so recording an invalid copy would shadow the genuine one.
+
+use crate::Hash;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Default number of content hashes remembered
+pub const DEFAULT_SEEN_CACHE_SIZE: usize = 10_000;
+
+/// Default time an item counts as seen
+pub const DEFAULT_SEEN_TTL: Duration = Duration::from_secs(600);
+
+/// LRU set of recently seen content hashes with a TTL
+#[derive(Debug)]
+pub struct SeenCache {
+    /// First sighting and recency tick of each hash
+    entries: HashMap<Hash, (Instant, u64)>,
+    recency: BTreeMap<u64, Hash>,
+    next_tick: u64,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl SeenCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_tick: 0,
+            capacity: capacity.max(1),
+            ttl,
+        }
+    }
+
+    /// Record `hash` as seen at `now`; returns false if it was already seen within the TTL
+    pub fn insert(&mut self, hash: Hash, now: Instant) -> bool {
+        self.next_tick += 1;
+        let tick = self.next_tick;
+
+        if let Some((first_seen, last_tick)) = self.entries.get(&hash).copied() {
+            self.recency.remove(&last_tick);
+            let fresh = now.duration_since(first_seen) < self.ttl;
+            // Expired entries start over, so the TTL runs from the new sighting
+            let first_seen = if fresh { first_seen } else { now };
+            self.recency.insert(tick, hash.clone());
+            self.entries.insert(hash, (first_seen, tick));
+            return !fresh;
+        }
+
+        if self.entries.len() >= self.capacity {
+            if let Some((_, evicted)) = self.recency.pop_first() {
+                self.entries.remove(&evicted);
+            }
+        }
+        self.recency.insert(tick, hash.clone());
+        self.entries.insert(hash, (now, tick));
+        true
+    }
+
+    /// Whether `hash` was seen within the TTL, without recording a sighting
+    pub fn contains(&self, hash: &Hash, now: Instant) -> bool {
+        self.entries.get(hash)
+            .map_or(false, |(first_seen, _)| now.duration_since(*first_seen) < self.ttl)
+    }
+
+    /// Number of hashes remembered, including expired ones not yet evicted
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seen_cache_expires_and_evicts_least_recent() {
+        let start = Instant::now();
+        let mut cache = SeenCache::new(2, Duration::from_secs(60));
+        let (a, b, c) = (Hash::new(b"a"), Hash::new(b"b"), Hash::new(b"c"));
+
+        assert!(cache.insert(a.clone(), start));
+        assert!(!cache.insert(a.clone(), start + Duration::from_secs(59)));
+        assert!(cache.insert(a.clone(), start + Duration::from_secs(60)));
+
+        // `a` was seen more recently than `b`, so `b` makes way for `c`
+        assert!(cache.insert(b.clone(), start + Duration::from_secs(61)));
+        assert!(!cache.insert(a.clone(), start + Duration::from_secs(62)));
+        assert!(cache.insert(c, start + Duration::from_secs(63)));
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.insert(a, start + Duration::from_secs(64)));
+        assert!(cache.insert(b, start + Duration::from_secs(65)));
+    }
+}
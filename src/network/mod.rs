@@ -1,10 +1,25 @@
-use crate::{Hash, Address, Result, QoraNetError};
-use crate::consensus::Block;
-use crate::transaction::Transaction;
+pub mod checkpoint;
+pub mod gossip;
+pub mod request;
+pub mod validation;
+pub mod wire;
+
+pub use checkpoint::{CheckpointVerifier, ValidatorSignature};
+pub use gossip::SeenCache;
+pub use request::PendingResponses;
+pub use validation::BlockValidator;
+pub use wire::{decode_message, encode_message, PROTOCOL_VERSION};
+
+use crate::{Hash, Address, BlockHeight, Result, QoraNetError};
+use crate::consensus::{Block, ConsensusState};
+use crate::storage::BlockchainStorage;
+use crate::transaction::{NonceGap, Transaction};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::sync::{broadcast, mpsc};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::{info, warn, debug};
 
 /// Network message types
@@ -19,20 +34,22 @@ pub enum NetworkMessage {
     /// Request for block by hash
     BlockRequest(Hash),
     
-    /// Block response
-    BlockResponse(Option<Block>),
+    /// Block response, echoing the requested hash
+    BlockResponse(Hash, BlockResponse),
     
     /// Request for transaction by hash
     TransactionRequest(Hash),
     
-    /// Transaction response
-    TransactionResponse(Option<Transaction>),
+    /// Transaction response, echoing the requested hash
+    TransactionResponse(Hash, Option<Transaction>),
     
-    /// Peer discovery
+    /// Peer discovery (handshake)
     PeerDiscovery {
         peer_id: String,
         address: String,
         port: u16,
+        protocol_version: u16,
+        genesis_hash: Hash,
     },
     
     /// Validator announcement
@@ -60,6 +77,56 @@ pub enum NetworkMessage {
         timestamp: u64,
         peer_id: String,
     },
+
+    /// Ask peers for a signer's transactions with nonces in `from_nonce..to_nonce`
+    MissingTransactionsRequest {
+        signer: Address,
+        from_nonce: u64,
+        to_nonce: u64,
+    },
+
+    /// Chain tip co-signed by validators, for light clients
+    SignedCheckpoint {
+        height: BlockHeight,
+        block_hash: Hash,
+        state_root: Hash,
+        validator_signatures: Vec<ValidatorSignature>,
+    },
+
+    /// First message on a connection: which chain we are on and how far along
+    Handshake {
+        version: u16,
+        genesis_hash: Hash,
+        chain_id: u64,
+        best_height: BlockHeight,
+        best_hash: Hash,
+        /// Oldest block still served; 0 for archive nodes
+        earliest_available_height: BlockHeight,
+    },
+
+    /// Ask a peer for the blocks in `from_height..=to_height`
+    SyncRequest {
+        from_height: BlockHeight,
+        to_height: BlockHeight,
+    },
+
+    /// Answer to a `SyncRequest`: consecutive blocks from its `from_height`, possibly fewer than asked
+    SyncResponse {
+        blocks: Vec<Block>,
+    },
+}
+
+/// Most blocks sent in one `SyncResponse`
+pub const MAX_SYNC_BLOCKS: u64 = 500;
+
+/// Answer to a `BlockRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BlockResponse {
+    Found(Block),
+    /// We never had the block
+    NotFound,
+    /// We may have had it but pruned it; ask a node that keeps older history
+    Pruned { earliest_available_height: BlockHeight },
 }
 
 /// Peer information
@@ -74,6 +141,10 @@ pub struct PeerInfo {
     pub apps_count: u32,
     pub ping_ms: Option<u64>,
     pub connection_status: ConnectionStatus,
+    /// Oldest block the peer serves, learned from its handshake
+    pub earliest_available_height: BlockHeight,
+    /// Requests the peer left unanswered; peers with fewer are asked first
+    pub failed_requests: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -96,6 +167,9 @@ pub struct NetworkManager {
     /// Known peers
     peers: HashMap<String, PeerInfo>,
     
+    /// Peer id of the connection to each dialled socket address
+    dialled: HashMap<SocketAddr, String>,
+    
     /// Message broadcaster
     message_tx: broadcast::Sender<NetworkMessage>,
     
@@ -106,27 +180,79 @@ pub struct NetworkManager {
     outgoing_tx: mpsc::UnboundedSender<(String, NetworkMessage)>, // (peer_id, message)
     outgoing_rx: mpsc::UnboundedReceiver<(String, NetworkMessage)>,
     
+    /// Off-loop block validation
+    block_validator: BlockValidator,
+    
+    /// Requests awaiting a peer's answer
+    pending_responses: PendingResponses,
+    
+    /// Hashes of recently relayed transactions and blocks
+    seen: SeenCache,
+    
+    /// Local chain tip advertised in handshakes
+    best_height: BlockHeight,
+    best_hash: Hash,
+    
+    /// Oldest block we still serve, advertised in handshakes
+    earliest_available_height: BlockHeight,
+    
+    /// Consensus state that received blocks' producers are checked against
+    consensus: Option<Arc<RwLock<ConsensusState>>>,
+    
+    /// Local chain, read for our tip and to serve sync requests
+    storage: Option<Arc<RwLock<BlockchainStorage>>>,
+    
     /// Network configuration
     config: NetworkConfig,
 }
 
 #[derive(Debug, Clone)]
 pub struct NetworkConfig {
+    /// Interface the P2P listener binds to
+    pub listen_addr: IpAddr,
     pub listen_port: u16,
+    /// Address peers should dial, if different from the bind address (e.g. behind NAT)
+    pub advertised_addr: Option<SocketAddr>,
     pub max_peers: usize,
     pub connection_timeout: Duration,
     pub ping_interval: Duration,
     pub bootstrap_peers: Vec<String>,
+    /// Genesis hash of our chain; peers on another chain are refused
+    pub genesis_hash: Hash,
+    /// Chain id checked in handshakes
+    pub chain_id: u64,
+    /// First height whose blocks carry a prefixed transactions root
+    pub domain_separated_root_height: BlockHeight,
+    /// Blocks validated concurrently before incoming blocks are held back
+    pub max_pending_validations: usize,
+    /// How long a peer has to answer a block or transaction request
+    pub request_timeout: Duration,
+    /// Peers a request is tried against before giving up
+    pub max_request_attempts: usize,
+    /// Transactions and blocks remembered for gossip deduplication
+    pub seen_cache_size: usize,
+    /// How long a relayed item is remembered
+    pub seen_ttl: Duration,
 }
 
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
+            listen_addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             listen_port: 8080,
+            advertised_addr: None,
             max_peers: 100,
             connection_timeout: Duration::from_secs(10),
             ping_interval: Duration::from_secs(30),
             bootstrap_peers: Vec::new(),
+            genesis_hash: crate::consensus::GenesisConfig::default().genesis_hash(),
+            chain_id: crate::consensus::GenesisConfig::default().chain_id,
+            domain_separated_root_height: 0,
+            max_pending_validations: validation::DEFAULT_MAX_PENDING_VALIDATIONS,
+            request_timeout: request::DEFAULT_REQUEST_TIMEOUT,
+            max_request_attempts: request::DEFAULT_MAX_REQUEST_ATTEMPTS,
+            seen_cache_size: gossip::DEFAULT_SEEN_CACHE_SIZE,
+            seen_ttl: gossip::DEFAULT_SEEN_TTL,
         }
     }
 }
@@ -142,19 +268,128 @@ impl NetworkManager {
             peer_id,
             validator_address,
             peers: HashMap::new(),
+            dialled: HashMap::new(),
             message_tx,
             message_rx,
             outgoing_tx,
             outgoing_rx,
+            block_validator: BlockValidator::new(config.max_pending_validations)
+                .with_domain_separated_root_height(config.domain_separated_root_height),
+            pending_responses: PendingResponses::new(),
+            seen: SeenCache::new(config.seen_cache_size, config.seen_ttl),
+            best_height: 0,
+            best_hash: Hash::zero(),
+            earliest_available_height: 0,
+            consensus: None,
+            storage: None,
             config,
         }
     }
     
+    /// Record the local chain tip advertised to peers
+    pub fn set_local_tip(&mut self, height: BlockHeight, hash: Hash) {
+        self.best_height = height;
+        self.best_hash = hash;
+    }
+    
+    /// Record the oldest block still kept locally, after pruning
+    pub fn set_earliest_available_height(&mut self, height: BlockHeight) {
+        self.earliest_available_height = height;
+    }
+    
+    /// Share the node's consensus state so received blocks are checked for producer and equivocation
+    pub fn set_consensus(&mut self, consensus: Arc<RwLock<ConsensusState>>) {
+        self.consensus = Some(consensus);
+    }
+    
+    /// Share the node's storage, which handshakes advertise and sync requests are served from
+    pub fn set_storage(&mut self, storage: Arc<RwLock<BlockchainStorage>>) {
+        self.storage = Some(storage);
+    }
+    
+    /// Refresh the advertised tip and oldest kept block from storage, if shared
+    async fn refresh_local_tip(&mut self) -> Result<()> {
+        let storage = match &self.storage {
+            Some(storage) => Arc::clone(storage),
+            None => return Ok(()),
+        };
+        let storage = storage.read().await;
+        if let (Some(hash), height) = storage.get_latest_block_info() {
+            self.set_local_tip(height, hash);
+        }
+        self.set_earliest_available_height(storage.pruned_below()?);
+        Ok(())
+    }
+    
+    /// Handshake describing our chain and tip
+    pub fn local_handshake(&self) -> NetworkMessage {
+        NetworkMessage::Handshake {
+            version: PROTOCOL_VERSION,
+            genesis_hash: self.config.genesis_hash.clone(),
+            chain_id: self.config.chain_id,
+            best_height: self.best_height,
+            best_hash: self.best_hash.clone(),
+            earliest_available_height: self.earliest_available_height,
+        }
+    }
+    
+    /// Answer a `BlockRequest` given the local lookup result.
+    ///
+    /// `pruned_height` is where the height index places a missing block (see
+    /// `BlockchainStorage::pruned_block_height`). Only a miss dated below our
+    /// earliest available height is reported as `Pruned`, so the peer retries
+    /// against a node that keeps older blocks; hashes we never had are `NotFound`.
+    pub fn block_response(&self, block: Option<Block>, pruned_height: Option<BlockHeight>) -> BlockResponse {
+        match (block, pruned_height) {
+            (Some(block), _) => BlockResponse::Found(block),
+            (None, Some(height)) if height < self.earliest_available_height => BlockResponse::Pruned {
+                earliest_available_height: self.earliest_available_height,
+            },
+            (None, _) => BlockResponse::NotFound,
+        }
+    }
+    
+    /// A connected peer that still serves blocks at `height`
+    pub fn peer_serving_height(&self, height: BlockHeight) -> Option<&PeerInfo> {
+        self.peers.values()
+            .filter(|peer| matches!(peer.connection_status, ConnectionStatus::Connected))
+            .find(|peer| peer.earliest_available_height <= height)
+    }
+    
+    /// Address the P2P listener binds to
+    pub fn listen_socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.config.listen_addr, self.config.listen_port)
+    }
+    
+    /// Address announced to peers: the configured external address, else the bind address
+    pub fn advertised_socket_addr(&self) -> SocketAddr {
+        self.config.advertised_addr.unwrap_or_else(|| self.listen_socket_addr())
+    }
+    
+    /// Bind the P2P listener on the configured interface
+    pub async fn bind_listener(&self) -> Result<tokio::net::TcpListener> {
+        let addr = self.listen_socket_addr();
+        tokio::net::TcpListener::bind(addr).await
+            .map_err(|e| QoraNetError::NetworkError(format!("Failed to bind {}: {}", addr, e)))
+    }
+    
+    /// Discovery announcement carrying our advertised address
+    fn discovery_message(&self) -> NetworkMessage {
+        let advertised = self.advertised_socket_addr();
+        NetworkMessage::PeerDiscovery {
+            peer_id: self.peer_id.clone(),
+            address: advertised.ip().to_string(),
+            port: advertised.port(),
+            protocol_version: PROTOCOL_VERSION,
+            genesis_hash: self.config.genesis_hash.clone(),
+        }
+    }
+    
     /// Start the network manager
     pub async fn start(&mut self) -> Result<()> {
         info!("🌐 Starting QoraNet P2P network...");
         info!("📡 Peer ID: {}", self.peer_id);
-        info!("🔗 Listening on port: {}", self.config.listen_port);
+        info!("🔗 Listening on {}, advertised as {}", self.listen_socket_addr(), self.advertised_socket_addr());
         
         // Start message processing task
         let message_tx = self.message_tx.clone();
@@ -202,19 +437,16 @@ impl NetworkManager {
         }
         
         // Broadcast our presence
-        let discovery_msg = NetworkMessage::PeerDiscovery {
-            peer_id: self.peer_id.clone(),
-            address: "127.0.0.1".to_string(), // Would use actual IP
-            port: self.config.listen_port,
-        };
-        
-        self.broadcast_message(discovery_msg).await?;
+        self.broadcast_message(self.discovery_message(), None).await?;
         
         Ok(())
     }
     
-    /// Connect to a specific peer
-    async fn connect_to_peer(&mut self, peer_address: &str) -> Result<()> {
+    /// Connect to a specific peer, returning its peer id.
+    ///
+    /// Connections are keyed by resolved socket address, so a peer listed twice
+    /// (or under two names) reuses the live connection instead of opening another.
+    async fn connect_to_peer(&mut self, peer_address: &str) -> Result<String> {
         debug!("Connecting to peer: {}", peer_address);
         
         // Parse address (simplified)
@@ -227,6 +459,17 @@ impl NetworkManager {
         let port: u16 = parts[1].parse()
             .map_err(|_| QoraNetError::NetworkError("Invalid port number".to_string()))?;
         
+        let socket_addr = tokio::net::lookup_host((address.as_str(), port)).await
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| QoraNetError::NetworkError(format!("Could not resolve peer address {}", peer_address)))?;
+        
+        if let Some(existing) = self.live_connection(&socket_addr) {
+            debug!("Already connected to {} as {}, reusing connection", socket_addr, existing);
+            return Ok(existing);
+        }
+        
+        // Placeholder until the handshake tells us the peer's real id
         let peer_id = format!("peer-{}-{}", address, port);
         
         let peer_info = PeerInfo {
@@ -239,14 +482,49 @@ impl NetworkManager {
             apps_count: 0,
             ping_ms: None,
             connection_status: ConnectionStatus::Connecting,
+            earliest_available_height: 0,
+            failed_requests: 0,
         };
         
         self.peers.insert(peer_id.clone(), peer_info);
+        self.dialled.insert(socket_addr, peer_id.clone());
         
         // In a real implementation, this would establish a TCP/libp2p connection
         info!("📡 Connected to peer: {}", peer_id);
         
-        Ok(())
+        self.refresh_local_tip().await?;
+        self.send_to_peer(&peer_id, self.local_handshake()).await?;
+        Ok(peer_id)
+    }
+    
+    /// Peer id of a connection to `socket_addr` that has not failed or dropped
+    fn live_connection(&self, socket_addr: &SocketAddr) -> Option<String> {
+        let peer_id = self.dialled.get(socket_addr)?;
+        let peer = self.peers.get(peer_id)?;
+        match peer.connection_status {
+            ConnectionStatus::Connected | ConnectionStatus::Connecting => Some(peer_id.clone()),
+            ConnectionStatus::Disconnected | ConnectionStatus::Failed(_) => None,
+        }
+    }
+    
+    /// Re-key a dialled connection under the id the peer announced for itself.
+    ///
+    /// If that peer is already known (e.g. it dialled us first), the placeholder
+    /// entry is dropped so the peer keeps a single entry.
+    fn adopt_peer_id(&mut self, socket_addr: &SocketAddr, peer_id: &str) {
+        let placeholder = match self.dialled.get(socket_addr) {
+            Some(placeholder) if placeholder != peer_id => placeholder.clone(),
+            _ => return,
+        };
+        
+        if let Some(mut peer) = self.peers.remove(&placeholder) {
+            if !self.peers.contains_key(peer_id) {
+                peer.peer_id = peer_id.to_string();
+                self.peers.insert(peer_id.to_string(), peer);
+            }
+        }
+        debug!("Peer {} identified itself as {}", placeholder, peer_id);
+        self.dialled.insert(*socket_addr, peer_id.to_string());
     }
     
     /// Start periodic ping task
@@ -279,11 +557,14 @@ impl NetworkManager {
         });
     }
     
-    /// Broadcast message to all peers
-    pub async fn broadcast_message(&self, message: NetworkMessage) -> Result<()> {
+    /// Broadcast message to all peers, except `exclude_peer` (the peer it came from, when relaying)
+    pub async fn broadcast_message(&self, message: NetworkMessage, exclude_peer: Option<&str>) -> Result<()> {
         debug!("Broadcasting message: {:?}", message);
         
         for peer_id in self.peers.keys() {
+            if exclude_peer == Some(peer_id.as_str()) {
+                continue;
+            }
             if let Err(e) = self.outgoing_tx.send((peer_id.clone(), message.clone())) {
                 warn!("Failed to queue message for peer {}: {}", peer_id, e);
             }
@@ -292,6 +573,34 @@ impl NetworkManager {
         Ok(())
     }
     
+    /// Broadcast a checkpoint once its validator signatures have been collected
+    pub async fn broadcast_checkpoint(
+        &self,
+        height: BlockHeight,
+        block_hash: Hash,
+        state_root: Hash,
+        validator_signatures: Vec<ValidatorSignature>,
+    ) -> Result<()> {
+        info!("📌 Broadcasting checkpoint #{} with {} signatures", height, validator_signatures.len());
+
+        let msg = NetworkMessage::SignedCheckpoint {
+            height,
+            block_hash,
+            state_root,
+            validator_signatures,
+        };
+        self.broadcast_message(msg, None).await
+    }
+
+    /// Ask peers for transactions missing from a signer's nonce sequence
+    pub async fn request_missing_transactions(&self, gap: &NonceGap) -> Result<()> {
+        debug!(
+            "Requesting nonces {}..{} for signer {} from peers",
+            gap.from_nonce, gap.to_nonce, gap.signer
+        );
+        self.broadcast_message(gap.to_request(), None).await
+    }
+
     /// Send message to specific peer
     pub async fn send_to_peer(&self, peer_id: &str, message: NetworkMessage) -> Result<()> {
         debug!("Sending message to peer {}: {:?}", peer_id, message);
@@ -307,46 +616,317 @@ impl NetworkManager {
         Ok(())
     }
     
-    /// Handle incoming transaction
-    pub async fn handle_new_transaction(&mut self, transaction: Transaction) -> Result<()> {
-        info!("📥 Received new transaction: {}", transaction.hash());
+    /// Handle for delivering peers' answers to outstanding requests
+    pub fn pending_responses(&self) -> PendingResponses {
+        self.pending_responses.clone()
+    }
+    
+    /// Ask peers for a block by hash.
+    ///
+    /// Fails with a `NetworkError` if no peer answers within the configured
+    /// timeout after `max_request_attempts` tries.
+    pub async fn request_block(&mut self, hash: Hash) -> Result<BlockResponse> {
+        match self.request(&hash, NetworkMessage::BlockRequest(hash.clone())).await? {
+            NetworkMessage::BlockResponse(_, response) => Ok(response),
+            other => Err(QoraNetError::NetworkError(format!(
+                "Unexpected answer to block request {}: {:?}", hash, other
+            ))),
+        }
+    }
+    
+    /// Ask peers for a transaction by hash, with the same timeout and retries as `request_block`
+    pub async fn request_transaction(&mut self, hash: Hash) -> Result<Option<Transaction>> {
+        match self.request(&hash, NetworkMessage::TransactionRequest(hash.clone())).await? {
+            NetworkMessage::TransactionResponse(_, response) => Ok(response),
+            other => Err(QoraNetError::NetworkError(format!(
+                "Unexpected answer to transaction request {}: {:?}", hash, other
+            ))),
+        }
+    }
+    
+    /// Send `message`, a request for `hash`, to one peer at a time until one answers in time.
+    ///
+    /// Peers that time out are penalized, so later requests try them last.
+    async fn request(&mut self, hash: &Hash, message: NetworkMessage) -> Result<NetworkMessage> {
+        let candidates = self.request_candidates();
+        let attempts = candidates.len().min(self.config.max_request_attempts.max(1));
+        if attempts == 0 {
+            return Err(QoraNetError::NetworkError("No connected peers to send request to".to_string()));
+        }
+        
+        for peer_id in candidates.into_iter().take(attempts) {
+            let response = self.pending_responses.register(&peer_id, hash);
+            if let Err(e) = self.send_to_peer(&peer_id, message.clone()).await {
+                self.pending_responses.cancel(&peer_id, hash);
+                warn!("Failed to send request to peer {}: {}", peer_id, e);
+                continue;
+            }
+            
+            match tokio::time::timeout(self.config.request_timeout, response).await {
+                Ok(Ok(answer)) => return Ok(answer),
+                _ => {
+                    self.pending_responses.cancel(&peer_id, hash);
+                    warn!("Peer {} did not answer within {:?}, trying another peer", peer_id, self.config.request_timeout);
+                    self.penalize_peer(&peer_id);
+                }
+            }
+        }
+        
+        Err(QoraNetError::NetworkError(format!(
+            "Request timed out after {} attempt(s)", attempts
+        )))
+    }
+    
+    /// Connected peers, fewest failed requests first (ties by peer id)
+    fn request_candidates(&self) -> Vec<String> {
+        let mut candidates: Vec<&PeerInfo> = self.peers.values()
+            .filter(|peer| matches!(peer.connection_status, ConnectionStatus::Connected))
+            .collect();
+        candidates.sort_by(|a, b| a.failed_requests.cmp(&b.failed_requests)
+            .then_with(|| a.peer_id.cmp(&b.peer_id)));
+        candidates.into_iter().map(|peer| peer.peer_id.clone()).collect()
+    }
+    
+    /// Count an unanswered request against a peer, dropping it once it has too many
+    fn penalize_peer(&mut self, peer_id: &str) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.failed_requests += 1;
+            if peer.failed_requests >= request::MAX_PEER_REQUEST_FAILURES {
+                warn!("Disconnecting peer {}: {} unanswered requests", peer_id, peer.failed_requests);
+                peer.connection_status = ConnectionStatus::Failed("unresponsive".to_string());
+            }
+        }
+    }
+    
+    /// Handle incoming transaction from `from_peer` (`None` if submitted locally).
+    ///
+    /// Transactions already relayed are dropped, so gossip doesn't loop.
+    pub async fn handle_new_transaction(&mut self, transaction: Transaction, from_peer: Option<&str>) -> Result<()> {
+        let tx_hash = transaction.hash();
+        if self.seen.contains(&tx_hash, Instant::now()) {
+            debug!("Dropping already seen transaction {}", tx_hash);
+            return Ok(());
+        }
+        info!("📥 Received new transaction: {}", tx_hash);
         
         // Validate transaction
         // In a real implementation, this would be more comprehensive
         transaction.verify_signature()?;
+        self.seen.insert(tx_hash, Instant::now());
         
-        // Broadcast to other peers
+        // Broadcast to other peers (excluding sender)
         let msg = NetworkMessage::NewTransaction(transaction);
-        self.broadcast_message(msg).await?;
+        self.broadcast_message(msg, from_peer).await?;
         
         Ok(())
     }
     
-    /// Handle incoming block
-    pub async fn handle_new_block(&mut self, block: Block) -> Result<()> {
-        info!("📥 Received new block #{}: {}", block.header.height, block.hash());
+    /// Handle incoming block from `from_peer`, dropping blocks already relayed
+    pub async fn handle_new_block(&mut self, block: Block, from_peer: Option<&str>) -> Result<()> {
+        let block_hash = block.hash();
+        if self.seen.contains(&block_hash, Instant::now()) {
+            debug!("Dropping already seen block {}", block_hash);
+            return Ok(());
+        }
+        info!("📥 Received new block #{}: {}", block.header.height, block_hash);
         
-        // Basic validation
-        // In a real implementation, this would be more comprehensive
+        // Validate off the networking task; waits for a slot if validation is behind
         let expected_height = 0; // Would get from local blockchain
         let expected_previous = Hash::zero(); // Would get from local blockchain
-        block.validate(expected_height, &expected_previous)?;
+        if self.block_validator.is_saturated() {
+            debug!("Block validation backlog full, waiting for a slot");
+        }
+        let block = self.block_validator.validate(block, expected_height, expected_previous).await?;
+        
+        if let Some(consensus) = &self.consensus {
+            let mut consensus = consensus.write().await;
+            // Only the selected validator, proven by its signature, may produce the block
+            consensus.validate_block_producer(&block)?;
+            // A second signed block for the same height queues evidence for our next block
+            consensus.record_produced_block(&block);
+        }
+        // Remembered only once valid, so an unsigned or forged copy can't shadow the real block
+        self.seen.insert(block_hash, Instant::now());
         
         // Broadcast to other peers (excluding sender)
         let msg = NetworkMessage::NewBlock(block);
-        self.broadcast_message(msg).await?;
+        self.broadcast_message(msg, from_peer).await?;
         
         Ok(())
     }
     
+    /// Decode a frame received from a peer, disconnecting peers on an incompatible protocol version.
+    ///
+    /// Answers to our block and transaction requests are handed to the waiting requester.
+    pub fn handle_incoming_bytes(&mut self, peer_id: &str, bytes: &[u8]) -> Result<NetworkMessage> {
+        let version = wire::peek_version(bytes)?;
+        if !wire::is_compatible_version(version) {
+            self.disconnect_incompatible_peer(peer_id, version);
+            return Err(QoraNetError::NetworkError(format!(
+                "Peer {} speaks protocol version {}, local version is {}",
+                peer_id, version, PROTOCOL_VERSION
+            )));
+        }
+
+        let message = decode_message(bytes)?;
+        if matches!(message, NetworkMessage::BlockResponse(..) | NetworkMessage::TransactionResponse(..))
+            && !self.pending_responses.deliver(peer_id, message.clone())
+        {
+            debug!("Dropping unrequested or mismatched response from peer {}", peer_id);
+        }
+        Ok(message)
+    }
+
+    /// Handle a frame from a connected peer: the connection loop's entry point.
+    ///
+    /// Handshakes are checked against our current tip and may start a sync;
+    /// sync requests are answered from storage, and synced blocks that extend
+    /// our tip are published to `subscribe`rs as `NewBlock`s for import.
+    pub async fn handle_peer_frame(&mut self, peer_id: &str, bytes: &[u8]) -> Result<NetworkMessage> {
+        let message = self.handle_incoming_bytes(peer_id, bytes)?;
+        match &message {
+            NetworkMessage::Handshake {
+                version, genesis_hash, chain_id, best_height, best_hash, earliest_available_height,
+            } => {
+                self.refresh_local_tip().await?;
+                self.handle_handshake(
+                    peer_id, *version, genesis_hash.clone(), *chain_id,
+                    *best_height, best_hash.clone(), *earliest_available_height,
+                )?;
+            }
+            NetworkMessage::SyncRequest { from_height, to_height } => {
+                self.handle_sync_request(peer_id, *from_height, *to_height).await?;
+            }
+            NetworkMessage::SyncResponse { blocks } => {
+                self.handle_sync_response(peer_id, blocks.clone()).await?;
+            }
+            _ => {}
+        }
+        Ok(message)
+    }
+    
+    /// Send a peer the blocks it asked for, up to `MAX_SYNC_BLOCKS` and stopping at the first one we don't have
+    pub async fn handle_sync_request(&mut self, peer_id: &str, from_height: BlockHeight, to_height: BlockHeight) -> Result<()> {
+        let storage = match &self.storage {
+            Some(storage) => Arc::clone(storage),
+            None => return Ok(()),
+        };
+        let to_height = to_height.min(from_height.saturating_add(MAX_SYNC_BLOCKS - 1));
+        
+        let mut blocks = Vec::new();
+        {
+            let storage = storage.read().await;
+            for height in from_height..=to_height {
+                match storage.get_block_by_height(height)? {
+                    Some(block) => blocks.push(block),
+                    None => break,
+                }
+            }
+        }
+        
+        debug!("Serving {} block(s) from #{} to peer {}", blocks.len(), from_height, peer_id);
+        self.send_to_peer(peer_id, NetworkMessage::SyncResponse { blocks }).await
+    }
+    
+    /// Validate synced blocks in order on top of our tip, publishing each valid one for import.
+    ///
+    /// Blocks that don't extend the chain stop the import; the peer is penalized
+    /// unless it merely had nothing newer. Returns the number of blocks published.
+    pub async fn handle_sync_response(&mut self, peer_id: &str, blocks: Vec<Block>) -> Result<usize> {
+        if blocks.len() as u64 > MAX_SYNC_BLOCKS {
+            self.penalize_peer(peer_id);
+            return Err(QoraNetError::NetworkError(format!(
+                "Peer {} sent {} blocks, more than the {} a sync response may carry",
+                peer_id, blocks.len(), MAX_SYNC_BLOCKS
+            )));
+        }
+        
+        let (mut height, mut previous) = (self.best_height, self.best_hash.clone());
+        let mut published = 0;
+        for block in blocks {
+            if block.header.height <= height {
+                continue;
+            }
+            let block = match self.block_validator.validate(block, height + 1, previous.clone()).await {
+                Ok(block) => block,
+                Err(e) => {
+                    warn!("Stopping sync from peer {}: {}", peer_id, e);
+                    self.penalize_peer(peer_id);
+                    break;
+                }
+            };
+            height = block.header.height;
+            previous = block.hash();
+            if self.message_tx.send(NetworkMessage::NewBlock(block)).is_err() {
+                debug!("No subscribers for synced block #{}", height);
+            }
+            published += 1;
+        }
+        
+        if published > 0 {
+            info!("🔄 Synced {} block(s) from peer {}, now at #{}", published, peer_id, height);
+        }
+        Ok(published)
+    }
+    
+    /// Mark a peer with an incompatible protocol version as failed
+    fn disconnect_incompatible_peer(&mut self, peer_id: &str, version: u16) {
+        warn!(
+            "Disconnecting peer {}: protocol version {} is incompatible with local version {}",
+            peer_id, version, PROTOCOL_VERSION
+        );
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.connection_status = ConnectionStatus::Failed(format!(
+                "incompatible protocol version {}", version
+            ));
+        }
+    }
+    
     /// Handle peer discovery message
-    pub async fn handle_peer_discovery(&mut self, peer_id: String, address: String, port: u16) -> Result<()> {
+    pub async fn handle_peer_discovery(
+        &mut self,
+        peer_id: String,
+        address: String,
+        port: u16,
+        protocol_version: u16,
+        genesis_hash: Hash,
+    ) -> Result<()> {
         if peer_id == self.peer_id {
-            return Ok((); // Ignore our own discovery message
+            return Ok(()); // Ignore our own discovery message
+        }
+
+        if genesis_hash != self.config.genesis_hash {
+            warn!(
+                "Refusing peer {}: genesis hash {} does not match ours ({})",
+                peer_id, genesis_hash, self.config.genesis_hash
+            );
+            self.peers.remove(&peer_id);
+            return Err(QoraNetError::NetworkError(format!(
+                "Peer {} is on a different chain (genesis {})",
+                peer_id, genesis_hash
+            )));
+        }
+
+        if !wire::is_compatible_version(protocol_version) {
+            self.disconnect_incompatible_peer(&peer_id, protocol_version);
+            return Err(QoraNetError::NetworkError(format!(
+                "Peer {} speaks protocol version {}, local version is {}",
+                peer_id, protocol_version, PROTOCOL_VERSION
+            )));
         }
         
         info!("🔍 Discovered peer: {} at {}:{}", peer_id, address, port);
         
+        if let Ok(ip) = address.parse::<IpAddr>() {
+            self.adopt_peer_id(&SocketAddr::new(ip, port), &peer_id);
+        }
+        
+        if let Some(peer) = self.peers.get_mut(&peer_id) {
+            peer.connection_status = ConnectionStatus::Connected;
+            peer.last_seen = SystemTime::now();
+            return Ok(());
+        }
+        
         let peer_info = PeerInfo {
             peer_id: peer_id.clone(),
             address,
@@ -357,6 +937,8 @@ impl NetworkManager {
             apps_count: 0,
             ping_ms: None,
             connection_status: ConnectionStatus::Connected,
+            earliest_available_height: 0,
+            failed_requests: 0,
         };
         
         self.peers.insert(peer_id, peer_info);
@@ -364,6 +946,65 @@ impl NetworkManager {
         Ok(())
     }
     
+    /// Handle a peer's handshake.
+    ///
+    /// Peers on another chain or protocol version are disconnected. If the peer
+    /// is ahead of us, the missing blocks are requested from it; returns whether
+    /// a sync was started.
+    pub fn handle_handshake(
+        &mut self,
+        peer_id: &str,
+        version: u16,
+        genesis_hash: Hash,
+        chain_id: u64,
+        best_height: BlockHeight,
+        best_hash: Hash,
+        earliest_available_height: BlockHeight,
+    ) -> Result<bool> {
+        if genesis_hash != self.config.genesis_hash || chain_id != self.config.chain_id {
+            warn!(
+                "Disconnecting peer {}: chain {} / genesis {} does not match ours ({} / {})",
+                peer_id, chain_id, genesis_hash, self.config.chain_id, self.config.genesis_hash
+            );
+            self.peers.remove(peer_id);
+            return Err(QoraNetError::NetworkError(format!(
+                "Peer {} is on a different chain (chain id {}, genesis {})",
+                peer_id, chain_id, genesis_hash
+            )));
+        }
+
+        if !wire::is_compatible_version(version) {
+            self.disconnect_incompatible_peer(peer_id, version);
+            return Err(QoraNetError::NetworkError(format!(
+                "Peer {} speaks protocol version {}, local version is {}",
+                peer_id, version, PROTOCOL_VERSION
+            )));
+        }
+
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.connection_status = ConnectionStatus::Connected;
+            peer.last_seen = SystemTime::now();
+            peer.earliest_available_height = earliest_available_height;
+        }
+
+        if best_height <= self.best_height {
+            return Ok(false);
+        }
+
+        info!(
+            "🔄 Peer {} is at #{} ({}), local tip #{}; syncing",
+            peer_id, best_height, best_hash, self.best_height
+        );
+        let request = NetworkMessage::SyncRequest {
+            from_height: self.best_height + 1,
+            to_height: best_height,
+        };
+        self.outgoing_tx.send((peer_id.to_string(), request))
+            .map_err(|e| QoraNetError::NetworkError(format!("Failed to send message: {}", e)))?;
+
+        Ok(true)
+    }
+    
     /// Handle validator announcement
     pub async fn handle_validator_announcement(&mut self, validator: Address, stake: u64, apps_count: u32) -> Result<()> {
         info!("👤 Validator announcement: {} with {} QOR stake, {} apps", 
@@ -437,3 +1078,451 @@ pub struct NetworkStats {
     pub total_apps: u32,
     pub average_ping_ms: Option<u64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::GenesisConfig;
+
+    fn manager(genesis: &GenesisConfig) -> NetworkManager {
+        let config = NetworkConfig {
+            genesis_hash: genesis.genesis_hash(),
+            ..NetworkConfig::default()
+        };
+        NetworkManager::new(Address([1u8; 32]), config)
+    }
+
+    #[tokio::test]
+    async fn test_peer_with_same_genesis_accepted() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+
+        node.handle_peer_discovery(
+            "peer-a".to_string(), "10.0.0.2".to_string(), 8080, PROTOCOL_VERSION, genesis.genesis_hash(),
+        ).await.unwrap();
+        assert_eq!(node.get_peers().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_peer_with_different_genesis_refused() {
+        let genesis = GenesisConfig::default();
+        let other_chain = GenesisConfig { chain_id: 9999, ..GenesisConfig::default() };
+        let mut node = manager(&genesis);
+
+        let result = node.handle_peer_discovery(
+            "peer-b".to_string(), "10.0.0.3".to_string(), 8080, PROTOCOL_VERSION, other_chain.genesis_hash(),
+        ).await;
+        assert!(result.is_err());
+        assert!(node.get_peers().is_empty());
+    }
+
+    #[test]
+    fn test_handshake_from_other_genesis_disconnects_peer() {
+        let genesis = GenesisConfig::default();
+        let other_chain = GenesisConfig { chain_id: 9999, ..GenesisConfig::default() };
+        let mut node = manager(&genesis);
+        node.peers.insert("peer-c".to_string(), PeerInfo {
+            peer_id: "peer-c".to_string(),
+            address: "10.0.0.4".to_string(),
+            port: 8080,
+            last_seen: SystemTime::now(),
+            validator_address: None,
+            stake: 0,
+            apps_count: 0,
+            ping_ms: None,
+            connection_status: ConnectionStatus::Connecting,
+            earliest_available_height: 0,
+            failed_requests: 0,
+        });
+
+        let result = node.handle_handshake(
+            "peer-c", PROTOCOL_VERSION, other_chain.genesis_hash(), other_chain.chain_id, 50, Hash::zero(), 0,
+        );
+        assert!(result.is_err());
+        assert!(node.get_peers().is_empty());
+        assert!(node.outgoing_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_handshake_from_higher_peer_requests_sync() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+        node.set_local_tip(10, Hash::new(b"tip-10"));
+
+        // A peer at or behind our tip needs nothing
+        let behind = node.handle_handshake(
+            "peer-d", PROTOCOL_VERSION, genesis.genesis_hash(), genesis.chain_id, 10, Hash::new(b"tip-10"), 0,
+        ).unwrap();
+        assert!(!behind);
+        assert!(node.outgoing_rx.try_recv().is_err());
+
+        let ahead = node.handle_handshake(
+            "peer-d", PROTOCOL_VERSION, genesis.genesis_hash(), genesis.chain_id, 25, Hash::new(b"tip-25"), 0,
+        ).unwrap();
+        assert!(ahead);
+        match node.outgoing_rx.try_recv().unwrap() {
+            (peer, NetworkMessage::SyncRequest { from_height, to_height }) => {
+                assert_eq!(peer, "peer-d");
+                assert_eq!((from_height, to_height), (11, 25));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pruned_block_request_points_to_earliest_height() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+        assert!(matches!(node.block_response(None, None), BlockResponse::NotFound));
+
+        node.set_earliest_available_height(1_000);
+        let block = Block::genesis(Address([1u8; 32]));
+        assert!(matches!(node.block_response(Some(block), None), BlockResponse::Found(_)));
+
+        // A hash the index doesn't date below our earliest block was never ours
+        assert!(matches!(node.block_response(None, None), BlockResponse::NotFound));
+        assert!(matches!(node.block_response(None, Some(1_000)), BlockResponse::NotFound));
+
+        let frame = encode_message(&NetworkMessage::BlockResponse(Hash::zero(), node.block_response(None, Some(999)))).unwrap();
+        match decode_message(&frame).unwrap() {
+            NetworkMessage::BlockResponse(_, BlockResponse::Pruned { earliest_available_height }) => {
+                assert_eq!(earliest_available_height, 1_000);
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        match node.local_handshake() {
+            NetworkMessage::Handshake { earliest_available_height, .. } => assert_eq!(earliest_available_height, 1_000),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handshake_frame_syncs_from_stored_tip() {
+        let genesis = GenesisConfig::default();
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let first = Block::genesis(Address([1u8; 32]));
+        let second = Block::new(first.hash(), 1, Address([1u8; 32]), Vec::new(), 0, 0);
+        storage.store_block(&first).unwrap();
+        storage.store_block(&second).unwrap();
+
+        let mut node = manager(&genesis);
+        node.set_storage(Arc::new(RwLock::new(storage)));
+        connected_peer(&mut node, "peer-a");
+
+        let handshake = encode_message(&NetworkMessage::Handshake {
+            version: PROTOCOL_VERSION,
+            genesis_hash: genesis.genesis_hash(),
+            chain_id: genesis.chain_id,
+            best_height: 5,
+            best_hash: Hash::new(b"tip-5"),
+            earliest_available_height: 0,
+        }).unwrap();
+        node.handle_peer_frame("peer-a", &handshake).await.unwrap();
+        match node.outgoing_rx.try_recv().unwrap() {
+            (_, NetworkMessage::SyncRequest { from_height, to_height }) => assert_eq!((from_height, to_height), (2, 5)),
+            other => panic!("unexpected message {:?}", other),
+        }
+
+        // Requests are served from storage, up to the blocks we have
+        let request = encode_message(&NetworkMessage::SyncRequest { from_height: 0, to_height: 10 }).unwrap();
+        node.handle_peer_frame("peer-a", &request).await.unwrap();
+        match node.outgoing_rx.try_recv().unwrap() {
+            (peer, NetworkMessage::SyncResponse { blocks }) => {
+                assert_eq!(peer, "peer-a");
+                assert_eq!(blocks.iter().map(Block::hash).collect::<Vec<_>>(), vec![first.hash(), second.hash()]);
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_response_publishes_blocks_extending_tip() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+        connected_peer(&mut node, "peer-a");
+        let first = Block::genesis(Address([1u8; 32]));
+        node.set_local_tip(0, first.hash());
+        let mut imported = node.subscribe();
+
+        let second = Block::new(first.hash(), 1, Address([1u8; 32]), Vec::new(), 0, 0);
+        let unlinked = Block::new(Hash::new(b"elsewhere"), 2, Address([1u8; 32]), Vec::new(), 0, 0);
+        let published = node.handle_sync_response("peer-a", vec![first, second.clone(), unlinked]).await.unwrap();
+
+        assert_eq!(published, 1);
+        assert!(matches!(imported.try_recv(), Ok(NetworkMessage::NewBlock(block)) if block.hash() == second.hash()));
+        assert!(imported.try_recv().is_err());
+        assert_eq!(node.peers["peer-a"].failed_requests, 1);
+    }
+
+    #[test]
+    fn test_old_blocks_routed_to_archive_peer() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+        for (peer_id, earliest) in [("pruned", 5_000), ("archive", 0)] {
+            node.peers.insert(peer_id.to_string(), PeerInfo {
+                peer_id: peer_id.to_string(),
+                address: "10.0.0.5".to_string(),
+                port: 8080,
+                last_seen: SystemTime::now(),
+                validator_address: None,
+                stake: 0,
+                apps_count: 0,
+                ping_ms: None,
+                connection_status: ConnectionStatus::Connecting,
+                earliest_available_height: 0,
+                failed_requests: 0,
+            });
+            node.handle_handshake(
+                peer_id, PROTOCOL_VERSION, genesis.genesis_hash(), genesis.chain_id, 0, Hash::zero(), earliest,
+            ).unwrap();
+        }
+        assert_eq!(node.peer_serving_height(10).unwrap().peer_id, "archive");
+        assert!(node.peer_serving_height(6_000).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_bootstrap_peer_reuses_connection() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+
+        let first = node.connect_to_peer("127.0.0.1:9100").await.unwrap();
+        let second = node.connect_to_peer("127.0.0.1:9100").await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(node.get_peers().len(), 1);
+
+        // Only the first connection sent a handshake
+        assert!(matches!(node.outgoing_rx.try_recv(), Ok((_, NetworkMessage::Handshake { .. }))));
+        assert!(node.outgoing_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_connection_is_redialled() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+
+        let peer_id = node.connect_to_peer("127.0.0.1:9101").await.unwrap();
+        node.disconnect_incompatible_peer(&peer_id, PROTOCOL_VERSION + 1);
+        node.connect_to_peer("127.0.0.1:9101").await.unwrap();
+
+        assert_eq!(node.get_peers().len(), 1);
+        assert!(matches!(node.get_peers()[0].connection_status, ConnectionStatus::Connecting));
+    }
+
+    #[tokio::test]
+    async fn test_discovery_replaces_placeholder_peer_id() {
+        let genesis = GenesisConfig::default();
+        let mut node = manager(&genesis);
+
+        let placeholder = node.connect_to_peer("127.0.0.1:9102").await.unwrap();
+        node.handle_peer_discovery(
+            "qora-0a0b0c0d".to_string(), "127.0.0.1".to_string(), 9102, PROTOCOL_VERSION, genesis.genesis_hash(),
+        ).await.unwrap();
+
+        let peers = node.get_peers();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].peer_id, "qora-0a0b0c0d");
+        assert!(!node.peers.contains_key(&placeholder));
+
+        // Dialling the same address again finds the peer under its real id
+        assert_eq!(node.connect_to_peer("127.0.0.1:9102").await.unwrap(), "qora-0a0b0c0d");
+        assert_eq!(node.get_peers().len(), 1);
+    }
+
+    fn connected_peer(node: &mut NetworkManager, peer_id: &str) {
+        node.peers.insert(peer_id.to_string(), PeerInfo {
+            peer_id: peer_id.to_string(),
+            address: "10.0.0.6".to_string(),
+            port: 8080,
+            last_seen: SystemTime::now(),
+            validator_address: None,
+            stake: 0,
+            apps_count: 0,
+            ping_ms: None,
+            connection_status: ConnectionStatus::Connected,
+            earliest_available_height: 0,
+            failed_requests: 0,
+        });
+    }
+
+    fn signed_transaction() -> Transaction {
+        use ed25519_dalek::{Keypair, Signer};
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let sender = Address::from_pubkey(&keypair.public);
+        let mut tx = Transaction {
+            data: crate::transaction::TransactionData::Transfer {
+                from: sender.clone(),
+                to: Address([7u8; 32]),
+                amount: 1,
+                memo: None,
+            },
+            nonce: 0,
+            fee_qor: 1_000,
+            fee_usd: 0.0001,
+            priority: crate::FeePriority::Low,
+            signature: crate::QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+            signer: sender,
+            valid_until_height: None,
+        };
+        tx.signature = keypair.sign(&tx.signing_message());
+        tx
+    }
+
+    fn drain_outgoing(node: &mut NetworkManager) -> Vec<String> {
+        let mut recipients = Vec::new();
+        while let Ok((peer_id, _)) = node.outgoing_rx.try_recv() {
+            recipients.push(peer_id);
+        }
+        recipients.sort();
+        recipients
+    }
+
+    #[tokio::test]
+    async fn test_repeated_transaction_broadcast_once() {
+        let mut node = manager(&GenesisConfig::default());
+        connected_peer(&mut node, "peer-a");
+        connected_peer(&mut node, "peer-b");
+        let tx = signed_transaction();
+
+        node.handle_new_transaction(tx.clone(), Some("peer-a")).await.unwrap();
+        // Echoed back by another peer
+        node.handle_new_transaction(tx, Some("peer-b")).await.unwrap();
+
+        // Relayed once, and not back to the peer it came from
+        assert_eq!(drain_outgoing(&mut node), vec!["peer-b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_copy_does_not_shadow_genuine_transaction() {
+        let mut node = manager(&GenesisConfig::default());
+        connected_peer(&mut node, "peer-a");
+        connected_peer(&mut node, "peer-b");
+        let tx = signed_transaction();
+        let mut forged = tx.clone();
+        forged.signature = crate::QoraSignature::from_bytes(&[0u8; 64]).unwrap();
+
+        assert!(node.handle_new_transaction(forged, Some("peer-a")).await.is_err());
+        assert!(drain_outgoing(&mut node).is_empty());
+
+        // The genuine transaction still gets through and is relayed
+        node.handle_new_transaction(tx, Some("peer-a")).await.unwrap();
+        assert_eq!(drain_outgoing(&mut node), vec!["peer-b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_excludes_origin_peer() {
+        let mut node = manager(&GenesisConfig::default());
+        for peer_id in ["peer-a", "peer-b", "peer-c"] {
+            connected_peer(&mut node, peer_id);
+        }
+
+        node.broadcast_message(NetworkMessage::TransactionRequest(Hash::zero()), Some("peer-b")).await.unwrap();
+        assert_eq!(drain_outgoing(&mut node), vec!["peer-a".to_string(), "peer-c".to_string()]);
+
+        // Locally originated items go to everyone
+        node.handle_new_transaction(signed_transaction(), None).await.unwrap();
+        assert_eq!(drain_outgoing(&mut node).len(), 3);
+    }
+
+    fn impatient_manager() -> NetworkManager {
+        let config = NetworkConfig {
+            request_timeout: Duration::from_millis(50),
+            ..NetworkConfig::default()
+        };
+        NetworkManager::new(Address([1u8; 32]), config)
+    }
+
+    #[tokio::test]
+    async fn test_silent_peer_times_out_and_request_retries_elsewhere() {
+        let mut node = impatient_manager();
+        // "a-silent" sorts first, so it is asked first and never answers
+        connected_peer(&mut node, "a-silent");
+        connected_peer(&mut node, "b-responsive");
+
+        let pending = node.pending_responses();
+        let hash = Hash::new(b"tx");
+        let requested = hash.clone();
+        let responder = tokio::spawn(async move {
+            while !pending.is_pending("b-responsive", &requested) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            pending.deliver("b-responsive", NetworkMessage::TransactionResponse(requested, None));
+        });
+
+        let answer = node.request_transaction(hash).await.unwrap();
+        responder.await.unwrap();
+        assert!(answer.is_none());
+        assert_eq!(node.peers["a-silent"].failed_requests, 1);
+        assert_eq!(node.peers["b-responsive"].failed_requests, 0);
+
+        // The penalized peer is now asked last
+        assert_eq!(node.request_candidates(), vec!["b-responsive".to_string(), "a-silent".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_incoming_response_reaches_waiting_request() {
+        let mut node = impatient_manager();
+        connected_peer(&mut node, "peer-a");
+        let hash = Hash::new(b"tx");
+        let response = node.pending_responses().register("peer-a", &hash);
+
+        let frame = encode_message(&NetworkMessage::TransactionResponse(hash.clone(), None)).unwrap();
+        node.handle_incoming_bytes("peer-a", &frame).unwrap();
+        assert!(matches!(response.await, Ok(NetworkMessage::TransactionResponse(_, None))));
+        assert!(!node.pending_responses().is_pending("peer-a", &hash));
+    }
+
+    #[tokio::test]
+    async fn test_request_fails_cleanly_when_no_peer_answers() {
+        let mut node = impatient_manager();
+        connected_peer(&mut node, "a-silent");
+        connected_peer(&mut node, "b-silent");
+
+        let result = node.request_block(Hash::new(b"block")).await;
+        assert!(matches!(result, Err(QoraNetError::NetworkError(_))));
+        assert_eq!(node.peers["a-silent"].failed_requests, 1);
+        assert_eq!(node.peers["b-silent"].failed_requests, 1);
+        assert!(!node.pending_responses().is_pending("a-silent", &Hash::new(b"block")));
+
+        // Repeat offenders are dropped
+        for _ in 1..request::MAX_PEER_REQUEST_FAILURES {
+            let _ = node.request_block(Hash::new(b"block")).await;
+        }
+        assert!(matches!(node.peers["a-silent"].connection_status, ConnectionStatus::Failed(_)));
+        assert!(node.request_candidates().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_listener_binds_configured_address() {
+        let config = NetworkConfig {
+            listen_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            listen_port: 0,
+            ..NetworkConfig::default()
+        };
+        let node = NetworkManager::new(Address([1u8; 32]), config);
+
+        let listener = node.bind_listener().await.unwrap();
+        assert_eq!(listener.local_addr().unwrap().ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(node.advertised_socket_addr(), node.listen_socket_addr());
+    }
+
+    #[test]
+    fn test_discovery_advertises_external_address() {
+        let external: SocketAddr = "203.0.113.7:9100".parse().unwrap();
+        let config = NetworkConfig {
+            listen_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 8)),
+            listen_port: 8080,
+            advertised_addr: Some(external),
+            ..NetworkConfig::default()
+        };
+        let node = NetworkManager::new(Address([1u8; 32]), config);
+
+        match node.discovery_message() {
+            NetworkMessage::PeerDiscovery { address, port, .. } => {
+                assert_eq!(address, "203.0.113.7");
+                assert_eq!(port, 9100);
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+}
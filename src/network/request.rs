@@ -0,0 +1,133 @@
+//! Request/response bookkeeping
+//!
+//! `BlockRequest` and `TransactionRequest` are answered asynchronously by
+//! whichever peer they were sent to. Each outstanding request registers a
+//! one-shot slot keyed by peer id and requested hash; answers echo the hash,
+//! the connection handler delivers each into the matching slot, and the
+//! requester gives up once its timeout runs out. A late answer to an earlier
+//! request therefore can't be taken for the answer to a later one, and an
+//! answer whose content doesn't match the hash it claims is dropped.
+
+use super::{BlockResponse, NetworkMessage};
+use crate::Hash;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Default time to wait for a peer to answer a request
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of peers a request is sent to before giving up
+pub const DEFAULT_MAX_REQUEST_ATTEMPTS: usize = 3;
+
+/// Requests a peer may leave unanswered before it is dropped
+pub const MAX_PEER_REQUEST_FAILURES: u32 = 3;
+
+type Slots = HashMap<(String, Hash), oneshot::Sender<NetworkMessage>>;
+
+/// Outstanding requests awaiting a peer's answer.
+///
+/// Clones share state: the requester keeps one while the connection handler
+/// delivers responses through another.
+#[derive(Debug, Clone, Default)]
+pub struct PendingResponses {
+    slots: Arc<Mutex<Slots>>,
+}
+
+impl PendingResponses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Await `peer_id`'s answer to a request for `hash`, replacing any earlier identical request
+    pub fn register(&self, peer_id: &str, hash: &Hash) -> oneshot::Receiver<NetworkMessage> {
+        let (tx, rx) = oneshot::channel();
+        self.lock().insert((peer_id.to_string(), hash.clone()), tx);
+        rx
+    }
+
+    /// Hand a response to whoever is waiting on it; false if nobody is or it doesn't check out
+    pub fn deliver(&self, peer_id: &str, response: NetworkMessage) -> bool {
+        let hash = match answered_hash(&response) {
+            Some(hash) => hash,
+            None => return false,
+        };
+        match self.lock().remove(&(peer_id.to_string(), hash)) {
+            Some(slot) => slot.send(response).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Stop waiting on `peer_id`'s answer for `hash`, e.g. after a timeout, so a late answer is dropped
+    pub fn cancel(&self, peer_id: &str, hash: &Hash) {
+        self.lock().remove(&(peer_id.to_string(), hash.clone()));
+    }
+
+    /// Whether a request for `hash` to `peer_id` is awaiting its answer
+    pub fn is_pending(&self, peer_id: &str, hash: &Hash) -> bool {
+        self.lock().contains_key(&(peer_id.to_string(), hash.clone()))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Slots> {
+        self.slots.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Hash a response answers, if it is a response and what it carries has that hash
+fn answered_hash(response: &NetworkMessage) -> Option<Hash> {
+    match response {
+        NetworkMessage::BlockResponse(hash, BlockResponse::Found(block)) => {
+            (block.hash() == *hash).then(|| hash.clone())
+        }
+        NetworkMessage::BlockResponse(hash, _) => Some(hash.clone()),
+        NetworkMessage::TransactionResponse(hash, Some(tx)) => {
+            (tx.hash() == *hash).then(|| hash.clone())
+        }
+        NetworkMessage::TransactionResponse(hash, None) => Some(hash.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Address;
+    use crate::consensus::Block;
+
+    #[tokio::test]
+    async fn test_late_response_after_cancel_is_dropped() {
+        let pending = PendingResponses::new();
+        let hash = Hash::new(b"tx");
+        let rx = pending.register("peer-a", &hash);
+        assert!(pending.is_pending("peer-a", &hash));
+
+        pending.cancel("peer-a", &hash);
+        assert!(!pending.deliver("peer-a", NetworkMessage::TransactionResponse(hash.clone(), None)));
+        assert!(rx.await.is_err());
+
+        let rx = pending.register("peer-a", &hash);
+        assert!(pending.deliver("peer-a", NetworkMessage::TransactionResponse(hash.clone(), None)));
+        assert!(matches!(rx.await, Ok(NetworkMessage::TransactionResponse(_, None))));
+    }
+
+    #[tokio::test]
+    async fn test_answer_to_earlier_request_not_taken_for_later_one() {
+        let pending = PendingResponses::new();
+        let (earlier, later) = (Hash::new(b"earlier"), Hash::new(b"later"));
+        pending.register("peer-a", &earlier);
+        pending.cancel("peer-a", &earlier);
+        let rx = pending.register("peer-a", &later);
+
+        assert!(!pending.deliver("peer-a", NetworkMessage::BlockResponse(earlier, BlockResponse::NotFound)));
+        assert!(pending.is_pending("peer-a", &later));
+
+        // A block claimed to be the requested one must actually hash to it
+        let block = Block::genesis(Address([1u8; 32]));
+        assert!(!pending.deliver("peer-a", NetworkMessage::BlockResponse(later.clone(), BlockResponse::Found(block))));
+        assert!(pending.is_pending("peer-a", &later));
+
+        assert!(pending.deliver("peer-a", NetworkMessage::BlockResponse(later, BlockResponse::NotFound)));
+        assert!(matches!(rx.await, Ok(NetworkMessage::BlockResponse(_, BlockResponse::NotFound))));
+    }
+}
@@ -0,0 +1,151 @@
+//! Off-loop block validation
+//!
+//! Block validation verifies every transaction signature, which is too much
+//! CPU work to do on the async networking task. Blocks are validated on the
+//! blocking thread pool instead, with a bounded number in flight: once the
+//! bound is reached, callers wait for a slot, which pushes back on whoever is
+//! feeding blocks in.
+
+use crate::consensus::Block;
+use crate::{BlockHeight, Hash, QoraNetError, Result};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Default number of blocks validated concurrently
+pub const DEFAULT_MAX_PENDING_VALIDATIONS: usize = 8;
+
+/// Validates blocks on the blocking thread pool with bounded concurrency
+#[derive(Debug, Clone)]
+pub struct BlockValidator {
+    permits: Arc<Semaphore>,
+    max_pending: usize,
+    /// First height whose transactions root uses the prefixed merkle scheme
+    domain_separated_root_height: BlockHeight,
+}
+
+impl BlockValidator {
+    pub fn new(max_pending: usize) -> Self {
+        let max_pending = max_pending.max(1);
+        Self {
+            permits: Arc::new(Semaphore::new(max_pending)),
+            max_pending,
+            domain_separated_root_height: 0,
+        }
+    }
+
+    /// Accept the legacy transactions root below `height`
+    pub fn with_domain_separated_root_height(mut self, height: BlockHeight) -> Self {
+        self.domain_separated_root_height = height;
+        self
+    }
+
+    /// Number of validations currently running
+    pub fn pending(&self) -> usize {
+        self.max_pending - self.permits.available_permits()
+    }
+
+    /// Whether new validations will have to wait for a slot
+    pub fn is_saturated(&self) -> bool {
+        self.permits.available_permits() == 0
+    }
+
+    /// Validate a block off the async runtime; returns the block once it is valid
+    pub async fn validate(
+        &self,
+        block: Block,
+        expected_height: BlockHeight,
+        expected_previous: Hash,
+    ) -> Result<Block> {
+        let permit = self.permits.clone().acquire_owned().await
+            .map_err(|_| QoraNetError::NetworkError("Block validator shut down".to_string()))?;
+
+        let root_height = self.domain_separated_root_height;
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            block.validate_with_root_height(expected_height, &expected_previous, root_height)?;
+            Ok(block)
+        })
+        .await
+        .map_err(|e| QoraNetError::NetworkError(format!("Block validation task failed: {}", e)))?
+    }
+}
+
+impl Default for BlockValidator {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PENDING_VALIDATIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{Transaction, TransactionData};
+    use crate::{Address, FeePriority, QoraSignature};
+    use ed25519_dalek::{Keypair, Signer};
+    use rand::rngs::OsRng;
+    use std::time::{Duration, Instant};
+
+    fn signed_block(tx_count: usize) -> Block {
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+
+        let transactions = (0..tx_count as u64).map(|nonce| {
+            let mut tx = Transaction {
+                data: TransactionData::Transfer {
+                    from: signer.clone(),
+                    to: Address([7u8; 32]),
+                    amount: 1,
+                    memo: None,
+                },
+                nonce,
+                fee_qor: 1_000,
+                fee_usd: 0.0001,
+                priority: FeePriority::Low,
+                signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+                signer: signer.clone(),
+                valid_until_height: None,
+            };
+            tx.signature = keypair.sign(&tx.signing_message());
+            tx
+        }).collect();
+
+        Block::new(Hash::zero(), 0, signer, transactions, 0, 0)
+    }
+
+    #[tokio::test]
+    async fn test_ping_stays_responsive_while_validating() {
+        let validator = BlockValidator::new(4);
+        let blocks: Vec<Block> = (0..40).map(|_| signed_block(50)).collect();
+
+        // Flood: all blocks submitted at once
+        let flood: Vec<_> = blocks.into_iter().map(|block| {
+            let validator = validator.clone();
+            tokio::spawn(async move { validator.validate(block, 0, Hash::zero()).await })
+        }).collect();
+
+        // Meanwhile the (single-threaded) runtime keeps answering pings
+        let mut worst = Duration::ZERO;
+        for _ in 0..20 {
+            let sent = Instant::now();
+            let pong = tokio::spawn(async move { sent.elapsed() }).await.unwrap();
+            worst = worst.max(pong);
+            tokio::time::sleep(Duration::from_millis(2)).await;
+            assert!(validator.pending() <= 4);
+        }
+        assert!(worst < Duration::from_millis(50), "ping latency {:?}", worst);
+
+        for handle in flood {
+            assert!(handle.await.unwrap().is_ok());
+        }
+        assert_eq!(validator.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_block_rejected() {
+        let validator = BlockValidator::default();
+        let mut block = signed_block(3);
+        block.transactions[1].nonce += 1; // Breaks the signature and the root
+
+        assert!(validator.validate(block, 0, Hash::zero()).await.is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! Wire framing for network messages
+//!
+//! Every message is sent as `MAGIC ‖ version (u16 BE) ‖ bincode(NetworkMessage)`.
+//! The header is checked before the payload reaches the deserializer, so a
+//! peer speaking another protocol version is detected instead of misparsed.
+
+use crate::{QoraNetError, Result};
+use super::NetworkMessage;
+
+/// Magic prefix identifying QoraNet wire messages
+pub const WIRE_MAGIC: [u8; 4] = *b"QORA";
+
+/// Current wire protocol version; bump whenever `NetworkMessage` changes layout
+pub const PROTOCOL_VERSION: u16 = 9;
+
+/// Magic plus version
+const HEADER_LEN: usize = WIRE_MAGIC.len() + 2;
+
+/// Whether a peer's protocol version can talk to ours
+pub fn is_compatible_version(version: u16) -> bool {
+    version == PROTOCOL_VERSION
+}
+
+/// Serialize a message with the magic and protocol version header
+pub fn encode_message(message: &NetworkMessage) -> Result<Vec<u8>> {
+    let payload = bincode::serialize(message)
+        .map_err(|e| QoraNetError::NetworkError(format!("Failed to encode message: {}", e)))?;
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&WIRE_MAGIC);
+    bytes.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// Read the protocol version from a frame header without decoding the payload
+pub fn peek_version(bytes: &[u8]) -> Result<u16> {
+    if bytes.len() < HEADER_LEN {
+        return Err(QoraNetError::NetworkError("Message too short for wire header".to_string()));
+    }
+    if bytes[..WIRE_MAGIC.len()] != WIRE_MAGIC {
+        return Err(QoraNetError::NetworkError("Bad magic: not a QoraNet message".to_string()));
+    }
+    Ok(u16::from_be_bytes([bytes[4], bytes[5]]))
+}
+
+/// Check the header and deserialize a message
+pub fn decode_message(bytes: &[u8]) -> Result<NetworkMessage> {
+    let version = peek_version(bytes)?;
+    if !is_compatible_version(version) {
+        return Err(QoraNetError::NetworkError(format!(
+            "Incompatible protocol version: peer {}, local {}",
+            version, PROTOCOL_VERSION
+        )));
+    }
+
+    bincode::deserialize(&bytes[HEADER_LEN..])
+        .map_err(|e| QoraNetError::NetworkError(format!("Failed to decode message: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ping() -> NetworkMessage {
+        NetworkMessage::Ping {
+            timestamp: 1_700_000_000,
+            peer_id: "qora-test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_versioned_message_round_trip() {
+        let bytes = encode_message(&ping()).unwrap();
+        assert_eq!(&bytes[..4], &WIRE_MAGIC);
+        assert_eq!(peek_version(&bytes).unwrap(), PROTOCOL_VERSION);
+
+        match decode_message(&bytes).unwrap() {
+            NetworkMessage::Ping { timestamp, peer_id } => {
+                assert_eq!(timestamp, 1_700_000_000);
+                assert_eq!(peer_id, "qora-test");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mismatched_version_rejected() {
+        let mut bytes = encode_message(&ping()).unwrap();
+        bytes[5] = bytes[5].wrapping_add(1);
+
+        let err = decode_message(&bytes).unwrap_err();
+        assert!(err.to_string().contains("Incompatible protocol version"));
+    }
+
+    #[test]
+    fn test_bad_magic_rejected() {
+        let mut bytes = encode_message(&ping()).unwrap();
+        bytes[0] = b'X';
+        assert!(decode_message(&bytes).is_err());
+        assert!(decode_message(&bytes[..3]).is_err());
+    }
+}
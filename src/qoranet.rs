@@ -291,6 +291,40 @@ fn generate_erc20_bytecode(
 pub mod cli {
     use super::*;
     use clap::{App, Arg, SubCommand};
+    use qoranet::QRC20Registry;
+
+    /// Table of a token's metadata, or a message if the contract is unknown
+    pub fn token_info_table(registry: &QRC20Registry, contract: H160) -> Result<String, String> {
+        let info = registry.get_token(contract)
+            .map(|token| token.get_info())
+            .ok_or_else(|| format!("No QRC-20 token deployed at 0x{:x}", contract))?;
+
+        let max_supply = if info.max_supply.is_zero() {
+            "unlimited".to_string()
+        } else {
+            format!("{} {}", wallet::format_token_balance(info.max_supply, info.decimals), info.symbol)
+        };
+        let flag = |enabled: bool| if enabled { "yes" } else { "no" };
+
+        let rows = [
+            ("Contract", format!("0x{:x}", info.contract_address)),
+            ("Name", info.name.clone()),
+            ("Symbol", info.symbol.clone()),
+            ("Decimals", info.decimals.to_string()),
+            ("Total supply", format!("{} {}", wallet::format_token_balance(info.total_supply, info.decimals), info.symbol)),
+            ("Max supply", max_supply),
+            ("Owner", format!("0x{:x}", info.owner)),
+            ("Pending owner", info.pending_owner.map_or("-".to_string(), |owner| format!("0x{:x}", owner))),
+            ("Mintable", flag(info.mintable).to_string()),
+            ("Burnable", flag(info.burnable).to_string()),
+            ("Paused", flag(info.paused).to_string()),
+        ];
+
+        Ok(rows.iter()
+            .map(|(label, value)| format!("{:<14} {}", format!("{}:", label), value))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
 
     pub fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
         let matches = App::new("qoranet-tokens")
@@ -317,6 +351,11 @@ pub mod cli {
                     .arg(Arg::with_name("account").required(true))
                     .arg(Arg::with_name("token"))
             )
+            .subcommand(
+                SubCommand::with_name("info")
+                    .about("Show a token's metadata")
+                    .arg(Arg::with_name("contract").required(true))
+            )
             .get_matches();
 
         let mut qoranet = QoraNet::new();
@@ -357,6 +396,16 @@ pub mod cli {
                 
                 println!("Balance: {}", wallet::format_token_balance(balance, decimals));
             }
+            ("info", Some(info_matches)) => {
+                let contract_str = info_matches.value_of("contract").unwrap();
+                let bytes = hex::decode(contract_str.trim_start_matches("0x"))?;
+                if bytes.len() != 20 {
+                    return Err(format!("Invalid contract address: {}", contract_str).into());
+                }
+
+                let table = token_info_table(&qoranet.qrc20_registry, H160::from_slice(&bytes))?;
+                println!("{}", table);
+            }
             _ => {
                 println!("Use --help for available commands");
             }
@@ -364,4 +413,42 @@ pub mod cli {
 
         Ok(())
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_token_info_table_matches_token_state() {
+            let mut registry = QRC20Registry::new();
+            let deployer = H160::from_low_u64_be(1);
+            let contract = registry.deploy_token_advanced(
+                deployer,
+                "Qora USD".to_string(),
+                "QUSD".to_string(),
+                6,
+                U256::from(1_500_000u64),
+                Some(U256::from(10_000_000u64)),
+                Some(true),
+                Some(false),
+            ).unwrap();
+
+            let table = token_info_table(&registry, contract).unwrap();
+            assert!(table.contains(&format!("Contract:      0x{:x}", contract)));
+            assert!(table.contains("Symbol:        QUSD"));
+            assert!(table.contains("Total supply:  1.5 QUSD"));
+            assert!(table.contains("Max supply:    10 QUSD"));
+            assert!(table.contains(&format!("Owner:         0x{:x}", deployer)));
+            assert!(table.contains("Mintable:      yes"));
+            assert!(table.contains("Burnable:      no"));
+            assert!(table.contains("Paused:        no"));
+        }
+
+        #[test]
+        fn test_token_info_unknown_contract() {
+            let registry = QRC20Registry::new();
+            let err = token_info_table(&registry, H160::from_low_u64_be(42)).unwrap_err();
+            assert!(err.contains("No QRC-20 token deployed"));
+        }
+    }
 }
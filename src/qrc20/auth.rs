@@ -0,0 +1,244 @@
+//! Request authentication for privileged QRC-20 RPC methods
+//!
+//! Privileged calls (mint, burn, pause, unpause, ownership transfer) must carry
+//! an ed25519 signature over the method name and parameters, plus a nonce and
+//! timestamp so a captured request can't be replayed.
+
+use serde_json::Value;
+use primitive_types::H160;
+use ed25519_dalek::{PublicKey, Signature};
+use std::collections::HashMap;
+use super::{QRC20Error, QRC20Result};
+
+/// Default allowed clock skew between client and node (seconds)
+pub const DEFAULT_MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// Verifies signed privileged requests and tracks per-caller nonces
+#[derive(Debug, Clone)]
+pub struct RequestAuthenticator {
+    last_nonce: HashMap<H160, u64>,
+    max_clock_skew_secs: u64,
+}
+
+impl RequestAuthenticator {
+    pub fn new() -> Self {
+        Self {
+            last_nonce: HashMap::new(),
+            max_clock_skew_secs: DEFAULT_MAX_CLOCK_SKEW_SECS,
+        }
+    }
+
+    /// Set the allowed clock skew for request timestamps
+    pub fn set_max_clock_skew(&mut self, secs: u64) {
+        self.max_clock_skew_secs = secs;
+    }
+
+    /// Verify a signed request and return the authenticated caller.
+    ///
+    /// The nonce must be strictly greater than the last accepted nonce for
+    /// the caller; it is only consumed once every check has passed.
+    pub fn authenticate(&mut self, method: &str, params: &Value, now: u64) -> QRC20Result<H160> {
+        let caller = parse_h160(params.get("from"), "from")?;
+        let public_key_bytes = parse_hex_field(params.get("publicKey"), "publicKey", 32)?;
+        let signature_bytes = parse_hex_field(params.get("signature"), "signature", 64)?;
+        let nonce = params.get("nonce").and_then(|v| v.as_u64())
+            .ok_or_else(|| unauthorized("Missing 'nonce' field"))?;
+        let timestamp = params.get("timestamp").and_then(|v| v.as_u64())
+            .ok_or_else(|| unauthorized("Missing 'timestamp' field"))?;
+
+        // Claimed caller must own the signing key
+        let public_key = PublicKey::from_bytes(&public_key_bytes)
+            .map_err(|e| unauthorized(&format!("Invalid public key: {}", e)))?;
+        if address_from_public_key(&public_key) != caller {
+            return Err(unauthorized("Public key does not match 'from' address"));
+        }
+
+        if timestamp.abs_diff(now) > self.max_clock_skew_secs {
+            return Err(unauthorized("Request timestamp outside allowed window"));
+        }
+
+        if let Some(last) = self.last_nonce.get(&caller) {
+            if nonce <= *last {
+                return Err(unauthorized("Nonce already used"));
+            }
+        }
+
+        let signature = Signature::from_bytes(&signature_bytes)
+            .map_err(|e| unauthorized(&format!("Invalid signature: {}", e)))?;
+        public_key.verify_strict(&signing_payload(method, params), &signature)
+            .map_err(|_| unauthorized("Signature verification failed"))?;
+
+        self.last_nonce.insert(caller, nonce);
+        Ok(caller)
+    }
+}
+
+impl Default for RequestAuthenticator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bytes a client signs for a privileged request: method name followed by the
+/// params (including nonce and timestamp, excluding the signature) as JSON with
+/// sorted keys
+pub fn signing_payload(method: &str, params: &Value) -> Vec<u8> {
+    let mut unsigned = params.clone();
+    if let Some(object) = unsigned.as_object_mut() {
+        object.remove("signature");
+    }
+
+    let mut payload = Vec::new();
+    payload.extend_from_slice(method.as_bytes());
+    payload.push(b'\n');
+    payload.extend_from_slice(canonical_json(&unsigned).as_bytes());
+    payload
+}
+
+/// EVM-style address for an ed25519 key: last 20 bytes of keccak256(pubkey)
+pub fn address_from_public_key(public_key: &PublicKey) -> H160 {
+    use sha3::{Digest, Keccak256};
+    let hash = Keccak256::digest(public_key.as_bytes());
+    H160::from_slice(&hash[12..])
+}
+
+/// JSON with object keys sorted, independent of serde_json feature flags
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys.into_iter()
+                .map(|key| format!("{}:{}", Value::String(key.clone()), canonical_json(&map[key])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn unauthorized(reason: &str) -> QRC20Error {
+    QRC20Error::Unauthorized { reason: reason.to_string() }
+}
+
+fn parse_hex_field(value: Option<&Value>, field: &str, len: usize) -> QRC20Result<Vec<u8>> {
+    let hex_str = value.and_then(|v| v.as_str())
+        .ok_or_else(|| unauthorized(&format!("Missing '{}' field", field)))?;
+    let bytes = hex::decode(hex_str.strip_prefix("0x").unwrap_or(hex_str))
+        .map_err(|_| unauthorized(&format!("Invalid hex in '{}'", field)))?;
+    if bytes.len() != len {
+        return Err(unauthorized(&format!("'{}' must be {} bytes", field, len)));
+    }
+    Ok(bytes)
+}
+
+fn parse_h160(value: Option<&Value>, field: &str) -> QRC20Result<H160> {
+    let bytes = parse_hex_field(value, field, 20)?;
+    Ok(H160::from_slice(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{QRC20Registry, QRC20Transaction};
+    use ed25519_dalek::{Keypair, Signer};
+    use primitive_types::U256;
+    use rand::rngs::OsRng;
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn signed_mint(keypair: &Keypair, contract: H160, nonce: u64) -> Value {
+        let from = address_from_public_key(&keypair.public);
+        let mut params = json!({
+            "from": format!("0x{:x}", from),
+            "contract": format!("0x{:x}", contract),
+            "to": format!("0x{:x}", from),
+            "amount": "500",
+            "publicKey": hex::encode(keypair.public.as_bytes()),
+            "nonce": nonce,
+            "timestamp": NOW
+        });
+        let signature = keypair.sign(&signing_payload("qrc20_mint", &params));
+        params["signature"] = json!(hex::encode(signature.to_bytes()));
+        params
+    }
+
+    fn setup() -> (Keypair, QRC20Registry, H160) {
+        let keypair = Keypair::generate(&mut OsRng);
+        let owner = address_from_public_key(&keypair.public);
+        let mut registry = QRC20Registry::new();
+        let contract = registry.deploy_token(
+            owner,
+            "Test Token".to_string(),
+            "TEST".to_string(),
+            18,
+            U256::from(1000),
+        ).unwrap();
+        (keypair, registry, contract)
+    }
+
+    #[test]
+    fn test_unsigned_mint_rejected() {
+        let (keypair, _, contract) = setup();
+        let mut auth = RequestAuthenticator::new();
+
+        let mut params = signed_mint(&keypair, contract, 1);
+        params.as_object_mut().unwrap().remove("signature");
+
+        let result = auth.authenticate("qrc20_mint", &params, NOW);
+        assert!(matches!(result, Err(QRC20Error::Unauthorized { .. })));
+    }
+
+    #[test]
+    fn test_signed_mint_succeeds() {
+        let (keypair, mut registry, contract) = setup();
+        let mut auth = RequestAuthenticator::new();
+
+        let params = signed_mint(&keypair, contract, 1);
+        let caller = auth.authenticate("qrc20_mint", &params, NOW).unwrap();
+
+        let tx = QRC20Transaction::Mint { contract, to: caller, amount: U256::from(500) };
+        assert!(registry.execute_transaction(caller, tx).is_ok());
+        assert_eq!(registry.get_token(contract).unwrap().balance_of(caller), U256::from(1500));
+    }
+
+    #[test]
+    fn test_replayed_request_rejected() {
+        let (keypair, _, contract) = setup();
+        let mut auth = RequestAuthenticator::new();
+
+        let params = signed_mint(&keypair, contract, 1);
+        assert!(auth.authenticate("qrc20_mint", &params, NOW).is_ok());
+        assert!(auth.authenticate("qrc20_mint", &params, NOW).is_err());
+
+        // Fresh nonce is accepted
+        let next = signed_mint(&keypair, contract, 2);
+        assert!(auth.authenticate("qrc20_mint", &next, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_spoofed_from_and_tampered_params_rejected() {
+        let (keypair, _, contract) = setup();
+        let mut auth = RequestAuthenticator::new();
+
+        let mut spoofed = signed_mint(&keypair, contract, 1);
+        spoofed["from"] = json!(format!("0x{:x}", H160::from_low_u64_be(42)));
+        assert!(auth.authenticate("qrc20_mint", &spoofed, NOW).is_err());
+
+        let mut tampered = signed_mint(&keypair, contract, 1);
+        tampered["amount"] = json!("1000000");
+        assert!(auth.authenticate("qrc20_mint", &tampered, NOW).is_err());
+
+        // Signature is bound to the method name
+        let params = signed_mint(&keypair, contract, 1);
+        assert!(auth.authenticate("qrc20_burn", &params, NOW).is_err());
+
+        // Stale timestamp
+        assert!(auth.authenticate("qrc20_mint", &params, NOW + 3_600).is_err());
+    }
+}
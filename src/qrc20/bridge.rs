@@ -1,7 +1,31 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use primitive_types::{H160, H256, U256};
-use super::{QRC20Registry, QRC20Error, QRC20Result, QRC20Event};
+use sha3::{Digest, Keccak256};
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use super::{EVMSignature, QRC20Registry, QRC20Error, QRC20Result, QRC20Event};
+use super::evm_integration::evm_address;
+
+fn accounting_error(reason: String) -> QRC20Error {
+    tracing::error!("Bridge accounting error: {}", reason);
+    QRC20Error::BridgeAccounting { reason }
+}
+
+/// Amount left after the bridge fee
+fn net_of_fee(amount: U256, fee: U256) -> QRC20Result<U256> {
+    amount.checked_sub(fee)
+        .ok_or_else(|| accounting_error(format!("fee {} exceeds amount {}", fee, amount)))
+}
+
+/// Minted tokens may never exceed the tokens locked behind them
+fn ensure_backed(eth_token: H160, locked: U256, minted: U256) -> QRC20Result<()> {
+    if minted > locked {
+        return Err(accounting_error(format!(
+            "{:?} insolvent: {} minted against {} locked", eth_token, minted, locked
+        )));
+    }
+    Ok(())
+}
 
 /// Bridge for ERC-20 to QRC-20 conversion
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +56,26 @@ pub struct ERC20Bridge {
     
     /// Bridge treasury address
     pub bridge_treasury: H160,
+
+    /// Ethereum tokens refused by the bridge (compliance)
+    #[serde(default)]
+    pub blocked_tokens: HashSet<H160>,
+
+    /// Number of bridge records created; feeds deterministic record ids
+    #[serde(default)]
+    pub bridge_nonce: u64,
+
+    /// Latest QoraNet block height seen by the bridge; burns are recorded at this height
+    #[serde(default)]
+    pub qora_height: u64,
+
+    /// Latest finalized QoraNet height; `None` until the chain reports one
+    #[serde(default)]
+    pub finalized_height: Option<u64>,
+
+    /// Ethereum deposits already minted; each attestation is honoured once
+    #[serde(default)]
+    pub processed_deposits: HashSet<H256>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +92,113 @@ pub struct BridgeTransaction {
     pub confirmations: u64,
     pub timestamp: u64,
     pub fee_paid: U256,
+    /// QoraNet height of the burn, for transfers to Ethereum
+    #[serde(default)]
+    pub qora_block_height: Option<u64>,
+    /// Whether the QoraNet side is final; a burn can't be released on Ethereum before this
+    #[serde(default = "finalized_by_default")]
+    pub qora_finalized: bool,
+}
+
+/// Records from before burn finality was tracked were released under the old rules
+fn finalized_by_default() -> bool {
+    true
+}
+
+/// Bridge request as submitted by a user; processing it produces a `BridgeTransaction` record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BridgeRequest {
+    /// Mint the bridged QRC-20 for an operator-attested Ethereum deposit
+    FromEthereum {
+        attestation: DepositAttestation,
+        token_name: String,
+        token_symbol: String,
+        decimals: u8,
+    },
+    /// Burn `amount` of a bridged QRC-20 and release the original on Ethereum
+    ToEthereum {
+        qora_token: H160,
+        amount: U256,
+    },
+}
+
+/// Domain separator for operator deposit attestations
+const DEPOSIT_ATTESTATION_DOMAIN: &[u8] = b"QORANET_BRIDGE_DEPOSIT_V1";
+
+/// A bridge operator's signed statement that an ERC-20 deposit happened on Ethereum.
+///
+/// Minting trusts the deposit details and the confirmation count only because
+/// an operator vouches for them; nothing here is taken from the submitter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositAttestation {
+    /// Ethereum transaction that locked the tokens
+    pub eth_tx_hash: H256,
+    pub eth_token: H160,
+    /// QoraNet account credited with the bridged tokens
+    pub recipient: H160,
+    pub amount: U256,
+    /// Ethereum blocks on top of the deposit when it was attested
+    pub confirmations: u64,
+    /// Operator's secp256k1 signature over `signing_hash`; `v` is 27 or 28
+    pub signature: Option<EVMSignature>,
+}
+
+impl DepositAttestation {
+    pub fn new(eth_tx_hash: H256, eth_token: H160, recipient: H160, amount: U256, confirmations: u64) -> Self {
+        Self { eth_tx_hash, eth_token, recipient, amount, confirmations, signature: None }
+    }
+
+    /// Hash the operator signs
+    pub fn signing_hash(&self) -> H256 {
+        let mut amount_bytes = [0u8; 32];
+        self.amount.to_big_endian(&mut amount_bytes);
+
+        let mut hasher = Keccak256::new();
+        hasher.update(DEPOSIT_ATTESTATION_DOMAIN);
+        hasher.update(self.eth_tx_hash.as_bytes());
+        hasher.update(self.eth_token.as_bytes());
+        hasher.update(self.recipient.as_bytes());
+        hasher.update(amount_bytes);
+        hasher.update(self.confirmations.to_be_bytes());
+        H256::from_slice(&hasher.finalize())
+    }
+
+    /// Sign as the operator holding `key`
+    pub fn sign(mut self, key: &SigningKey) -> QRC20Result<Self> {
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(self.signing_hash().as_bytes())
+            .map_err(|e| QRC20Error::Unauthorized { reason: format!("Failed to sign deposit attestation: {}", e) })?;
+        let (r, s) = signature.split_bytes();
+        self.signature = Some(EVMSignature {
+            v: 27 + recovery_id.is_y_odd() as u64,
+            r: U256::from_big_endian(&r),
+            s: U256::from_big_endian(&s),
+        });
+        Ok(self)
+    }
+
+    /// Address of the operator that signed the attestation
+    pub fn recover_signer(&self) -> QRC20Result<H160> {
+        let invalid = |reason: &str| QRC20Error::Unauthorized { reason: reason.to_string() };
+        let signature = self.signature.as_ref().ok_or_else(|| invalid("Deposit attestation is not signed"))?;
+        let recovery_id = match signature.v {
+            27 | 28 => RecoveryId::from_byte((signature.v - 27) as u8),
+            _ => None,
+        }.ok_or_else(|| invalid("Deposit attestation has an invalid recovery id"))?;
+
+        let mut rs = [0u8; 64];
+        signature.r.to_big_endian(&mut rs[..32]);
+        signature.s.to_big_endian(&mut rs[32..]);
+        let ecdsa = Signature::from_slice(&rs).map_err(|_| invalid("Invalid deposit attestation signature"))?;
+        // Only the low-s form is accepted, so a signature can't be malleated
+        if ecdsa.normalize_s().is_some() {
+            return Err(invalid("Deposit attestation s value is not canonical"));
+        }
+
+        let key = VerifyingKey::recover_from_prehash(self.signing_hash().as_bytes(), &ecdsa, recovery_id)
+            .map_err(|_| invalid("Failed to recover deposit attestation signer"))?;
+        Ok(evm_address(&key))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +207,34 @@ pub enum BridgeDirection {
     QoraNetToEthereum,
 }
 
+/// Domain separator for bridge record ids
+const BRIDGE_TX_ID_DOMAIN: &[u8] = b"QORANET_BRIDGE_TX_V1";
+
+/// Domain separator for the mock hashes standing in for the other chain's transactions
+const BRIDGE_MOCK_HASH_DOMAIN: &[u8] = b"QORANET_BRIDGE_MOCK_V1";
+
+/// Deterministic bridge record id derived from the request contents and bridge nonce
+pub fn bridge_tx_id(direction: &BridgeDirection, user: H160, eth_token: H160, amount: U256, nonce: u64) -> H256 {
+    request_hash(BRIDGE_TX_ID_DOMAIN, direction, user, eth_token, amount, nonce)
+}
+
+fn request_hash(domain: &[u8], direction: &BridgeDirection, user: H160, eth_token: H160, amount: U256, nonce: u64) -> H256 {
+    let mut amount_bytes = [0u8; 32];
+    amount.to_big_endian(&mut amount_bytes);
+
+    let mut hasher = Keccak256::new();
+    hasher.update(domain);
+    hasher.update([match direction {
+        BridgeDirection::EthereumToQoraNet => 0u8,
+        BridgeDirection::QoraNetToEthereum => 1u8,
+    }]);
+    hasher.update(user.as_bytes());
+    hasher.update(eth_token.as_bytes());
+    hasher.update(amount_bytes);
+    hasher.update(nonce.to_be_bytes());
+    H256::from_slice(&hasher.finalize())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BridgeStatus {
     Pending,
@@ -77,6 +256,11 @@ impl ERC20Bridge {
             min_confirmations: 12, // Ethereum blocks
             bridge_fee_bp: 50, // 0.5% bridge fee
             bridge_treasury: H160::zero(),
+            blocked_tokens: HashSet::new(),
+            bridge_nonce: 0,
+            qora_height: 0,
+            finalized_height: None,
+            processed_deposits: HashSet::new(),
         }
     }
 
@@ -96,25 +280,76 @@ impl ERC20Bridge {
             min_confirmations,
             bridge_fee_bp,
             bridge_treasury: treasury,
+            blocked_tokens: HashSet::new(),
+            bridge_nonce: 0,
+            qora_height: 0,
+            finalized_height: None,
+            processed_deposits: HashSet::new(),
         }
     }
 
-    /// Bridge ERC-20 token from Ethereum to QoraNet
+    /// Process a user bridge request and return the resulting bridge record
+    pub fn process_request(
+        &mut self,
+        registry: &mut QRC20Registry,
+        user: H160,
+        request: BridgeRequest,
+    ) -> QRC20Result<BridgeTransaction> {
+        let tx_id = match request {
+            // Anyone may relay an attested deposit; the tokens always go to its recipient
+            BridgeRequest::FromEthereum { attestation, token_name, token_symbol, decimals } => {
+                self.lock_and_mint(registry, &attestation, token_name, token_symbol, decimals)?
+            }
+            BridgeRequest::ToEthereum { qora_token, amount } => {
+                self.burn_and_release(registry, qora_token, user, amount)?
+            }
+        };
+
+        Ok(self.bridge_transactions[&tx_id].clone())
+    }
+
+    /// Bridge an operator-attested ERC-20 deposit from Ethereum to QoraNet
     pub fn bridge_from_ethereum(
         &mut self,
         registry: &mut QRC20Registry,
-        eth_token: H160,
+        attestation: &DepositAttestation,
+        token_name: String,
+        token_symbol: String,
+        decimals: u8,
+    ) -> QRC20Result<H160> {
+        let tx_id = self.lock_and_mint(registry, attestation, token_name, token_symbol, decimals)?;
+        Ok(self.bridge_transactions[&tx_id].qora_token)
+    }
+
+    /// Bridge QRC-20 token back to Ethereum
+    pub fn bridge_to_ethereum(
+        &mut self,
+        registry: &mut QRC20Registry,
+        qora_token: H160,
         user: H160,
         amount: U256,
+    ) -> QRC20Result<H160> {
+        let tx_id = self.burn_and_release(registry, qora_token, user, amount)?;
+        Ok(self.bridge_transactions[&tx_id].eth_token)
+    }
+
+    /// Mint bridged tokens for an attested Ethereum deposit; returns the bridge record id
+    fn lock_and_mint(
+        &mut self,
+        registry: &mut QRC20Registry,
+        attestation: &DepositAttestation,
         token_name: String,
         token_symbol: String,
         decimals: u8,
-        eth_tx_hash: H256,
-        confirmations: u64,
-    ) -> QRC20Result<H160> {
+    ) -> QRC20Result<H256> {
+        self.verify_deposit(attestation)?;
+        let DepositAttestation { eth_tx_hash, eth_token, recipient: user, amount, confirmations, .. } = *attestation;
+        self.ensure_not_blocked(eth_token)?;
+        let tx_id = self.next_tx_id(&BridgeDirection::EthereumToQoraNet, user, eth_token, amount)?;
+
         // Calculate bridge fee
         let fee = self.calculate_bridge_fee(amount);
-        let net_amount = amount.saturating_sub(fee);
+        let net_amount = net_of_fee(amount, fee)?;
 
         if net_amount.is_zero() {
             return Err(QRC20Error::EVMExecutionFailed { 
@@ -122,6 +357,15 @@ impl ERC20Bridge {
             });
         }
 
+        // Settle the books before minting so a failure leaves nothing half-applied
+        let new_locked = self.locked_amount(eth_token).checked_add(amount)
+            .ok_or_else(|| accounting_error(format!("locked {:?} overflows", eth_token)))?;
+        let minted = self.eth_to_qora_mapping.get(&eth_token)
+            .map_or(U256::zero(), |qora_token| self.minted_amount(*qora_token));
+        let new_minted = minted.checked_add(net_amount)
+            .ok_or_else(|| accounting_error(format!("minted for {:?} overflows", eth_token)))?;
+        ensure_backed(eth_token, new_locked, new_minted)?;
+
         let qora_token = if let Some(existing_token) = self.eth_to_qora_mapping.get(&eth_token) {
             // Token already bridged, mint tokens to user
             let token = registry.get_token_mut(*existing_token)
@@ -157,17 +401,13 @@ impl ERC20Bridge {
             qora_token
         };
 
-        // Update locked amounts
-        let locked = self.locked_eth_tokens.get(&eth_token).unwrap_or(&U256::zero());
-        self.locked_eth_tokens.insert(eth_token, locked + amount);
-
-        // Update minted amounts
-        let minted = self.minted_qora_tokens.get(&qora_token).unwrap_or(&U256::zero());
-        self.minted_qora_tokens.insert(qora_token, minted + net_amount);
+        // Update locked and minted amounts
+        self.locked_eth_tokens.insert(eth_token, new_locked);
+        self.minted_qora_tokens.insert(qora_token, new_minted);
 
         // Create bridge transaction record
         let bridge_tx = BridgeTransaction {
-            id: H256::random(),
+            id: tx_id,
             eth_tx_hash: Some(eth_tx_hash),
             qora_tx_hash: None,
             user,
@@ -175,46 +415,48 @@ impl ERC20Bridge {
             qora_token,
             amount,
             direction: BridgeDirection::EthereumToQoraNet,
-            status: if confirmations >= self.min_confirmations {
-                BridgeStatus::Completed
-            } else {
-                BridgeStatus::Confirmed
-            },
+            status: BridgeStatus::Completed,
             confirmations,
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
             fee_paid: fee,
+            qora_block_height: None,
+            qora_finalized: true, // Nothing is burned on QoraNet
         };
 
-        self.bridge_transactions.insert(bridge_tx.id, bridge_tx);
+        self.bridge_transactions.insert(tx_id, bridge_tx);
+        self.processed_deposits.insert(eth_tx_hash);
+        self.bridge_nonce += 1;
 
         tracing::info!(
             "Bridged {} {} from Ethereum to QoraNet (net: {} after fee: {})",
             amount, token_symbol, net_amount, fee
         );
 
-        Ok(qora_token)
+        Ok(tx_id)
     }
 
-    /// Bridge QRC-20 token back to Ethereum
-    pub fn bridge_to_ethereum(
+    /// Burn bridged tokens for release on Ethereum; returns the bridge record id
+    fn burn_and_release(
         &mut self,
         registry: &mut QRC20Registry,
         qora_token: H160,
         user: H160,
         amount: U256,
-    ) -> QRC20Result<H160> {
+    ) -> QRC20Result<H256> {
         // Check if this is a bridged token
         let eth_token = *self.qora_to_eth_mapping.get(&qora_token)
             .ok_or(QRC20Error::EVMExecutionFailed { 
                 reason: "Token is not bridged from Ethereum".to_string() 
             })?;
+        self.ensure_not_blocked(eth_token)?;
+        let tx_id = self.next_tx_id(&BridgeDirection::QoraNetToEthereum, user, eth_token, amount)?;
 
         // Calculate bridge fee
         let fee = self.calculate_bridge_fee(amount);
-        let net_amount = amount.saturating_sub(fee);
+        let net_amount = net_of_fee(amount, fee)?;
 
         if net_amount.is_zero() {
             return Err(QRC20Error::EVMExecutionFailed { 
@@ -233,28 +475,32 @@ impl ERC20Bridge {
             });
         }
 
+        // Locked decreases as tokens are released on Ethereum, minted as they are burned.
+        // Settle the books before burning so a mismatch never destroys user funds.
+        let locked = self.locked_amount(eth_token);
+        let new_locked = locked.checked_sub(net_amount).ok_or_else(|| accounting_error(format!(
+            "releasing {} of {:?} but only {} locked", net_amount, eth_token, locked
+        )))?;
+        let minted = self.minted_amount(qora_token);
+        let new_minted = minted.checked_sub(amount).ok_or_else(|| accounting_error(format!(
+            "burning {} of {:?} but only {} minted", amount, qora_token, minted
+        )))?;
+        ensure_backed(eth_token, new_locked, new_minted)?;
+
         // Burn QRC-20 tokens from user
         let token = registry.get_token_mut(qora_token).unwrap();
         token.burn(user, amount)?;
 
-        // Update locked amounts (decrease as tokens are released on Ethereum)
-        let locked = self.locked_eth_tokens.get(&eth_token).unwrap_or(&U256::zero());
-        if *locked < net_amount {
-            return Err(QRC20Error::EVMExecutionFailed { 
-                reason: "Insufficient locked tokens".to_string() 
-            });
-        }
-        self.locked_eth_tokens.insert(eth_token, locked - net_amount);
-
-        // Update minted amounts (decrease as tokens are burned)
-        let minted = self.minted_qora_tokens.get(&qora_token).unwrap_or(&U256::zero());
-        self.minted_qora_tokens.insert(qora_token, minted.saturating_sub(amount));
+        self.locked_eth_tokens.insert(eth_token, new_locked);
+        self.minted_qora_tokens.insert(qora_token, new_minted);
 
         // Create bridge transaction record
         let bridge_tx = BridgeTransaction {
-            id: H256::random(),
+            id: tx_id,
             eth_tx_hash: None, // Will be set when processed on Ethereum
-            qora_tx_hash: Some(H256::random()), // Mock QoraNet tx hash
+            qora_tx_hash: Some(request_hash( // Mock QoraNet tx hash
+                BRIDGE_MOCK_HASH_DOMAIN, &BridgeDirection::QoraNetToEthereum, user, eth_token, amount, self.bridge_nonce,
+            )),
             user,
             eth_token,
             qora_token,
@@ -267,16 +513,70 @@ impl ERC20Bridge {
                 .unwrap()
                 .as_secs(),
             fee_paid: fee,
+            qora_block_height: Some(self.qora_height),
+            qora_finalized: self.is_qora_final(self.qora_height),
         };
 
-        self.bridge_transactions.insert(bridge_tx.id, bridge_tx);
+        self.bridge_transactions.insert(tx_id, bridge_tx);
+        self.bridge_nonce += 1;
 
         tracing::info!(
             "Initiated bridge from QoraNet to Ethereum: {} tokens (net: {} after fee: {})",
             amount, net_amount, fee
         );
 
-        Ok(eth_token)
+        Ok(tx_id)
+    }
+
+    /// Check a deposit is attested by an operator, deep enough on Ethereum and not minted yet
+    fn verify_deposit(&self, attestation: &DepositAttestation) -> QRC20Result<()> {
+        let signer = attestation.recover_signer()?;
+        if !self.is_operator(signer) {
+            return Err(QRC20Error::Unauthorized {
+                reason: format!("Deposit attested by {:?}, which is not a bridge operator", signer),
+            });
+        }
+        if attestation.confirmations < self.min_confirmations {
+            return Err(QRC20Error::InsufficientConfirmations {
+                confirmations: attestation.confirmations,
+                required: self.min_confirmations,
+            });
+        }
+        if self.processed_deposits.contains(&attestation.eth_tx_hash) {
+            return Err(QRC20Error::Unauthorized {
+                reason: format!("Deposit {:?} was already bridged", attestation.eth_tx_hash),
+            });
+        }
+        Ok(())
+    }
+
+    /// Id for the next bridge record; refuses to overwrite an existing record
+    fn next_tx_id(&self, direction: &BridgeDirection, user: H160, eth_token: H160, amount: U256) -> QRC20Result<H256> {
+        let tx_id = bridge_tx_id(direction, user, eth_token, amount, self.bridge_nonce);
+        if self.bridge_transactions.contains_key(&tx_id) {
+            return Err(QRC20Error::EVMExecutionFailed {
+                reason: format!("Bridge transaction {:?} already exists", tx_id),
+            });
+        }
+        Ok(tx_id)
+    }
+
+    /// Ethereum tokens locked for `eth_token`
+    fn locked_amount(&self, eth_token: H160) -> U256 {
+        self.locked_eth_tokens.get(&eth_token).copied().unwrap_or_default()
+    }
+
+    /// QRC-20 tokens minted for `qora_token`
+    fn minted_amount(&self, qora_token: H160) -> U256 {
+        self.minted_qora_tokens.get(&qora_token).copied().unwrap_or_default()
+    }
+
+    /// Check every bridged token's minted supply is backed by locked tokens
+    pub fn check_solvency(&self) -> QRC20Result<()> {
+        for (eth_token, qora_token) in &self.eth_to_qora_mapping {
+            ensure_backed(*eth_token, self.locked_amount(*eth_token), self.minted_amount(*qora_token))?;
+        }
+        Ok(())
     }
 
     /// Calculate bridge fee
@@ -286,7 +586,8 @@ impl ERC20Bridge {
 
     /// Add bridge operator
     pub fn add_operator(&mut self, caller: H160, operator: H160) -> QRC20Result<()> {
-        if !self.is_operator(caller) && !self.bridge_treasury.is_zero() && caller != self.bridge_treasury {
+        // Operators vouch for deposits, so an unset treasury can't appoint them
+        if !self.is_operator(caller) && (self.bridge_treasury.is_zero() || caller != self.bridge_treasury) {
             return Err(QRC20Error::OnlyOwner);
         }
 
@@ -300,7 +601,7 @@ impl ERC20Bridge {
 
     /// Remove bridge operator
     pub fn remove_operator(&mut self, caller: H160, operator: H160) -> QRC20Result<()> {
-        if !self.is_operator(caller) && !self.bridge_treasury.is_zero() && caller != self.bridge_treasury {
+        if !self.is_operator(caller) && (self.bridge_treasury.is_zero() || caller != self.bridge_treasury) {
             return Err(QRC20Error::OnlyOwner);
         }
 
@@ -310,12 +611,116 @@ impl ERC20Bridge {
         Ok(())
     }
 
+    /// Refuse to bridge an Ethereum token (only operators); existing balances are untouched
+    pub fn block_token(&mut self, caller: H160, eth_token: H160) -> QRC20Result<()> {
+        if !self.is_operator(caller) {
+            return Err(QRC20Error::OnlyOwner);
+        }
+
+        if self.blocked_tokens.insert(eth_token) {
+            tracing::warn!("Blocked bridging of Ethereum token {:?}", eth_token);
+        }
+        Ok(())
+    }
+
+    /// Allow a previously blocked Ethereum token again (only operators)
+    pub fn unblock_token(&mut self, caller: H160, eth_token: H160) -> QRC20Result<()> {
+        if !self.is_operator(caller) {
+            return Err(QRC20Error::OnlyOwner);
+        }
+
+        if self.blocked_tokens.remove(&eth_token) {
+            tracing::info!("Unblocked bridging of Ethereum token {:?}", eth_token);
+        }
+        Ok(())
+    }
+
+    /// Remove a token's bridge mapping (only operators).
+    ///
+    /// Refused while any bridged tokens are still minted or a bridge
+    /// transaction for the token is unfinished. Locked-token accounting is
+    /// kept, since bridge fees stay locked on Ethereum.
+    pub fn remove_mapping(&mut self, caller: H160, eth_token: H160) -> QRC20Result<()> {
+        if !self.is_operator(caller) {
+            return Err(QRC20Error::OnlyOwner);
+        }
+
+        let qora_token = *self.eth_to_qora_mapping.get(&eth_token).ok_or(QRC20Error::TokenNotFound)?;
+
+        let minted = self.minted_amount(qora_token);
+        if !minted.is_zero() {
+            return Err(QRC20Error::EVMExecutionFailed {
+                reason: format!("{} bridged tokens of {:?} are still outstanding", minted, qora_token),
+            });
+        }
+
+        let unfinished = self.bridge_transactions.values()
+            .filter(|tx| tx.eth_token == eth_token)
+            .filter(|tx| matches!(tx.status, BridgeStatus::Pending | BridgeStatus::Confirmed))
+            .count();
+        if unfinished > 0 {
+            return Err(QRC20Error::EVMExecutionFailed {
+                reason: format!("{} bridge transactions for {:?} are still in flight", unfinished, eth_token),
+            });
+        }
+
+        self.eth_to_qora_mapping.remove(&eth_token);
+        self.qora_to_eth_mapping.remove(&qora_token);
+        self.minted_qora_tokens.remove(&qora_token);
+        tracing::warn!("Removed bridge mapping: ETH token {:?} -> QRC-20 token {:?}", eth_token, qora_token);
+        Ok(())
+    }
+
+    /// Check if an Ethereum token is blocked
+    pub fn is_token_blocked(&self, eth_token: H160) -> bool {
+        self.blocked_tokens.contains(&eth_token)
+    }
+
+    fn ensure_not_blocked(&self, eth_token: H160) -> QRC20Result<()> {
+        if self.is_token_blocked(eth_token) {
+            return Err(QRC20Error::TokenBlocked {
+                token: format!("{:?}", eth_token),
+            });
+        }
+        Ok(())
+    }
+
     /// Check if address is a bridge operator
     pub fn is_operator(&self, address: H160) -> bool {
         self.bridge_operators.contains(&address)
     }
 
-    /// Update bridge transaction status
+    /// Whether QoraNet `height` is at or below the finalized height
+    fn is_qora_final(&self, height: u64) -> bool {
+        self.finalized_height.map_or(false, |finalized| height <= finalized)
+    }
+
+    /// Record QoraNet's current and finalized heights, marking burns at or below
+    /// the finalized height as final. Heights never move backwards.
+    pub fn set_qora_heights(&mut self, height: u64, finalized_height: Option<u64>) {
+        self.qora_height = self.qora_height.max(height);
+        let finalized = match finalized_height {
+            Some(finalized) => finalized.min(self.qora_height),
+            None => return,
+        };
+        if self.finalized_height.map_or(false, |current| finalized <= current) {
+            return;
+        }
+        self.finalized_height = Some(finalized);
+
+        for tx in self.bridge_transactions.values_mut() {
+            if !tx.qora_finalized && tx.qora_block_height.map_or(false, |burned| burned <= finalized) {
+                tx.qora_finalized = true;
+                tracing::info!("Bridge burn {:?} finalized on QoraNet", tx.id);
+            }
+        }
+    }
+
+    /// Update bridge transaction status.
+    ///
+    /// A transfer to Ethereum can only be marked released (given an Ethereum
+    /// hash, or confirmed/completed) once its QoraNet burn is final, so a
+    /// QoraNet reorg can't undo a burn Ethereum has already paid out.
     pub fn update_transaction_status(
         &mut self,
         caller: H160,
@@ -333,6 +738,13 @@ impl ERC20Bridge {
                 reason: "Bridge transaction not found".to_string() 
             })?;
 
+        let releases = eth_tx_hash.is_some() || matches!(status, BridgeStatus::Confirmed | BridgeStatus::Completed);
+        if matches!(bridge_tx.direction, BridgeDirection::QoraNetToEthereum) && releases && !bridge_tx.qora_finalized {
+            return Err(QRC20Error::BurnNotFinalized {
+                burn_height: bridge_tx.qora_block_height.unwrap_or_default(),
+            });
+        }
+
         bridge_tx.status = status;
         
         if let Some(hash) = eth_tx_hash {
@@ -494,6 +906,26 @@ impl Default for ERC20Bridge {
 mod tests {
     use super::*;
 
+    fn operator_key() -> SigningKey {
+        SigningKey::from_slice(&[0x46u8; 32]).unwrap()
+    }
+
+    fn operator() -> H160 {
+        evm_address(operator_key().verifying_key())
+    }
+
+    /// Bridge run by the test operator, with the default confirmations and fee
+    fn attested_bridge() -> ERC20Bridge {
+        ERC20Bridge::new_with_config(vec![operator()], 12, 50, H160::zero())
+    }
+
+    /// Operator-signed deposit of `amount` with exactly the required confirmations
+    fn attest(eth_token: H160, user: H160, amount: u64) -> DepositAttestation {
+        DepositAttestation::new(H256::random(), eth_token, user, U256::from(amount), 12)
+            .sign(&operator_key())
+            .unwrap()
+    }
+
     #[test]
     fn test_bridge_creation() {
         let bridge = ERC20Bridge::new();
@@ -526,6 +958,11 @@ mod tests {
         assert!(result.is_ok());
         assert!(bridge.is_operator(operator));
 
+        // Without a treasury only operators can appoint operators
+        let outsider = H160::from_low_u64_be(3);
+        let mut open = ERC20Bridge::new();
+        assert!(matches!(open.add_operator(outsider, outsider), Err(QRC20Error::OnlyOwner)));
+
         // Remove operator
         let result = bridge.remove_operator(admin, operator);
         assert!(result.is_ok());
@@ -534,24 +971,19 @@ mod tests {
 
     #[test]
     fn test_bridge_from_ethereum() {
-        let mut bridge = ERC20Bridge::new();
+        let mut bridge = attested_bridge();
         let mut registry = QRC20Registry::new();
         
         let user = H160::from_low_u64_be(1);
         let eth_token = H160::from_low_u64_be(999);
         let amount = U256::from(1000);
-        let eth_tx_hash = H256::random();
 
         let qora_token = bridge.bridge_from_ethereum(
             &mut registry,
-            eth_token,
-            user,
-            amount,
+            &attest(eth_token, user, 1000),
             "USDC".to_string(),
             "USDC".to_string(),
             6,
-            eth_tx_hash,
-            12,
         ).unwrap();
 
         // Check token was created and user has balance (minus fee)
@@ -570,7 +1002,7 @@ mod tests {
 
     #[test]
     fn test_bridge_to_ethereum() {
-        let mut bridge = ERC20Bridge::new();
+        let mut bridge = attested_bridge();
         let mut registry = QRC20Registry::new();
         
         let user = H160::from_low_u64_be(1);
@@ -581,14 +1013,10 @@ mod tests {
         // First bridge from Ethereum to create the token
         let qora_token = bridge.bridge_from_ethereum(
             &mut registry,
-            eth_token,
-            user,
-            amount,
+            &attest(eth_token, user, 1000),
             "USDC".to_string(),
             "USDC".to_string(),
             6,
-            H256::random(),
-            12,
         ).unwrap();
 
         // Get initial balance
@@ -609,7 +1037,7 @@ mod tests {
 
     #[test]
     fn test_bridge_stats() {
-        let mut bridge = ERC20Bridge::new();
+        let mut bridge = attested_bridge();
         let mut registry = QRC20Registry::new();
         
         let user1 = H160::from_low_u64_be(1);
@@ -620,26 +1048,18 @@ mod tests {
         // Bridge multiple tokens
         let _qora_token1 = bridge.bridge_from_ethereum(
             &mut registry,
-            eth_token1,
-            user1,
-            U256::from(1000),
+            &attest(eth_token1, user1, 1000),
             "USDC".to_string(),
             "USDC".to_string(),
             6,
-            H256::random(),
-            12,
         ).unwrap();
 
         let _qora_token2 = bridge.bridge_from_ethereum(
             &mut registry,
-            eth_token2,
-            user2,
-            U256::from(2000),
+            &attest(eth_token2, user2, 2000),
             "USDT".to_string(),
             "USDT".to_string(),
             6,
-            H256::random(),
-            12,
         ).unwrap();
 
         let stats = bridge.get_bridge_stats();
@@ -648,4 +1068,306 @@ mod tests {
         assert_eq!(stats.completed_transactions, 2);
         assert_eq!(stats.total_transactions, 2);
     }
+
+    #[test]
+    fn test_process_request_from_ethereum() {
+        let mut bridge = attested_bridge();
+        let mut registry = QRC20Registry::new();
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+        let amount = U256::from(5000);
+        let attestation = attest(eth_token, user, 5000);
+
+        let record = bridge.process_request(&mut registry, user, BridgeRequest::FromEthereum {
+            attestation: attestation.clone(),
+            token_name: "USD Coin".to_string(),
+            token_symbol: "USDC".to_string(),
+            decimals: 6,
+        }).unwrap();
+
+        assert!(matches!(record.direction, BridgeDirection::EthereumToQoraNet));
+        assert!(matches!(record.status, BridgeStatus::Completed));
+        assert_eq!(record.eth_token, eth_token);
+        assert_eq!(record.amount, amount);
+        assert_eq!(record.eth_tx_hash, Some(attestation.eth_tx_hash));
+        assert_eq!(record.confirmations, 12);
+        assert!(bridge.get_transaction(record.id).is_some());
+
+        let token = registry.get_token(record.qora_token).unwrap();
+        assert_eq!(token.symbol, "bUSDC");
+        assert_eq!(token.balance_of(user), amount - record.fee_paid);
+        assert_eq!(bridge.eth_to_qora_mapping[&eth_token], record.qora_token);
+    }
+
+    #[test]
+    fn test_process_request_to_ethereum() {
+        let mut bridge = attested_bridge();
+        let mut registry = QRC20Registry::new();
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+
+        let deposit = bridge.process_request(&mut registry, user, BridgeRequest::FromEthereum {
+            attestation: attest(eth_token, user, 5000),
+            token_name: "USD Coin".to_string(),
+            token_symbol: "USDC".to_string(),
+            decimals: 6,
+        }).unwrap();
+        let balance_before = registry.get_token(deposit.qora_token).unwrap().balance_of(user);
+
+        let withdrawal = bridge.process_request(&mut registry, user, BridgeRequest::ToEthereum {
+            qora_token: deposit.qora_token,
+            amount: U256::from(1000),
+        }).unwrap();
+
+        assert!(matches!(withdrawal.direction, BridgeDirection::QoraNetToEthereum));
+        assert!(matches!(withdrawal.status, BridgeStatus::Pending));
+        assert_eq!(withdrawal.eth_token, eth_token);
+        assert_eq!(withdrawal.qora_token, deposit.qora_token);
+        assert_eq!(
+            registry.get_token(deposit.qora_token).unwrap().balance_of(user),
+            balance_before - U256::from(1000)
+        );
+        assert_eq!(bridge.get_pending_transactions().len(), 1);
+
+        // Tokens that were never bridged cannot be sent to Ethereum
+        let result = bridge.process_request(&mut registry, user, BridgeRequest::ToEthereum {
+            qora_token: H160::from_low_u64_be(12345),
+            amount: U256::from(1),
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_blocked_token_rejected_and_unblocked() {
+        let operator = operator();
+        let mut bridge = attested_bridge();
+        let mut registry = QRC20Registry::new();
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+        let deposit = |bridge: &mut ERC20Bridge, registry: &mut QRC20Registry| {
+            bridge.bridge_from_ethereum(
+                registry, &attest(eth_token, user, 1000), "USDC".to_string(), "USDC".to_string(), 6,
+            )
+        };
+
+        let qora_token = deposit(&mut bridge, &mut registry).unwrap();
+        let minted = registry.get_token(qora_token).unwrap().balance_of(user);
+        let locked = bridge.locked_eth_tokens[&eth_token];
+
+        // Only operators can block
+        assert!(matches!(bridge.block_token(user, eth_token), Err(QRC20Error::OnlyOwner)));
+        bridge.block_token(operator, eth_token).unwrap();
+
+        // New bridging halts in both directions, nothing is minted, locked or burned
+        assert!(matches!(deposit(&mut bridge, &mut registry), Err(QRC20Error::TokenBlocked { .. })));
+        assert!(matches!(
+            bridge.bridge_to_ethereum(&mut registry, qora_token, user, U256::from(100)),
+            Err(QRC20Error::TokenBlocked { .. })
+        ));
+        assert_eq!(registry.get_token(qora_token).unwrap().balance_of(user), minted);
+        assert_eq!(bridge.locked_eth_tokens[&eth_token], locked);
+        assert_eq!(bridge.eth_to_qora_mapping[&eth_token], qora_token);
+
+        // Unblocking resumes bridging
+        bridge.unblock_token(operator, eth_token).unwrap();
+        assert_eq!(deposit(&mut bridge, &mut registry).unwrap(), qora_token);
+        assert!(bridge.bridge_to_ethereum(&mut registry, qora_token, user, U256::from(100)).is_ok());
+    }
+
+    #[test]
+    fn test_bridge_tx_ids_deterministic_and_distinct() {
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+        let deposit = |eth_tx: u64, amount: u64| BridgeRequest::FromEthereum {
+            attestation: DepositAttestation::new(H256::from_low_u64_be(eth_tx), eth_token, user, U256::from(amount), 12)
+                .sign(&operator_key())
+                .unwrap(),
+            token_name: "USDC".to_string(),
+            token_symbol: "USDC".to_string(),
+            decimals: 6,
+        };
+        let run = || {
+            let mut bridge = attested_bridge();
+            let mut registry = QRC20Registry::new();
+            // Identical deposits still get distinct ids through the nonce
+            [deposit(1, 1000), deposit(2, 1000), deposit(3, 2000)]
+                .into_iter()
+                .map(|request| bridge.process_request(&mut registry, user, request).unwrap())
+                .map(|tx| (tx.id, tx.eth_tx_hash))
+                .collect::<Vec<_>>()
+        };
+
+        let first = run();
+        let ids: HashSet<H256> = first.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(first[0].0, bridge_tx_id(&BridgeDirection::EthereumToQoraNet, user, eth_token, U256::from(1000), 0));
+
+        // Replaying the same requests on a fresh bridge reproduces every id
+        assert_eq!(run(), first);
+    }
+
+    #[test]
+    fn test_accounting_underflow_is_an_explicit_error() {
+        let mut bridge = attested_bridge();
+        let mut registry = QRC20Registry::new();
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+
+        let qora_token = bridge.bridge_from_ethereum(
+            &mut registry, &attest(eth_token, user, 1000), "USDC".to_string(), "USDC".to_string(), 6,
+        ).unwrap();
+        bridge.check_solvency().unwrap();
+        let balance = registry.get_token(qora_token).unwrap().balance_of(user);
+
+        // Locked drifted below what a release needs
+        bridge.locked_eth_tokens.insert(eth_token, U256::from(10));
+        assert!(matches!(
+            bridge.bridge_to_ethereum(&mut registry, qora_token, user, U256::from(500)),
+            Err(QRC20Error::BridgeAccounting { .. })
+        ));
+        assert!(matches!(bridge.check_solvency(), Err(QRC20Error::BridgeAccounting { .. })));
+
+        // Minted drifted below what a burn removes: an error, not a silent zero
+        bridge.locked_eth_tokens.insert(eth_token, U256::from(1000));
+        bridge.minted_qora_tokens.insert(qora_token, U256::from(100));
+        assert!(matches!(
+            bridge.bridge_to_ethereum(&mut registry, qora_token, user, U256::from(500)),
+            Err(QRC20Error::BridgeAccounting { .. })
+        ));
+        assert_eq!(bridge.minted_qora_tokens[&qora_token], U256::from(100));
+
+        // Nothing was burned by the failed releases
+        assert_eq!(registry.get_token(qora_token).unwrap().balance_of(user), balance);
+    }
+
+    #[test]
+    fn test_remove_mapping_requires_unwound_supply() {
+        let operator = operator();
+        let mut bridge = ERC20Bridge::new_with_config(vec![operator], 12, 0, H160::zero());
+        let mut registry = QRC20Registry::new();
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+
+        let qora_token = bridge.bridge_from_ethereum(
+            &mut registry, &attest(eth_token, user, 1000), "USDC".to_string(), "USDC".to_string(), 6,
+        ).unwrap();
+
+        // Outstanding minted supply blocks removal
+        assert!(matches!(bridge.remove_mapping(user, eth_token), Err(QRC20Error::OnlyOwner)));
+        assert!(bridge.remove_mapping(operator, eth_token).is_err());
+        assert_eq!(bridge.eth_to_qora_mapping[&eth_token], qora_token);
+
+        // Burn everything back; the release is still pending on Ethereum
+        let release = bridge.burn_and_release(&mut registry, qora_token, user, U256::from(1000)).unwrap();
+        assert!(bridge.minted_qora_tokens[&qora_token].is_zero());
+        assert!(bridge.remove_mapping(operator, eth_token).is_err());
+
+        bridge.bridge_transactions.get_mut(&release).unwrap().status = BridgeStatus::Completed;
+        bridge.remove_mapping(operator, eth_token).unwrap();
+        assert!(!bridge.eth_to_qora_mapping.contains_key(&eth_token));
+        assert!(!bridge.qora_to_eth_mapping.contains_key(&qora_token));
+    }
+
+    #[test]
+    fn test_ethereum_release_waits_for_qora_finality() {
+        let operator = operator();
+        let mut bridge = ERC20Bridge::new_with_config(vec![operator], 12, 0, H160::zero());
+        let mut registry = QRC20Registry::new();
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+
+        let qora_token = bridge.bridge_from_ethereum(
+            &mut registry, &attest(eth_token, user, 1000), "USDC".to_string(), "USDC".to_string(), 6,
+        ).unwrap();
+
+        bridge.set_qora_heights(100, Some(68));
+        let burn = bridge.burn_and_release(&mut registry, qora_token, user, U256::from(400)).unwrap();
+        assert_eq!(bridge.bridge_transactions[&burn].qora_block_height, Some(100));
+        assert!(!bridge.bridge_transactions[&burn].qora_finalized);
+
+        // No release on Ethereum while the burn could still be reorged away
+        for (status, eth_tx_hash) in [
+            (BridgeStatus::Completed, None),
+            (BridgeStatus::Confirmed, None),
+            (BridgeStatus::Pending, Some(H256::random())),
+        ] {
+            let result = bridge.update_transaction_status(operator, burn, status, eth_tx_hash, Some(12));
+            assert!(matches!(result, Err(QRC20Error::BurnNotFinalized { burn_height: 100 })));
+        }
+        assert!(matches!(bridge.bridge_transactions[&burn].status, BridgeStatus::Pending));
+        assert!(bridge.bridge_transactions[&burn].eth_tx_hash.is_none());
+
+        // One block short of final
+        bridge.set_qora_heights(131, Some(99));
+        assert!(!bridge.bridge_transactions[&burn].qora_finalized);
+        assert!(bridge.update_transaction_status(operator, burn, BridgeStatus::Completed, None, None).is_err());
+
+        bridge.set_qora_heights(132, Some(100));
+        assert!(bridge.bridge_transactions[&burn].qora_finalized);
+        bridge.update_transaction_status(operator, burn, BridgeStatus::Completed, Some(H256::random()), Some(12)).unwrap();
+        assert!(matches!(bridge.bridge_transactions[&burn].status, BridgeStatus::Completed));
+
+        // Deposits have no QoraNet burn to wait for
+        let deposit = bridge.get_user_transactions(user).into_iter()
+            .find(|tx| matches!(tx.direction, BridgeDirection::EthereumToQoraNet))
+            .unwrap();
+        assert!(deposit.qora_finalized);
+    }
+
+    #[test]
+    fn test_deposit_requires_operator_attestation() {
+        let mut bridge = attested_bridge();
+        let mut registry = QRC20Registry::new();
+        let user = H160::from_low_u64_be(1);
+        let eth_token = H160::from_low_u64_be(999);
+        let mint = |bridge: &mut ERC20Bridge, registry: &mut QRC20Registry, attestation: DepositAttestation| {
+            bridge.process_request(registry, user, BridgeRequest::FromEthereum {
+                attestation,
+                token_name: "USDC".to_string(),
+                token_symbol: "USDC".to_string(),
+                decimals: 6,
+            })
+        };
+        let deposit = DepositAttestation::new(H256::random(), eth_token, user, U256::from(1000), 12);
+
+        // Unsigned, or signed by someone who isn't an operator
+        assert!(matches!(
+            mint(&mut bridge, &mut registry, deposit.clone()),
+            Err(QRC20Error::Unauthorized { .. })
+        ));
+        let stranger = SigningKey::from_slice(&[0x47u8; 32]).unwrap();
+        assert!(matches!(
+            mint(&mut bridge, &mut registry, deposit.clone().sign(&stranger).unwrap()),
+            Err(QRC20Error::Unauthorized { .. })
+        ));
+
+        // Altering a signed deposit invalidates the operator's signature
+        let mut inflated = deposit.clone().sign(&operator_key()).unwrap();
+        inflated.amount = U256::from(1_000_000);
+        assert!(matches!(
+            mint(&mut bridge, &mut registry, inflated),
+            Err(QRC20Error::Unauthorized { .. })
+        ));
+
+        // Confirmations are the operator's count, and must reach the minimum
+        let shallow = DepositAttestation::new(H256::random(), eth_token, user, U256::from(1000), 11)
+            .sign(&operator_key())
+            .unwrap();
+        assert!(matches!(
+            mint(&mut bridge, &mut registry, shallow),
+            Err(QRC20Error::InsufficientConfirmations { confirmations: 11, required: 12 })
+        ));
+        assert_eq!(registry.token_count(), 0);
+        assert!(bridge.bridge_transactions.is_empty());
+
+        // A valid attestation mints once; replaying it is refused
+        let attested = deposit.sign(&operator_key()).unwrap();
+        assert_eq!(attested.recover_signer().unwrap(), operator());
+        mint(&mut bridge, &mut registry, attested.clone()).unwrap();
+        assert!(matches!(
+            mint(&mut bridge, &mut registry, attested),
+            Err(QRC20Error::Unauthorized { .. })
+        ));
+        assert_eq!(bridge.locked_eth_tokens[&eth_token], U256::from(1000));
+    }
 }
@@ -1,11 +1,20 @@
 use evm::{
-    executor::stack::{MemoryStackState, StackSubstateMetadata, StackState},
-    Config, Context, CreateScheme, ExitReason, Handler, Runtime,
+    backend::Apply,
+    executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata, StackState},
+    Config, Context, CreateScheme, ExitReason, ExitRevert, Handler, Runtime,
 };
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
 use primitive_types::{H160, H256, U256};
-use std::collections::BTreeMap;
+use sha3::{Digest, Keccak256};
+use std::collections::{BTreeMap, VecDeque};
 use serde::{Deserialize, Serialize};
 
+/// Default most logs a single transaction may emit before it reverts
+pub const DEFAULT_MAX_LOGS_PER_TX: usize = 1_024;
+
+/// Default most logs kept in memory; the oldest are evicted first
+pub const DEFAULT_MAX_RETAINED_LOGS: usize = 65_536;
+
 /// QoraNet EVM compatibility layer for QRC-20 tokens
 pub struct QoraNetEVM {
     /// EVM configuration
@@ -16,6 +25,19 @@ pub struct QoraNetEVM {
     storage: BTreeMap<(H160, H256), H256>,
     /// Block context
     block_context: BlockContext,
+    /// Logs of committed executions, oldest first
+    logs: VecDeque<EVMLog>,
+    max_logs_per_tx: usize,
+    max_retained_logs: usize,
+}
+
+/// Log emitted by a contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EVMLog {
+    pub address: H160,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+    pub block_number: U256,
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +74,28 @@ impl QoraNetEVM {
                 coinbase: H160::zero(), // Set to QOR treasury
                 chain_id: U256::from(2024), // QoraNet chain ID
             },
+            logs: VecDeque::new(),
+            max_logs_per_tx: DEFAULT_MAX_LOGS_PER_TX,
+            max_retained_logs: DEFAULT_MAX_RETAINED_LOGS,
+        }
+    }
+
+    /// Set the per-transaction log cap and the total number of logs retained
+    pub fn with_log_limits(mut self, max_logs_per_tx: usize, max_retained_logs: usize) -> Self {
+        self.max_logs_per_tx = max_logs_per_tx;
+        self.max_retained_logs = max_retained_logs;
+        self.trim_logs();
+        self
+    }
+
+    /// Retained logs, oldest first
+    pub fn logs(&self) -> impl Iterator<Item = &EVMLog> {
+        self.logs.iter()
+    }
+
+    fn trim_logs(&mut self) {
+        while self.logs.len() > self.max_retained_logs {
+            self.logs.pop_front();
         }
     }
 
@@ -283,28 +327,10 @@ impl QoraNetEVM {
         code: Vec<u8>,
         value: U256,
     ) -> Result<ExitReason, String> {
-        let backend = self.create_backend();
-        let metadata = StackSubstateMetadata::new(1_000_000, &self.config);
-        let state = MemoryStackState::new(metadata, &backend);
-        let precompiles = BTreeMap::new(); // No precompiles for now
-        
-        let mut executor = StackState::new(state, &self.config, &precompiles);
+        let execution = self.execute(caller, None, code, value, 1_000_000);
+        let exit_reason = execution.exit_reason.clone();
+        self.commit(execution);
 
-        let (exit_reason, _) = executor.transact_create(
-            caller,
-            value,
-            code,
-            1_000_000, // Gas limit
-            Vec::new(), // Access list
-        );
-
-        // Commit changes back to storage (simplified)
-        self.commit_backend(backend);
-        
-        // Increment nonce
-        let nonce = self.get_nonce(&caller);
-        self.set_nonce(caller, nonce + U256::one());
-        
         Ok(exit_reason)
     }
 
@@ -316,29 +342,155 @@ impl QoraNetEVM {
         input: Vec<u8>,
         value: U256,
     ) -> Result<Vec<u8>, String> {
+        let execution = self.execute(caller, Some(contract), input, value, 1_000_000);
+        let result = self.commit(execution);
+
+        match result.exit_reason {
+            ExitReason::Succeed(_) => Ok(result.output),
+            ExitReason::Revert(_) => Err("Contract call reverted".to_string()),
+            ExitReason::Error(err) => Err(format!("Contract call error: {:?}", err)),
+            ExitReason::Fatal(err) => Err(format!("Fatal error during call: {:?}", err)),
+        }
+    }
+
+    /// Run a call or contract creation against the current state without modifying it.
+    ///
+    /// State is read through the backend on demand; only the touched accounts and
+    /// slots are held in the returned execution until it is committed.
+    pub fn execute(
+        &self,
+        caller: H160,
+        to: Option<H160>,
+        data: Vec<u8>,
+        value: U256,
+        gas_limit: u64,
+    ) -> EVMExecution {
         let backend = self.create_backend();
-        let metadata = StackSubstateMetadata::new(1_000_000, &self.config);
+        let metadata = StackSubstateMetadata::new(gas_limit, &self.config);
         let state = MemoryStackState::new(metadata, &backend);
-        let precompiles = BTreeMap::new();
-        
-        let mut executor = StackState::new(state, &self.config, &precompiles);
+        let precompiles = (); // No precompiles for now
+
+        let mut executor = StackExecutor::new_with_precompiles(state, &self.config, &precompiles);
+
+        let (contract_address, exit_reason, output) = match to {
+            Some(contract) => {
+                let (exit_reason, output) = executor.transact_call(
+                    caller,
+                    contract,
+                    value,
+                    data,
+                    gas_limit,
+                    Vec::new(), // Access list
+                );
+                (None, exit_reason, output)
+            }
+            None => {
+                let address = self.create_address(&caller, self.get_nonce(&caller));
+                let (exit_reason, output) = executor.transact_create(
+                    caller,
+                    value,
+                    data,
+                    gas_limit,
+                    Vec::new(), // Access list
+                );
+                (Some(address), exit_reason, output)
+            }
+        };
+        let gas_used = executor.used_gas();
+
+        // Detach the changes from the borrowed state before it is dropped
+        let (values, logs) = executor.into_state().deconstruct();
+
+        // Stop collecting one past the cap; a transaction over it reverts
+        let logs: Vec<EVMLog> = logs.into_iter()
+            .take(self.max_logs_per_tx.saturating_add(1))
+            .map(|log| EVMLog {
+                address: log.address,
+                topics: log.topics,
+                data: log.data,
+                block_number: self.block_context.number,
+            })
+            .collect();
+        if logs.len() > self.max_logs_per_tx {
+            tracing::warn!("EVM execution emitted more than {} logs, reverting", self.max_logs_per_tx);
+            return EVMExecution {
+                exit_reason: ExitReason::Revert(ExitRevert::Reverted),
+                output: Vec::new(),
+                gas_used,
+                contract_address: None,
+                changes: Vec::new(),
+                logs: Vec::new(),
+            };
+        }
 
-        let (exit_reason, output) = executor.transact_call(
-            caller,
-            contract,
-            value,
-            input,
-            1_000_000, // Gas limit
-            Vec::new(), // Access list
-        );
+        let changes = values.into_iter().map(|apply| match apply {
+            Apply::Modify { address, basic, code, storage, reset_storage } => Apply::Modify {
+                address,
+                basic,
+                code,
+                storage: storage.into_iter().collect(),
+                reset_storage,
+            },
+            Apply::Delete { address } => Apply::Delete { address },
+        }).collect();
+
+        EVMExecution {
+            exit_reason,
+            output,
+            gas_used,
+            contract_address,
+            changes,
+            logs,
+        }
+    }
 
-        self.commit_backend(backend);
+    /// Apply the state changes of a successful execution
+    pub fn commit(&mut self, execution: EVMExecution) -> EVMExecutionResult {
+        let success = matches!(execution.exit_reason, ExitReason::Succeed(_));
+
+        if success {
+            for change in execution.changes {
+                match change {
+                    Apply::Modify { address, basic, code, storage, reset_storage } => {
+                        if reset_storage {
+                            self.storage.retain(|(owner, _), _| *owner != address);
+                        }
+                        for (index, value) in storage {
+                            if value == H256::zero() {
+                                self.storage.remove(&(address, index));
+                            } else {
+                                self.storage.insert((address, index), value);
+                            }
+                        }
+
+                        let account = self.accounts.entry(address).or_insert_with(|| Account {
+                            balance: U256::zero(),
+                            nonce: U256::zero(),
+                            code: Vec::new(),
+                        });
+                        account.balance = basic.balance;
+                        account.nonce = basic.nonce;
+                        if let Some(code) = code {
+                            account.code = code;
+                        }
+                    }
+                    Apply::Delete { address } => {
+                        self.accounts.remove(&address);
+                        self.storage.retain(|(owner, _), _| *owner != address);
+                    }
+                }
+            }
 
-        match exit_reason {
-            ExitReason::Succeed(_) => Ok(output),
-            ExitReason::Revert(_) => Err("Contract call reverted".to_string()),
-            ExitReason::Error(err) => Err(format!("Contract call error: {:?}", err)),
-            ExitReason::Fatal(err) => Err(format!("Fatal error during call: {:?}", err)),
+            self.logs.extend(execution.logs.iter().cloned());
+            self.trim_logs();
+        }
+
+        EVMExecutionResult {
+            exit_reason: execution.exit_reason,
+            output: execution.output,
+            gas_used: execution.gas_used,
+            contract_address: if success { execution.contract_address } else { None },
+            logs: if success { execution.logs } else { Vec::new() },
         }
     }
 
@@ -386,21 +538,13 @@ impl QoraNetEVM {
         bytecode
     }
 
-    /// Create EVM backend
-    fn create_backend(&self) -> EVMBackend {
+    /// Create a read-through EVM backend over the current state
+    fn create_backend(&self) -> EVMBackend<'_> {
         EVMBackend::new(&self.accounts, &self.storage, &self.block_context)
     }
 
-    /// Commit backend changes (simplified)
-    fn commit_backend(&mut self, backend: EVMBackend) {
-        // Apply state changes back to QoraNet storage
-        self.accounts = backend.accounts;
-        self.storage = backend.storage;
-    }
-
     /// Generate contract address using CREATE opcode rules
     fn create_address(&self, deployer: &H160, nonce: U256) -> H160 {
-        use sha3::{Digest, Keccak256};
         use rlp::RlpStream;
         
         let mut stream = RlpStream::new_list(2);
@@ -413,8 +557,6 @@ impl QoraNetEVM {
 
     /// Generate contract address using CREATE2 opcode rules
     fn create2_address(&self, deployer: &H160, salt: H256, code_hash: H256) -> H160 {
-        use sha3::{Digest, Keccak256};
-        
         let mut data = Vec::new();
         data.push(0xff);
         data.extend_from_slice(deployer.as_bytes());
@@ -426,7 +568,7 @@ impl QoraNetEVM {
     }
 
     /// Get account nonce
-    fn get_nonce(&self, address: &H160) -> U256 {
+    pub fn get_nonce(&self, address: &H160) -> U256 {
         self.accounts
             .get(address)
             .map(|account| account.nonce)
@@ -477,6 +619,28 @@ impl QoraNetEVM {
         self.block_context.chain_id
     }
 
+    /// Check a signed transaction's chain id and recover its sender.
+    ///
+    /// Unsigned, pre-EIP-155 and other-chain transactions are rejected, as is a
+    /// `from` field that doesn't match the signer.
+    pub fn verify_transaction(&self, tx: &EVMTransaction) -> Result<H160, String> {
+        let signature = tx.signature.as_ref().ok_or("EVM transaction is not signed")?;
+        let chain_id = signature.chain_id()
+            .ok_or("EVM transaction has no EIP-155 chain id")?;
+        if U256::from(chain_id) != self.block_context.chain_id {
+            return Err(format!(
+                "EVM transaction is for chain {}, this chain is {}",
+                chain_id, self.block_context.chain_id
+            ));
+        }
+
+        let sender = tx.recover_sender()?;
+        if sender != tx.from {
+            return Err(format!("EVM transaction from 0x{:x} is signed by 0x{:x}", tx.from, sender));
+        }
+        Ok(sender)
+    }
+
     /// Estimate gas for ERC-20 operations
     pub fn estimate_gas(&self, operation: EVMOperation) -> u64 {
         match operation {
@@ -490,29 +654,56 @@ impl QoraNetEVM {
     }
 }
 
-/// EVM Backend for QoraNet integration
-pub struct EVMBackend {
-    accounts: BTreeMap<H160, Account>,
-    storage: BTreeMap<(H160, H256), H256>,
-    block_context: BlockContext,
+/// Changes produced by an EVM execution, not yet applied to state
+pub struct EVMExecution {
+    pub exit_reason: ExitReason,
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+    /// Address of the created contract (creations only)
+    pub contract_address: Option<H160>,
+    changes: Vec<Apply<Vec<(H256, H256)>>>,
+    logs: Vec<EVMLog>,
+}
+
+/// Outcome of a committed EVM execution
+#[derive(Debug, Clone)]
+pub struct EVMExecutionResult {
+    pub exit_reason: ExitReason,
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+    pub contract_address: Option<H160>,
+    pub logs: Vec<EVMLog>,
+}
+
+impl EVMExecutionResult {
+    pub fn succeeded(&self) -> bool {
+        matches!(self.exit_reason, ExitReason::Succeed(_))
+    }
+}
+
+/// Read-through EVM backend over QoraNet state (borrows, never clones)
+pub struct EVMBackend<'a> {
+    accounts: &'a BTreeMap<H160, Account>,
+    storage: &'a BTreeMap<(H160, H256), H256>,
+    block_context: &'a BlockContext,
 }
 
-impl EVMBackend {
+impl<'a> EVMBackend<'a> {
     pub fn new(
-        accounts: &BTreeMap<H160, Account>,
-        storage: &BTreeMap<(H160, H256), H256>,
-        block_context: &BlockContext,
+        accounts: &'a BTreeMap<H160, Account>,
+        storage: &'a BTreeMap<(H160, H256), H256>,
+        block_context: &'a BlockContext,
     ) -> Self {
         Self {
-            accounts: accounts.clone(),
-            storage: storage.clone(),
-            block_context: block_context.clone(),
+            accounts,
+            storage,
+            block_context,
         }
     }
 }
 
 // Implement EVM Handler traits for backend
-impl evm::backend::Backend for EVMBackend {
+impl evm::backend::Backend for EVMBackend<'_> {
     fn gas_price(&self) -> U256 {
         // Convert QOR gas price to wei equivalent
         U256::from(20_000_000_000u64) // 20 gwei equivalent
@@ -595,6 +786,38 @@ pub struct EVMTransaction {
     pub data: Vec<u8>,
     pub nonce: U256,
     pub transaction_type: EVMTransactionType,
+    /// Set by `sign`; `from` is only trusted once it matches the recovered signer
+    #[serde(default)]
+    pub signature: Option<EVMSignature>,
+}
+
+/// EIP-155 signature of an EVM transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EVMSignature {
+    /// `chain_id * 2 + 35 + recovery id`
+    pub v: u64,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl EVMSignature {
+    /// Chain id encoded in `v`; `None` for pre-EIP-155 signatures (v = 27 / 28)
+    pub fn chain_id(&self) -> Option<u64> {
+        self.v.checked_sub(35).map(|v| v / 2)
+    }
+
+    fn recovery_id(&self) -> Option<RecoveryId> {
+        let parity = self.v.checked_sub(35)? % 2;
+        RecoveryId::from_byte(parity as u8)
+    }
+}
+
+/// Ethereum address of a secp256k1 public key
+pub fn evm_address(key: &VerifyingKey) -> H160 {
+    let point = key.to_encoded_point(false);
+    // Uncompressed point without its 0x04 tag
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    H160::from_slice(&hash[12..])
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -637,6 +860,7 @@ impl EVMTransaction {
             data,
             nonce,
             transaction_type: EVMTransactionType::Legacy,
+            signature: None,
         }
     }
 
@@ -667,6 +891,7 @@ impl EVMTransaction {
             data,
             nonce,
             transaction_type: EVMTransactionType::Legacy,
+            signature: None,
         }
     }
 
@@ -697,6 +922,7 @@ impl EVMTransaction {
             data,
             nonce,
             transaction_type: EVMTransactionType::Legacy,
+            signature: None,
         }
     }
 
@@ -724,12 +950,9 @@ impl EVMTransaction {
         data
     }
 
-    /// Get transaction hash
-    pub fn hash(&self) -> H256 {
-        use sha3::{Digest, Keccak256};
-        use rlp::RlpStream;
-        
-        let mut stream = RlpStream::new_list(9);
+    /// RLP list of the fields common to every encoding, followed by `extra` more items
+    fn rlp_stream(&self, extra: usize) -> rlp::RlpStream {
+        let mut stream = rlp::RlpStream::new_list(6 + extra);
         stream.append(&self.nonce);
         stream.append(&self.gas_price);
         stream.append(&self.gas_limit);
@@ -742,10 +965,77 @@ impl EVMTransaction {
         
         stream.append(&self.value);
         stream.append(&self.data);
-        stream.append(&self.from); // Simplified - normally would use v,r,s signature
-        
-        let hash = Keccak256::digest(&stream.out());
-        H256::from_slice(&hash)
+        stream
+    }
+
+    /// EIP-155 signing hash: the fields plus `chain_id, 0, 0`
+    pub fn signing_hash(&self, chain_id: u64) -> H256 {
+        let mut stream = self.rlp_stream(3);
+        stream.append(&chain_id);
+        stream.append(&0u8);
+        stream.append(&0u8);
+        H256::from_slice(&Keccak256::digest(&stream.out()))
+    }
+
+    /// Sign for `chain_id`, setting `from` to the key's address
+    pub fn sign(mut self, key: &SigningKey, chain_id: u64) -> Result<Self, String> {
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(self.signing_hash(chain_id).as_bytes())
+            .map_err(|e| format!("Failed to sign EVM transaction: {}", e))?;
+        let (r, s) = signature.split_bytes();
+        let v = chain_id.checked_mul(2)
+            .and_then(|v| v.checked_add(35 + recovery_id.is_y_odd() as u64))
+            .ok_or("Chain id too large for EIP-155")?;
+
+        self.from = evm_address(key.verifying_key());
+        self.signature = Some(EVMSignature {
+            v,
+            r: U256::from_big_endian(&r),
+            s: U256::from_big_endian(&s),
+        });
+        Ok(self)
+    }
+
+    /// Address that signed the transaction, recovered from `v`, `r`, `s`
+    pub fn recover_sender(&self) -> Result<H160, String> {
+        let signature = self.signature.as_ref().ok_or("EVM transaction is not signed")?;
+        let (chain_id, recovery_id) = signature.chain_id().zip(signature.recovery_id())
+            .ok_or("EVM transaction has no EIP-155 chain id")?;
+
+        let mut rs = [0u8; 64];
+        signature.r.to_big_endian(&mut rs[..32]);
+        signature.s.to_big_endian(&mut rs[32..]);
+        let ecdsa = Signature::from_slice(&rs)
+            .map_err(|_| "Invalid EVM signature".to_string())?;
+        // EIP-2: only the low-s form is valid
+        if ecdsa.normalize_s().is_some() {
+            return Err("EVM signature s value is not canonical".to_string());
+        }
+
+        let key = VerifyingKey::recover_from_prehash(
+            self.signing_hash(chain_id).as_bytes(),
+            &ecdsa,
+            recovery_id,
+        ).map_err(|_| "Failed to recover EVM transaction sender".to_string())?;
+        Ok(evm_address(&key))
+    }
+
+    /// Get transaction hash.
+    ///
+    /// For a signed transaction this is the Ethereum hash of its signed RLP
+    /// encoding; an unsigned one hashes the bare fields.
+    pub fn hash(&self) -> H256 {
+        let encoded = match &self.signature {
+            Some(signature) => {
+                let mut stream = self.rlp_stream(3);
+                stream.append(&signature.v);
+                stream.append(&signature.r);
+                stream.append(&signature.s);
+                stream.out()
+            }
+            None => self.rlp_stream(0).out(),
+        };
+        H256::from_slice(&Keccak256::digest(&encoded))
     }
 }
 
@@ -848,6 +1138,83 @@ mod tests {
         assert_eq!(&tx.data[0..4], &[0xa9, 0x05, 0x9c, 0xbb]);
     }
 
+    /// The example transaction from EIP-155
+    fn eip155_example() -> (SigningKey, EVMTransaction) {
+        let key = SigningKey::from_slice(&[0x46u8; 32]).unwrap();
+        let mut tx = EVMTransaction::erc20_transfer(
+            H160::zero(),
+            H160::from_slice(&[0x35u8; 20]),
+            H160::zero(),
+            U256::zero(),
+            U256::from(21_000),
+            U256::from(20_000_000_000u64),
+            U256::from(9),
+        );
+        tx.value = U256::from(1_000_000_000_000_000_000u64);
+        tx.data.clear();
+        (key, tx)
+    }
+
+    #[test]
+    fn test_eip155_signing_matches_reference() {
+        let (key, tx) = eip155_example();
+        assert_eq!(
+            hex::encode(tx.signing_hash(1)),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+
+        let signed = tx.sign(&key, 1).unwrap();
+        let signature = signed.signature.unwrap();
+        assert_eq!(signature.v, 37);
+        assert_eq!(signature.chain_id(), Some(1));
+        assert_eq!(
+            signature.r,
+            U256::from_dec_str("18515461264373351373200002665853028612451056578545711640558177340181847433846").unwrap()
+        );
+        assert_eq!(
+            signature.s,
+            U256::from_dec_str("46948507304638947509940763649030358759909902576025900602547168820602576006531").unwrap()
+        );
+
+        let expected_sender = H160::from_slice(&hex::decode("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap());
+        assert_eq!(signed.from, expected_sender);
+        assert_eq!(signed.recover_sender().unwrap(), expected_sender);
+        assert_eq!(
+            hex::encode(signed.hash()),
+            "33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788"
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_chain_id() {
+        let (key, tx) = eip155_example();
+        let evm = QoraNetEVM::with_config(2024, 30_000_000, H160::zero());
+
+        let mainnet = tx.clone().sign(&key, 1).unwrap();
+        let err = evm.verify_transaction(&mainnet).unwrap_err();
+        assert!(err.contains("chain 1"), "{}", err);
+
+        let local = tx.sign(&key, 2024).unwrap();
+        assert_eq!(evm.verify_transaction(&local).unwrap(), local.from);
+    }
+
+    #[test]
+    fn test_verify_rejects_unsigned_and_forged_transactions() {
+        let (key, tx) = eip155_example();
+        let evm = QoraNetEVM::with_config(1, 30_000_000, H160::zero());
+        assert!(evm.verify_transaction(&tx).is_err());
+
+        // Claiming someone else's address doesn't survive recovery
+        let mut forged = tx.clone().sign(&key, 1).unwrap();
+        forged.from = H160::from_low_u64_be(7);
+        assert!(evm.verify_transaction(&forged).is_err());
+
+        // Nor does changing a signed field
+        let mut tampered = tx.sign(&key, 1).unwrap();
+        tampered.value = U256::from(2);
+        assert!(evm.verify_transaction(&tampered).is_err());
+    }
+
     #[test]
     fn test_block_context_updates() {
         let mut evm = QoraNetEVM::new();
@@ -859,4 +1226,48 @@ mod tests {
         assert_eq!(evm.block_number(), new_block);
         assert_eq!(evm.block_context.timestamp, new_timestamp);
     }
+
+    /// Creation code emitting `count` LOG1s with topics `first..first + count`
+    fn log_emitter(first: u8, count: u8) -> Vec<u8> {
+        let mut code = Vec::new();
+        for topic in first..first + count {
+            // PUSH1 topic, PUSH1 0 (size), PUSH1 0 (offset), LOG1
+            code.extend_from_slice(&[0x60, topic, 0x60, 0x00, 0x60, 0x00, 0xa1]);
+        }
+        code.push(0x00); // STOP
+        code
+    }
+
+    #[test]
+    fn test_transaction_over_log_cap_reverts() {
+        let mut evm = QoraNetEVM::new().with_log_limits(5, 100);
+        let caller = H160::from_low_u64_be(1);
+
+        let result = evm.commit(evm.execute(caller, None, log_emitter(0, 6), U256::zero(), 1_000_000));
+        assert!(matches!(result.exit_reason, ExitReason::Revert(_)));
+        assert!(result.logs.is_empty());
+        assert_eq!(evm.logs().count(), 0);
+        assert_eq!(evm.get_nonce(&caller), U256::zero());
+
+        let result = evm.commit(evm.execute(caller, None, log_emitter(0, 5), U256::zero(), 1_000_000));
+        assert!(result.succeeded());
+        assert_eq!(result.logs.len(), 5);
+        assert_eq!(evm.logs().count(), 5);
+    }
+
+    #[test]
+    fn test_retained_logs_evict_oldest() {
+        let mut evm = QoraNetEVM::new().with_log_limits(10, 8);
+        let caller = H160::from_low_u64_be(1);
+
+        for batch in 0..3u8 {
+            let execution = evm.execute(caller, None, log_emitter(batch * 5, 5), U256::zero(), 1_000_000);
+            assert!(evm.commit(execution).succeeded());
+        }
+
+        // 15 emitted, the 7 oldest evicted
+        let topics: Vec<H256> = evm.logs().map(|log| log.topics[0]).collect();
+        let expected: Vec<H256> = (7..15u64).map(H256::from_low_u64_be).collect();
+        assert_eq!(topics, expected);
+    }
 }
@@ -0,0 +1,165 @@
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use primitive_types::{H160, U256};
+use super::evm_integration::{EVMExecutionResult, QoraNetEVM};
+
+/// Default number of EVM executions allowed to run at once
+pub const DEFAULT_MAX_CONCURRENT_EXECUTIONS: usize = 4;
+
+/// Bounded pool for EVM execution.
+///
+/// Each execution runs and commits under a single write lock on the shared
+/// EVM, so an execution always sees every earlier commit and concurrent
+/// callers can't overwrite each other's balances, nonces or CREATE addresses.
+/// At most `max_concurrent` callers hold a permit (and a pending substate);
+/// callers beyond the bound queue until a permit is released.
+#[derive(Clone)]
+pub struct EVMExecutionPool {
+    evm: Arc<RwLock<QoraNetEVM>>,
+    permits: Arc<Permits>,
+}
+
+impl EVMExecutionPool {
+    pub fn new(evm: QoraNetEVM, max_concurrent: usize) -> Self {
+        Self {
+            evm: Arc::new(RwLock::new(evm)),
+            permits: Arc::new(Permits::new(max_concurrent.max(1))),
+        }
+    }
+
+    /// Maximum number of concurrent executions
+    pub fn max_concurrent(&self) -> usize {
+        self.permits.max
+    }
+
+    /// Number of executions currently running
+    pub fn in_flight(&self) -> usize {
+        self.permits.max - *self.permits.available.lock().unwrap()
+    }
+
+    /// Shared EVM state
+    pub fn evm(&self) -> &Arc<RwLock<QoraNetEVM>> {
+        &self.evm
+    }
+
+    /// Execute a call (`to = Some`) or contract creation (`to = None`), waiting for a permit
+    pub fn execute(
+        &self,
+        caller: H160,
+        to: Option<H160>,
+        data: Vec<u8>,
+        value: U256,
+        gas_limit: u64,
+    ) -> Result<EVMExecutionResult, String> {
+        self.with_permit(|| {
+            // Execute and commit under one lock: a snapshot read earlier could be stale by commit time
+            let mut evm = self.evm.write()
+                .map_err(|_| "EVM state lock poisoned".to_string())?;
+            let execution = evm.execute(caller, to, data, value, gas_limit);
+            Ok(evm.commit(execution))
+        })
+    }
+
+    /// Execute as `execute`, but only if `nonce` is `caller`'s next nonce.
+    ///
+    /// The nonce is checked under the same lock as the execution, so a signed
+    /// transaction can't run twice.
+    pub fn execute_at_nonce(
+        &self,
+        caller: H160,
+        nonce: U256,
+        to: Option<H160>,
+        data: Vec<u8>,
+        value: U256,
+        gas_limit: u64,
+    ) -> Result<EVMExecutionResult, String> {
+        self.with_permit(|| {
+            let mut evm = self.evm.write()
+                .map_err(|_| "EVM state lock poisoned".to_string())?;
+            let expected = evm.get_nonce(&caller);
+            if nonce != expected {
+                return Err(format!("Invalid nonce: expected {}, got {}", expected, nonce));
+            }
+            let execution = evm.execute(caller, to, data, value, gas_limit);
+            Ok(evm.commit(execution))
+        })
+    }
+
+    /// Run `f` while holding an execution permit
+    fn with_permit<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _permit = self.permits.acquire();
+        f()
+    }
+}
+
+/// Counting semaphore guarding EVM execution
+struct Permits {
+    max: usize,
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Permits {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            available: Mutex::new(max),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> PermitGuard<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
+        }
+        *available -= 1;
+        PermitGuard { permits: self }
+    }
+}
+
+/// Returns its permit on drop
+struct PermitGuard<'a> {
+    permits: &'a Permits,
+}
+
+impl Drop for PermitGuard<'_> {
+    fn drop(&mut self) {
+        let mut available = self.permits.available.lock().unwrap();
+        *available += 1;
+        self.permits.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_conflicting_transfers_do_not_lose_updates() {
+        let sender = H160::from_low_u64_be(1);
+        let mut evm = QoraNetEVM::new();
+        evm.set_balance(sender, U256::from(1_000));
+        let pool = EVMExecutionPool::new(evm, 3);
+
+        // Every transfer debits the same sender, so a stale snapshot would overwrite earlier debits
+        let handles: Vec<_> = (0..24u64).map(|i| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let recipient = H160::from_low_u64_be(0x1000 + i);
+                pool.execute(sender, Some(recipient), Vec::new(), U256::from(10), 100_000)
+            })
+        }).collect();
+
+        for handle in handles {
+            assert!(handle.join().unwrap().unwrap().succeeded());
+        }
+
+        let evm = pool.evm().read().unwrap();
+        assert_eq!(evm.get_balance(sender), U256::from(1_000 - 24 * 10));
+        for i in 0..24u64 {
+            assert_eq!(evm.get_balance(H160::from_low_u64_be(0x1000 + i)), U256::from(10));
+        }
+        assert_eq!(pool.in_flight(), 0);
+    }
+}
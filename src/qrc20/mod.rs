@@ -7,12 +7,16 @@ pub mod token;
 pub mod registry;
 pub mod bridge;
 pub mod evm_integration;
+pub mod evm_pool;
 pub mod rpc;
+pub mod auth;
 
-pub use token::{QRC20Token, QRC20Transaction, QRC20TokenInfo};
+pub use token::{HolderLimits, PendingRateLimits, QRC20Token, QRC20Transaction, QRC20TokenInfo, SupplyRateLimits};
 pub use registry::QRC20Registry;
 pub use bridge::ERC20Bridge;
-pub use evm_integration::{QoraNetEVM, EVMTransaction};
+pub use evm_integration::{QoraNetEVM, EVMSignature, EVMTransaction};
+pub use evm_pool::EVMExecutionPool;
+pub use auth::RequestAuthenticator;
 
 use primitive_types::{H160, U256};
 use serde::{Deserialize, Serialize};
@@ -43,6 +47,30 @@ pub enum QRC20Error {
     
     #[error("EVM execution failed: {reason}")]
     EVMExecutionFailed { reason: String },
+    
+    #[error("Unauthorized request: {reason}")]
+    Unauthorized { reason: String },
+    
+    #[error("Token is blocked by the bridge: {token}")]
+    TokenBlocked { token: String },
+    
+    #[error("Allowance expired at {expiry}")]
+    AllowanceExpired { expiry: u64 },
+    
+    #[error("Bridge accounting error: {reason}")]
+    BridgeAccounting { reason: String },
+    
+    #[error("Holder limit reached: {reason}")]
+    HolderLimit { reason: String },
+    
+    #[error("Rate limit exceeded: {reason}")]
+    RateLimitExceeded { reason: String },
+    
+    #[error("QoraNet burn at height {burn_height} is not finalized yet")]
+    BurnNotFinalized { burn_height: u64 },
+    
+    #[error("Deposit has {confirmations} confirmations, {required} required")]
+    InsufficientConfirmations { confirmations: u64, required: u64 },
 }
 
 /// Result type for QRC-20 operations
@@ -102,4 +130,17 @@ pub enum QRC20Event {
         old_owner: H160,
         new_owner: H160,
     },
+
+    /// Two-step ownership transfer started
+    OwnershipTransferStarted {
+        contract: H160,
+        owner: H160,
+        pending_owner: H160,
+    },
+
+    /// Pending ownership transfer cancelled
+    OwnershipTransferCancelled {
+        contract: H160,
+        pending_owner: H160,
+    },
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use primitive_types::{H160, U256};
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
 use super::{QRC20Token, QRC20Transaction, QRC20Error, QRC20Result, QRC20Event};
 
 /// QRC-20 Registry - manages all tokens on QoraNet
@@ -15,8 +17,9 @@ pub struct QRC20Registry {
     /// Token name to address mapping
     pub name_to_address: HashMap<String, H160>,
     
-    /// Next contract address counter
-    pub next_contract_id: u64,
+    /// Tokens deployed so far by each deployer, used as the address-derivation nonce
+    #[serde(default)]
+    pub deploy_nonces: HashMap<H160, u64>,
     
     /// Registry owner (can be governance contract later)
     pub registry_owner: H160,
@@ -29,7 +32,7 @@ impl QRC20Registry {
             tokens: HashMap::new(),
             symbol_to_address: HashMap::new(),
             name_to_address: HashMap::new(),
-            next_contract_id: 1000, // Start from 1000 to avoid conflicts
+            deploy_nonces: HashMap::new(),
             registry_owner: H160::zero(), // Set to governance later
         }
     }
@@ -86,9 +89,14 @@ impl QRC20Registry {
             });
         }
 
-        // Generate contract address
-        let contract_address = H160::from_low_u64_be(self.next_contract_id);
-        self.next_contract_id += 1;
+        // Derive contract address from the deployer's nonce, like CREATE
+        let nonce = self.deploy_nonce(deployer);
+        let contract_address = Self::contract_address(deployer, nonce, &name, &symbol, decimals, total_supply);
+        if self.tokens.contains_key(&contract_address) {
+            return Err(QRC20Error::EVMExecutionFailed {
+                reason: format!("Contract address {:?} already in use", contract_address)
+            });
+        }
 
         // Create token
         let mut token = if let Some(max_supply) = max_supply {
@@ -109,6 +117,7 @@ impl QRC20Registry {
         token.set_contract_address(contract_address);
 
         // Register token
+        self.deploy_nonces.insert(deployer, nonce + 1);
         self.tokens.insert(contract_address, token);
         self.symbol_to_address.insert(symbol, contract_address);
         self.name_to_address.insert(name, contract_address);
@@ -123,6 +132,37 @@ impl QRC20Registry {
         Ok(contract_address)
     }
 
+    /// Number of tokens `deployer` has deployed, i.e. the nonce its next deployment uses
+    pub fn deploy_nonce(&self, deployer: H160) -> u64 {
+        self.deploy_nonces.get(&deployer).copied().unwrap_or(0)
+    }
+
+    /// Derive a token contract address from the deployer, its deployment nonce and the
+    /// token parameters.
+    ///
+    /// The address is the last 20 bytes of the Keccak-256 hash of their RLP encoding,
+    /// so replaying the same deployment against the same registry state always yields
+    /// the same address, independent of deployments by other accounts.
+    pub fn contract_address(
+        deployer: H160,
+        nonce: u64,
+        name: &str,
+        symbol: &str,
+        decimals: u8,
+        total_supply: U256,
+    ) -> H160 {
+        let mut stream = RlpStream::new_list(6);
+        stream.append(&deployer);
+        stream.append(&nonce);
+        stream.append(&name);
+        stream.append(&symbol);
+        stream.append(&decimals);
+        stream.append(&total_supply);
+
+        let hash = Keccak256::digest(&stream.out());
+        H160::from_slice(&hash[12..])
+    }
+
     /// Execute QRC-20 transaction
     pub fn execute_transaction(
         &mut self,
@@ -206,6 +246,24 @@ impl QRC20Registry {
                     .ok_or(QRC20Error::TokenNotFound)?;
                 token.transfer_ownership(caller, new_owner)
             }
+
+            QRC20Transaction::BeginOwnershipTransfer { contract, pending_owner } => {
+                let token = self.tokens.get_mut(&contract)
+                    .ok_or(QRC20Error::TokenNotFound)?;
+                token.begin_transfer_ownership(caller, pending_owner)
+            }
+
+            QRC20Transaction::AcceptOwnership { contract } => {
+                let token = self.tokens.get_mut(&contract)
+                    .ok_or(QRC20Error::TokenNotFound)?;
+                token.accept_ownership(caller)
+            }
+
+            QRC20Transaction::CancelOwnershipTransfer { contract } => {
+                let token = self.tokens.get_mut(&contract)
+                    .ok_or(QRC20Error::TokenNotFound)?;
+                token.cancel_ownership_transfer(caller)
+            }
         }
     }
 
@@ -333,7 +391,7 @@ mod tests {
     fn test_registry_creation() {
         let registry = QRC20Registry::new();
         assert_eq!(registry.token_count(), 0);
-        assert_eq!(registry.next_contract_id, 1000);
+        assert!(registry.deploy_nonces.is_empty());
     }
 
     #[test]
@@ -513,4 +571,57 @@ mod tests {
         assert_eq!(owner1_tokens.len(), 2);
         assert_eq!(owner2_tokens.len(), 1);
     }
+
+    #[test]
+    fn test_replayed_deployment_gets_same_address() {
+        let alice = H160::from_low_u64_be(1);
+        let bob = H160::from_low_u64_be(2);
+        let snapshot = QRC20Registry::new();
+
+        let mut original = snapshot.clone();
+        let alice_token = original.deploy_token(
+            alice, "Alice Token".to_string(), "ALC".to_string(), 18, U256::from(1000),
+        ).unwrap();
+        let bob_token = original.deploy_token(
+            bob, "Bob Token".to_string(), "BOB".to_string(), 18, U256::from(2000),
+        ).unwrap();
+
+        // Reorg: roll back to the snapshot and replay the same deployments in a different order
+        let mut replayed = snapshot.clone();
+        let bob_replayed = replayed.deploy_token(
+            bob, "Bob Token".to_string(), "BOB".to_string(), 18, U256::from(2000),
+        ).unwrap();
+        let alice_replayed = replayed.deploy_token(
+            alice, "Alice Token".to_string(), "ALC".to_string(), 18, U256::from(1000),
+        ).unwrap();
+
+        assert_eq!(alice_replayed, alice_token);
+        assert_eq!(bob_replayed, bob_token);
+        assert_eq!(
+            alice_token,
+            QRC20Registry::contract_address(alice, 0, "Alice Token", "ALC", 18, U256::from(1000))
+        );
+    }
+
+    #[test]
+    fn test_deployer_nonce_keeps_addresses_unique() {
+        let mut registry = QRC20Registry::new();
+        let deployer = H160::from_low_u64_be(1);
+
+        let first = registry.deploy_token(
+            deployer, "Token 1".to_string(), "TK1".to_string(), 18, U256::from(1000),
+        ).unwrap();
+        let second = registry.deploy_token(
+            deployer, "Token 2".to_string(), "TK2".to_string(), 18, U256::from(1000),
+        ).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(registry.deploy_nonce(deployer), 2);
+
+        // Same parameters at a different nonce still derive a different address
+        assert_ne!(
+            QRC20Registry::contract_address(deployer, 0, "Token 1", "TK1", 18, U256::from(1000)),
+            QRC20Registry::contract_address(deployer, 1, "Token 1", "TK1", 18, U256::from(1000)),
+        );
+    }
 }
@@ -1,7 +1,7 @@
 /// JSON-RPC methods for QRC-20 integration
 use serde_json::{Value, json};
 use primitive_types::{H160, H256, U256};
-use super::{QRC20Transaction, QRC20Error};
+use super::{QRC20Transaction, QRC20Error, RequestAuthenticator};
 
 /// QRC-20 RPC handler
 pub struct QRC20RpcHandler;
@@ -160,12 +160,13 @@ impl QRC20RpcHandler {
         }
     }
 
-    /// Mint new tokens (only token owner)
+    /// Mint new tokens (only token owner, signed request)
     pub fn qrc20_mint(
         blockchain: &mut crate::QoraNet,
+        auth: &mut RequestAuthenticator,
         params: Value,
     ) -> Result<Value, String> {
-        let caller = parse_address(&params["from"])?;
+        let caller = authenticate(auth, "qrc20_mint", &params)?;
         let contract = parse_address(&params["contract"])?;
         let to = parse_address(&params["to"])?;
         let amount = parse_u256(&params["amount"])?;
@@ -191,12 +192,13 @@ impl QRC20RpcHandler {
         }
     }
 
-    /// Burn tokens
+    /// Burn tokens (signed request)
     pub fn qrc20_burn(
         blockchain: &mut crate::QoraNet,
+        auth: &mut RequestAuthenticator,
         params: Value,
     ) -> Result<Value, String> {
-        let caller = parse_address(&params["from"])?;
+        let caller = authenticate(auth, "qrc20_burn", &params)?;
         let contract = parse_address(&params["contract"])?;
         let amount = parse_u256(&params["amount"])?;
 
@@ -221,6 +223,90 @@ impl QRC20RpcHandler {
         }
     }
 
+    /// Pause token transfers (only token owner, signed request)
+    pub fn qrc20_pause(
+        blockchain: &mut crate::QoraNet,
+        auth: &mut RequestAuthenticator,
+        params: Value,
+    ) -> Result<Value, String> {
+        let caller = authenticate(auth, "qrc20_pause", &params)?;
+        let contract = parse_address(&params["contract"])?;
+
+        let transaction = QRC20Transaction::Pause { contract };
+        Self::set_pause_status(blockchain, caller, transaction, &params)
+    }
+
+    /// Unpause token transfers (only token owner, signed request)
+    pub fn qrc20_unpause(
+        blockchain: &mut crate::QoraNet,
+        auth: &mut RequestAuthenticator,
+        params: Value,
+    ) -> Result<Value, String> {
+        let caller = authenticate(auth, "qrc20_unpause", &params)?;
+        let contract = parse_address(&params["contract"])?;
+
+        let transaction = QRC20Transaction::Unpause { contract };
+        Self::set_pause_status(blockchain, caller, transaction, &params)
+    }
+
+    fn set_pause_status(
+        blockchain: &mut crate::QoraNet,
+        caller: H160,
+        transaction: QRC20Transaction,
+        params: &Value,
+    ) -> Result<Value, String> {
+        let gas_limit = params.get("gasLimit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(30_000);
+
+        let event = blockchain.process_qrc20_transaction(caller, transaction, gas_limit)?;
+
+        match event {
+            crate::QRC20Event::PauseStatusChanged { contract, paused } => {
+                Ok(json!({
+                    "transactionHash": format!("0x{:x}", H256::random()),
+                    "status": "success",
+                    "gasUsed": gas_limit,
+                    "contractAddress": format!("0x{:x}", contract),
+                    "paused": paused
+                }))
+            }
+            _ => Err("Unexpected event type".to_string()),
+        }
+    }
+
+    /// Transfer token ownership (only token owner, signed request)
+    pub fn qrc20_transfer_ownership(
+        blockchain: &mut crate::QoraNet,
+        auth: &mut RequestAuthenticator,
+        params: Value,
+    ) -> Result<Value, String> {
+        let caller = authenticate(auth, "qrc20_transfer_ownership", &params)?;
+        let contract = parse_address(&params["contract"])?;
+        let new_owner = parse_address(&params["newOwner"])?;
+
+        let transaction = QRC20Transaction::TransferOwnership { contract, new_owner };
+        let gas_limit = params.get("gasLimit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(30_000);
+
+        let event = blockchain.process_qrc20_transaction(caller, transaction, gas_limit)?;
+
+        match event {
+            crate::QRC20Event::OwnershipTransferred { contract, old_owner, new_owner } => {
+                Ok(json!({
+                    "transactionHash": format!("0x{:x}", H256::random()),
+                    "status": "success",
+                    "gasUsed": gas_limit,
+                    "contractAddress": format!("0x{:x}", contract),
+                    "oldOwner": format!("0x{:x}", old_owner),
+                    "newOwner": format!("0x{:x}", new_owner)
+                }))
+            }
+            _ => Err("Unexpected event type".to_string()),
+        }
+    }
+
     /// Get QRC-20 balance
     pub fn qrc20_balance(
         blockchain: &crate::QoraNet,
@@ -481,6 +567,12 @@ impl QRC20RpcHandler {
 
 // Helper functions
 
+/// Verify a privileged request's signature and return the authenticated caller
+fn authenticate(auth: &mut RequestAuthenticator, method: &str, params: &Value) -> Result<H160, String> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    auth.authenticate(method, params, now).map_err(|e| e.to_string())
+}
+
 /// Parse address from JSON value
 fn parse_address(value: &Value) -> Result<H160, String> {
     let addr_str = value.as_str()
@@ -527,22 +619,7 @@ fn parse_u256(value: &Value) -> Result<U256, String> {
 
 /// Format balance with proper decimals
 fn format_balance(balance: U256, decimals: u8) -> String {
-    let divisor = U256::from(10).pow(U256::from(decimals));
-    let integer_part = balance / divisor;
-    let fractional_part = balance % divisor;
-    
-    if fractional_part.is_zero() {
-        integer_part.to_string()
-    } else {
-        let frac_str = format!("{:0width$}", fractional_part, width = decimals as usize);
-        let trimmed = frac_str.trim_end_matches('0');
-        
-        if trimmed.is_empty() {
-            integer_part.to_string()
-        } else {
-            format!("{}.{}", integer_part, trimmed)
-        }
-    }
+    crate::wallet::format_token_balance(balance, decimals)
 }
 
 #[cfg(test)]
@@ -3,6 +3,63 @@ use std::collections::HashMap;
 use primitive_types::{H160, U256};
 use super::{QRC20Error, QRC20Result, QRC20Event};
 
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Bounds on how many holders transfers may create
+///
+/// Every holder is an entry in the serialized token, so unbounded 1-wei
+/// transfers to fresh addresses would bloat it. Minting is not limited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HolderLimits {
+    /// Most addresses holding a non-zero balance
+    pub max_holders: Option<usize>,
+    /// Smallest transfer that may create a new holder
+    pub min_new_holder_amount: U256,
+}
+
+/// Delay before loosened supply rate limits take effect (2 days)
+pub const RATE_LIMIT_INCREASE_DELAY_SECS: u64 = 2 * 24 * 60 * 60;
+
+/// Caps on how much supply may change within a rolling window
+///
+/// Bounds the damage a compromised owner key can do before anyone reacts.
+/// Zero caps mean unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplyRateLimits {
+    /// Most that may be minted per window
+    pub max_mint_per_window: U256,
+    /// Most that may be burned per window
+    pub max_burn_per_window: U256,
+    /// Window length in seconds
+    pub window_secs: u64,
+}
+
+impl SupplyRateLimits {
+    /// Whether moving to `proposed` allows more supply change per window
+    fn is_loosened_by(&self, proposed: &SupplyRateLimits) -> bool {
+        let cap_raised = |current: U256, proposed: U256| {
+            !current.is_zero() && (proposed.is_zero() || proposed > current)
+        };
+        let capped = !self.max_mint_per_window.is_zero() || !self.max_burn_per_window.is_zero();
+        cap_raised(self.max_mint_per_window, proposed.max_mint_per_window)
+            || cap_raised(self.max_burn_per_window, proposed.max_burn_per_window)
+            || (capped && proposed.window_secs < self.window_secs)
+    }
+}
+
+/// Rate limits scheduled to replace the current ones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingRateLimits {
+    pub limits: SupplyRateLimits,
+    /// Unix seconds from which the new limits apply
+    pub effective_at: u64,
+}
+
 /// QRC-20 Token Standard - ERC-20 compatible on QoraNet
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QRC20Token {
@@ -20,9 +77,17 @@ pub struct QRC20Token {
     
     /// Allowance mapping: owner => spender => amount
     pub allowances: HashMap<H160, HashMap<H160, U256>>,
+
+    /// Expiry (unix seconds) of time-bounded allowances: owner => spender => expiry
+    #[serde(default)]
+    pub allowance_expiries: HashMap<H160, HashMap<H160, u64>>,
     
     /// Owner of the contract
     pub owner: H160,
+
+    /// Owner nominated by a two-step transfer, until they accept
+    #[serde(default)]
+    pub pending_owner: Option<H160>,
     
     /// Whether the token is paused
     pub paused: bool,
@@ -35,6 +100,26 @@ pub struct QRC20Token {
     
     /// Whether the token is burnable
     pub burnable: bool,
+
+    /// Limits on holders created by transfers
+    #[serde(default)]
+    pub holder_limits: HolderLimits,
+
+    /// Limits on minting and burning per rolling window
+    #[serde(default)]
+    pub rate_limits: SupplyRateLimits,
+
+    /// Loosened limits waiting out the timelock
+    #[serde(default)]
+    pub pending_rate_limits: Option<PendingRateLimits>,
+
+    /// Mints within the current window, as (unix seconds, amount)
+    #[serde(default)]
+    pub recent_mints: Vec<(u64, U256)>,
+
+    /// Burns within the current window, as (unix seconds, amount)
+    #[serde(default)]
+    pub recent_burns: Vec<(u64, U256)>,
 }
 
 impl QRC20Token {
@@ -47,7 +132,9 @@ impl QRC20Token {
         owner: H160,
     ) -> Self {
         let mut balances = HashMap::new();
-        balances.insert(owner, total_supply);
+        if !total_supply.is_zero() {
+            balances.insert(owner, total_supply);
+        }
 
         Self {
             name,
@@ -57,11 +144,18 @@ impl QRC20Token {
             contract_address: H160::zero(), // Set during deployment
             balances,
             allowances: HashMap::new(),
+            allowance_expiries: HashMap::new(),
             owner,
+            pending_owner: None,
             paused: false,
             max_supply: U256::zero(), // No limit by default
             mintable: true,
             burnable: true,
+            holder_limits: HolderLimits::default(),
+            rate_limits: SupplyRateLimits::default(),
+            pending_rate_limits: None,
+            recent_mints: Vec::new(),
+            recent_burns: Vec::new(),
         }
     }
 
@@ -77,7 +171,9 @@ impl QRC20Token {
         burnable: bool,
     ) -> Self {
         let mut balances = HashMap::new();
-        balances.insert(owner, total_supply);
+        if !total_supply.is_zero() {
+            balances.insert(owner, total_supply);
+        }
 
         Self {
             name,
@@ -87,12 +183,154 @@ impl QRC20Token {
             contract_address: H160::zero(),
             balances,
             allowances: HashMap::new(),
+            allowance_expiries: HashMap::new(),
             owner,
+            pending_owner: None,
             paused: false,
             max_supply,
             mintable,
             burnable,
+            holder_limits: HolderLimits::default(),
+            rate_limits: SupplyRateLimits::default(),
+            pending_rate_limits: None,
+            recent_mints: Vec::new(),
+            recent_burns: Vec::new(),
+        }
+    }
+
+    /// Limit holders created by transfers
+    pub fn with_holder_limits(mut self, holder_limits: HolderLimits) -> Self {
+        self.holder_limits = holder_limits;
+        self
+    }
+
+    /// Limit minting and burning per window, set at deploy time
+    pub fn with_rate_limits(mut self, rate_limits: SupplyRateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Change the rate limits (only owner).
+    ///
+    /// Tightening applies immediately. Anything that allows more supply change
+    /// is scheduled and applies `RATE_LIMIT_INCREASE_DELAY_SECS` after `now`.
+    pub fn set_rate_limits_at(&mut self, caller: H160, limits: SupplyRateLimits, now: u64) -> QRC20Result<()> {
+        if caller != self.owner {
+            return Err(QRC20Error::OnlyOwner);
+        }
+
+        if self.rate_limits.is_loosened_by(&limits) {
+            self.pending_rate_limits = Some(PendingRateLimits {
+                limits,
+                effective_at: now + RATE_LIMIT_INCREASE_DELAY_SECS,
+            });
+        } else {
+            self.rate_limits = limits;
+            self.pending_rate_limits = None;
+        }
+        Ok(())
+    }
+
+    /// Swap in scheduled limits once their timelock has passed
+    fn apply_pending_rate_limits(&mut self, now: u64) {
+        if self.pending_rate_limits.as_ref().map_or(false, |pending| now >= pending.effective_at) {
+            self.rate_limits = self.pending_rate_limits.take().unwrap().limits;
+        }
+    }
+
+    /// Check `amount` fits under `cap` within the window ending at `now`, and record it
+    fn consume_rate_limit(
+        history: &mut Vec<(u64, U256)>,
+        cap: U256,
+        window_secs: u64,
+        amount: U256,
+        now: u64,
+        action: &str,
+    ) -> QRC20Result<()> {
+        history.retain(|(at, _)| at.saturating_add(window_secs) > now);
+        if cap.is_zero() {
+            history.clear();
+            return Ok(());
+        }
+
+        let used = history.iter().fold(U256::zero(), |total, (_, amount)| total.saturating_add(*amount));
+        if used.saturating_add(amount) > cap {
+            return Err(QRC20Error::RateLimitExceeded {
+                reason: format!(
+                    "{} of {} would exceed {} per {}s ({} already used)",
+                    action, amount, cap, window_secs, used
+                ),
+            });
+        }
+        history.push((now, amount));
+        Ok(())
+    }
+
+    /// Number of addresses with a non-zero balance
+    pub fn holder_count(&self) -> usize {
+        self.balances.len()
+    }
+
+    /// Store a balance, dropping the entry when it reaches zero
+    fn set_balance(&mut self, account: H160, balance: U256) {
+        if balance.is_zero() {
+            self.balances.remove(&account);
+        } else {
+            self.balances.insert(account, balance);
+        }
+    }
+
+    /// Store an allowance, dropping zeroed entries (and their expiry)
+    fn set_allowance(&mut self, owner: H160, spender: H160, amount: U256) {
+        if !amount.is_zero() {
+            self.allowances.entry(owner).or_default().insert(spender, amount);
+            return;
+        }
+
+        if let Some(allowances) = self.allowances.get_mut(&owner) {
+            allowances.remove(&spender);
+            if allowances.is_empty() {
+                self.allowances.remove(&owner);
+            }
+        }
+        if let Some(expiries) = self.allowance_expiries.get_mut(&owner) {
+            expiries.remove(&spender);
+            if expiries.is_empty() {
+                self.allowance_expiries.remove(&owner);
+            }
+        }
+    }
+
+    /// Check a transfer of `amount` to `to` respects the holder limits
+    fn check_new_holder(&self, to: H160, amount: U256) -> QRC20Result<()> {
+        if amount.is_zero() || self.balances.contains_key(&to) {
+            return Ok(());
+        }
+
+        if amount < self.holder_limits.min_new_holder_amount {
+            return Err(QRC20Error::HolderLimit {
+                reason: format!(
+                    "transfer of {} to a new holder is below the minimum {}",
+                    amount, self.holder_limits.min_new_holder_amount
+                ),
+            });
         }
+        if let Some(max_holders) = self.holder_limits.max_holders {
+            if self.balances.len() >= max_holders {
+                return Err(QRC20Error::HolderLimit {
+                    reason: format!("token already has {} holders", max_holders),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Move `amount` between balances; checks must already have passed
+    fn move_balance(&mut self, from: H160, to: H160, amount: U256) {
+        let from_balance = self.balance_of(from);
+        self.set_balance(from, from_balance - amount);
+        let to_balance = self.balance_of(to);
+        self.set_balance(to, to_balance + amount);
     }
 
     /// Get balance of an address
@@ -114,10 +352,13 @@ impl QRC20Token {
             });
         }
 
+        // A self-transfer never creates a holder
+        if from != to {
+            self.check_new_holder(to, amount)?;
+        }
+
         // Update balances
-        self.balances.insert(from, from_balance - amount);
-        let to_balance = self.balance_of(to);
-        self.balances.insert(to, to_balance + amount);
+        self.move_balance(from, to, amount);
 
         Ok(QRC20Event::Transfer {
             contract: self.contract_address,
@@ -133,10 +374,15 @@ impl QRC20Token {
             return Err(QRC20Error::TokenPaused);
         }
 
-        self.allowances
-            .entry(owner)
-            .or_insert_with(HashMap::new)
-            .insert(spender, amount);
+        self.set_allowance(owner, spender, amount);
+
+        // Plain approvals never expire
+        if let Some(expiries) = self.allowance_expiries.get_mut(&owner) {
+            expiries.remove(&spender);
+            if expiries.is_empty() {
+                self.allowance_expiries.remove(&owner);
+            }
+        }
 
         Ok(QRC20Event::Approval {
             contract: self.contract_address,
@@ -146,6 +392,27 @@ impl QRC20Token {
         })
     }
 
+    /// Approve spender until `expiry` (unix seconds); the allowance reads zero from then on
+    pub fn approve_until(&mut self, owner: H160, spender: H160, amount: U256, expiry: u64) -> QRC20Result<QRC20Event> {
+        let event = self.approve(owner, spender, amount)?;
+        // A zero approval leaves nothing to expire
+        if !amount.is_zero() {
+            self.allowance_expiries
+                .entry(owner)
+                .or_default()
+                .insert(spender, expiry);
+        }
+        Ok(event)
+    }
+
+    /// Expiry of a time-bounded allowance, if any
+    pub fn allowance_expiry(&self, owner: H160, spender: H160) -> Option<u64> {
+        self.allowance_expiries
+            .get(&owner)
+            .and_then(|expiries| expiries.get(&spender))
+            .copied()
+    }
+
     /// Transfer tokens from one address to another (requires allowance)
     pub fn transfer_from(
         &mut self,
@@ -153,13 +420,29 @@ impl QRC20Token {
         from: H160,
         to: H160,
         amount: U256,
+    ) -> QRC20Result<QRC20Event> {
+        self.transfer_from_at(spender, from, to, amount, unix_now())
+    }
+
+    /// `transfer_from` with allowance expiry checked against `now` (unix seconds)
+    pub fn transfer_from_at(
+        &mut self,
+        spender: H160,
+        from: H160,
+        to: H160,
+        amount: U256,
+        now: u64,
     ) -> QRC20Result<QRC20Event> {
         if self.paused {
             return Err(QRC20Error::TokenPaused);
         }
 
+        if let Some(expiry) = self.allowance_expiry(from, spender).filter(|expiry| now >= *expiry) {
+            return Err(QRC20Error::AllowanceExpired { expiry });
+        }
+
         // Check allowance
-        let allowance = self.allowance(from, spender);
+        let allowance = self.allowance_at(from, spender, now);
         if allowance < amount {
             return Err(QRC20Error::InsufficientAllowance { 
                 required: amount, 
@@ -176,16 +459,15 @@ impl QRC20Token {
             });
         }
 
+        if from != to {
+            self.check_new_holder(to, amount)?;
+        }
+
         // Update allowance
-        self.allowances
-            .get_mut(&from)
-            .unwrap()
-            .insert(spender, allowance - amount);
+        self.set_allowance(from, spender, allowance - amount);
 
         // Update balances
-        self.balances.insert(from, from_balance - amount);
-        let to_balance = self.balance_of(to);
-        self.balances.insert(to, to_balance + amount);
+        self.move_balance(from, to, amount);
 
         Ok(QRC20Event::Transfer {
             contract: self.contract_address,
@@ -197,6 +479,15 @@ impl QRC20Token {
 
     /// Get allowance amount
     pub fn allowance(&self, owner: H160, spender: H160) -> U256 {
+        self.allowance_at(owner, spender, unix_now())
+    }
+
+    /// Allowance amount as of `now` (unix seconds); zero once a time-bounded approval expires
+    pub fn allowance_at(&self, owner: H160, spender: H160, now: u64) -> U256 {
+        if self.allowance_expiry(owner, spender).map_or(false, |expiry| now >= expiry) {
+            return U256::zero();
+        }
+
         self.allowances
             .get(&owner)
             .and_then(|allowances| allowances.get(&spender))
@@ -206,6 +497,11 @@ impl QRC20Token {
 
     /// Mint new tokens (only owner)
     pub fn mint(&mut self, caller: H160, to: H160, amount: U256) -> QRC20Result<QRC20Event> {
+        self.mint_at(caller, to, amount, unix_now())
+    }
+
+    /// `mint` with the rate limit window ending at `now` (unix seconds)
+    pub fn mint_at(&mut self, caller: H160, to: H160, amount: U256, now: u64) -> QRC20Result<QRC20Event> {
         if caller != self.owner {
             return Err(QRC20Error::OnlyOwner);
         }
@@ -227,8 +523,18 @@ impl QRC20Token {
             });
         }
 
+        self.apply_pending_rate_limits(now);
+        Self::consume_rate_limit(
+            &mut self.recent_mints,
+            self.rate_limits.max_mint_per_window,
+            self.rate_limits.window_secs,
+            amount,
+            now,
+            "mint",
+        )?;
+
         let to_balance = self.balance_of(to);
-        self.balances.insert(to, to_balance + amount);
+        self.set_balance(to, to_balance + amount);
         self.total_supply += amount;
 
         Ok(QRC20Event::Mint {
@@ -240,6 +546,11 @@ impl QRC20Token {
 
     /// Burn tokens
     pub fn burn(&mut self, from: H160, amount: U256) -> QRC20Result<QRC20Event> {
+        self.burn_at(from, amount, unix_now())
+    }
+
+    /// `burn` with the rate limit window ending at `now` (unix seconds)
+    pub fn burn_at(&mut self, from: H160, amount: U256, now: u64) -> QRC20Result<QRC20Event> {
         if !self.burnable {
             return Err(QRC20Error::EVMExecutionFailed { 
                 reason: "Token is not burnable".to_string() 
@@ -258,7 +569,17 @@ impl QRC20Token {
             });
         }
 
-        self.balances.insert(from, from_balance - amount);
+        self.apply_pending_rate_limits(now);
+        Self::consume_rate_limit(
+            &mut self.recent_burns,
+            self.rate_limits.max_burn_per_window,
+            self.rate_limits.window_secs,
+            amount,
+            now,
+            "burn",
+        )?;
+
+        self.set_balance(from, from_balance - amount);
         self.total_supply -= amount;
 
         Ok(QRC20Event::Burn {
@@ -302,6 +623,7 @@ impl QRC20Token {
 
         let old_owner = self.owner;
         self.owner = new_owner;
+        self.pending_owner = None;
         
         Ok(QRC20Event::OwnershipTransferred {
             contract: self.contract_address,
@@ -310,6 +632,55 @@ impl QRC20Token {
         })
     }
 
+    /// Nominate a new owner; ownership moves only once they accept (only owner)
+    pub fn begin_transfer_ownership(&mut self, caller: H160, pending_owner: H160) -> QRC20Result<QRC20Event> {
+        if caller != self.owner {
+            return Err(QRC20Error::OnlyOwner);
+        }
+
+        self.pending_owner = Some(pending_owner);
+        Ok(QRC20Event::OwnershipTransferStarted {
+            contract: self.contract_address,
+            owner: self.owner,
+            pending_owner,
+        })
+    }
+
+    /// Complete a two-step transfer (only the pending owner)
+    pub fn accept_ownership(&mut self, caller: H160) -> QRC20Result<QRC20Event> {
+        if self.pending_owner != Some(caller) {
+            return Err(QRC20Error::Unauthorized {
+                reason: "caller is not the pending owner".to_string(),
+            });
+        }
+
+        let old_owner = self.owner;
+        self.owner = caller;
+        self.pending_owner = None;
+
+        Ok(QRC20Event::OwnershipTransferred {
+            contract: self.contract_address,
+            old_owner,
+            new_owner: caller,
+        })
+    }
+
+    /// Cancel a pending two-step transfer (only owner)
+    pub fn cancel_ownership_transfer(&mut self, caller: H160) -> QRC20Result<QRC20Event> {
+        if caller != self.owner {
+            return Err(QRC20Error::OnlyOwner);
+        }
+
+        let pending_owner = self.pending_owner.take().ok_or_else(|| QRC20Error::Unauthorized {
+            reason: "no ownership transfer pending".to_string(),
+        })?;
+
+        Ok(QRC20Event::OwnershipTransferCancelled {
+            contract: self.contract_address,
+            pending_owner,
+        })
+    }
+
     /// Set contract address (only called during deployment)
     pub fn set_contract_address(&mut self, address: H160) {
         self.contract_address = address;
@@ -324,6 +695,7 @@ impl QRC20Token {
             total_supply: self.total_supply,
             contract_address: self.contract_address,
             owner: self.owner,
+            pending_owner: self.pending_owner,
             paused: self.paused,
             max_supply: self.max_supply,
             mintable: self.mintable,
@@ -379,6 +751,16 @@ pub enum QRC20Transaction {
         contract: H160,
         new_owner: H160,
     },
+    BeginOwnershipTransfer {
+        contract: H160,
+        pending_owner: H160,
+    },
+    AcceptOwnership {
+        contract: H160,
+    },
+    CancelOwnershipTransfer {
+        contract: H160,
+    },
 }
 
 /// QRC-20 token information for external queries
@@ -390,6 +772,7 @@ pub struct QRC20TokenInfo {
     pub total_supply: U256,
     pub contract_address: H160,
     pub owner: H160,
+    pub pending_owner: Option<H160>,
     pub paused: bool,
     pub max_supply: U256,
     pub mintable: bool,
@@ -535,4 +918,221 @@ mod tests {
         let result = token.transfer(owner, recipient, U256::from(100));
         assert!(result.is_ok());
     }
+
+    fn owned_token() -> (QRC20Token, H160, H160) {
+        let owner = H160::from_low_u64_be(1);
+        let multisig = H160::from_low_u64_be(2);
+        let token = QRC20Token::new(
+            "Test Token".to_string(),
+            "TEST".to_string(),
+            18,
+            U256::from(1000),
+            owner,
+        );
+        (token, owner, multisig)
+    }
+
+    #[test]
+    fn test_two_step_ownership_accepted_by_pending_owner() {
+        let (mut token, owner, multisig) = owned_token();
+
+        token.begin_transfer_ownership(owner, multisig).unwrap();
+        assert_eq!(token.owner, owner);
+        assert_eq!(token.pending_owner, Some(multisig));
+
+        let event = token.accept_ownership(multisig).unwrap();
+        assert!(matches!(
+            event,
+            QRC20Event::OwnershipTransferred { old_owner, new_owner, .. } if old_owner == owner && new_owner == multisig
+        ));
+        assert_eq!(token.owner, multisig);
+        assert_eq!(token.pending_owner, None);
+    }
+
+    #[test]
+    fn test_accept_ownership_by_other_rejected() {
+        let (mut token, owner, multisig) = owned_token();
+        let attacker = H160::from_low_u64_be(3);
+
+        // Nothing pending yet
+        assert!(token.accept_ownership(multisig).is_err());
+        // Only the owner can nominate
+        assert!(matches!(token.begin_transfer_ownership(attacker, attacker), Err(QRC20Error::OnlyOwner)));
+
+        token.begin_transfer_ownership(owner, multisig).unwrap();
+        assert!(matches!(token.accept_ownership(attacker), Err(QRC20Error::Unauthorized { .. })));
+        assert!(token.accept_ownership(owner).is_err());
+        assert_eq!(token.owner, owner);
+        assert_eq!(token.pending_owner, Some(multisig));
+    }
+
+    #[test]
+    fn test_cancel_pending_ownership_transfer() {
+        let (mut token, owner, multisig) = owned_token();
+
+        token.begin_transfer_ownership(owner, multisig).unwrap();
+        assert!(matches!(token.cancel_ownership_transfer(multisig), Err(QRC20Error::OnlyOwner)));
+
+        token.cancel_ownership_transfer(owner).unwrap();
+        assert_eq!(token.pending_owner, None);
+        assert!(token.accept_ownership(multisig).is_err());
+        assert_eq!(token.owner, owner);
+        assert!(token.cancel_ownership_transfer(owner).is_err());
+    }
+
+    #[test]
+    fn test_time_bounded_allowance() {
+        let owner = H160::from_low_u64_be(1);
+        let spender = H160::from_low_u64_be(2);
+        let recipient = H160::from_low_u64_be(3);
+        let mut token = QRC20Token::new(
+            "Test Token".to_string(), "TEST".to_string(), 18, U256::from(1000), owner,
+        );
+
+        token.approve_until(owner, spender, U256::from(200), 1_000).unwrap();
+        assert_eq!(token.allowance_at(owner, spender, 999), U256::from(200));
+
+        // Before expiry
+        token.transfer_from_at(spender, owner, recipient, U256::from(50), 999).unwrap();
+        assert_eq!(token.balance_of(recipient), U256::from(50));
+        assert_eq!(token.allowance_at(owner, spender, 999), U256::from(150));
+
+        // At and after expiry
+        assert_eq!(token.allowance_at(owner, spender, 1_000), U256::zero());
+        let result = token.transfer_from_at(spender, owner, recipient, U256::from(50), 1_001);
+        assert!(matches!(result, Err(QRC20Error::AllowanceExpired { expiry: 1_000 })));
+        assert_eq!(token.balance_of(recipient), U256::from(50));
+
+        // A plain approve replaces it with a non-expiring allowance
+        token.approve(owner, spender, U256::from(10)).unwrap();
+        assert_eq!(token.allowance_at(owner, spender, u64::MAX), U256::from(10));
+    }
+
+    #[test]
+    fn test_zeroed_entries_pruned() {
+        let owner = H160::from_low_u64_be(1);
+        let spender = H160::from_low_u64_be(2);
+        let recipient = H160::from_low_u64_be(3);
+        let mut token = QRC20Token::new(
+            "Test Token".to_string(), "TEST".to_string(), 18, U256::from(1000), owner,
+        );
+
+        // Spending an allowance down to zero removes it
+        token.approve_until(owner, spender, U256::from(100), u64::MAX).unwrap();
+        token.transfer_from_at(spender, owner, recipient, U256::from(100), 0).unwrap();
+        assert!(token.allowances.is_empty());
+        assert!(token.allowance_expiries.is_empty());
+
+        // So does approving zero
+        token.approve(owner, spender, U256::from(5)).unwrap();
+        token.approve(owner, spender, U256::zero()).unwrap();
+        assert!(token.allowances.is_empty());
+
+        // Emptied balances are removed, not kept as zero
+        token.transfer(recipient, owner, U256::from(100)).unwrap();
+        assert!(!token.balances.contains_key(&recipient));
+        token.burn(owner, U256::from(1000)).unwrap();
+        assert!(token.balances.is_empty());
+        assert_eq!(token.holder_count(), 0);
+    }
+
+    #[test]
+    fn test_dust_spray_bounded() {
+        let owner = H160::from_low_u64_be(1);
+        let mut token = QRC20Token::new(
+            "Test Token".to_string(), "TEST".to_string(), 18, U256::from(1_000_000), owner,
+        ).with_holder_limits(HolderLimits {
+            max_holders: Some(10),
+            min_new_holder_amount: U256::from(100),
+        });
+
+        // 1-wei transfers can't create holders
+        let dust = token.transfer(owner, H160::from_low_u64_be(1_000), U256::one());
+        assert!(matches!(dust, Err(QRC20Error::HolderLimit { .. })));
+
+        // Larger transfers stop at the holder cap
+        let created = (2..100u64)
+            .filter(|i| token.transfer(owner, H160::from_low_u64_be(*i), U256::from(100)).is_ok())
+            .count();
+        assert_eq!(created, 9);
+        assert_eq!(token.holder_count(), 10);
+
+        // Existing holders can still receive dust
+        token.transfer(owner, H160::from_low_u64_be(2), U256::one()).unwrap();
+        assert_eq!(token.balance_of(H160::from_low_u64_be(2)), U256::from(101));
+    }
+
+    fn rate_limited_token(owner: H160) -> QRC20Token {
+        QRC20Token::new(
+            "Test Token".to_string(), "TEST".to_string(), 18, U256::from(10_000), owner,
+        ).with_rate_limits(SupplyRateLimits {
+            max_mint_per_window: U256::from(1_000),
+            max_burn_per_window: U256::from(500),
+            window_secs: 3_600,
+        })
+    }
+
+    #[test]
+    fn test_mint_rate_limit_window() {
+        let owner = H160::from_low_u64_be(1);
+        let mut token = rate_limited_token(owner);
+        let start = 1_000_000;
+
+        // Up to the limit within one window
+        token.mint_at(owner, owner, U256::from(600), start).unwrap();
+        token.mint_at(owner, owner, U256::from(400), start + 1_800).unwrap();
+
+        let excess = token.mint_at(owner, owner, U256::one(), start + 3_599);
+        assert!(matches!(excess, Err(QRC20Error::RateLimitExceeded { .. })));
+        assert_eq!(token.total_supply, U256::from(11_000));
+
+        // The first mint rolls out of the window, the second is still in it
+        token.mint_at(owner, owner, U256::from(600), start + 3_600).unwrap();
+        assert!(token.mint_at(owner, owner, U256::one(), start + 3_600).is_err());
+        token.mint_at(owner, owner, U256::from(1_000), start + 3_600 * 3).unwrap();
+    }
+
+    #[test]
+    fn test_burn_rate_limit() {
+        let owner = H160::from_low_u64_be(1);
+        let mut token = rate_limited_token(owner);
+
+        token.burn_at(owner, U256::from(500), 100).unwrap();
+        assert!(matches!(token.burn_at(owner, U256::one(), 200), Err(QRC20Error::RateLimitExceeded { .. })));
+        token.burn_at(owner, U256::from(500), 100 + 3_600).unwrap();
+        assert_eq!(token.total_supply, U256::from(9_000));
+    }
+
+    #[test]
+    fn test_unset_rate_limits_are_unlimited() {
+        let owner = H160::from_low_u64_be(1);
+        let mut token = QRC20Token::new(
+            "Test Token".to_string(), "TEST".to_string(), 18, U256::zero(), owner,
+        );
+        token.mint_at(owner, owner, U256::from(u64::MAX), 1).unwrap();
+        token.burn_at(owner, U256::from(u64::MAX), 1).unwrap();
+        assert!(token.recent_mints.is_empty());
+    }
+
+    #[test]
+    fn test_raising_rate_limits_is_timelocked() {
+        let owner = H160::from_low_u64_be(1);
+        let mut token = rate_limited_token(owner);
+        let now = 1_000_000;
+        let raised = SupplyRateLimits { max_mint_per_window: U256::from(1_000_000), ..token.rate_limits.clone() };
+
+        assert!(matches!(token.set_rate_limits_at(H160::from_low_u64_be(2), raised.clone(), now), Err(QRC20Error::OnlyOwner)));
+        token.set_rate_limits_at(owner, raised.clone(), now).unwrap();
+        assert_eq!(token.rate_limits.max_mint_per_window, U256::from(1_000));
+        assert!(token.mint_at(owner, owner, U256::from(5_000), now).is_err());
+
+        token.mint_at(owner, owner, U256::from(5_000), now + RATE_LIMIT_INCREASE_DELAY_SECS).unwrap();
+        assert_eq!(token.rate_limits, raised);
+        assert!(token.pending_rate_limits.is_none());
+
+        // Tightening takes effect at once
+        let tightened = SupplyRateLimits { max_mint_per_window: U256::from(10), ..raised };
+        token.set_rate_limits_at(owner, tightened.clone(), now + RATE_LIMIT_INCREASE_DELAY_SECS).unwrap();
+        assert_eq!(token.rate_limits, tightened);
+    }
 }
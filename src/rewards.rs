@@ -0,0 +1,479 @@
+//! Epoch reward distribution
+//!
+//! The app reward pool is split between hosted apps by smoothed performance
+//! score, and only the best-scoring apps up to a per-chain maximum take part.
+//! A per-validator cap keeps a validator hosting many apps from capturing the
+//! whole pool: anything above the cap is handed to the other validators' apps
+//! in proportion to their scores.
+//!
+//! The pool itself is funded from a share of each block's fees and minted
+//! reward. What an epoch collects becomes claimable once the next epoch
+//! starts, and claims can never take out more than was collected.
+
+use crate::{Address, QoraNetError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Resolution used when turning performance scores into integer weights
+pub const SCORE_SCALE: f64 = 1_000_000.0;
+
+/// Weight of a new metrics report in an app's smoothed score, in basis points
+pub const SCORE_SMOOTHING_BPS: u64 = 2_000;
+
+/// Basis points in 100%
+const MAX_CAP_BPS: u16 = 10_000;
+
+/// Default number of apps rewarded per epoch
+pub const DEFAULT_MAX_REWARDED_APPS: usize = 1_000;
+
+/// An app's claim on the epoch app reward pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppRewardShare {
+    pub validator: Address,
+    pub app_owner: Address,
+    pub app_id: String,
+    /// Smoothed performance score (see `smooth_score`)
+    pub score: f64,
+}
+
+/// Reward paid to an app for the epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppReward {
+    pub validator: Address,
+    pub app_owner: Address,
+    pub app_id: String,
+    pub amount: u64,
+}
+
+/// Most of the app reward pool any one validator's apps may receive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ValidatorRewardCap {
+    #[default]
+    Unlimited,
+    /// Fixed amount per epoch
+    Absolute(u64),
+    /// Share of the pool in basis points
+    PoolBps(u16),
+}
+
+impl ValidatorRewardCap {
+    /// Cap for a pool split between `validators` validators.
+    ///
+    /// A cap too tight for the pool to be paid out in full is raised to an
+    /// even split, so the whole pool is always distributed.
+    fn limit(&self, pool: u64, validators: usize) -> u64 {
+        let cap = match self {
+            ValidatorRewardCap::Unlimited => pool,
+            ValidatorRewardCap::Absolute(amount) => *amount,
+            ValidatorRewardCap::PoolBps(bps) => {
+                (pool as u128 * (*bps).min(MAX_CAP_BPS) as u128 / MAX_CAP_BPS as u128) as u64
+            }
+        };
+        let even_split = (pool as u128).div_ceil(validators.max(1) as u128) as u64;
+        cap.max(even_split)
+    }
+}
+
+/// Split `epoch_reward_pool` between apps by score, capping each validator's total.
+///
+/// Rewards are returned in the order of `apps`. Unless no app has a positive
+/// score, the amounts always sum to exactly `epoch_reward_pool`.
+pub fn compute_app_rewards(
+    epoch_reward_pool: u64,
+    apps: &[AppRewardShare],
+    cap: ValidatorRewardCap,
+) -> Vec<AppReward> {
+    let weights: Vec<u128> = apps.iter()
+        .map(|app| if app.score.is_finite() && app.score > 0.0 { (app.score * SCORE_SCALE).round() as u128 } else { 0 })
+        .collect();
+
+    // Ordered by address, so leftover units land on the same validators on every node
+    let mut validator_weights: BTreeMap<&Address, u128> = BTreeMap::new();
+    for (app, weight) in apps.iter().zip(&weights) {
+        if *weight > 0 {
+            *validator_weights.entry(&app.validator).or_insert(0) += weight;
+        }
+    }
+
+    let mut amounts = vec![0u64; apps.len()];
+    if validator_weights.is_empty() {
+        return to_rewards(apps, amounts);
+    }
+
+    let cap = cap.limit(epoch_reward_pool, validator_weights.len());
+    let validator_totals = split_with_cap(epoch_reward_pool, &validator_weights, cap);
+
+    // Split each validator's total between its own apps by score
+    for (validator, total) in validator_totals {
+        let members: Vec<(usize, u128)> = apps.iter().enumerate()
+            .filter(|(i, app)| &app.validator == validator && weights[*i] > 0)
+            .map(|(i, _)| (i, weights[i]))
+            .collect();
+        for (i, amount) in proportional_split(total, &members) {
+            amounts[i] = amount;
+        }
+    }
+
+    to_rewards(apps, amounts)
+}
+
+/// Like `compute_app_rewards`, but only the `max_apps` best-scoring apps share the pool.
+///
+/// This bounds the per-epoch reward work: beyond one linear selection pass,
+/// everything scales with `max_apps` rather than the number of hosted apps.
+/// Apps left out get a zero reward; ties are broken by validator, owner and
+/// app id so every node picks the same set.
+pub fn compute_top_app_rewards(
+    epoch_reward_pool: u64,
+    apps: &[AppRewardShare],
+    cap: ValidatorRewardCap,
+    max_apps: usize,
+) -> Vec<AppReward> {
+    let selected = select_top_apps(apps, max_apps);
+    let shares: Vec<AppRewardShare> = selected.iter().map(|i| apps[*i].clone()).collect();
+
+    let mut amounts = vec![0u64; apps.len()];
+    for (i, reward) in selected.into_iter().zip(compute_app_rewards(epoch_reward_pool, &shares, cap)) {
+        amounts[i] = reward.amount;
+    }
+    to_rewards(apps, amounts)
+}
+
+/// Indices of the `max_apps` highest-scoring apps, in input order
+fn select_top_apps(apps: &[AppRewardShare], max_apps: usize) -> Vec<usize> {
+    let score = |app: &AppRewardShare| if app.score.is_finite() { app.score.max(0.0) } else { 0.0 };
+    let rank = |a: &usize, b: &usize| {
+        let (a, b) = (&apps[*a], &apps[*b]);
+        score(b).total_cmp(&score(a))
+            .then_with(|| a.validator.0.cmp(&b.validator.0))
+            .then_with(|| a.app_owner.0.cmp(&b.app_owner.0))
+            .then_with(|| a.app_id.cmp(&b.app_id))
+    };
+
+    let mut indices: Vec<usize> = (0..apps.len()).collect();
+    if max_apps < indices.len() {
+        if max_apps == 0 {
+            return Vec::new();
+        }
+        indices.select_nth_unstable_by(max_apps - 1, rank);
+        indices.truncate(max_apps);
+    }
+    indices.sort_unstable();
+    indices
+}
+
+/// Fold a reported performance `score` into an app's smoothed score.
+///
+/// Scores are kept in millionths so every node rounds the same way; an app's
+/// first report sets its score outright.
+pub fn smooth_score(previous: Option<u64>, score: f64) -> u64 {
+    let sample = if score.is_finite() { (score.clamp(0.0, 1.0) * SCORE_SCALE).round() as u64 } else { 0 };
+    match previous {
+        Some(previous) => {
+            let weight = SCORE_SMOOTHING_BPS.min(MAX_CAP_BPS as u64);
+            (previous.min(SCORE_SCALE as u64) * (MAX_CAP_BPS as u64 - weight) + sample * weight) / MAX_CAP_BPS as u64
+        }
+        None => sample,
+    }
+}
+
+/// Water-fill `pool` across validators by weight, never exceeding `cap`.
+///
+/// Each round caps every validator whose proportional share of what remains
+/// exceeds the cap; the round with no new caps pays out the rest. At most one
+/// round per validator, so this always terminates. Validators are visited in
+/// address order, which decides who gets the leftover units on a tie.
+fn split_with_cap<'a>(pool: u64, weights: &BTreeMap<&'a Address, u128>, cap: u64) -> Vec<(&'a Address, u64)> {
+    let mut capped: Vec<&Address> = Vec::new();
+
+    loop {
+        let remaining = pool as u128 - capped.len() as u128 * cap as u128;
+        let uncapped: Vec<(&Address, u128)> = weights.iter()
+            .filter(|(validator, _)| !capped.contains(validator))
+            .map(|(validator, weight)| (*validator, *weight))
+            .collect();
+        let uncapped_weight: u128 = uncapped.iter().map(|(_, weight)| weight).sum();
+
+        let over_cap: Vec<&Address> = uncapped.iter()
+            .filter(|(_, weight)| remaining * weight > cap as u128 * uncapped_weight)
+            .map(|(validator, _)| *validator)
+            .collect();
+
+        if over_cap.is_empty() {
+            let mut totals: Vec<(&Address, u64)> = capped.iter().map(|validator| (*validator, cap)).collect();
+            totals.extend(proportional_split(remaining as u64, &uncapped));
+            return totals;
+        }
+        capped.extend(over_cap);
+    }
+}
+
+/// Split `amount` by weight, flooring each share and handing the leftover units
+/// out one at a time to the heaviest members, so the shares sum to `amount`
+fn proportional_split<K: Clone>(amount: u64, members: &[(K, u128)]) -> Vec<(K, u64)> {
+    let total_weight: u128 = members.iter().map(|(_, weight)| weight).sum();
+    if total_weight == 0 {
+        return members.iter().map(|(key, _)| (key.clone(), 0)).collect();
+    }
+
+    let mut shares: Vec<(K, u64, u128)> = members.iter()
+        .map(|(key, weight)| {
+            let exact = amount as u128 * weight;
+            (key.clone(), (exact / total_weight) as u64, exact % total_weight)
+        })
+        .collect();
+
+    // Leftover is below the member count; largest remainders go first, ties by position
+    let mut leftover = amount - shares.iter().map(|(_, share, _)| share).sum::<u64>();
+    let mut order: Vec<usize> = (0..shares.len()).collect();
+    order.sort_by(|a, b| shares[*b].2.cmp(&shares[*a].2).then(a.cmp(b)));
+    for i in order {
+        if leftover == 0 {
+            break;
+        }
+        shares[i].1 += 1;
+        leftover -= 1;
+    }
+
+    shares.into_iter().map(|(key, share, _)| (key, share)).collect()
+}
+
+/// Share of each block routed into the epoch reward pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewardPoolConfig {
+    /// Basis points of block fees
+    pub fee_share_bps: u16,
+    /// Basis points of the minted block reward
+    pub block_reward_share_bps: u16,
+}
+
+impl Default for RewardPoolConfig {
+    fn default() -> Self {
+        Self {
+            fee_share_bps: 5_000,
+            block_reward_share_bps: 0,
+        }
+    }
+}
+
+impl RewardPoolConfig {
+    fn share(amount: u64, bps: u16) -> u64 {
+        (amount as u128 * bps.min(MAX_CAP_BPS) as u128 / MAX_CAP_BPS as u128) as u64
+    }
+
+    /// Amount of a block's fees and minted reward that goes to the pool
+    pub fn pooled(&self, fees: u64, minted: u64) -> u64 {
+        Self::share(fees, self.fee_share_bps).saturating_add(Self::share(minted, self.block_reward_share_bps))
+    }
+}
+
+/// Epoch reward pool accounting
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewardPool {
+    /// Epoch currently collecting
+    pub epoch: u64,
+    /// Collected so far in `epoch`, not yet claimable
+    pub accumulating: u64,
+    /// Claimable balance from finalized epochs
+    pub finalized: u64,
+    /// Paid out of `finalized` so far
+    pub claimed: u64,
+}
+
+impl RewardPool {
+    /// Claimable amount left
+    pub fn available(&self) -> u64 {
+        self.finalized - self.claimed
+    }
+
+    /// Close every epoch before `epoch`, making what they collected claimable.
+    ///
+    /// Unclaimed rewards from earlier epochs stay in the pool.
+    pub fn finalize_until(&mut self, epoch: u64) {
+        if epoch <= self.epoch {
+            return;
+        }
+        self.finalized = self.available() + self.accumulating;
+        self.claimed = 0;
+        self.accumulating = 0;
+        self.epoch = epoch;
+    }
+
+    /// Add a block's contribution, finalizing first if it starts a new epoch
+    pub fn deposit(&mut self, epoch: u64, amount: u64) -> Result<()> {
+        self.finalize_until(epoch);
+        self.accumulating = self.accumulating.checked_add(amount)
+            .ok_or_else(|| QoraNetError::InvalidTransaction("Reward pool overflow".to_string()))?;
+        Ok(())
+    }
+
+    /// Pay `amount` from the finalized pool, refusing to over-distribute
+    pub fn claim(&mut self, amount: u64) -> Result<()> {
+        if amount > self.available() {
+            return Err(QoraNetError::InvalidTransaction(format!(
+                "Claim of {} exceeds the {} left in the reward pool",
+                amount,
+                self.available()
+            )));
+        }
+        self.claimed += amount;
+        Ok(())
+    }
+}
+
+fn to_rewards(apps: &[AppRewardShare], amounts: Vec<u64>) -> Vec<AppReward> {
+    apps.iter().zip(amounts)
+        .map(|(app, amount)| AppReward {
+            validator: app.validator.clone(),
+            app_owner: app.app_owner.clone(),
+            app_id: app.app_id.clone(),
+            amount,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(validator: u8, id: &str, score: f64) -> AppRewardShare {
+        AppRewardShare {
+            validator: Address([validator; 32]),
+            app_owner: Address([validator + 100; 32]),
+            app_id: id.to_string(),
+            score,
+        }
+    }
+
+    fn validator_total(rewards: &[AppReward], validator: u8) -> u64 {
+        rewards.iter().filter(|r| r.validator == Address([validator; 32])).map(|r| r.amount).sum()
+    }
+
+    #[test]
+    fn test_dominant_validator_capped_and_overflow_redistributed() {
+        let apps = vec![
+            app(1, "a", 1.0), app(1, "b", 1.0), app(1, "c", 1.0), app(1, "d", 1.0),
+            app(2, "e", 1.0),
+            app(3, "f", 0.5),
+        ];
+
+        // Uncapped: validator 1 would take 8/11 of the pool
+        let uncapped = compute_app_rewards(1_100, &apps, ValidatorRewardCap::Unlimited);
+        assert_eq!(validator_total(&uncapped, 1), 800);
+
+        let rewards = compute_app_rewards(1_100, &apps, ValidatorRewardCap::PoolBps(5_000));
+        assert_eq!(validator_total(&rewards, 1), 550);
+        // The 250 overflow is shared 2:1 between the others
+        assert_eq!(validator_total(&rewards, 2), 367);
+        assert_eq!(validator_total(&rewards, 3), 183);
+        assert_eq!(rewards.iter().map(|r| r.amount).sum::<u64>(), 1_100);
+    }
+
+    #[test]
+    fn test_overflow_cascades_across_rounds() {
+        // Weights 6:3:1 with a 40% cap: 600 -> 400, then 450 -> 400, then the rest
+        let apps = vec![app(1, "a", 0.6), app(2, "b", 0.3), app(3, "c", 0.1)];
+        let rewards = compute_app_rewards(1_000, &apps, ValidatorRewardCap::PoolBps(4_000));
+
+        assert_eq!(validator_total(&rewards, 1), 400);
+        assert_eq!(validator_total(&rewards, 2), 400);
+        assert_eq!(validator_total(&rewards, 3), 200);
+    }
+
+    #[test]
+    fn test_leftover_split_independent_of_app_order() {
+        let apps = vec![app(3, "c", 1.0), app(1, "a", 1.0), app(2, "b", 1.0)];
+        let mut reversed = apps.clone();
+        reversed.reverse();
+
+        for apps in [apps, reversed] {
+            let rewards = compute_app_rewards(100, &apps, ValidatorRewardCap::Unlimited);
+            assert_eq!(validator_total(&rewards, 1), 34);
+            assert_eq!(validator_total(&rewards, 2), 33);
+            assert_eq!(validator_total(&rewards, 3), 33);
+        }
+    }
+
+    #[test]
+    fn test_infeasible_cap_still_distributes_whole_pool() {
+        let apps = vec![app(1, "a", 0.9), app(2, "b", 0.1), app(2, "c", 0.2)];
+        let rewards = compute_app_rewards(1_001, &apps, ValidatorRewardCap::Absolute(10));
+
+        assert_eq!(rewards.iter().map(|r| r.amount).sum::<u64>(), 1_001);
+        assert!(validator_total(&rewards, 1) <= 501);
+        assert!(validator_total(&rewards, 2) <= 501);
+    }
+
+    #[test]
+    fn test_reward_pool_claimable_after_epoch_closes() {
+        let config = RewardPoolConfig { fee_share_bps: 2_500, block_reward_share_bps: 10_000 };
+        assert_eq!(config.pooled(1_000, 100), 350);
+
+        let mut pool = RewardPool::default();
+        pool.deposit(0, 300).unwrap();
+        assert!(pool.claim(1).is_err());
+
+        pool.deposit(1, 50).unwrap();
+        pool.claim(200).unwrap();
+
+        // The unclaimed 100 rolls into the next epoch's pool
+        pool.finalize_until(2);
+        assert_eq!(pool.available(), 150);
+    }
+
+    #[test]
+    fn test_only_top_apps_rewarded() {
+        let apps: Vec<AppRewardShare> = (0..10u8)
+            .map(|i| app(i + 1, &format!("app-{}", i), (i % 5) as f64 / 10.0 + 0.1))
+            .collect();
+
+        // Scores 0.5 (i = 4, 9) and 0.4 (i = 3, 8) make the top four
+        let rewards = compute_top_app_rewards(1_000, &apps, ValidatorRewardCap::Unlimited, 4);
+        let rewarded: Vec<usize> = rewards.iter().enumerate().filter(|(_, r)| r.amount > 0).map(|(i, _)| i).collect();
+        assert_eq!(rewarded, vec![3, 4, 8, 9]);
+        assert_eq!(rewards.iter().map(|r| r.amount).sum::<u64>(), 1_000);
+
+        // Ties at the cut are resolved the same way regardless of input order
+        let mut reversed = apps.clone();
+        reversed.reverse();
+        let top3: Vec<String> = compute_top_app_rewards(1_000, &apps, ValidatorRewardCap::Unlimited, 3)
+            .into_iter().filter(|r| r.amount > 0).map(|r| r.app_id).collect();
+        let mut top3_reversed: Vec<String> = compute_top_app_rewards(1_000, &reversed, ValidatorRewardCap::Unlimited, 3)
+            .into_iter().filter(|r| r.amount > 0).map(|r| r.app_id).collect();
+        top3_reversed.reverse();
+        assert_eq!(top3, top3_reversed);
+    }
+
+    #[test]
+    fn test_many_apps_split_only_between_top_n() {
+        let apps: Vec<AppRewardShare> = (0..50_000u32)
+            .map(|i| app((i % 150) as u8, &i.to_string(), (i % 997) as f64 / 1_000.0))
+            .collect();
+        let rewards = compute_top_app_rewards(1_000_000, &apps, ValidatorRewardCap::PoolBps(2_000), DEFAULT_MAX_REWARDED_APPS);
+
+        // The capped split only ever sees the selected apps, so it costs the same however many are hosted
+        let mut ranked: Vec<&AppRewardShare> = apps.iter().collect();
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score)
+            .then_with(|| a.validator.0.cmp(&b.validator.0))
+            .then_with(|| a.app_owner.0.cmp(&b.app_owner.0))
+            .then_with(|| a.app_id.cmp(&b.app_id)));
+        let mut top: Vec<AppRewardShare> = ranked[..DEFAULT_MAX_REWARDED_APPS].iter().map(|app| (*app).clone()).collect();
+        top.sort_by_key(|app| app.app_id.parse::<u32>().unwrap());
+        let expected = compute_app_rewards(1_000_000, &top, ValidatorRewardCap::PoolBps(2_000));
+
+        let paid: Vec<AppReward> = rewards.into_iter()
+            .filter(|r| top.iter().any(|app| app.app_id == r.app_id))
+            .collect();
+        assert_eq!(paid, expected);
+        assert_eq!(paid.iter().map(|r| r.amount).sum::<u64>(), 1_000_000);
+    }
+
+    #[test]
+    fn test_smoothed_score_damps_single_reports() {
+        assert_eq!(smooth_score(None, 0.5), 500_000);
+        // One perfect report moves a middling app a fifth of the way
+        assert_eq!(smooth_score(Some(500_000), 1.0), 600_000);
+        assert_eq!(smooth_score(Some(500_000), f64::NAN), 400_000);
+        assert_eq!(smooth_score(Some(500_000), 7.0), 600_000);
+    }
+}
@@ -0,0 +1,82 @@
+//! Liveness and readiness probes
+//!
+//! A minimal HTTP responder for orchestration: `GET /health` answers 200 while
+//! the process is up, `GET /ready` answers 503 once the production watchdog has
+//! halted block production.
+
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::debug;
+
+use crate::consensus::ProductionWatchdog;
+
+/// Accept probe requests until the listener fails
+pub async fn serve_health(listener: TcpListener, watchdog: Arc<ProductionWatchdog>) -> std::io::Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let watchdog = Arc::clone(&watchdog);
+        tokio::spawn(async move {
+            if let Err(e) = handle_probe(stream, &watchdog).await {
+                debug!("Health probe from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_probe(mut stream: TcpStream, watchdog: &ProductionWatchdog) -> std::io::Result<()> {
+    let mut buf = [0u8; 1024];
+    let read = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, body) = probe_response(path, watchdog);
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn probe_response(path: &str, watchdog: &ProductionWatchdog) -> (&'static str, &'static str) {
+    match path {
+        "/health" => ("200 OK", "ok"),
+        "/ready" if watchdog.is_ready() => ("200 OK", "ready"),
+        "/ready" => ("503 Service Unavailable", "block production halted"),
+        _ => ("404 Not Found", "not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QoraNetError;
+
+    async fn get(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_ready_fails_once_production_halts() {
+        let watchdog = Arc::new(ProductionWatchdog::new(2));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_health(listener, Arc::clone(&watchdog)));
+
+        assert!(get(addr, "/ready").await.starts_with("HTTP/1.1 200"));
+
+        let write_failure: crate::Result<()> = Err(QoraNetError::StorageError("disk full".to_string()));
+        watchdog.observe(&write_failure);
+        assert!(get(addr, "/ready").await.starts_with("HTTP/1.1 200"));
+        watchdog.observe(&write_failure);
+
+        assert!(get(addr, "/ready").await.starts_with("HTTP/1.1 503"));
+        // Still alive, just not ready
+        assert!(get(addr, "/health").await.starts_with("HTTP/1.1 200"));
+    }
+}
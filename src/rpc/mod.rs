@@ -0,0 +1,436 @@
+/// JSON-RPC methods for the QoraNet core chain
+pub mod health;
+pub mod ws;
+
+use serde_json::{Value, json};
+use crate::{Address, FeeOracle, Hash, Transaction};
+use crate::consensus::ConsensusState;
+use crate::dex::PoolRegistry;
+use crate::storage::{BlockchainStorage, HistoryCursor};
+use crate::transaction::TransactionPool;
+
+/// Default and maximum page size for account history
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const MAX_HISTORY_LIMIT: usize = 100;
+
+/// Core QoraNet RPC handler
+pub struct QoraRpcHandler;
+
+impl QoraRpcHandler {
+    /// qora_nextProducer - predict the producer of the upcoming block
+    pub fn next_producer(
+        consensus: &ConsensusState,
+        storage: &BlockchainStorage,
+        params: Value,
+    ) -> Result<Value, String> {
+        let (latest_hash, latest_height) = storage.get_latest_block_info();
+        let previous_hash = latest_hash.unwrap_or_else(Hash::zero);
+        let height = latest_height + 1;
+
+        let producer = consensus.producer_for(&previous_hash, height)
+            .map_err(|e| e.to_string())?;
+
+        let mut response = json!({
+            "height": height,
+            "previousHash": format!("0x{}", previous_hash),
+            "producer": format_address(&producer)
+        });
+
+        // Optionally answer "am I next?" for a given validator
+        if let Some(validator_val) = params.get("validator") {
+            let validator = parse_address(validator_val)?;
+            response["willProduce"] = json!(consensus.would_produce(&validator, &previous_hash, height));
+        }
+
+        Ok(response)
+    }
+
+    /// qora_getValidators - consensus validator set, largest liquidity first
+    pub fn get_validators(consensus: &ConsensusState) -> Result<Value, String> {
+        let validators: Vec<Value> = consensus.validator_set().iter().map(|status| json!({
+            "address": format_address(&status.info.address),
+            "liquidity": status.info.liquidity,
+            "activeApps": status.info.active_apps,
+            "lastActive": status.info.last_active,
+            "eligible": status.eligible,
+            "inSelectionSet": status.in_selection_set
+        })).collect();
+
+        Ok(json!({
+            "height": consensus.current_height(),
+            "validatorCount": validators.len(),
+            "eligibleCount": consensus.eligible_validator_count(),
+            "validators": validators
+        }))
+    }
+
+    /// qora_getBalance - confirmed balance and the balance including pending transactions
+    pub fn get_balance(storage: &BlockchainStorage, pool: &TransactionPool, params: Value) -> Result<Value, String> {
+        let address = parse_address(params.get("address").ok_or("Missing address")?)?;
+
+        let confirmed = storage.get_account(&address)
+            .map_err(|e| e.to_string())?
+            .map_or(0, |account| account.balance.amount);
+        let pending = storage.pending_balance(&address, pool)
+            .map_err(|e| e.to_string())?;
+
+        Ok(json!({
+            "address": format_address(&address),
+            "confirmed": confirmed,
+            "pending": pending
+        }))
+    }
+
+    /// qora_getAccountHistory - transactions for an account, newest first
+    pub fn get_account_history(storage: &BlockchainStorage, params: Value) -> Result<Value, String> {
+        let address = parse_address(params.get("address").ok_or("Missing address")?)?;
+
+        let limit = match params.get("limit") {
+            Some(value) => value.as_u64().ok_or("Limit must be a number")? as usize,
+            None => DEFAULT_HISTORY_LIMIT,
+        };
+        if limit == 0 || limit > MAX_HISTORY_LIMIT {
+            return Err(format!("Limit must be between 1 and {}", MAX_HISTORY_LIMIT));
+        }
+
+        let cursor = match params.get("cursor") {
+            Some(Value::Null) | None => None,
+            Some(value) => {
+                let cursor_str = value.as_str().ok_or("Cursor must be a string")?;
+                Some(cursor_str.parse::<HistoryCursor>().map_err(|e| e.to_string())?)
+            }
+        };
+
+        let page = storage.get_account_history(&address, limit, cursor)
+            .map_err(|e| e.to_string())?;
+
+        let transactions: Vec<Value> = page.entries.iter().map(|entry| {
+            json!({
+                "hash": format!("0x{}", entry.transaction.hash()),
+                "blockHeight": entry.location.block_height,
+                "blockHash": format!("0x{}", entry.location.block_hash),
+                "index": entry.location.index,
+                "timestamp": entry.location.timestamp,
+                "direction": entry.direction,
+                "memo": entry.transaction.data.memo().map(hex::encode),
+                "signer": format_address(&entry.transaction.signer),
+                "nonce": entry.transaction.nonce,
+                "fee": entry.transaction.fee_qor,
+                "data": entry.transaction.data
+            })
+        }).collect();
+
+        Ok(json!({
+            "transactions": transactions,
+            "nextCursor": page.next_cursor.map(|cursor| cursor.to_string())
+        }))
+    }
+
+    /// qora_getTransactionReceipt - inclusion details for a transaction
+    pub fn get_transaction_receipt(storage: &BlockchainStorage, params: Value) -> Result<Value, String> {
+        let tx_hash = parse_hash(params.get("hash").ok_or("Missing hash")?)?;
+
+        let transaction = match storage.get_transaction(&tx_hash).map_err(|e| e.to_string())? {
+            Some(tx) => tx,
+            None => return Ok(Value::Null),
+        };
+        let location = storage.get_transaction_location(&tx_hash)
+            .map_err(|e| e.to_string())?
+            .ok_or("Transaction location not indexed")?;
+        let fee_breakdown = storage.get_fee_breakdown(&tx_hash).map_err(|e| e.to_string())?;
+        let failure = storage.get_transaction_failure(&tx_hash).map_err(|e| e.to_string())?;
+
+        Ok(json!({
+            "hash": format!("0x{}", tx_hash),
+            "blockHeight": location.block_height,
+            "blockHash": format!("0x{}", location.block_hash),
+            "index": location.index,
+            "timestamp": location.timestamp,
+            "signer": format_address(&transaction.signer),
+            "fee": transaction.fee_qor,
+            "feeBreakdown": fee_breakdown.map(|breakdown| json!({
+                "baseFeeQor": breakdown.base_fee_qor,
+                "priorityTipQor": breakdown.priority_tip_qor,
+                "totalFeeQor": breakdown.total_fee_qor,
+                "feeToken": breakdown.fee_token.as_ref().map(format_address),
+                "feeTokenAmount": breakdown.fee_token_amount,
+                "priceUsed": breakdown.price_used
+            })),
+            "memo": transaction.data.memo().map(hex::encode),
+            "status": if failure.is_some() { "failed" } else { "success" },
+            "error": failure
+        }))
+    }
+
+    /// qora_decodeRawTransaction - decode a signed transaction without broadcasting it
+    pub fn decode_raw_transaction(params: Value) -> Result<Value, String> {
+        let raw = params.get("raw")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing 'raw' field")?;
+        let bytes = hex::decode(raw.strip_prefix("0x").unwrap_or(raw))
+            .map_err(|_| "Raw transaction must be hex".to_string())?;
+        let transaction: Transaction = bincode::deserialize(&bytes)
+            .map_err(|e| format!("Malformed transaction: {}", e))?;
+
+        let signature_error = transaction.verify_signature().err().map(|e| e.to_string());
+
+        Ok(json!({
+            "id": format!("0x{}", transaction.hash()),
+            "type": format!("{:?}", transaction.data.transaction_type()),
+            "data": transaction.data,
+            "memo": transaction.data.memo().map(hex::encode),
+            "nonce": transaction.nonce,
+            "fee": transaction.fee_qor,
+            "feeUsd": transaction.fee_usd,
+            "priority": transaction.priority,
+            "signer": format_address(&transaction.signer),
+            "signatureValid": signature_error.is_none(),
+            "signatureError": signature_error
+        }))
+    }
+
+    /// qora_getPriceAlarm - QOR price jump held back for confirmation, if any
+    pub fn get_price_alarm(oracle: &FeeOracle) -> Result<Value, String> {
+        let alarm = oracle.price_alarm().map(|alarm| json!({
+            "pendingPrice": alarm.pending_price,
+            "deviationBps": alarm.deviation_bps,
+            "observations": alarm.observations,
+            "confirmationsRequired": alarm.confirmations_required
+        }));
+
+        Ok(json!({
+            "price": oracle.get_qor_price(),
+            "alarmRaised": alarm.is_some(),
+            "alarm": alarm
+        }))
+    }
+
+    /// qora_getLpPosition - current worth and impermanent loss of an LP position
+    pub fn get_lp_position(pools: &PoolRegistry, params: Value) -> Result<Value, String> {
+        let pool_address = parse_address(params.get("pool").ok_or("Missing pool")?)?;
+        let lp_amount = params.get("lpAmount")
+            .and_then(|v| v.as_u64())
+            .ok_or("Missing 'lpAmount' field")?;
+        let entry_price = match params.get("entryPrice") {
+            Some(Value::Null) | None => None,
+            Some(value) => Some(value.as_f64().ok_or("Entry price must be a number")?),
+        };
+
+        let pool = pools.get(&pool_address).ok_or("Pool not found")?;
+        let position = pool.position(lp_amount, entry_price);
+
+        Ok(json!({
+            "pool": format_address(&pool.address),
+            "tokenA": format_address(&pool.token_a),
+            "tokenB": format_address(&pool.token_b),
+            "lpAmount": position.lp_amount,
+            "amountA": position.amount_a,
+            "amountB": position.amount_b,
+            "shareOfPool": position.share_of_pool,
+            "currentPrice": pool.price(),
+            "impermanentLoss": position.impermanent_loss,
+            "totalSupply": pool.total_supply
+        }))
+    }
+}
+
+// Helper functions
+
+/// Parse a 32-byte QoraNet address from JSON value
+fn parse_address(value: &Value) -> Result<Address, String> {
+    let addr_str = value.as_str()
+        .ok_or("Address must be a string")?;
+
+    Address::from_hex(addr_str).map_err(|e| e.to_string())
+}
+
+/// Parse a 32-byte hash from JSON value
+fn parse_hash(value: &Value) -> Result<Hash, String> {
+    let hash_str = value.as_str()
+        .ok_or("Hash must be a string")?;
+    let bytes = hex::decode(hash_str.strip_prefix("0x").unwrap_or(hash_str))
+        .map_err(|_| "Invalid hex hash".to_string())?;
+    if bytes.len() != 32 {
+        return Err("Hash must be 32 bytes".to_string());
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&bytes);
+    Ok(Hash(hash))
+}
+
+/// Format address as 0x-prefixed hex (the native QOR address included)
+fn format_address(address: &Address) -> String {
+    format!("0x{}", hex::encode(address.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address_roundtrip() {
+        let address = Address([7u8; 32]);
+        let parsed = parse_address(&json!(format_address(&address))).unwrap();
+        assert_eq!(parsed, address);
+    }
+
+    fn raw_transfer() -> Transaction {
+        use crate::transaction::TransactionData;
+        use crate::{FeePriority, QoraSignature};
+        use ed25519_dalek::{Keypair, Signer};
+
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        let mut tx = Transaction {
+            data: TransactionData::Transfer { from: signer.clone(), to: Address([5u8; 32]), amount: 250, memo: None },
+            nonce: 4,
+            fee_qor: 1_000,
+            fee_usd: 0.0001,
+            priority: FeePriority::Low,
+            signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+            signer,
+            valid_until_height: None,
+        };
+        tx.signature = keypair.sign(&tx.signing_message());
+        tx
+    }
+
+    #[test]
+    fn test_decode_raw_transaction_reports_signature_validity() {
+        let tx = raw_transfer();
+        let raw = format!("0x{}", hex::encode(bincode::serialize(&tx).unwrap()));
+
+        let decoded = QoraRpcHandler::decode_raw_transaction(json!({ "raw": raw })).unwrap();
+        assert_eq!(decoded["id"], format!("0x{}", tx.hash()));
+        assert_eq!(decoded["type"], "Transfer");
+        assert_eq!(decoded["nonce"], 4);
+        assert_eq!(decoded["fee"], 1_000);
+        assert_eq!(decoded["signer"], format_address(&tx.signer));
+        assert_eq!(decoded["data"]["Transfer"]["amount"], 250);
+        assert_eq!(decoded["signatureValid"], true);
+
+        // Bump the amount: still decodes, but the signature no longer covers it
+        let mut corrupted = tx.clone();
+        if let crate::transaction::TransactionData::Transfer { amount, .. } = &mut corrupted.data {
+            *amount = 251;
+        }
+        let raw = hex::encode(bincode::serialize(&corrupted).unwrap());
+        let decoded = QoraRpcHandler::decode_raw_transaction(json!({ "raw": raw })).unwrap();
+        assert_eq!(decoded["signatureValid"], false);
+        assert!(decoded["signatureError"].is_string());
+    }
+
+    #[test]
+    fn test_validators_listed_by_liquidity() {
+        use crate::consensus::ValidatorInfo;
+
+        let mut consensus = ConsensusState::new(1_000, 1);
+        for (byte, liquidity, active_apps) in [(1u8, 5_000, 2), (2, 20_000, 0), (3, 500, 3), (4, 8_000, 1)] {
+            consensus.update_validator(ValidatorInfo {
+                liquidity,
+                active_apps,
+                ..ValidatorInfo::new(Address([byte; 32]))
+            }).unwrap();
+        }
+
+        let response = QoraRpcHandler::get_validators(&consensus).unwrap();
+        let validators = response["validators"].as_array().unwrap();
+        let order: Vec<u64> = validators.iter().map(|v| v["liquidity"].as_u64().unwrap()).collect();
+        assert_eq!(order, vec![20_000, 8_000, 5_000, 500]);
+
+        // No apps, and too little liquidity, respectively
+        let eligible: Vec<bool> = validators.iter().map(|v| v["eligible"].as_bool().unwrap()).collect();
+        assert_eq!(eligible, vec![false, true, true, false]);
+        assert_eq!(response["eligibleCount"], 2);
+        assert_eq!(validators[1]["address"], format_address(&Address([4u8; 32])));
+        assert_eq!(validators[1]["activeApps"], 1);
+        assert_eq!(validators[1]["inSelectionSet"], true);
+        assert_eq!(validators[0]["inSelectionSet"], false);
+    }
+
+    #[test]
+    fn test_price_alarm_reported() {
+        let mut oracle = FeeOracle::new().with_price_sources(Vec::new());
+        oracle.submit_price(1.0).unwrap();
+        assert_eq!(QoraRpcHandler::get_price_alarm(&oracle).unwrap()["alarmRaised"], false);
+
+        assert!(oracle.submit_price(10.0).is_err());
+        let response = QoraRpcHandler::get_price_alarm(&oracle).unwrap();
+        assert_eq!(response["alarmRaised"], true);
+        assert_eq!(response["price"], 1.0);
+        assert_eq!(response["alarm"]["pendingPrice"], 10.0);
+    }
+
+    #[test]
+    fn test_balance_includes_pending_transfers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let tx = raw_transfer();
+        storage.apply_balance_delta(&tx.signer, 10_000).unwrap();
+
+        let mut pool = TransactionPool::new();
+        pool.insert(tx.clone(), 0).unwrap();
+
+        let response = QoraRpcHandler::get_balance(&storage, &pool, json!({ "address": format_address(&tx.signer) })).unwrap();
+        assert_eq!(response["confirmed"], 10_000);
+        // 250 sent plus the 1_000 fee
+        assert_eq!(response["pending"], 8_750);
+
+        let recipient = QoraRpcHandler::get_balance(&storage, &pool, json!({ "address": format_address(&Address([5u8; 32])) })).unwrap();
+        assert_eq!(recipient["confirmed"], 0);
+        assert_eq!(recipient["pending"], 250);
+    }
+
+    #[test]
+    fn test_receipt_carries_fee_breakdown() {
+        use crate::consensus::Block;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let tx = raw_transfer();
+        storage.apply_balance_delta(&tx.signer, 10_000).unwrap();
+        let genesis = Block::genesis(Address([1u8; 32]));
+        storage.store_block(&genesis).unwrap();
+        let block = Block::new(genesis.hash(), 1, Address([1u8; 32]), vec![tx.clone()], 0, 0);
+        storage.store_block(&block).unwrap();
+        assert!(storage.apply_block_transactions(&block).unwrap().is_empty());
+
+        let receipt = QoraRpcHandler::get_transaction_receipt(&storage, json!({ "hash": format!("0x{}", tx.hash()) })).unwrap();
+        let breakdown = &receipt["feeBreakdown"];
+        // Low priority pays no tip; $0.0001 for 1_000 base units prices QOR at $0.10
+        assert_eq!(breakdown["totalFeeQor"], 1_000);
+        assert_eq!(breakdown["baseFeeQor"], 1_000);
+        assert_eq!(breakdown["priorityTipQor"], 0);
+        assert!((breakdown["priceUsed"].as_f64().unwrap() - 0.1).abs() < 1e-9);
+        assert!(breakdown["feeToken"].is_null());
+        assert_eq!(receipt["status"], "success");
+    }
+
+    #[test]
+    fn test_receipt_reports_failed_execution() {
+        use crate::consensus::Block;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        // The sender can't cover the transfer, so it is included without effect
+        let tx = raw_transfer();
+        let genesis = Block::genesis(Address([1u8; 32]));
+        storage.store_block(&genesis).unwrap();
+        let block = Block::new(genesis.hash(), 1, Address([1u8; 32]), vec![tx.clone()], 0, 0);
+        storage.store_block(&block).unwrap();
+        assert_eq!(storage.apply_block_transactions(&block).unwrap(), vec![tx.hash()]);
+
+        let receipt = QoraRpcHandler::get_transaction_receipt(&storage, json!({ "hash": format!("0x{}", tx.hash()) })).unwrap();
+        assert_eq!(receipt["status"], "failed");
+        assert!(receipt["error"].as_str().is_some());
+        assert!(receipt["feeBreakdown"].is_null());
+    }
+
+    #[test]
+    fn test_decode_raw_transaction_rejects_malformed_input() {
+        assert!(QoraRpcHandler::decode_raw_transaction(json!({ "raw": "0xzz" })).is_err());
+        assert!(QoraRpcHandler::decode_raw_transaction(json!({ "raw": "0x0102" })).is_err());
+        assert!(QoraRpcHandler::decode_raw_transaction(json!({})).is_err());
+    }
+}
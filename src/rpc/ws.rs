@@ -0,0 +1,249 @@
+//! WebSocket subscriptions
+//!
+//! Implements `eth_subscribe("newHeads")` / `eth_unsubscribe` over JSON-RPC.
+//! Each connection subscribes to the storage head feed; heads rewound by a
+//! reorg are delivered again with `"removed": true`. `qora_reorgs` delivers
+//! one summary per reorg, including the reverted transaction ids.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, warn};
+
+use crate::storage::{HeadEvent, HeadSummary, ReorgEvent};
+
+/// What a subscription receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubscriptionKind {
+    NewHeads,
+    Reorgs,
+}
+
+/// Accept WebSocket clients until the listener fails
+pub async fn serve_ws(listener: TcpListener, heads: broadcast::Sender<HeadEvent>) -> std::io::Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let heads = heads.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, heads).await {
+                debug!("WebSocket client {} closed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    mut heads: broadcast::Receiver<HeadEvent>,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let mut ws = tokio_tungstenite::accept_async(stream).await?;
+    let mut subscriptions: HashMap<String, SubscriptionKind> = HashMap::new();
+    let mut next_id: u64 = 1;
+
+    loop {
+        tokio::select! {
+            incoming = ws.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e),
+                };
+                let was_idle = subscriptions.is_empty();
+                let response = handle_request(&text, &mut subscriptions, &mut next_id);
+                if was_idle && !subscriptions.is_empty() {
+                    // Skip heads that arrived before the first subscription
+                    heads = heads.resubscribe();
+                }
+                ws.send(Message::Text(response.to_string())).await?;
+            }
+            event = heads.recv(), if !subscriptions.is_empty() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("WebSocket subscriber lagged, {} heads dropped", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                };
+                for (subscription, kind) in &subscriptions {
+                    if let Some(notification) = notification(subscription, *kind, &event) {
+                        ws.send(Message::Text(notification.to_string())).await?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Answer one JSON-RPC request, updating the connection's subscriptions
+fn handle_request(text: &str, subscriptions: &mut HashMap<String, SubscriptionKind>, next_id: &mut u64) -> Value {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(_) => return error_response(Value::Null, -32700, "Parse error"),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    match request.get("method").and_then(Value::as_str) {
+        Some("eth_subscribe") => {
+            let kind = match params.get(0).and_then(Value::as_str) {
+                Some("newHeads") => SubscriptionKind::NewHeads,
+                Some("qora_reorgs") => SubscriptionKind::Reorgs,
+                _ => return error_response(id, -32602, "Unsupported subscription"),
+            };
+            let subscription = format!("0x{:x}", *next_id);
+            *next_id += 1;
+            subscriptions.insert(subscription.clone(), kind);
+            json!({ "jsonrpc": "2.0", "id": id, "result": subscription })
+        }
+        Some("eth_unsubscribe") => {
+            let removed = params.get(0)
+                .and_then(Value::as_str)
+                .map_or(false, |subscription| subscriptions.remove(subscription).is_some());
+            json!({ "jsonrpc": "2.0", "id": id, "result": removed })
+        }
+        _ => error_response(id, -32601, "Method not found"),
+    }
+}
+
+/// JSON header fields for a head
+pub fn head_json(head: &HeadSummary, removed: bool) -> Value {
+    json!({
+        "number": head.height,
+        "hash": format!("0x{}", head.hash),
+        "timestamp": head.timestamp,
+        "validator": head.validator.to_string(),
+        "transactionCount": head.tx_count,
+        "feesQor": head.fees_qor,
+        "removed": removed,
+    })
+}
+
+/// JSON summary of a reorg
+pub fn reorg_json(reorg: &ReorgEvent) -> Value {
+    json!({
+        "commonAncestor": reorg.common_ancestor_height,
+        "oldTip": head_json(&reorg.old_tip, true),
+        "newTip": head_json(&reorg.new_tip, false),
+        "revertedTransactions": reorg.reverted_tx_ids.iter()
+            .map(|tx_hash| format!("0x{}", tx_hash))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// `eth_subscription` notification for a head event
+pub fn head_notification(subscription: &str, event: &HeadEvent) -> Value {
+    let result = match event {
+        HeadEvent::New(head) => head_json(head, false),
+        HeadEvent::Removed(head) => head_json(head, true),
+        HeadEvent::Reorg(reorg) => reorg_json(reorg),
+    };
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": { "subscription": subscription, "result": result },
+    })
+}
+
+/// Notification for a subscription of `kind`, if the event concerns it
+fn notification(subscription: &str, kind: SubscriptionKind, event: &HeadEvent) -> Option<Value> {
+    match (kind, event) {
+        (SubscriptionKind::NewHeads, HeadEvent::Reorg(_)) => None,
+        (SubscriptionKind::NewHeads, _) => Some(head_notification(subscription, event)),
+        (SubscriptionKind::Reorgs, HeadEvent::Reorg(_)) => Some(head_notification(subscription, event)),
+        (SubscriptionKind::Reorgs, _) => None,
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::Block;
+    use crate::storage::BlockchainStorage;
+    use crate::Address;
+    use tempfile::TempDir;
+
+    async fn next_json<S>(ws: &mut S) -> Value
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        match ws.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_heads_subscription_delivers_header() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve_ws(listener, storage.head_feed()));
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let subscribe = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_subscribe", "params": ["newHeads"] });
+        ws.send(Message::Text(subscribe.to_string())).await.unwrap();
+        let subscription = next_json(&mut ws).await["result"].as_str().unwrap().to_string();
+
+        let validator = Address([4u8; 32]);
+        let block = Block::genesis(validator.clone());
+        storage.store_block(&block).unwrap();
+
+        let notification = next_json(&mut ws).await;
+        assert_eq!(notification["method"], "eth_subscription");
+        assert_eq!(notification["params"]["subscription"], subscription.as_str());
+
+        let head = &notification["params"]["result"];
+        assert_eq!(head["number"], 0);
+        assert_eq!(head["hash"], format!("0x{}", block.hash()));
+        assert_eq!(head["timestamp"], block.header.timestamp);
+        assert_eq!(head["validator"], validator.to_string());
+        assert_eq!(head["transactionCount"], 0);
+        assert_eq!(head["feesQor"], 0);
+        assert_eq!(head["removed"], false);
+    }
+
+    #[test]
+    fn test_unknown_subscription_rejected() {
+        let mut subscriptions = HashMap::new();
+        let mut next_id = 1;
+        let request = json!({ "jsonrpc": "2.0", "id": 7, "method": "eth_subscribe", "params": ["logs"] });
+
+        let response = handle_request(&request.to_string(), &mut subscriptions, &mut next_id);
+        assert_eq!(response["error"]["code"], -32602);
+        assert!(subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_reorg_subscription_only_gets_reorgs() {
+        let mut subscriptions = HashMap::new();
+        let mut next_id = 1;
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_subscribe", "params": ["qora_reorgs"] });
+        let subscription = handle_request(&request.to_string(), &mut subscriptions, &mut next_id)["result"]
+            .as_str().unwrap().to_string();
+        assert_eq!(subscriptions[&subscription], SubscriptionKind::Reorgs);
+
+        let head = HeadSummary::from_block(&Block::genesis(Address([4u8; 32])));
+        let reorg = HeadEvent::Reorg(ReorgEvent {
+            common_ancestor_height: 0,
+            old_tip: head.clone(),
+            new_tip: head.clone(),
+            reverted_tx_ids: vec![crate::Hash::new(b"tx")],
+        });
+
+        assert!(notification(&subscription, SubscriptionKind::Reorgs, &HeadEvent::New(head.clone())).is_none());
+        assert!(notification(&subscription, SubscriptionKind::NewHeads, &reorg).is_none());
+        let sent = notification(&subscription, SubscriptionKind::Reorgs, &reorg).unwrap();
+        assert_eq!(sent["params"]["result"]["revertedTransactions"][0], format!("0x{}", crate::Hash::new(b"tx")));
+    }
+}
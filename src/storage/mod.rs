@@ -1,10 +1,16 @@
-use crate::{Hash, Address, BlockHeight, Result, QoraNetError, Balance};
-use crate::consensus::Block;
-use crate::transaction::Transaction;
+use crate::{Hash, Address, BlockHeight, Result, QoraNetError, Balance, Timestamp, TokenBalance};
+use crate::consensus::{Block, BlockHeader, EquivocationProof, GenesisConfig};
+use crate::rewards::{compute_top_app_rewards, smooth_score, AppRewardShare, RewardPool, RewardPoolConfig, ValidatorRewardCap, SCORE_SCALE};
+use crate::transaction::{canonical_outputs, Transaction, TransactionData, TransactionPool};
+use crate::{AccountHoldings, AppId, AppMetrics, FeeBreakdown, FeeDiscountPolicy, FeePriority, LPToken, QoraSignature};
+use crate::transaction::{AppType, ResourceRequirements};
+use bincode::Options as _;
 use serde::{Deserialize, Serialize};
-use rocksdb::{DB, Options, IteratorMode};
+use rocksdb::{DB, Direction, Options, IteratorMode, WriteBatch};
 use std::path::Path;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
 
 /// Database column families
 pub const CF_BLOCKS: &str = "blocks";
@@ -13,15 +19,278 @@ pub const CF_ACCOUNTS: &str = "accounts";
 pub const CF_VALIDATORS: &str = "validators";
 pub const CF_APPS: &str = "applications";
 pub const CF_METADATA: &str = "metadata";
+pub const CF_TX_INDEX: &str = "tx_index";
+pub const CF_ACCOUNT_HISTORY: &str = "account_history";
+pub const CF_ACCOUNT_CHECKPOINTS: &str = "account_checkpoints";
+
+const ALL_COLUMN_FAMILIES: [&str; 9] = [
+    CF_BLOCKS, CF_TRANSACTIONS, CF_ACCOUNTS, CF_VALIDATORS, CF_APPS, CF_METADATA,
+    CF_TX_INDEX, CF_ACCOUNT_HISTORY, CF_ACCOUNT_CHECKPOINTS,
+];
+
+/// On-disk schema version understood by this binary.
+///
+/// 1: blocks, transactions and accounts only
+/// 2: adds the transaction location and account history indexes
+/// 3: adds the account frozen flag and per-height account checkpoints
+/// 4: adds per-token balances to accounts
+/// 5: adds per-account send limits
+/// 6: indexes metrics reports under the reporting validator too
+/// 7: keeps block, transaction and account counts in metadata
+/// 8: stores the producer's signature with each block
+/// 9: keys transactions by an id covering only signed fields
+/// 10: block headers may commit to the account state root
+/// 11: transfers with an empty memo are stored without one
+pub const SCHEMA_VERSION: u32 = 11;
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+const TOTAL_QOR_SUPPLY_KEY: &str = "total_qor_supply";
+const BLOCK_COUNT_KEY: &str = "block_count";
+const TRANSACTION_COUNT_KEY: &str = "transaction_count";
+const ACCOUNT_COUNT_KEY: &str = "account_count";
+const PRUNED_BELOW_KEY: &str = "pruned_below";
+const REWARD_POOL_KEY: &str = "reward_pool";
+const SPENDING_LIMIT_AUTHORITY_KEY: &str = "spending_limit_authority";
+/// First height whose state writes are all in the undo journal
+const UNDO_FROM_KEY: &str = "undo_from";
+/// Prefix of each account's unclaimed reward entitlement
+const ACCRUED_REWARDS_PREFIX: &str = "accrued_rewards:";
+
+/// Rolling window over which an account's send limit applies (seconds)
+pub const SPENDING_LIMIT_WINDOW_SECS: u64 = 86_400;
+
+/// Head events buffered per subscriber before slow subscribers start lagging
+const HEAD_FEED_CAPACITY: usize = 256;
+
+/// Rows rewritten per write batch by streaming migrations
+const MIGRATION_BATCH_SIZE: usize = 1_000;
+
+/// Block body retention for long-running validators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruningConfig {
+    /// Most recent blocks whose bodies are kept by `prune_to_retention`
+    pub retention_blocks: u64,
+    /// Blocks after which a block is final; non-final blocks are never pruned
+    pub finality_depth: u64,
+}
+
+impl PruningConfig {
+    /// Default retention under the finality depth of the chain `genesis` configures
+    pub fn for_genesis(genesis: &GenesisConfig) -> Self {
+        Self {
+            finality_depth: genesis.finality_depth,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        Self {
+            retention_blocks: 100_000,
+            finality_depth: GenesisConfig::default().finality_depth,
+        }
+    }
+}
 
 /// Account state information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountState {
     pub address: Address,
+    /// Native QOR balance
     pub balance: Balance,
     pub nonce: u64,
+    pub frozen: bool,
     pub created_at: u64,
     pub last_updated: u64,
+    /// Balances of tokens other than QOR; use `get_token_balance` to read either
+    #[serde(default)]
+    pub token_balances: TokenBalance,
+    /// Most QOR the account may send per `SPENDING_LIMIT_WINDOW_SECS`; `None` for no limit
+    #[serde(default)]
+    pub daily_send_limit: Option<u64>,
+    /// `(timestamp, amount)` of sends still inside the window, oldest first
+    #[serde(default)]
+    pub recent_sends: Vec<(Timestamp, u64)>,
+}
+
+/// Account state exported for fast sync.
+///
+/// `state_root` is the `accounts_root` over `accounts`, which are in address
+/// order, so a block header's state root can vouch for it; importers
+/// recompute it before writing anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub height: BlockHeight,
+    pub state_root: Hash,
+    pub accounts: Vec<AccountState>,
+}
+
+/// Account layout before schema version 3 (no frozen flag)
+#[derive(Deserialize)]
+struct LegacyAccountState {
+    address: Address,
+    balance: Balance,
+    nonce: u64,
+    created_at: u64,
+    last_updated: u64,
+}
+
+/// Account layout of schema version 3 (no token balances)
+#[derive(Deserialize)]
+struct AccountStateV3 {
+    address: Address,
+    balance: Balance,
+    nonce: u64,
+    frozen: bool,
+    created_at: u64,
+    last_updated: u64,
+}
+
+/// Account layout of schema version 4 (no send limits)
+#[derive(Deserialize)]
+struct AccountStateV4 {
+    address: Address,
+    balance: Balance,
+    nonce: u64,
+    frozen: bool,
+    created_at: u64,
+    last_updated: u64,
+    token_balances: TokenBalance,
+}
+
+/// Block layout before schema version 8 (no producer signature)
+#[derive(Deserialize)]
+struct BlockV7 {
+    header: BlockHeader,
+    transactions: Vec<Transaction>,
+}
+
+/// Transaction layout before expiry heights, which landed without a schema
+/// bump; only databases at schema version 3 or earlier can hold it
+#[derive(Deserialize)]
+struct TransactionV3 {
+    data: TransactionData,
+    nonce: u64,
+    fee_qor: u64,
+    fee_usd: f64,
+    priority: FeePriority,
+    signature: QoraSignature,
+    signer: Address,
+}
+
+impl From<TransactionV3> for Transaction {
+    fn from(old: TransactionV3) -> Self {
+        Self {
+            data: old.data,
+            nonce: old.nonce,
+            fee_qor: old.fee_qor,
+            fee_usd: old.fee_usd,
+            priority: old.priority,
+            signature: old.signature,
+            signer: old.signer,
+            valid_until_height: None,
+        }
+    }
+}
+
+/// Block layout holding pre-expiry transactions
+#[derive(Deserialize)]
+struct BlockV3 {
+    header: BlockHeader,
+    transactions: Vec<TransactionV3>,
+}
+
+/// Transaction data before transfer memos, which landed before schema versions
+/// existed; only unversioned databases can hold it
+#[derive(Deserialize)]
+enum TransactionDataV0 {
+    Transfer { from: Address, to: Address, amount: u64 },
+    ProvideLiquidity { provider: Address, lp_tokens: Vec<LPToken> },
+    RegisterApp { owner: Address, app_id: AppId, app_type: AppType, resource_requirements: ResourceRequirements },
+    ReportMetrics { validator: Address, app_owner: Address, app_id: AppId, metrics: AppMetrics },
+    ClaimRewards { claimant: Address, lp_rewards: u64, app_rewards: u64 },
+}
+
+impl From<TransactionDataV0> for TransactionData {
+    fn from(old: TransactionDataV0) -> Self {
+        match old {
+            TransactionDataV0::Transfer { from, to, amount } => TransactionData::Transfer { from, to, amount, memo: None },
+            TransactionDataV0::ProvideLiquidity { provider, lp_tokens } => TransactionData::ProvideLiquidity { provider, lp_tokens },
+            TransactionDataV0::RegisterApp { owner, app_id, app_type, resource_requirements } => {
+                TransactionData::RegisterApp { owner, app_id, app_type, resource_requirements }
+            }
+            TransactionDataV0::ReportMetrics { validator, app_owner, app_id, metrics } => {
+                TransactionData::ReportMetrics { validator, app_owner, app_id, metrics }
+            }
+            TransactionDataV0::ClaimRewards { claimant, lp_rewards, app_rewards } => {
+                TransactionData::ClaimRewards { claimant, lp_rewards, app_rewards }
+            }
+        }
+    }
+}
+
+/// Transaction layout before transfer memos (and expiry heights)
+#[derive(Deserialize)]
+struct TransactionV0 {
+    data: TransactionDataV0,
+    nonce: u64,
+    fee_qor: u64,
+    fee_usd: f64,
+    priority: FeePriority,
+    signature: QoraSignature,
+    signer: Address,
+}
+
+impl From<TransactionV0> for Transaction {
+    fn from(old: TransactionV0) -> Self {
+        Self {
+            data: old.data.into(),
+            nonce: old.nonce,
+            fee_qor: old.fee_qor,
+            fee_usd: old.fee_usd,
+            priority: old.priority,
+            signature: old.signature,
+            signer: old.signer,
+            valid_until_height: None,
+        }
+    }
+}
+
+/// Block layout holding pre-memo transactions
+#[derive(Deserialize)]
+struct BlockV0 {
+    header: BlockHeader,
+    transactions: Vec<TransactionV0>,
+}
+
+/// Decode `bytes` as exactly one `T`, refusing trailing bytes.
+///
+/// Used where a value may be in either of two layouts: plain bincode decoding
+/// would accept an older, shorter layout read as a prefix of a newer one.
+fn decode_exact<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> std::result::Result<T, bincode::Error> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)
+}
+
+/// How an account changed between two states
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountDiff {
+    pub address: Address,
+    /// New balance minus old balance
+    pub balance_delta: i128,
+    /// New nonce minus old nonce
+    pub nonce_delta: i128,
+    /// `(old, new)` frozen flags, if they differ
+    pub frozen_change: Option<(bool, bool)>,
+}
+
+impl AccountDiff {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.balance_delta == 0 && self.nonce_delta == 0 && self.frozen_change.is_none()
+    }
 }
 
 impl AccountState {
@@ -31,8 +300,12 @@ impl AccountState {
             address,
             balance: Balance::zero(),
             nonce: 0,
+            frozen: false,
             created_at: now,
             last_updated: now,
+            token_balances: TokenBalance::new(),
+            daily_send_limit: None,
+            recent_sends: Vec::new(),
         }
     }
     
@@ -41,10 +314,74 @@ impl AccountState {
         self.last_updated = chrono::Utc::now().timestamp() as u64;
     }
     
+    /// Balance of `token`; the native QOR address reads `balance`
+    pub fn get_token_balance(&self, token: &Address) -> u64 {
+        if token.is_native_qor() {
+            self.balance.amount
+        } else {
+            self.token_balances.get_token_balance(token)
+        }
+    }
+    
+    /// Set the balance of `token`; the native QOR address writes `balance`
+    pub fn set_token_balance(&mut self, token: &Address, amount: u64) {
+        if token.is_native_qor() {
+            self.update_balance(Balance::new(amount));
+            return;
+        }
+        if amount == 0 {
+            self.token_balances.balances.remove(token);
+        } else {
+            self.token_balances.balances.insert(token.clone(), amount);
+        }
+        self.last_updated = chrono::Utc::now().timestamp() as u64;
+    }
+    
     pub fn increment_nonce(&mut self) {
         self.nonce += 1;
         self.last_updated = chrono::Utc::now().timestamp() as u64;
     }
+    
+    /// QOR sent within the window ending at `now`
+    pub fn sent_in_window(&self, now: Timestamp) -> u64 {
+        let cutoff = now.saturating_sub(SPENDING_LIMIT_WINDOW_SECS);
+        self.recent_sends.iter()
+            .filter(|(at, _)| *at > cutoff)
+            .fold(0u64, |total, (_, amount)| total.saturating_add(*amount))
+    }
+    
+    /// Check that sending `amount` at `now` stays within the send limit
+    pub fn check_send_limit(&self, amount: u64, now: Timestamp) -> Result<()> {
+        let limit = match self.daily_send_limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let spent = self.sent_in_window(now);
+        if spent.saturating_add(amount) > limit {
+            return Err(QoraNetError::SpendingLimitExceeded { limit, spent, requested: amount });
+        }
+        Ok(())
+    }
+    
+    /// Record a send against the window, dropping sends that have left it
+    fn record_send(&mut self, amount: u64, now: Timestamp) {
+        let cutoff = now.saturating_sub(SPENDING_LIMIT_WINDOW_SECS);
+        self.recent_sends.retain(|(at, _)| *at > cutoff);
+        // Nothing to track without a limit
+        if self.daily_send_limit.is_some() {
+            self.recent_sends.push((now, amount));
+        }
+    }
+    
+    /// Changes from `self` (older) to `other` (newer)
+    pub fn diff(&self, other: &AccountState) -> AccountDiff {
+        AccountDiff {
+            address: other.address.clone(),
+            balance_delta: other.balance.amount as i128 - self.balance.amount as i128,
+            nonce_delta: other.nonce as i128 - self.nonce as i128,
+            frozen_change: (self.frozen != other.frozen).then(|| (self.frozen, other.frozen)),
+        }
+    }
 }
 
 /// Blockchain storage layer
@@ -52,45 +389,115 @@ impl AccountState {
 pub struct BlockchainStorage {
     db: DB,
     cache: StorageCache,
+    heads: broadcast::Sender<HeadEvent>,
+    pruning: PruningConfig,
+    /// Holdings discounts receipts itemize fees with
+    fee_discounts: FeeDiscountPolicy,
 }
 
+/// Accounts cached by default
+const DEFAULT_ACCOUNT_CACHE_SIZE: usize = 10_000;
+
 /// In-memory cache for frequently accessed data
 #[derive(Debug)]
 struct StorageCache {
     latest_block_hash: Option<Hash>,
     latest_block_height: BlockHeight,
-    account_cache: HashMap<Address, AccountState>,
+    /// Behind a mutex so reads through `&self` can bump recency
+    account_cache: Mutex<AccountLru>,
+}
+
+/// Least-recently-used account cache.
+///
+/// Every insert or hit takes a fresh tick; `recency` orders addresses by their
+/// last tick, so the least recently used entry is its first key (O(log n)).
+#[derive(Debug)]
+struct AccountLru {
+    entries: HashMap<Address, (AccountState, u64)>,
+    recency: BTreeMap<u64, Address>,
+    next_tick: u64,
     cache_size_limit: usize,
 }
 
+impl AccountLru {
+    fn new(cache_size_limit: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_tick: 0,
+            cache_size_limit,
+        }
+    }
+    
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+    
+    fn insert(&mut self, account: AccountState) {
+        let tick = self.tick();
+        if let Some((_, old_tick)) = self.entries.get(&account.address) {
+            self.recency.remove(old_tick);
+        } else if self.entries.len() >= self.cache_size_limit {
+            if let Some((_, evicted)) = self.recency.pop_first() {
+                self.entries.remove(&evicted);
+            }
+        }
+        
+        self.recency.insert(tick, account.address.clone());
+        self.entries.insert(account.address.clone(), (account, tick));
+    }
+    
+    /// Look up an account, marking it most recently used
+    fn get(&mut self, address: &Address) -> Option<AccountState> {
+        let tick = self.tick();
+        let (account, last_tick) = self.entries.get_mut(address)?;
+        self.recency.remove(last_tick);
+        *last_tick = tick;
+        self.recency.insert(tick, address.clone());
+        Some(account.clone())
+    }
+    
+    fn remove(&mut self, address: &Address) {
+        if let Some((_, tick)) = self.entries.remove(address) {
+            self.recency.remove(&tick);
+        }
+    }
+    
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+    
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
 impl StorageCache {
     fn new() -> Self {
         Self {
             latest_block_hash: None,
             latest_block_height: 0,
-            account_cache: HashMap::new(),
-            cache_size_limit: 10000, // Cache up to 10k accounts
+            account_cache: Mutex::new(AccountLru::new(DEFAULT_ACCOUNT_CACHE_SIZE)),
         }
     }
     
+    /// The cache holds no invariants a panicking holder could break, so poisoning is ignored
+    fn accounts(&self) -> std::sync::MutexGuard<'_, AccountLru> {
+        self.account_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    
     fn cache_account(&mut self, account: AccountState) {
-        if self.account_cache.len() >= self.cache_size_limit {
-            // Simple eviction: remove oldest entry
-            if let Some((oldest_addr, _)) = self.account_cache.iter().min_by_key(|(_, acc)| acc.last_updated) {
-                let oldest_addr = oldest_addr.clone();
-                self.account_cache.remove(&oldest_addr);
-            }
-        }
-        
-        self.account_cache.insert(account.address.clone(), account);
+        self.accounts().insert(account);
     }
     
-    fn get_cached_account(&self, address: &Address) -> Option<&AccountState> {
-        self.account_cache.get(address)
+    fn get_cached_account(&self, address: &Address) -> Option<AccountState> {
+        self.accounts().get(address)
     }
     
     fn invalidate_account(&mut self, address: &Address) {
-        self.account_cache.remove(address);
+        self.accounts().remove(address);
     }
 }
 
@@ -101,7 +508,21 @@ impl BlockchainStorage {
         opts.create_if_missing(true);
         opts.create_missing_column_families(true);
         
-        let column_families = vec![CF_BLOCKS, CF_TRANSACTIONS, CF_ACCOUNTS, CF_VALIDATORS, CF_APPS, CF_METADATA];
+        let column_families = ALL_COLUMN_FAMILIES.to_vec();
+        
+        // Column families we don't know about mean a newer binary created this database
+        if let Ok(existing) = DB::list_cf(&opts, path.as_ref()) {
+            let unknown: Vec<String> = existing.into_iter()
+                .filter(|cf| cf != "default" && !column_families.contains(&cf.as_str()))
+                .collect();
+            if !unknown.is_empty() {
+                return Err(QoraNetError::StorageError(format!(
+                    "Database contains unknown column families {:?}; it was created by a newer version \
+                     (this binary supports schema version {})",
+                    unknown, SCHEMA_VERSION
+                )));
+            }
+        }
         
         let db = DB::open_cf(&opts, path, column_families)
             .map_err(|e| QoraNetError::StorageError(format!("Failed to open database: {}", e)))?;
@@ -109,343 +530,4023 @@ impl BlockchainStorage {
         let mut storage = Self {
             db,
             cache: StorageCache::new(),
+            heads: broadcast::channel(HEAD_FEED_CAPACITY).0,
+            pruning: PruningConfig::default(),
+            fee_discounts: FeeDiscountPolicy::none(),
         };
         
         // Initialize cache with latest block info
         storage.load_latest_block_info()?;
+        storage.check_schema_version()?;
+        if storage.get_metadata(UNDO_FROM_KEY)?.is_none() {
+            // Heights already applied left no journal, so they can't be rewound
+            let from = storage.cache.latest_block_hash.as_ref().map_or(0, |_| storage.cache.latest_block_height + 1);
+            storage.update_metadata(UNDO_FROM_KEY, &from.to_le_bytes())?;
+        }
         
         Ok(storage)
     }
     
-    /// Store a block
+    /// Replace the block pruning settings
+    pub fn set_pruning_config(&mut self, config: PruningConfig) {
+        self.pruning = config;
+    }
+    
+    pub fn pruning_config(&self) -> &PruningConfig {
+        &self.pruning
+    }
+    
+    /// Use the fee oracle's discount policy when itemizing charged fees
+    pub fn set_fee_discount_policy(&mut self, policy: FeeDiscountPolicy) {
+        self.fee_discounts = policy;
+    }
+    
+    /// Subscribe to canonical head changes
+    pub fn subscribe_heads(&self) -> broadcast::Receiver<HeadEvent> {
+        self.heads.subscribe()
+    }
+    
+    /// Sender side of the head feed, for servers that subscribe per client
+    pub fn head_feed(&self) -> broadcast::Sender<HeadEvent> {
+        self.heads.clone()
+    }
+    
+    /// Store a block as the new canonical tip.
+    ///
+    /// A block at or below the current tip replaces those heads (reorg): the
+    /// state their application wrote is rolled back to the fork point and
+    /// their index entries are dropped, so the caller applies the new block on
+    /// top of the state as of its parent. Forks below the oldest journaled
+    /// height are refused.
     pub fn store_block(&mut self, block: &Block) -> Result<()> {
         let block_hash = block.hash();
+        
+        let mut removed = Vec::new();
+        if self.cache.latest_block_hash.is_some() && block.header.height <= self.cache.latest_block_height {
+            for height in (block.header.height..=self.cache.latest_block_height).rev() {
+                if let Some(old) = self.get_block_by_height(height)? {
+                    if old.hash() != block_hash {
+                        removed.push(old);
+                    }
+                }
+            }
+        }
+        
+        // Rollback, block, height mapping, transactions, indexes and tip metadata
+        // land together or not at all
+        let mut batch = WriteBatch::default();
+        if let Some(fork) = removed.iter().map(|old| old.header.height).min() {
+            let revertible = self.get_counter(UNDO_FROM_KEY)?.max(self.pruned_below()?);
+            if fork < revertible {
+                return Err(QoraNetError::StorageError(format!(
+                    "Cannot replace block #{}: state is only journaled from height {}",
+                    fork, revertible
+                )));
+            }
+            self.revert_state_batch(&mut batch, fork)?;
+            self.unindex_blocks_batch(&mut batch, &removed)?;
+        }
+        // Writes in a batch apply in order, so the new block's own index entries win over the rollback
+        self.block_write_batch(&mut batch, block, &block_hash, !removed.is_empty())?;
+        self.db.write(batch)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to store block: {}", e)))?;
+        if !removed.is_empty() {
+            self.cache.accounts().clear();
+        }
+        
+        // Update cache
+        self.cache.latest_block_hash = Some(block_hash);
+        self.cache.latest_block_height = block.header.height;
+        
+        // Publish after commit; no subscribers is fine
+        for old in &removed {
+            let _ = self.heads.send(HeadEvent::Removed(HeadSummary::from_block(old)));
+        }
+        let _ = self.heads.send(HeadEvent::New(HeadSummary::from_block(block)));
+        if let Some(reorg) = ReorgEvent::new(&removed, block) {
+            let _ = self.heads.send(HeadEvent::Reorg(reorg));
+        }
+        
+        Ok(())
+    }
+    
+    /// Add every write needed to make `block` the tip to `batch`, for committing atomically.
+    ///
+    /// With `rewound`, height mappings above the block (from the replaced branch) are deleted.
+    fn block_write_batch(&self, batch: &mut WriteBatch, block: &Block, block_hash: &Hash, rewound: bool) -> Result<()> {
         let serialized_block = bincode::serialize(block)
             .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize block: {}", e)))?;
-        
-        // Store block
         let cf_blocks = self.db.cf_handle(CF_BLOCKS)
             .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
         
-        self.db.put_cf(cf_blocks, block_hash.as_bytes(), &serialized_block)
-            .map_err(|e| QoraNetError::StorageError(format!("Failed to store block: {}", e)))?;
+        // Counters only move for blocks and transactions not already stored (re-stored reorg blocks)
+        if self.get_block(block_hash)?.is_none() {
+            let blocks = self.get_counter(BLOCK_COUNT_KEY)? + 1;
+            batch.put_cf(cf_metadata, BLOCK_COUNT_KEY.as_bytes(), blocks.to_le_bytes());
+        }
+        let mut new_tx_hashes = std::collections::HashSet::new();
+        for tx in &block.transactions {
+            let tx_hash = tx.hash();
+            if self.get_transaction(&tx_hash)?.is_none() {
+                new_tx_hashes.insert(tx_hash);
+            }
+        }
+        if !new_tx_hashes.is_empty() {
+            let transactions = self.get_counter(TRANSACTION_COUNT_KEY)? + new_tx_hashes.len() as u64;
+            batch.put_cf(cf_metadata, TRANSACTION_COUNT_KEY.as_bytes(), transactions.to_le_bytes());
+        }
+        
+        batch.put_cf(cf_blocks, block_hash.as_bytes(), &serialized_block);
         
         // Store block hash by height for quick lookup
-        self.db.put_cf(cf_blocks, format!("height:{}", block.header.height).as_bytes(), block_hash.as_bytes())
-            .map_err(|e| QoraNetError::StorageError(format!("Failed to store block height mapping: {}", e)))?;
+        batch.put_cf(cf_blocks, format!("height:{}", block.header.height).as_bytes(), block_hash.as_bytes());
         
         // Store individual transactions
-        self.store_block_transactions(&block.transactions)?;
+        self.store_block_transactions(batch, block, block_hash)?;
         
-        // Update cache
-        self.cache.latest_block_hash = Some(block_hash);
-        self.cache.latest_block_height = block.header.height;
+        batch.put_cf(cf_metadata, b"latest_block_hash", block_hash.as_bytes());
+        batch.put_cf(cf_metadata, b"latest_block_height", block.header.height.to_le_bytes());
         
-        // Update metadata
-        self.update_metadata("latest_block_hash", block_hash.as_bytes())?;
-        self.update_metadata("latest_block_height", &block.header.height.to_le_bytes())?;
+        // Heights above a rewound tip are no longer canonical
+        if rewound {
+            for height in block.header.height + 1..=self.cache.latest_block_height {
+                batch.delete_cf(cf_blocks, format!("height:{}", height).as_bytes());
+            }
+        }
         
         Ok(())
     }
     
-    /// Store transactions from a block
-    fn store_block_transactions(&self, transactions: &[Transaction]) -> Result<()> {
+    /// Add a block's transactions, along with the tx→block and account history indexes, to `batch`
+    fn store_block_transactions(&self, batch: &mut WriteBatch, block: &Block, block_hash: &Hash) -> Result<()> {
         let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
             .ok_or_else(|| QoraNetError::StorageError("Transactions column family not found".to_string()))?;
+        let cf_tx_index = self.db.cf_handle(CF_TX_INDEX)
+            .ok_or_else(|| QoraNetError::StorageError("Transaction index column family not found".to_string()))?;
+        let cf_history = self.db.cf_handle(CF_ACCOUNT_HISTORY)
+            .ok_or_else(|| QoraNetError::StorageError("Account history column family not found".to_string()))?;
         
-        for tx in transactions {
+        for (index, tx) in block.transactions.iter().enumerate() {
             let tx_hash = tx.hash();
             let serialized_tx = bincode::serialize(tx)
                 .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize transaction: {}", e)))?;
             
-            self.db.put_cf(cf_transactions, tx_hash.as_bytes(), &serialized_tx)
-                .map_err(|e| QoraNetError::StorageError(format!("Failed to store transaction: {}", e)))?;
+            batch.put_cf(cf_transactions, tx_hash.as_bytes(), &serialized_tx);
+            
+            let location = TxLocation {
+                block_hash: block_hash.clone(),
+                block_height: block.header.height,
+                index: index as u32,
+                timestamp: block.header.timestamp,
+            };
+            let serialized_location = bincode::serialize(&location)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize transaction location: {}", e)))?;
+            
+            batch.put_cf(cf_tx_index, tx_hash.as_bytes(), &serialized_location);
+            
+            let cursor = HistoryCursor { height: location.block_height, index: location.index };
+            for participant in tx.data.participants() {
+                batch.put_cf(cf_history, cursor.history_key(participant), tx_hash.as_bytes());
+            }
         }
         
         Ok(())
     }
     
-    /// Get block by hash
-    pub fn get_block(&self, block_hash: &Hash) -> Result<Option<Block>> {
-        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
-            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
+    /// Get the block a transaction was included in
+    pub fn get_transaction_location(&self, tx_hash: &Hash) -> Result<Option<TxLocation>> {
+        let cf_tx_index = self.db.cf_handle(CF_TX_INDEX)
+            .ok_or_else(|| QoraNetError::StorageError("Transaction index column family not found".to_string()))?;
         
-        match self.db.get_cf(cf_blocks, block_hash.as_bytes()) {
+        match self.db.get_cf(cf_tx_index, tx_hash.as_bytes()) {
             Ok(Some(data)) => {
-                let block = bincode::deserialize(&data)
-                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize block: {}", e)))?;
-                Ok(Some(block))
+                let location = bincode::deserialize(&data)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize transaction location: {}", e)))?;
+                Ok(Some(location))
             },
             Ok(None) => Ok(None),
-            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get block: {}", e))),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get transaction location: {}", e))),
         }
     }
     
-    /// Get block by height
-    pub fn get_block_by_height(&self, height: BlockHeight) -> Result<Option<Block>> {
-        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
-            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
+    /// Get an account's transactions newest-first, starting strictly after `cursor`
+    pub fn get_account_history(
+        &self,
+        address: &Address,
+        limit: usize,
+        cursor: Option<HistoryCursor>,
+    ) -> Result<AccountHistoryPage> {
+        let cf_history = self.db.cf_handle(CF_ACCOUNT_HISTORY)
+            .ok_or_else(|| QoraNetError::StorageError("Account history column family not found".to_string()))?;
         
-        // Get block hash by height
-        let height_key = format!("height:{}", height);
-        match self.db.get_cf(cf_blocks, height_key.as_bytes()) {
-            Ok(Some(hash_bytes)) => {
-                if hash_bytes.len() == 32 {
-                    let mut hash_array = [0u8; 32];
-                    hash_array.copy_from_slice(&hash_bytes);
-                    let block_hash = Hash(hash_array);
-                    self.get_block(&block_hash)
-                } else {
-                    Err(QoraNetError::StorageError("Invalid block hash length".to_string()))
-                }
-            },
-            Ok(None) => Ok(None),
-            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get block by height: {}", e))),
+        let start_key = match &cursor {
+            Some(cursor) => cursor.history_key(address),
+            None => HistoryCursor { height: u64::MAX, index: u32::MAX }.history_key(address),
+        };
+        
+        let mut entries = Vec::new();
+        let mut has_more = false;
+        let iter = self.db.iterator_cf(cf_history, IteratorMode::From(&start_key, Direction::Reverse));
+        
+        for item in iter {
+            let (key, value) = item
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to read account history: {}", e)))?;
+            
+            if !key.starts_with(address.as_bytes()) {
+                break;
+            }
+            // Cursor is exclusive
+            if cursor.is_some() && *key == start_key[..] {
+                continue;
+            }
+            if entries.len() >= limit {
+                has_more = true;
+                break;
+            }
+            
+            if value.len() != 32 {
+                return Err(QoraNetError::StorageError("Invalid transaction hash length".to_string()));
+            }
+            let mut hash_array = [0u8; 32];
+            hash_array.copy_from_slice(&value);
+            let tx_hash = Hash(hash_array);
+            
+            let transaction = self.get_transaction(&tx_hash)?
+                .ok_or_else(|| QoraNetError::StorageError(format!("Transaction {} missing from index", tx_hash)))?;
+            let location = self.get_transaction_location(&tx_hash)?
+                .ok_or_else(|| QoraNetError::StorageError(format!("Location of transaction {} missing", tx_hash)))?;
+            let direction = TransferDirection::for_address(&transaction.data, address);
+            
+            entries.push(AccountHistoryEntry { transaction, location, direction });
         }
+        
+        let next_cursor = if has_more {
+            entries.last().map(|entry| HistoryCursor {
+                height: entry.location.block_height,
+                index: entry.location.index,
+            })
+        } else {
+            None
+        };
+        
+        Ok(AccountHistoryPage { entries, next_cursor })
     }
     
-    /// Get transaction by hash
-    pub fn get_transaction(&self, tx_hash: &Hash) -> Result<Option<Transaction>> {
-        let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
-            .ok_or_else(|| QoraNetError::StorageError("Transactions column family not found".to_string()))?;
-        
-        match self.db.get_cf(cf_transactions, tx_hash.as_bytes()) {
-            Ok(Some(data)) => {
-                let transaction = bincode::deserialize(&data)
-                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize transaction: {}", e)))?;
-                Ok(Some(transaction))
+    /// Check the on-disk schema version, migrating older databases in place
+    fn check_schema_version(&mut self) -> Result<()> {
+        let stored_version = match self.get_metadata(SCHEMA_VERSION_KEY)? {
+            Some(bytes) => {
+                let version_bytes: [u8; 4] = bytes.as_slice().try_into()
+                    .map_err(|_| QoraNetError::StorageError("Invalid schema version entry".to_string()))?;
+                u32::from_le_bytes(version_bytes)
             },
-            Ok(None) => Ok(None),
-            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get transaction: {}", e))),
+            // Unversioned databases with blocks predate the transaction indexes
+            None if self.cache.latest_block_hash.is_some() => 1,
+            None => SCHEMA_VERSION,
+        };
+        
+        if stored_version > SCHEMA_VERSION {
+            return Err(QoraNetError::StorageError(format!(
+                "Database schema version {} is newer than supported version {}; \
+                 upgrade the node or restore a compatible backup",
+                stored_version, SCHEMA_VERSION
+            )));
+        }
+        
+        // Blocks first: rebuilding the indexes reads them back
+        if stored_version < 8 {
+            self.migrate_unsigned_blocks(stored_version <= 3)?;
+        }
+        if stored_version <= 3 {
+            self.migrate_unexpiring_transactions()?;
+        }
+        // Before anything rekeys transactions, whose legacy ids depend on memos
+        if stored_version < 11 {
+            self.drop_empty_memos()?;
+        }
+        if stored_version < 6 {
+            self.rebuild_transaction_indexes()?;
         }
+        if stored_version < 9 {
+            self.rekey_transactions()?;
+        }
+        if stored_version < 7 {
+            self.recount_entities()?;
+        }
+        if stored_version < 3 {
+            self.migrate_legacy_accounts()?;
+        } else if stored_version < 4 {
+            self.migrate_accounts(|old: AccountStateV3| AccountState {
+                address: old.address,
+                balance: old.balance,
+                nonce: old.nonce,
+                frozen: old.frozen,
+                created_at: old.created_at,
+                last_updated: old.last_updated,
+                token_balances: TokenBalance::new(),
+                daily_send_limit: None,
+                recent_sends: Vec::new(),
+            })?;
+        } else if stored_version < 5 {
+            self.migrate_accounts(|old: AccountStateV4| AccountState {
+                address: old.address,
+                balance: old.balance,
+                nonce: old.nonce,
+                frozen: old.frozen,
+                created_at: old.created_at,
+                last_updated: old.last_updated,
+                token_balances: old.token_balances,
+                daily_send_limit: None,
+                recent_sends: Vec::new(),
+            })?;
+        }
+        
+        self.update_metadata(SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_le_bytes())
     }
     
-    /// Store account state
-    pub fn store_account(&mut self, account: &AccountState) -> Result<()> {
+    /// Rewrite accounts stored before the frozen flag existed
+    fn migrate_legacy_accounts(&self) -> Result<()> {
         let cf_accounts = self.db.cf_handle(CF_ACCOUNTS)
             .ok_or_else(|| QoraNetError::StorageError("Accounts column family not found".to_string()))?;
         
-        let serialized_account = bincode::serialize(account)
-            .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize account: {}", e)))?;
-        
-        self.db.put_cf(cf_accounts, account.address.as_bytes(), &serialized_account)
-            .map_err(|e| QoraNetError::StorageError(format!("Failed to store account: {}", e)))?;
+        let legacy: Vec<(Box<[u8]>, Box<[u8]>)> = self.db.iterator_cf(cf_accounts, IteratorMode::Start)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to read accounts: {}", e)))?;
         
-        // Update cache
-        self.cache.cache_account(account.clone());
+        for (key, value) in legacy {
+            let old: LegacyAccountState = bincode::deserialize(&value)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize legacy account: {}", e)))?;
+            let account = AccountState {
+                address: old.address,
+                balance: old.balance,
+                nonce: old.nonce,
+                frozen: false,
+                created_at: old.created_at,
+                last_updated: old.last_updated,
+                token_balances: TokenBalance::new(),
+                daily_send_limit: None,
+                recent_sends: Vec::new(),
+            };
+            let serialized_account = bincode::serialize(&account)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize account: {}", e)))?;
+            self.db.put_cf(cf_accounts, &key, &serialized_account)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to migrate account: {}", e)))?;
+        }
         
         Ok(())
     }
     
-    /// Get account state
-    pub fn get_account(&self, address: &Address) -> Result<Option<AccountState>> {
-        // Check cache first
-        if let Some(account) = self.cache.get_cached_account(address) {
-            return Ok(Some(account.clone()));
+    /// Rewrite accounts and account checkpoints stored in an older layout `T`
+    fn migrate_accounts<T: serde::de::DeserializeOwned>(&self, upgrade: impl Fn(T) -> AccountState) -> Result<()> {
+        for cf_name in [CF_ACCOUNTS, CF_ACCOUNT_CHECKPOINTS] {
+            let cf = self.db.cf_handle(cf_name)
+                .ok_or_else(|| QoraNetError::StorageError(format!("{} column family not found", cf_name)))?;
+            
+            let stored: Vec<(Box<[u8]>, Box<[u8]>)> = self.db.iterator_cf(cf, IteratorMode::Start)
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to read {}: {}", cf_name, e)))?;
+            
+            for (key, value) in stored {
+                // Accounts are keyed by address alone; anything else isn't an account
+                if cf_name == CF_ACCOUNTS && key.len() != std::mem::size_of::<Address>() {
+                    continue;
+                }
+                let old: T = bincode::deserialize(&value)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize old account: {}", e)))?;
+                let serialized_account = bincode::serialize(&upgrade(old))
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize account: {}", e)))?;
+                self.db.put_cf(cf, &key, &serialized_account)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to migrate account: {}", e)))?;
+            }
         }
         
-        // Get from database
-        let cf_accounts = self.db.cf_handle(CF_ACCOUNTS)
-            .ok_or_else(|| QoraNetError::StorageError("Accounts column family not found".to_string()))?;
-        
-        match self.db.get_cf(cf_accounts, address.as_bytes()) {
-            Ok(Some(data)) => {
-                let account = bincode::deserialize(&data)
-                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize account: {}", e)))?;
-                Ok(Some(account))
-            },
-            Ok(None) => Ok(None),
-            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get account: {}", e))),
-        }
+        Ok(())
     }
     
-    /// Get or create account state
-    pub fn get_or_create_account(&mut self, address: &Address) -> Result<AccountState> {
-        match self.get_account(address)? {
-            Some(account) => Ok(account),
-            None => {
-                let new_account = AccountState::new(address.clone());
-                self.store_account(&new_account)?;
-                Ok(new_account)
+    /// Rewrite blocks stored before blocks carried a producer signature.
+    ///
+    /// Blocks are streamed and written back in batches of `MIGRATION_BATCH_SIZE`,
+    /// so memory stays flat however long the chain is. An interrupted run is
+    /// safe to repeat: a rewritten block still reads as the old layout.
+    ///
+    /// With `may_predate_expiry`, a block that isn't exactly in a newer layout
+    /// holds transactions from before expiry heights.
+    fn migrate_unsigned_blocks(&self, may_predate_expiry: bool) -> Result<()> {
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
+        let write = |batch: WriteBatch| self.db.write(batch)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to migrate blocks: {}", e)));
+        
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator_cf(cf_blocks, IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read blocks: {}", e)))?;
+            // Height mappings share the column family with the blocks themselves
+            if key.starts_with(b"height:") {
+                continue;
+            }
+            let old = if may_predate_expiry {
+                decode_exact::<Block>(&value)
+                    .map(|block| BlockV7 { header: block.header, transactions: block.transactions })
+                    .or_else(|_| decode_exact::<BlockV7>(&value))
+                    .or_else(|_| decode_exact::<BlockV0>(&value).map(|old| BlockV7 {
+                        header: old.header,
+                        transactions: old.transactions.into_iter().map(Transaction::from).collect(),
+                    }))
+                    .or_else(|_| bincode::deserialize::<BlockV3>(&value).map(|old| BlockV7 {
+                        header: old.header,
+                        transactions: old.transactions.into_iter().map(Transaction::from).collect(),
+                    }))
+            } else {
+                bincode::deserialize::<BlockV7>(&value)
+            }.map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize old block: {}", e)))?;
+            let block = Block {
+                header: old.header,
+                transactions: old.transactions,
+                signature: None,
+            };
+            let serialized_block = bincode::serialize(&block)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize block: {}", e)))?;
+            batch.put_cf(cf_blocks, &key, &serialized_block);
+            if batch.len() >= MIGRATION_BATCH_SIZE {
+                write(std::mem::take(&mut batch))?;
             }
         }
+        
+        write(batch)
     }
     
-    /// Update account balance
-    pub fn update_account_balance(&mut self, address: &Address, new_balance: Balance) -> Result<()> {
-        let mut account = self.get_or_create_account(address)?;
-        account.update_balance(new_balance);
-        self.store_account(&account)?;
-        Ok(())
+    /// Rewrite stored transactions from before expiry heights, in batches.
+    ///
+    /// Transactions already in the current layout are left as they are, so an
+    /// interrupted run is safe to repeat.
+    fn migrate_unexpiring_transactions(&self) -> Result<()> {
+        let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
+            .ok_or_else(|| QoraNetError::StorageError("Transactions column family not found".to_string()))?;
+        let write = |batch: WriteBatch| self.db.write(batch)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to migrate transactions: {}", e)));
+        
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator_cf(cf_transactions, IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read transactions: {}", e)))?;
+            if decode_exact::<Transaction>(&value).is_ok() {
+                continue;
+            }
+            let old = decode_exact::<TransactionV0>(&value)
+                .map(Transaction::from)
+                .or_else(|_| bincode::deserialize::<TransactionV3>(&value).map(Transaction::from))
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize old transaction: {}", e)))?;
+            let serialized_tx = bincode::serialize(&old)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize transaction: {}", e)))?;
+            batch.put_cf(cf_transactions, &key, &serialized_tx);
+            if batch.len() >= MIGRATION_BATCH_SIZE {
+                write(std::mem::take(&mut batch))?;
+            }
+        }
+        
+        write(batch)
     }
     
-    /// Increment account nonce
-    pub fn increment_account_nonce(&mut self, address: &Address) -> Result<u64> {
-        let mut account = self.get_or_create_account(address)?;
-        account.increment_nonce();
-        let new_nonce = account.nonce;
-        self.store_account(&account)?;
-        Ok(new_nonce)
+    /// Store transfers that carry an empty memo as having none.
+    ///
+    /// Empty memos used to hash and sign like no memo, so this keeps every
+    /// stored id and signature valid now that the two encode differently.
+    /// Rewriting is idempotent, so an interrupted run is safe to repeat.
+    fn drop_empty_memos(&self) -> Result<()> {
+        fn drop_empty_memo(tx: &mut Transaction) -> bool {
+            match &mut tx.data {
+                TransactionData::Transfer { memo, .. } if memo.as_ref().map_or(false, |memo| memo.is_empty()) => {
+                    *memo = None;
+                    true
+                }
+                _ => false,
+            }
+        }
+        
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
+        let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
+            .ok_or_else(|| QoraNetError::StorageError("Transactions column family not found".to_string()))?;
+        let write = |batch: WriteBatch| self.db.write(batch)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to migrate memos: {}", e)));
+        
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator_cf(cf_blocks, IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read blocks: {}", e)))?;
+            // Height mappings share the column family with the blocks themselves
+            if key.starts_with(b"height:") {
+                continue;
+            }
+            let mut block: Block = bincode::deserialize(&value)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize block: {}", e)))?;
+            let mut changed = false;
+            for tx in &mut block.transactions {
+                changed |= drop_empty_memo(tx);
+            }
+            if changed {
+                let serialized_block = bincode::serialize(&block)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize block: {}", e)))?;
+                batch.put_cf(cf_blocks, &key, &serialized_block);
+            }
+            if batch.len() >= MIGRATION_BATCH_SIZE {
+                write(std::mem::take(&mut batch))?;
+            }
+        }
+        for item in self.db.iterator_cf(cf_transactions, IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read transactions: {}", e)))?;
+            let mut tx: Transaction = bincode::deserialize(&value)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize transaction: {}", e)))?;
+            if drop_empty_memo(&mut tx) {
+                let serialized_tx = bincode::serialize(&tx)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize transaction: {}", e)))?;
+                batch.put_cf(cf_transactions, &key, &serialized_tx);
+            }
+            if batch.len() >= MIGRATION_BATCH_SIZE {
+                write(std::mem::take(&mut batch))?;
+            }
+        }
+        
+        write(batch)
     }
     
-    /// Get latest block info
-    pub fn get_latest_block_info(&self) -> (Option<Hash>, BlockHeight) {
-        (self.cache.latest_block_hash.clone(), self.cache.latest_block_height)
+    /// Initialize the block, transaction and account counters by counting keys once
+    fn recount_entities(&self) -> Result<()> {
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
+        let mut blocks = 0u64;
+        for item in self.db.iterator_cf(cf_blocks, IteratorMode::Start) {
+            let (key, _) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read blocks: {}", e)))?;
+            // Height mappings share the column family with the blocks themselves
+            if !key.starts_with(b"height:") {
+                blocks += 1;
+            }
+        }
+        
+        let cf_accounts = self.db.cf_handle(CF_ACCOUNTS)
+            .ok_or_else(|| QoraNetError::StorageError("Accounts column family not found".to_string()))?;
+        let mut accounts = 0u64;
+        for item in self.db.iterator_cf(cf_accounts, IteratorMode::Start) {
+            let (key, _) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read accounts: {}", e)))?;
+            if key.len() == std::mem::size_of::<Address>() {
+                accounts += 1;
+            }
+        }
+        
+        let transactions = self.count_keys(CF_TRANSACTIONS)? as u64;
+        
+        self.update_metadata(BLOCK_COUNT_KEY, &blocks.to_le_bytes())?;
+        self.update_metadata(TRANSACTION_COUNT_KEY, &transactions.to_le_bytes())?;
+        self.update_metadata(ACCOUNT_COUNT_KEY, &accounts.to_le_bytes())
     }
     
-    /// Load latest block info into cache
-    fn load_latest_block_info(&mut self) -> Result<()> {
-        // Load latest block height
-        if let Some(height_bytes) = self.get_metadata("latest_block_height")? {
-            if height_bytes.len() == 8 {
-                let mut height_array = [0u8; 8];
-                height_array.copy_from_slice(&height_bytes);
-                self.cache.latest_block_height = u64::from_le_bytes(height_array);
-            }
+    /// Rebuild the transaction location and account history indexes from stored blocks
+    fn rebuild_transaction_indexes(&self) -> Result<()> {
+        if self.cache.latest_block_hash.is_none() {
+            return Ok(());
         }
         
-        // Load latest block hash
-        if let Some(hash_bytes) = self.get_metadata("latest_block_hash")? {
-            if hash_bytes.len() == 32 {
-                let mut hash_array = [0u8; 32];
-                hash_array.copy_from_slice(&hash_bytes);
-                self.cache.latest_block_hash = Some(Hash(hash_array));
+        for height in 0..=self.cache.latest_block_height {
+            if let Some(block_hash) = self.get_block_hash_by_height(height)? {
+                if let Some(block) = self.get_block(&block_hash)? {
+                    let mut batch = WriteBatch::default();
+                    self.store_block_transactions(&mut batch, &block, &block_hash)?;
+                    self.db.write(batch)
+                        .map_err(|e| QoraNetError::StorageError(format!("Failed to rebuild transaction indexes: {}", e)))?;
+                }
             }
         }
         
         Ok(())
     }
     
-    /// Update metadata
-    fn update_metadata(&self, key: &str, value: &[u8]) -> Result<()> {
-        let cf_metadata = self.db.cf_handle(CF_METADATA)
-            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+    /// Move stored transactions and their locations from legacy ids to current ones.
+    ///
+    /// Works block by block; an interrupted run is safe to repeat.
+    fn rekey_transactions(&self) -> Result<()> {
+        if self.cache.latest_block_hash.is_none() {
+            return Ok(());
+        }
+        let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
+            .ok_or_else(|| QoraNetError::StorageError("Transactions column family not found".to_string()))?;
+        let cf_tx_index = self.db.cf_handle(CF_TX_INDEX)
+            .ok_or_else(|| QoraNetError::StorageError("Transaction index column family not found".to_string()))?;
         
-        self.db.put_cf(cf_metadata, key.as_bytes(), value)
-            .map_err(|e| QoraNetError::StorageError(format!("Failed to update metadata: {}", e)))?;
+        for height in 0..=self.cache.latest_block_height {
+            let block_hash = match self.get_block_hash_by_height(height)? {
+                Some(block_hash) => block_hash,
+                None => continue,
+            };
+            if let Some(block) = self.get_block(&block_hash)? {
+                let mut batch = WriteBatch::default();
+                for tx in &block.transactions {
+                    let legacy = tx.legacy_hash();
+                    batch.delete_cf(cf_transactions, legacy.as_bytes());
+                    batch.delete_cf(cf_tx_index, legacy.as_bytes());
+                }
+                // Rewrites the history entries in place, now pointing at the new ids
+                self.store_block_transactions(&mut batch, &block, &block_hash)?;
+                self.db.write(batch)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to rekey transactions: {}", e)))?;
+            }
+        }
         
         Ok(())
     }
     
-    /// Get metadata
-    fn get_metadata(&self, key: &str) -> Result<Option<Vec<u8>>> {
-        let cf_metadata = self.db.cf_handle(CF_METADATA)
-            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+    /// Get block by hash
+    pub fn get_block(&self, block_hash: &Hash) -> Result<Option<Block>> {
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
         
-        match self.db.get_cf(cf_metadata, key.as_bytes()) {
-            Ok(data) => Ok(data),
-            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get metadata: {}", e))),
+        match self.db.get_cf(cf_blocks, block_hash.as_bytes()) {
+            Ok(Some(data)) => {
+                let block = bincode::deserialize(&data)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize block: {}", e)))?;
+                Ok(Some(block))
+            },
+            Ok(None) => Ok(None),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get block: {}", e))),
         }
     }
     
-    /// Get block range
-    pub fn get_blocks_range(&self, start_height: BlockHeight, end_height: BlockHeight) -> Result<Vec<Block>> {
-        let mut blocks = Vec::new();
+    /// Get block by height
+    pub fn get_block_by_height(&self, height: BlockHeight) -> Result<Option<Block>> {
+        match self.get_block_hash_by_height(height)? {
+            Some(block_hash) => self.get_block(&block_hash),
+            None => Ok(None),
+        }
+    }
+    
+    /// Get block hash from the height index
+    pub fn get_block_hash_by_height(&self, height: BlockHeight) -> Result<Option<Hash>> {
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
         
-        for height in start_height..=end_height {
-            if let Some(block) = self.get_block_by_height(height)? {
-                blocks.push(block);
+        let height_key = format!("height:{}", height);
+        match self.db.get_cf(cf_blocks, height_key.as_bytes()) {
+            Ok(Some(hash_bytes)) => {
+                if hash_bytes.len() == 32 {
+                    let mut hash_array = [0u8; 32];
+                    hash_array.copy_from_slice(&hash_bytes);
+                    Ok(Some(Hash(hash_array)))
+                } else {
+                    Err(QoraNetError::StorageError("Invalid block hash length".to_string()))
+                }
+            },
+            Ok(None) => Ok(None),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get block by height: {}", e))),
+        }
+    }
+    
+    /// Transactions reverted by a reorg that the canonical chain doesn't include
+    pub fn reverted_transactions(&self, reorg: &ReorgEvent) -> Result<Vec<Transaction>> {
+        let mut transactions = Vec::new();
+        for tx_hash in &reorg.reverted_tx_ids {
+            if self.is_canonical(tx_hash)? {
+                continue;
+            }
+            if let Some(transaction) = self.get_transaction(tx_hash)? {
+                transactions.push(transaction);
             }
         }
-        
-        Ok(blocks)
+        Ok(transactions)
     }
     
-    /// Get recent transactions for an account
-    pub fn get_account_transactions(&self, address: &Address, limit: usize) -> Result<Vec<Transaction>> {
+    /// Transactions of a head rewound by a reorg (a `HeadEvent::Removed`) that the canonical chain doesn't include
+    pub fn removed_head_transactions(&self, head: &HeadSummary) -> Result<Vec<Transaction>> {
+        let block = match self.get_block(&head.hash)? {
+            Some(block) => block,
+            None => return Ok(Vec::new()),
+        };
+        let mut transactions = Vec::new();
+        for transaction in block.transactions {
+            if !self.is_canonical(&transaction.hash())? {
+                transactions.push(transaction);
+            }
+        }
+        Ok(transactions)
+    }
+    
+    /// Whether a transaction's indexed block is on the canonical chain
+    fn is_canonical(&self, tx_hash: &Hash) -> Result<bool> {
+        let location = match self.get_transaction_location(tx_hash)? {
+            Some(location) => location,
+            None => return Ok(false),
+        };
+        Ok(self.get_block_by_height(location.block_height)?
+            .map_or(false, |block| block.hash() == location.block_hash))
+    }
+    
+    /// Get transaction by hash
+    pub fn get_transaction(&self, tx_hash: &Hash) -> Result<Option<Transaction>> {
         let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
             .ok_or_else(|| QoraNetError::StorageError("Transactions column family not found".to_string()))?;
         
-        let mut transactions = Vec::new();
-        let iter = self.db.iterator_cf(cf_transactions, IteratorMode::Start);
+        match self.db.get_cf(cf_transactions, tx_hash.as_bytes()) {
+            Ok(Some(data)) => {
+                let transaction = bincode::deserialize(&data)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize transaction: {}", e)))?;
+                Ok(Some(transaction))
+            },
+            Ok(None) => Ok(None),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get transaction: {}", e))),
+        }
+    }
+    
+    /// Store account state
+    pub fn store_account(&mut self, account: &AccountState) -> Result<()> {
+        let cf_accounts = self.db.cf_handle(CF_ACCOUNTS)
+            .ok_or_else(|| QoraNetError::StorageError("Accounts column family not found".to_string()))?;
+        let cf_checkpoints = self.db.cf_handle(CF_ACCOUNT_CHECKPOINTS)
+            .ok_or_else(|| QoraNetError::StorageError("Account checkpoints column family not found".to_string()))?;
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
         
-        for item in iter {
-            match item {
-                Ok((_, value)) => {
-                    if let Ok(tx) = bincode::deserialize::<Transaction>(&value) {
-                        // Check if transaction involves this address
-                        let involves_address = match &tx.data {
-                            crate::transaction::TransactionData::Transfer { from, to, .. } => {
-                                from == address || to == address
-                            },
-                            crate::transaction::TransactionData::ProvideLiquidity { provider, .. } => {
-                                provider == address
-                            },
-                            crate::transaction::TransactionData::RegisterApp { owner, .. } => {
-                                owner == address
-                            },
-                            crate::transaction::TransactionData::ReportMetrics { app_owner, .. } => {
-                                app_owner == address
-                            },
-                            crate::transaction::TransactionData::ClaimRewards { claimant, .. } => {
-                                claimant == address
-                            },
-                        };
-                        
-                        if involves_address {
-                            transactions.push(tx);
-                            if transactions.len() >= limit {
-                                break;
-                            }
-                        }
-                    }
-                },
-                Err(_) => continue,
-            }
+        let serialized_account = bincode::serialize(account)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize account: {}", e)))?;
+        
+        self.journal_state_write(CF_ACCOUNTS, account.address.as_bytes())?;
+        let mut batch = WriteBatch::default();
+        batch.put_cf(cf_accounts, account.address.as_bytes(), &serialized_account);
+        
+        // Checkpoint the state as of the current height (last write within a height wins)
+        batch.put_cf(
+            cf_checkpoints,
+            account_checkpoint_key(&account.address, self.cache.latest_block_height),
+            &serialized_account,
+        );
+        
+        // Updates to an existing account don't change the count
+        if self.get_account(&account.address)?.is_none() {
+            let accounts = self.get_counter(ACCOUNT_COUNT_KEY)? + 1;
+            batch.put_cf(cf_metadata, ACCOUNT_COUNT_KEY.as_bytes(), accounts.to_le_bytes());
         }
         
-        // Sort by most recent first (would need block timestamp in real implementation)
-        transactions.reverse();
-        Ok(transactions)
+        self.db.write(batch)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to store account: {}", e)))?;
+        
+        // Update cache
+        self.cache.cache_account(account.clone());
+        
+        Ok(())
     }
     
-    /// Database statistics
-    pub fn get_storage_stats(&self) -> Result<StorageStats> {
-        let cf_blocks = self.db.cf_handle(CF_BLOCKS).unwrap();
-        let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS).unwrap();
-        let cf_accounts = self.db.cf_handle(CF_ACCOUNTS).unwrap();
+    /// Get account state
+    pub fn get_account(&self, address: &Address) -> Result<Option<AccountState>> {
+        // Check cache first
+        if let Some(account) = self.cache.get_cached_account(address) {
+            return Ok(Some(account));
+        }
         
-        // Count entries (simplified - in production would use more efficient method)
-        let mut block_count = 0;
-        let mut transaction_count = 0;
-        let mut account_count = 0;
+        // Get from database
+        let cf_accounts = self.db.cf_handle(CF_ACCOUNTS)
+            .ok_or_else(|| QoraNetError::StorageError("Accounts column family not found".to_string()))?;
         
-        for _ in self.db.iterator_cf(cf_blocks, IteratorMode::Start) {
-            block_count += 1;
+        match self.db.get_cf(cf_accounts, address.as_bytes()) {
+            Ok(Some(data)) => {
+                let account = bincode::deserialize(&data)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize account: {}", e)))?;
+                Ok(Some(account))
+            },
+            Ok(None) => Ok(None),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get account: {}", e))),
         }
+    }
+    
+    /// Holdings a fee discount is granted for: the account's committed QOR balance.
+    ///
+    /// LP positions aren't tracked in account state, so they don't count yet.
+    pub fn account_holdings(&self, address: &Address) -> Result<AccountHoldings> {
+        let qor_balance = self.get_account(address)?.map_or(0, |account| account.balance.amount);
+        Ok(AccountHoldings { qor_balance, lp_value: 0 })
+    }
+    
+    /// Account state as of the end of `height`, or `None` if it didn't exist yet
+    pub fn get_account_at_height(&self, address: &Address, height: BlockHeight) -> Result<Option<AccountState>> {
+        let cf_checkpoints = self.db.cf_handle(CF_ACCOUNT_CHECKPOINTS)
+            .ok_or_else(|| QoraNetError::StorageError("Account checkpoints column family not found".to_string()))?;
         
-        for _ in self.db.iterator_cf(cf_transactions, IteratorMode::Start) {
-            transaction_count += 1;
+        let start_key = account_checkpoint_key(address, height);
+        let mut iter = self.db.iterator_cf(cf_checkpoints, IteratorMode::From(&start_key, Direction::Reverse));
+        
+        match iter.next() {
+            Some(Ok((key, value))) if key.starts_with(address.as_bytes()) => {
+                let account = bincode::deserialize(&value)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize account checkpoint: {}", e)))?;
+                Ok(Some(account))
+            },
+            Some(Err(e)) => Err(QoraNetError::StorageError(format!("Failed to read account checkpoint: {}", e))),
+            _ => Ok(None),
+        }
+    }
+    
+    /// How an account changed from the end of `height_a` to the end of `height_b`
+    pub fn account_diff_between(
+        &self,
+        address: &Address,
+        height_a: BlockHeight,
+        height_b: BlockHeight,
+    ) -> Result<AccountDiff> {
+        // Accounts that didn't exist yet diff as empty
+        let empty = || AccountState { created_at: 0, last_updated: 0, ..AccountState::new(address.clone()) };
+        let before = self.get_account_at_height(address, height_a)?.unwrap_or_else(empty);
+        let after = self.get_account_at_height(address, height_b)?.unwrap_or_else(empty);
+        Ok(before.diff(&after))
+    }
+    
+    /// Confirmed balance adjusted by the account's pending transactions, floored at zero
+    pub fn pending_balance(&self, address: &Address, pool: &TransactionPool) -> Result<u64> {
+        let confirmed = self.get_account(address)?.map_or(0, |account| account.balance.amount);
+        let pending = confirmed as i128 + pool.pending_balance_delta(address);
+        Ok(pending.clamp(0, u64::MAX as i128) as u64)
+    }
+    
+    /// Pending balance of `address` once `candidate` joins the pool, in place of any
+    /// pending transaction it replaces; negative when the signer can't cover it
+    pub fn pending_balance_with(&self, address: &Address, pool: &TransactionPool, candidate: &Transaction) -> Result<i128> {
+        let confirmed = self.get_account(address)?.map_or(0, |account| account.balance.amount);
+        Ok(confirmed as i128 + pool.pending_balance_delta_with(address, candidate))
+    }
+    
+    /// Get or create account state
+    pub fn get_or_create_account(&mut self, address: &Address) -> Result<AccountState> {
+        match self.get_account(address)? {
+            Some(account) => Ok(account),
+            None => {
+                let new_account = AccountState::new(address.clone());
+                self.store_account(&new_account)?;
+                Ok(new_account)
+            }
+        }
+    }
+    
+    /// Set `address`'s balance of `token` (QOR for the native address)
+    pub fn update_account_balance(&mut self, address: &Address, token: &Address, new_balance: Balance) -> Result<()> {
+        if !token.is_native_qor() {
+            let mut account = self.get_or_create_account(address)?;
+            account.set_token_balance(token, new_balance.amount);
+            return self.store_account(&account);
         }
         
-        for _ in self.db.iterator_cf(cf_accounts, IteratorMode::Start) {
-            account_count += 1;
+        let current = self.get_account(address)?
+            .map(|account| account.balance.amount)
+            .unwrap_or(0);
+        self.apply_balance_delta(address, new_balance.amount as i128 - current as i128)?;
+        Ok(())
+    }
+    
+    /// Atomically add `delta` (may be negative) to an account balance.
+    ///
+    /// The read-modify-write happens within one `&mut self` call, so callers
+    /// sharing storage across tasks must hold their write lock for the whole
+    /// call rather than reading the balance under a separate lock.
+    pub fn apply_balance_delta(&mut self, address: &Address, delta: i128) -> Result<Balance> {
+        let mut account = self.get_or_create_account(address)?;
+        let mut balance = account.balance.clone();
+        
+        let magnitude = u64::try_from(delta.unsigned_abs())
+            .map_err(|_| QoraNetError::InvalidTransaction("Balance delta out of range".to_string()))?;
+        if delta >= 0 {
+            balance.add(magnitude)?;
+        } else {
+            balance.subtract(magnitude)?;
         }
         
-        Ok(StorageStats {
-            latest_block_height: self.cache.latest_block_height,
-            total_blocks: block_count / 2, // Divide by 2 because we store height mapping too
-            total_transactions: transaction_count,
-            total_accounts: account_count,
-            cache_size: self.cache.account_cache.len(),
-        })
+        account.update_balance(balance.clone());
+        self.store_account(&account)?;
+        Ok(balance)
     }
     
-    /// Flush cache to disk
-    pub fn flush(&mut self) -> Result<()> {
-        // Invalidate cache to force reload from disk
-        self.cache.account_cache.clear();
-        self.load_latest_block_info()?;
+    /// Credit several accounts as one operation.
+    ///
+    /// Credits are applied in `canonical_outputs` order whatever order they are
+    /// listed in, and all are checked before any is written, so a failing
+    /// credit leaves no partial state behind.
+    pub fn apply_credits(&mut self, credits: &[(Address, u64)]) -> Result<()> {
+        let credits = canonical_outputs(credits)?;
+        for (address, amount) in &credits {
+            let current = self.get_account(address)?.map_or(0, |account| account.balance.amount);
+            if current.checked_add(*amount).is_none() {
+                return Err(QoraNetError::InvalidTransaction(format!("Balance overflow crediting {}", address)));
+            }
+        }
+        
+        for (address, amount) in &credits {
+            self.apply_balance_delta(address, *amount as i128)?;
+        }
         Ok(())
     }
-}
-
-/// Storage statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+    
+    /// Increment account nonce
+    pub fn increment_account_nonce(&mut self, address: &Address) -> Result<u64> {
+        let mut account = self.get_or_create_account(address)?;
+        account.increment_nonce();
+        let new_nonce = account.nonce;
+        self.store_account(&account)?;
+        Ok(new_nonce)
+    }
+    
+    /// Consume `expected_nonce` for a transaction from `address`.
+    ///
+    /// The check and the increment happen in the same `&mut self` call, so of
+    /// two transactions reusing a nonce only the first one applied succeeds.
+    pub fn try_consume_nonce(&mut self, address: &Address, expected_nonce: u64) -> Result<()> {
+        let mut account = self.get_or_create_account(address)?;
+        if account.nonce != expected_nonce {
+            return Err(QoraNetError::InvalidTransaction(format!(
+                "Nonce mismatch: expected {}, got {}",
+                account.nonce, expected_nonce
+            )));
+        }
+        account.increment_nonce();
+        self.store_account(&account)
+    }
+    
+    /// Total native QOR minted so far (genesis allocations plus block rewards)
+    pub fn total_qor_supply(&self) -> Result<u64> {
+        self.get_counter(TOTAL_QOR_SUPPLY_KEY)
+    }
+    
+    /// Credit the genesis allocations, refusing configs that exceed the supply cap
+    pub fn apply_genesis_allocations(&mut self, genesis: &GenesisConfig) -> Result<()> {
+        let total = genesis.total_allocated()?;
+        if self.total_qor_supply()? != 0 {
+            return Err(QoraNetError::StorageError("Genesis allocations already applied".to_string()));
+        }
+        
+        for allocation in &genesis.allocations {
+            self.apply_balance_delta(&allocation.address, allocation.amount as i128)?;
+        }
+        // Send limit updates are authorized against this, so blocks apply without the genesis config
+        if let Some(authority) = &genesis.spending_limit_authority {
+            self.update_state_metadata(SPENDING_LIMIT_AUTHORITY_KEY, authority.as_bytes())?;
+        }
+        self.update_state_metadata(TOTAL_QOR_SUPPLY_KEY, &total.to_le_bytes())
+    }
+    
+    /// Mint a block reward, clamped so total supply never exceeds `max_supply`.
+    ///
+    /// Returns the amount actually minted, which is zero once the cap is reached.
+    pub fn mint_block_reward(&mut self, validator: &Address, reward: u64, max_supply: u64) -> Result<u64> {
+        let supply = self.total_qor_supply()?;
+        let minted = reward.min(max_supply.saturating_sub(supply));
+        if minted == 0 {
+            return Ok(0);
+        }
+        
+        self.apply_credits(&[(validator.clone(), minted)])?;
+        self.update_state_metadata(TOTAL_QOR_SUPPLY_KEY, &(supply + minted).to_le_bytes())?;
+        Ok(minted)
+    }
+    
+    /// Epoch reward pool accounting
+    pub fn reward_pool(&self) -> Result<RewardPool> {
+        match self.get_metadata(REWARD_POOL_KEY)? {
+            Some(bytes) => bincode::deserialize(&bytes)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize reward pool: {}", e))),
+            None => Ok(RewardPool::default()),
+        }
+    }
+    
+    fn store_reward_pool(&self, pool: &RewardPool) -> Result<()> {
+        let serialized = bincode::serialize(pool)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize reward pool: {}", e)))?;
+        self.update_state_metadata(REWARD_POOL_KEY, &serialized)
+    }
+    
+    /// Route a block's share of fees and minted reward into the pool of `epoch`.
+    ///
+    /// The first block of a new epoch finalizes the previous one. Returns the
+    /// amount pooled.
+    pub fn fund_reward_pool(&mut self, epoch: u64, fees: u64, minted: u64, config: &RewardPoolConfig) -> Result<u64> {
+        let pooled = config.pooled(fees, minted);
+        let mut pool = self.reward_pool()?;
+        pool.deposit(epoch, pooled)?;
+        self.store_reward_pool(&pool)?;
+        Ok(pooled)
+    }
+    
+    /// Mint the reward for a block whose transactions paid `fees` and split
+    /// the fees and minted reward between the reward pool of `epoch` and the
+    /// block's `producer`.
+    ///
+    /// `fees` must be what `apply_block_transactions` debited from senders
+    /// (see `Block::collected_fees`); only the minted part adds to the supply,
+    /// so the pool's fee share keeps flowing once the cap is reached. The first
+    /// block of an epoch accrues what the previous one collected to the
+    /// validators of its top apps. Returns the amount paid to the producer.
+    pub fn reward_block(&mut self, producer: &Address, fees: u64, epoch: u64, genesis: &GenesisConfig) -> Result<u64> {
+        let supply = self.total_qor_supply()?;
+        let minted = genesis.block_reward.min(genesis.max_qor_supply.saturating_sub(supply));
+        let earned = fees.checked_add(minted)
+            .ok_or_else(|| QoraNetError::StorageError("Block reward overflows".to_string()))?;
+        let pooled = genesis.reward_pool.pooled(fees, minted).min(earned);
+        
+        // Deposit even when nothing is pooled: the first block of an epoch finalizes the last
+        let mut pool = self.reward_pool()?;
+        let closed = if epoch > pool.epoch { pool.accumulating } else { 0 };
+        pool.deposit(epoch, pooled)?;
+        self.store_reward_pool(&pool)?;
+        if closed > 0 {
+            self.distribute_app_rewards(closed, genesis.max_rewarded_apps)?;
+        }
+        if earned > pooled {
+            self.apply_credits(&[(producer.clone(), earned - pooled)])?;
+        }
+        if minted > 0 {
+            self.update_state_metadata(TOTAL_QOR_SUPPLY_KEY, &(supply + minted).to_le_bytes())?;
+        }
+        Ok(earned - pooled)
+    }
+    
+    /// Rewards `account` has earned but not yet claimed
+    pub fn accrued_rewards(&self, account: &Address) -> Result<u64> {
+        match self.get_metadata(&accrued_rewards_key(account))? {
+            Some(bytes) => {
+                let amount: [u8; 8] = bytes.as_slice().try_into()
+                    .map_err(|_| QoraNetError::StorageError("Corrupt accrued rewards".to_string()))?;
+                Ok(u64::from_le_bytes(amount))
+            }
+            None => Ok(0),
+        }
+    }
+    
+    /// Credit `amount` to what `account` may claim from the reward pool
+    pub fn accrue_rewards(&mut self, account: &Address, amount: u64) -> Result<()> {
+        let accrued = self.accrued_rewards(account)?.checked_add(amount)
+            .ok_or_else(|| QoraNetError::StorageError(format!("Accrued rewards overflow for {}", account)))?;
+        self.update_state_metadata(&accrued_rewards_key(account), &accrued.to_le_bytes())
+    }
+    
+    /// Apply a `ClaimRewards` transaction, paying it from the finalized pool.
+    ///
+    /// A claim can take no more than the claimant has accrued, and never more
+    /// than the pool holds.
+    pub fn apply_claim_rewards(&mut self, transaction: &Transaction) -> Result<u64> {
+        let (claimant, amount) = match &transaction.data {
+            TransactionData::ClaimRewards { claimant, lp_rewards, app_rewards } => {
+                let amount = lp_rewards.checked_add(*app_rewards)
+                    .ok_or_else(|| QoraNetError::InvalidTransaction("Claim amount overflows".to_string()))?;
+                (claimant, amount)
+            }
+            _ => return Err(QoraNetError::InvalidTransaction("Not a reward claim".to_string())),
+        };
+        if *claimant != transaction.signer {
+            return Err(QoraNetError::InvalidTransaction("Rewards can only be claimed by the claimant".to_string()));
+        }
+        
+        let accrued = self.accrued_rewards(claimant)?;
+        if amount > accrued {
+            return Err(QoraNetError::InvalidTransaction(format!(
+                "Claim of {} exceeds the {} rewards accrued to {}", amount, accrued, claimant
+            )));
+        }
+        
+        let mut pool = self.reward_pool()?;
+        pool.claim(amount)?;
+        self.try_consume_nonce(&transaction.signer, transaction.nonce)?;
+        self.apply_credits(&[(claimant.clone(), amount)])?;
+        self.update_state_metadata(&accrued_rewards_key(claimant), &(accrued - amount).to_le_bytes())?;
+        self.store_reward_pool(&pool)?;
+        Ok(amount)
+    }
+    
+    /// Split a closed epoch's pool between the top `max_apps` apps by smoothed
+    /// score, accruing each share to the validator hosting the app.
+    ///
+    /// Shares are merged per validator and accrued in `canonical_outputs` order,
+    /// like any other multi-recipient credit.
+    fn distribute_app_rewards(&mut self, amount: u64, max_apps: usize) -> Result<()> {
+        let shares: Vec<AppRewardShare> = self.app_scores()?.into_iter()
+            .map(|score| AppRewardShare {
+                validator: score.validator,
+                app_owner: score.app_owner,
+                app_id: score.app_id,
+                score: score.smoothed_score as f64 / SCORE_SCALE,
+            })
+            .collect();
+        let rewards: Vec<(Address, u64)> = compute_top_app_rewards(amount, &shares, ValidatorRewardCap::default(), max_apps)
+            .into_iter()
+            .filter(|reward| reward.amount > 0)
+            .map(|reward| (reward.validator, reward.amount))
+            .collect();
+        for (validator, amount) in canonical_outputs(&rewards)? {
+            self.accrue_rewards(&validator, amount)?;
+        }
+        Ok(())
+    }
+    
+    /// Apply a `ReportMetrics` transaction, folding the report into the app's smoothed score
+    fn apply_report_metrics(&mut self, transaction: &Transaction) -> Result<()> {
+        let (validator, app_owner, app_id, metrics) = match &transaction.data {
+            TransactionData::ReportMetrics { validator, app_owner, app_id, metrics } => (validator, app_owner, app_id, metrics),
+            _ => return Err(QoraNetError::InvalidTransaction("Not a metrics report".to_string())),
+        };
+        if *validator != transaction.signer {
+            return Err(QoraNetError::InvalidTransaction("Metrics can only be reported by the hosting validator".to_string()));
+        }
+        
+        self.try_consume_nonce(validator, transaction.nonce)?;
+        let key = app_score_key(validator, &app_id.namespaced(app_owner));
+        let cf_apps = self.db.cf_handle(CF_APPS)
+            .ok_or_else(|| QoraNetError::StorageError("Applications column family not found".to_string()))?;
+        let previous = match self.db.get_cf(cf_apps, &key) {
+            Ok(Some(bytes)) => Some(Self::decode_app_score(&bytes)?.smoothed_score),
+            Ok(None) => None,
+            Err(e) => return Err(QoraNetError::StorageError(format!("Failed to read app score: {}", e))),
+        };
+        let score = AppScore {
+            validator: validator.clone(),
+            app_owner: app_owner.clone(),
+            app_id: app_id.to_string(),
+            smoothed_score: smooth_score(previous, metrics.performance_score()),
+        };
+        let serialized = bincode::serialize(&score)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize app score: {}", e)))?;
+        self.put_state(CF_APPS, &key, &serialized)
+    }
+    
+    /// Smoothed score of every app with a metrics report on chain, in key order
+    pub fn app_scores(&self) -> Result<Vec<AppScore>> {
+        let cf_apps = self.db.cf_handle(CF_APPS)
+            .ok_or_else(|| QoraNetError::StorageError("Applications column family not found".to_string()))?;
+        let mut scores = Vec::new();
+        for item in self.db.prefix_iterator_cf(cf_apps, APP_SCORE_PREFIX) {
+            let (key, value) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read app scores: {}", e)))?;
+            if !key.starts_with(APP_SCORE_PREFIX) {
+                break;
+            }
+            scores.push(Self::decode_app_score(&value)?);
+        }
+        Ok(scores)
+    }
+    
+    fn decode_app_score(bytes: &[u8]) -> Result<AppScore> {
+        bincode::deserialize(bytes)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize app score: {}", e)))
+    }
+    
+    /// Custody key the chain's genesis allowed to set send limits, if any
+    pub fn spending_limit_authority(&self) -> Result<Option<Address>> {
+        match self.get_metadata(SPENDING_LIMIT_AUTHORITY_KEY)? {
+            Some(bytes) => {
+                let address: [u8; 32] = bytes.as_slice().try_into()
+                    .map_err(|_| QoraNetError::StorageError("Corrupt spending limit authority".to_string()))?;
+                Ok(Some(Address(address)))
+            }
+            None => Ok(None),
+        }
+    }
+    
+    /// Apply a `SetSendLimit` at `now`; its signer must be the spending limit authority
+    fn apply_set_send_limit(&mut self, transaction: &Transaction, now: Timestamp) -> Result<()> {
+        let (authority, account, limit) = match &transaction.data {
+            TransactionData::SetSendLimit { authority, account, limit } => (authority, account, *limit),
+            _ => return Err(QoraNetError::InvalidTransaction("Not a send limit update".to_string())),
+        };
+        if *authority != transaction.signer || self.spending_limit_authority()?.as_ref() != Some(authority) {
+            return Err(QoraNetError::InvalidTransaction(
+                "Only the spending limit authority may set send limits".to_string()
+            ));
+        }
+        
+        self.try_consume_nonce(authority, transaction.nonce)?;
+        let mut state = self.get_or_create_account(account)?;
+        state.daily_send_limit = limit;
+        if limit.is_none() {
+            state.recent_sends.clear();
+        }
+        state.last_updated = now;
+        self.store_account(&state)
+    }
+    
+    /// Apply a `Transfer` at `now`, enforcing the sender's balance and send limit
+    pub fn apply_transfer(&mut self, transaction: &Transaction, now: Timestamp) -> Result<()> {
+        let (from, to, amount) = match &transaction.data {
+            TransactionData::Transfer { from, to, amount, .. } => (from, to, *amount),
+            _ => return Err(QoraNetError::InvalidTransaction("Not a transfer".to_string())),
+        };
+        if *from != transaction.signer {
+            return Err(QoraNetError::InvalidTransaction("Transfers can only be sent by the sender".to_string()));
+        }
+        
+        // Check everything before the nonce is consumed
+        let sender = self.get_or_create_account(from)?;
+        sender.check_send_limit(amount, now)?;
+        if sender.balance.amount < amount {
+            return Err(QoraNetError::InsufficientLiquidity { required: amount, available: sender.balance.amount });
+        }
+        if from != to {
+            let recipient_balance = self.get_account(to)?.map_or(0, |account| account.balance.amount);
+            if recipient_balance.checked_add(amount).is_none() {
+                return Err(QoraNetError::InvalidTransaction(format!("Balance overflow crediting {}", to)));
+            }
+        }
+        
+        self.try_consume_nonce(from, transaction.nonce)?;
+        let mut sender = self.get_or_create_account(from)?;
+        sender.record_send(amount, now);
+        self.store_account(&sender)?;
+        self.apply_balance_delta(from, -(amount as i128))?;
+        self.apply_balance_delta(to, amount as i128)?;
+        Ok(())
+    }
+    
+    /// Apply one transaction of the block at `height`, timestamped `now`, consuming its signer's nonce
+    pub fn apply_transaction(&mut self, transaction: &Transaction, height: BlockHeight, now: Timestamp) -> Result<()> {
+        match &transaction.data {
+            TransactionData::Transfer { .. } => self.apply_transfer(transaction, now),
+            TransactionData::ClaimRewards { .. } => self.apply_claim_rewards(transaction).map(|_| ()),
+            TransactionData::ReportEquivocation { proof, .. } => self.apply_equivocation_report(transaction, proof, height),
+            TransactionData::SetSendLimit { .. } => self.apply_set_send_limit(transaction, now),
+            TransactionData::ReportMetrics { .. } => self.apply_report_metrics(transaction),
+            _ => self.try_consume_nonce(&transaction.signer, transaction.nonce),
+        }
+    }
+    
+    /// Record the offender of a verified equivocation proof as slashed at `height`
+    fn apply_equivocation_report(&mut self, transaction: &Transaction, proof: &EquivocationProof, height: BlockHeight) -> Result<()> {
+        proof.verify()
+            .map_err(|e| QoraNetError::InvalidTransaction(format!("Invalid equivocation proof: {}", e)))?;
+        let offender = proof.offender();
+        if self.slashing_height(offender)?.is_some() {
+            return Err(QoraNetError::InvalidTransaction(format!("Validator {} is already slashed", offender)));
+        }
+        
+        self.try_consume_nonce(&transaction.signer, transaction.nonce)?;
+        self.put_state(CF_VALIDATORS, &slashing_key(offender), &height.to_le_bytes())
+    }
+    
+    /// Height of the block whose evidence slashed `validator`, if it was slashed
+    pub fn slashing_height(&self, validator: &Address) -> Result<Option<BlockHeight>> {
+        let cf_validators = self.db.cf_handle(CF_VALIDATORS)
+            .ok_or_else(|| QoraNetError::StorageError("Validators column family not found".to_string()))?;
+        match self.db.get_cf(cf_validators, slashing_key(validator)) {
+            Ok(Some(bytes)) => {
+                let height: [u8; 8] = bytes.as_slice().try_into()
+                    .map_err(|_| QoraNetError::StorageError("Corrupt slashing record".to_string()))?;
+                Ok(Some(u64::from_le_bytes(height)))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to read slashing record: {}", e))),
+        }
+    }
+    
+    /// Every slashed validator with the height its evidence was included at, in address order
+    pub fn slashed_validators(&self) -> Result<Vec<(Address, BlockHeight)>> {
+        let cf_validators = self.db.cf_handle(CF_VALIDATORS)
+            .ok_or_else(|| QoraNetError::StorageError("Validators column family not found".to_string()))?;
+        let mut slashed = Vec::new();
+        for item in self.db.prefix_iterator_cf(cf_validators, SLASHING_PREFIX) {
+            let (key, value) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read slashing records: {}", e)))?;
+            if !key.starts_with(SLASHING_PREFIX) {
+                break;
+            }
+            let address: [u8; 32] = key[SLASHING_PREFIX.len()..].try_into()
+                .map_err(|_| QoraNetError::StorageError("Corrupt slashing key".to_string()))?;
+            let height: [u8; 8] = value.as_ref().try_into()
+                .map_err(|_| QoraNetError::StorageError("Corrupt slashing record".to_string()))?;
+            slashed.push((Address(address), u64::from_le_bytes(height)));
+        }
+        Ok(slashed)
+    }
+    
+    /// Apply a block's transactions in order at the block's timestamp, debiting each one's fee.
+    ///
+    /// A transaction that no longer applies (stale nonce, insufficient funds)
+    /// is left without effect, pays no fee and has its hash returned; storage
+    /// failures abort.
+    pub fn apply_block_transactions(&mut self, block: &Block) -> Result<Vec<Hash>> {
+        let mut failed = Vec::new();
+        for tx in &block.transactions {
+            // Discounts follow the signer's holdings before the transaction, as when it was admitted
+            let holdings = self.account_holdings(&tx.signer)?;
+            match self.apply_charging_fee(tx, block.header.height, block.header.timestamp) {
+                Ok(()) => self.store_fee_breakdown(&tx.hash(), &FeeBreakdown::charged(tx, &holdings, &self.fee_discounts))?,
+                Err(QoraNetError::StorageError(e)) => return Err(QoraNetError::StorageError(e)),
+                Err(e) => {
+                    tracing::warn!("Transaction {} in block #{} has no effect: {}", tx.hash(), block.header.height, e);
+                    self.store_transaction_failure(&tx.hash(), &e.to_string())?;
+                    failed.push(tx.hash());
+                }
+            }
+        }
+        Ok(failed)
+    }
+    
+    /// Apply `transaction` and debit its fee from the signer.
+    ///
+    /// The signer must cover the fee on top of what the transaction itself
+    /// spends (see `Transaction::balance_effect`); that is checked before
+    /// anything is written, so a transaction that can't pay has no effect.
+    fn apply_charging_fee(&mut self, transaction: &Transaction, height: BlockHeight, now: Timestamp) -> Result<()> {
+        let available = self.get_account(&transaction.signer)?.map_or(0, |account| account.balance.amount);
+        let spent = -transaction.balance_effect(&transaction.signer);
+        if spent > available as i128 {
+            return Err(QoraNetError::InsufficientLiquidity {
+                required: u64::try_from(spent).unwrap_or(u64::MAX),
+                available,
+            });
+        }
+        
+        self.apply_transaction(transaction, height, now)?;
+        if transaction.fee_qor > 0 {
+            // Covered by the check above; failing now would leave the transaction half applied
+            self.apply_balance_delta(&transaction.signer, -(transaction.fee_qor as i128))
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to charge fee for {}: {}", transaction.hash(), e)))?;
+        }
+        Ok(())
+    }
+    
+    /// Record the fee charged for an applied transaction, for its receipt.
+    ///
+    /// Journaled with the block's other state writes, so a reorg rolls it back.
+    fn store_fee_breakdown(&self, tx_hash: &Hash, breakdown: &FeeBreakdown) -> Result<()> {
+        let serialized = bincode::serialize(breakdown)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize fee breakdown: {}", e)))?;
+        self.put_state(CF_TX_INDEX, &fee_breakdown_key(tx_hash), &serialized)
+    }
+    
+    /// Fee charged for a transaction, if it was applied with effect
+    pub fn get_fee_breakdown(&self, tx_hash: &Hash) -> Result<Option<FeeBreakdown>> {
+        let cf_tx_index = self.db.cf_handle(CF_TX_INDEX)
+            .ok_or_else(|| QoraNetError::StorageError("Transaction index column family not found".to_string()))?;
+        match self.db.get_cf(cf_tx_index, fee_breakdown_key(tx_hash)) {
+            Ok(Some(data)) => bincode::deserialize(&data)
+                .map(Some)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize fee breakdown: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get fee breakdown: {}", e))),
+        }
+    }
+    
+    /// Record why an included transaction had no effect, for its receipt; journaled like the fee breakdown
+    fn store_transaction_failure(&self, tx_hash: &Hash, reason: &str) -> Result<()> {
+        self.put_state(CF_TX_INDEX, &tx_failure_key(tx_hash), reason.as_bytes())
+    }
+    
+    /// Why an included transaction had no effect, if it failed when its block was applied
+    pub fn get_transaction_failure(&self, tx_hash: &Hash) -> Result<Option<String>> {
+        let cf_tx_index = self.db.cf_handle(CF_TX_INDEX)
+            .ok_or_else(|| QoraNetError::StorageError("Transaction index column family not found".to_string()))?;
+        match self.db.get_cf(cf_tx_index, tx_failure_key(tx_hash)) {
+            Ok(Some(data)) => Ok(Some(String::from_utf8_lossy(&data).into_owned())),
+            Ok(None) => Ok(None),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get transaction failure: {}", e))),
+        }
+    }
+    
+    /// Get latest block info
+    pub fn get_latest_block_info(&self) -> (Option<Hash>, BlockHeight) {
+        (self.cache.latest_block_hash.clone(), self.cache.latest_block_height)
+    }
+    
+    /// Highest final height under the configured finality depth, if any block is final yet.
+    ///
+    /// Nodes on a non-default chain set the depth with `PruningConfig::for_genesis`.
+    pub fn finalized_height(&self) -> Option<BlockHeight> {
+        self.cache.latest_block_hash.as_ref()?;
+        self.cache.latest_block_height.checked_sub(self.pruning.finality_depth)
+    }
+    
+    /// Lowest height whose block body is still stored (0 if nothing was pruned)
+    pub fn pruned_below(&self) -> Result<BlockHeight> {
+        self.get_counter(PRUNED_BELOW_KEY)
+    }
+    
+    /// Delete block bodies and their transactions below `before_height`.
+    ///
+    /// The height→hash index and account state are kept. Only finalized blocks
+    /// may be pruned. Each block is removed in its own batch together with the
+    /// pruning marker, so an interrupted prune resumes where it stopped.
+    /// Returns the number of blocks pruned.
+    pub fn prune_blocks(&mut self, before_height: BlockHeight) -> Result<u64> {
+        let start = self.pruned_below()?;
+        if before_height <= start {
+            return Ok(0);
+        }
+        let finalized = self.finalized_height();
+        if finalized.map_or(true, |finalized| before_height - 1 > finalized) {
+            return Err(QoraNetError::StorageError(format!(
+                "Cannot prune below height {}: latest finalized height is {:?}",
+                before_height, finalized
+            )));
+        }
+        
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| QoraNetError::StorageError("Blocks column family not found".to_string()))?;
+        let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
+            .ok_or_else(|| QoraNetError::StorageError("Transactions column family not found".to_string()))?;
+        let cf_tx_index = self.db.cf_handle(CF_TX_INDEX)
+            .ok_or_else(|| QoraNetError::StorageError("Transaction index column family not found".to_string()))?;
+        let cf_history = self.db.cf_handle(CF_ACCOUNT_HISTORY)
+            .ok_or_else(|| QoraNetError::StorageError("Account history column family not found".to_string()))?;
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+        
+        let mut pruned = 0;
+        for height in start..before_height {
+            let mut batch = WriteBatch::default();
+            let block_hash = self.get_block_hash_by_height(height)?;
+            let block = match &block_hash {
+                Some(block_hash) => self.get_block(block_hash)?,
+                None => None,
+            };
+            
+            if let (Some(block_hash), Some(block)) = (block_hash, block) {
+                batch.delete_cf(cf_blocks, block_hash.as_bytes());
+                batch.put_cf(cf_metadata, pruned_block_key(&block_hash), height.to_le_bytes());
+                for (index, tx) in block.transactions.iter().enumerate() {
+                    let tx_hash = tx.hash();
+                    batch.delete_cf(cf_transactions, tx_hash.as_bytes());
+                    batch.delete_cf(cf_tx_index, tx_hash.as_bytes());
+                    batch.delete_cf(cf_tx_index, fee_breakdown_key(&tx_hash));
+                    batch.delete_cf(cf_tx_index, tx_failure_key(&tx_hash));
+                    let cursor = HistoryCursor { height, index: index as u32 };
+                    for participant in tx.data.participants() {
+                        batch.delete_cf(cf_history, cursor.history_key(participant));
+                    }
+                }
+                
+                let blocks = self.get_counter(BLOCK_COUNT_KEY)?.saturating_sub(1);
+                let transactions = self.get_counter(TRANSACTION_COUNT_KEY)?
+                    .saturating_sub(block.transactions.len() as u64);
+                batch.put_cf(cf_metadata, BLOCK_COUNT_KEY.as_bytes(), blocks.to_le_bytes());
+                batch.put_cf(cf_metadata, TRANSACTION_COUNT_KEY.as_bytes(), transactions.to_le_bytes());
+                pruned += 1;
+            }
+            
+            // Nothing below the pruned height can be rewound, so its undo entries go too
+            batch.delete_range_cf(cf_metadata, undo_height_prefix(height), undo_height_prefix(height + 1));
+            batch.put_cf(cf_metadata, PRUNED_BELOW_KEY.as_bytes(), (height + 1).to_le_bytes());
+            self.db.write(batch)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to prune block at height {}: {}", height, e)))?;
+        }
+        
+        tracing::info!("Pruned {} block bodies below height {}", pruned, before_height);
+        Ok(pruned)
+    }
+    
+    /// Height of a block whose body was pruned; `None` unless the height index
+    /// still maps that height to `block_hash` and it lies below the pruned height
+    pub fn pruned_block_height(&self, block_hash: &Hash) -> Result<Option<BlockHeight>> {
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+        let height = match self.db.get_cf(cf_metadata, pruned_block_key(block_hash)) {
+            Ok(Some(bytes)) if bytes.len() == 8 => {
+                let mut value = [0u8; 8];
+                value.copy_from_slice(&bytes);
+                u64::from_le_bytes(value)
+            }
+            Ok(Some(_)) => return Err(QoraNetError::StorageError("Corrupt pruned block entry".to_string())),
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(QoraNetError::StorageError(format!("Failed to get pruned block: {}", e))),
+        };
+        if height >= self.pruned_below()? || self.get_block_hash_by_height(height)?.as_ref() != Some(block_hash) {
+            return Ok(None);
+        }
+        Ok(Some(height))
+    }
+    
+    /// Prune everything older than the retention window, never past the finalized height
+    pub fn prune_to_retention(&mut self) -> Result<u64> {
+        let finalized = match self.finalized_height() {
+            Some(finalized) => finalized,
+            None => return Ok(0),
+        };
+        let retained_from = self.cache.latest_block_height.saturating_sub(self.pruning.retention_blocks);
+        self.prune_blocks(retained_from.min(finalized + 1))
+    }
+    
+    /// Load latest block info into cache
+    fn load_latest_block_info(&mut self) -> Result<()> {
+        // Load latest block height
+        if let Some(height_bytes) = self.get_metadata("latest_block_height")? {
+            if height_bytes.len() == 8 {
+                let mut height_array = [0u8; 8];
+                height_array.copy_from_slice(&height_bytes);
+                self.cache.latest_block_height = u64::from_le_bytes(height_array);
+            }
+        }
+        
+        // Load latest block hash
+        if let Some(hash_bytes) = self.get_metadata("latest_block_hash")? {
+            if hash_bytes.len() == 32 {
+                let mut hash_array = [0u8; 32];
+                hash_array.copy_from_slice(&hash_bytes);
+                self.cache.latest_block_hash = Some(Hash(hash_array));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Write chain state (accounts, supply, rewards, slashings, app scores,
+    /// receipts), journaling the value it replaces so a reorg can roll the write back
+    fn put_state(&self, cf_name: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.journal_state_write(cf_name, key)?;
+        let cf = self.db.cf_handle(cf_name)
+            .ok_or_else(|| QoraNetError::StorageError(format!("{} column family not found", cf_name)))?;
+        self.db.put_cf(cf, key, value)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to write {}: {}", cf_name, e)))
+    }
+    
+    /// Metadata that is chain state, written through the undo journal
+    fn update_state_metadata(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.put_state(CF_METADATA, key.as_bytes(), value)
+    }
+    
+    /// Remember what `key` held before the current height first wrote it.
+    ///
+    /// Nothing is journaled before the first block; there is no fork point to roll back to.
+    fn journal_state_write(&self, cf_name: &str, key: &[u8]) -> Result<()> {
+        if self.cache.latest_block_hash.is_none() {
+            return Ok(());
+        }
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+        let undo = undo_key(self.cache.latest_block_height, cf_name, key);
+        let journaled = self.db.get_cf(cf_metadata, &undo)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to read undo journal: {}", e)))?;
+        if journaled.is_some() {
+            return Ok(());
+        }
+        
+        let cf = self.db.cf_handle(cf_name)
+            .ok_or_else(|| QoraNetError::StorageError(format!("{} column family not found", cf_name)))?;
+        let previous = self.db.get_cf(cf, key)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to read {}: {}", cf_name, e)))?;
+        let serialized = bincode::serialize(&previous)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize undo entry: {}", e)))?;
+        self.db.put_cf(cf_metadata, undo, serialized)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to write undo journal: {}", e)))
+    }
+    
+    /// Add the writes restoring chain state to how it was before `fork` to `batch`.
+    ///
+    /// Heights are undone newest first, so each key ends up with the value it
+    /// had before its first write at or above `fork`. Account checkpoints and
+    /// the undo entries themselves go too.
+    fn revert_state_batch(&self, batch: &mut WriteBatch, fork: BlockHeight) -> Result<()> {
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+        let cf_checkpoints = self.db.cf_handle(CF_ACCOUNT_CHECKPOINTS)
+            .ok_or_else(|| QoraNetError::StorageError("Account checkpoints column family not found".to_string()))?;
+        
+        let start = undo_height_prefix(fork);
+        let mut entries = Vec::new();
+        for item in self.db.iterator_cf(cf_metadata, IteratorMode::From(&start, Direction::Forward)) {
+            let (undo, previous) = item.map_err(|e| QoraNetError::StorageError(format!("Failed to read undo journal: {}", e)))?;
+            if !undo.starts_with(UNDO_PREFIX) {
+                break;
+            }
+            entries.push((undo, previous));
+        }
+        
+        let mut accounts = self.get_counter(ACCOUNT_COUNT_KEY)?;
+        for (undo, previous) in entries.into_iter().rev() {
+            let (height, cf_name, key) = parse_undo_key(&undo)?;
+            let cf = self.db.cf_handle(cf_name)
+                .ok_or_else(|| QoraNetError::StorageError(format!("{} column family not found", cf_name)))?;
+            let previous: Option<Vec<u8>> = bincode::deserialize(&previous)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize undo entry: {}", e)))?;
+            match previous {
+                Some(value) => batch.put_cf(cf, key, value),
+                None => {
+                    batch.delete_cf(cf, key);
+                    if cf_name == CF_ACCOUNTS {
+                        accounts = accounts.saturating_sub(1);
+                    }
+                }
+            }
+            if cf_name == CF_ACCOUNTS {
+                if let Ok(address) = <[u8; 32]>::try_from(key) {
+                    batch.delete_cf(cf_checkpoints, account_checkpoint_key(&Address(address), height));
+                }
+            }
+            batch.delete_cf(cf_metadata, &undo);
+        }
+        batch.put_cf(cf_metadata, ACCOUNT_COUNT_KEY.as_bytes(), accounts.to_le_bytes());
+        Ok(())
+    }
+    
+    /// Add the removal of replaced blocks' transactions from the location and history indexes to `batch`.
+    ///
+    /// Transaction bodies stay, so what the reorg reverted can be read back and readmitted.
+    fn unindex_blocks_batch(&self, batch: &mut WriteBatch, removed: &[Block]) -> Result<()> {
+        let cf_tx_index = self.db.cf_handle(CF_TX_INDEX)
+            .ok_or_else(|| QoraNetError::StorageError("Transaction index column family not found".to_string()))?;
+        let cf_history = self.db.cf_handle(CF_ACCOUNT_HISTORY)
+            .ok_or_else(|| QoraNetError::StorageError("Account history column family not found".to_string()))?;
+        
+        for block in removed {
+            for (index, tx) in block.transactions.iter().enumerate() {
+                let tx_hash = tx.hash();
+                batch.delete_cf(cf_tx_index, tx_hash.as_bytes());
+                batch.delete_cf(cf_tx_index, fee_breakdown_key(&tx_hash));
+                batch.delete_cf(cf_tx_index, tx_failure_key(&tx_hash));
+                let cursor = HistoryCursor { height: block.header.height, index: index as u32 };
+                for participant in tx.data.participants() {
+                    batch.delete_cf(cf_history, cursor.history_key(participant));
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Update metadata
+    fn update_metadata(&self, key: &str, value: &[u8]) -> Result<()> {
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+        
+        self.db.put_cf(cf_metadata, key.as_bytes(), value)
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to update metadata: {}", e)))?;
+        
+        Ok(())
+    }
+    
+    /// Get metadata
+    fn get_metadata(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| QoraNetError::StorageError("Metadata column family not found".to_string()))?;
+        
+        match self.db.get_cf(cf_metadata, key.as_bytes()) {
+            Ok(data) => Ok(data),
+            Err(e) => Err(QoraNetError::StorageError(format!("Failed to get metadata: {}", e))),
+        }
+    }
+    
+    /// Read a little-endian `u64` metadata counter; absent counters are zero
+    fn get_counter(&self, key: &str) -> Result<u64> {
+        match self.get_metadata(key)? {
+            Some(bytes) if bytes.len() == 8 => {
+                let mut value = [0u8; 8];
+                value.copy_from_slice(&bytes);
+                Ok(u64::from_le_bytes(value))
+            }
+            Some(_) => Err(QoraNetError::StorageError(format!("Corrupt {} metadata", key))),
+            None => Ok(0),
+        }
+    }
+    
+    /// Get block range (eager, for small ranges)
+    pub fn get_blocks_range(&self, start_height: BlockHeight, end_height: BlockHeight) -> Result<Vec<Block>> {
+        self.iter_blocks_range(start_height, end_height).collect()
+    }
+    
+    /// Lazily iterate blocks in a height range, fetching one block at a time.
+    ///
+    /// Heights with no stored block are skipped, as in `get_blocks_range`.
+    pub fn iter_blocks_range(
+        &self,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> impl Iterator<Item = Result<Block>> + '_ {
+        (start_height..=end_height).filter_map(move |height| {
+            self.get_block_by_height(height).transpose()
+        })
+    }
+    
+    /// Iterate every stored account in address order.
+    ///
+    /// Only full address keys are yielded, so the order is stable across nodes
+    /// holding the same state.
+    pub fn iter_accounts(&self) -> impl Iterator<Item = Result<AccountState>> + '_ {
+        let cf_accounts = self.db.cf_handle(CF_ACCOUNTS);
+        let missing = cf_accounts.is_none()
+            .then(|| Err(QoraNetError::StorageError("Accounts column family not found".to_string())));
+        
+        let accounts = cf_accounts.into_iter()
+            .flat_map(move |cf| self.db.iterator_cf(cf, IteratorMode::Start))
+            .filter_map(|item| match item {
+                Ok((key, _)) if key.len() != std::mem::size_of::<Address>() => None,
+                Ok((_, value)) => Some(bincode::deserialize(&value)
+                    .map_err(|e| QoraNetError::StorageError(format!("Failed to deserialize account: {}", e)))),
+                Err(e) => Some(Err(QoraNetError::StorageError(format!("Failed to iterate accounts: {}", e)))),
+            });
+        
+        missing.into_iter().chain(accounts)
+    }
+    
+    /// Root of the account state block headers commit to
+    pub fn state_root(&self) -> Result<Hash> {
+        self.accounts_root()
+    }
+    
+    /// Hash committing to every account, in `iter_accounts` order
+    pub fn accounts_root(&self) -> Result<Hash> {
+        let accounts: Vec<AccountState> = self.iter_accounts().collect::<Result<_>>()?;
+        Self::compute_accounts_root(&accounts)
+    }
+    
+    /// `accounts_root` over accounts already in address order.
+    ///
+    /// Covers each account's balance, nonce and token balances. Timestamps are
+    /// left out: they are taken from the local clock, so nodes executing the
+    /// same blocks would otherwise disagree.
+    fn compute_accounts_root(accounts: &[AccountState]) -> Result<Hash> {
+        let mut serialized = Vec::new();
+        for account in accounts {
+            let committed = (&account.address, account.balance.amount, account.nonce, &account.token_balances);
+            serialized.extend_from_slice(&bincode::serialize(&committed)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to serialize account: {}", e)))?);
+        }
+        Ok(Hash::new(&serialized))
+    }
+    
+    /// Export every account at the current height, with the root they hash to
+    pub fn export_snapshot(&self) -> Result<AccountSnapshot> {
+        let accounts: Vec<AccountState> = self.iter_accounts().collect::<Result<_>>()?;
+        let state_root = Self::compute_accounts_root(&accounts)?;
+        
+        Ok(AccountSnapshot {
+            height: self.cache.latest_block_height,
+            state_root,
+            accounts,
+        })
+    }
+    
+    /// Import a fast-sync snapshot into an empty account state.
+    ///
+    /// The root is recomputed over the snapshot's accounts and must match the
+    /// snapshot's claimed root and, if given, the root from a trusted header.
+    /// Nothing is written unless both checks pass.
+    pub fn import_snapshot(&mut self, snapshot: &AccountSnapshot, trusted_root: Option<&Hash>) -> Result<()> {
+        if self.iter_accounts().next().is_some() {
+            return Err(QoraNetError::StorageError(
+                "Snapshot import requires an empty account state".to_string()
+            ));
+        }
+        
+        // Strictly ascending addresses: the order the root was computed in, with no duplicates
+        if snapshot.accounts.windows(2).any(|pair| pair[0].address.0 >= pair[1].address.0) {
+            return Err(QoraNetError::StorageError(
+                "Snapshot accounts are not in strict address order".to_string()
+            ));
+        }
+        
+        let computed_root = Self::compute_accounts_root(&snapshot.accounts)?;
+        if computed_root != snapshot.state_root {
+            return Err(QoraNetError::StorageError(format!(
+                "Snapshot state root mismatch: claimed {}, computed {}",
+                snapshot.state_root, computed_root
+            )));
+        }
+        if let Some(trusted_root) = trusted_root {
+            if computed_root != *trusted_root {
+                return Err(QoraNetError::StorageError(format!(
+                    "Snapshot state root {} does not match trusted root {}",
+                    computed_root, trusted_root
+                )));
+            }
+        }
+        
+        for account in &snapshot.accounts {
+            self.store_account(account)?;
+        }
+        Ok(())
+    }
+    
+    /// Get recent transactions for an account, newest first, via the account history index
+    pub fn get_account_transactions(&self, address: &Address, limit: usize) -> Result<Vec<Transaction>> {
+        let page = self.get_account_history(address, limit, None)?;
+        Ok(page.entries.into_iter().map(|entry| entry.transaction).collect())
+    }
+    
+    /// Database statistics; totals come from the counters kept in metadata, not from scans
+    pub fn get_storage_stats(&self) -> Result<StorageStats> {
+        let mut column_families = Vec::with_capacity(ALL_COLUMN_FAMILIES.len());
+        for name in ALL_COLUMN_FAMILIES {
+            column_families.push(self.column_family_stats(name)?);
+        }
+        
+        Ok(StorageStats {
+            latest_block_height: self.cache.latest_block_height,
+            total_blocks: self.get_counter(BLOCK_COUNT_KEY)? as usize,
+            total_transactions: self.get_counter(TRANSACTION_COUNT_KEY)? as usize,
+            total_accounts: self.get_counter(ACCOUNT_COUNT_KEY)? as usize,
+            cache_size: self.cache.accounts().len(),
+            column_families,
+        })
+    }
+    
+    /// RocksDB size estimates for one column family
+    fn column_family_stats(&self, name: &str) -> Result<ColumnFamilyStats> {
+        let cf = self.db.cf_handle(name)
+            .ok_or_else(|| QoraNetError::StorageError(format!("Column family {} not found", name)))?;
+        let property = |property: &str| self.db.property_int_value_cf(cf, property)
+            .map(|value| value.unwrap_or(0))
+            .map_err(|e| QoraNetError::StorageError(format!("Failed to read {} for {}: {}", property, name, e)));
+        
+        Ok(ColumnFamilyStats {
+            name: name.to_string(),
+            estimated_keys: property("rocksdb.estimate-num-keys")?,
+            sst_files_bytes: property("rocksdb.total-sst-files-size")?,
+            memtable_bytes: property("rocksdb.cur-size-all-mem-tables")?,
+            block_cache_bytes: property("rocksdb.block-cache-usage")?,
+        })
+    }
+    
+    /// Exact key count by full iteration (O(n)); only for migrations
+    fn count_keys(&self, name: &str) -> Result<usize> {
+        let cf = self.db.cf_handle(name)
+            .ok_or_else(|| QoraNetError::StorageError(format!("Column family {} not found", name)))?;
+        Ok(self.db.iterator_cf(cf, IteratorMode::Start).count())
+    }
+    
+    /// Walk the chain from genesis and report any internal inconsistencies.
+    ///
+    /// Every height is checked even after a discrepancy is found, so the report
+    /// lists all problems rather than just the first one.
+    pub fn verify_integrity(&self) -> Result<IntegrityReport> {
+        let latest_height = self.cache.latest_block_height;
+        let mut report = IntegrityReport {
+            latest_height,
+            blocks_checked: 0,
+            issues: Vec::new(),
+        };
+        
+        // Pruned bodies are gone by design; their index entries still anchor the first kept block
+        let pruned_below = self.pruned_below()?;
+        let mut previous_hash: Option<Hash> = match pruned_below.checked_sub(1) {
+            Some(height) => self.get_block_hash_by_height(height)?,
+            None => None,
+        };
+        
+        for height in pruned_below..=latest_height {
+            let indexed_hash = match self.get_block_hash_by_height(height) {
+                Ok(Some(hash)) => hash,
+                Ok(None) => {
+                    report.add_issue(height, "Height index entry missing".to_string());
+                    previous_hash = None;
+                    continue;
+                },
+                Err(e) => {
+                    report.add_issue(height, format!("Unreadable height index entry: {}", e));
+                    previous_hash = None;
+                    continue;
+                }
+            };
+            
+            let block = match self.get_block(&indexed_hash) {
+                Ok(Some(block)) => block,
+                Ok(None) => {
+                    report.add_issue(height, format!("Height index points at missing block {}", indexed_hash));
+                    previous_hash = None;
+                    continue;
+                },
+                Err(e) => {
+                    report.add_issue(height, format!("Unreadable block {}: {}", indexed_hash, e));
+                    previous_hash = None;
+                    continue;
+                }
+            };
+            
+            report.blocks_checked += 1;
+            let block_hash = block.hash();
+            
+            if block_hash != indexed_hash {
+                report.add_issue(height, format!("Block hash {} does not match height index {}", block_hash, indexed_hash));
+            }
+            
+            if block.header.height != height {
+                report.add_issue(height, format!("Block header claims height {}", block.header.height));
+            }
+            
+            // Check link to the previous block (skipped if the previous block was unreadable)
+            if height > 0 {
+                if let Some(expected_previous) = &previous_hash {
+                    if block.header.previous_hash != *expected_previous {
+                        report.add_issue(height, "Previous hash does not link to block at height - 1".to_string());
+                    }
+                }
+            }
+            
+            // Without the chain's upgrade height either root scheme is accepted; verify_chain checks the right one
+            let root = &block.header.transactions_root;
+            if Block::calculate_transactions_root(&block.transactions) != *root
+                && Block::legacy_transactions_root(&block.transactions) != *root
+            {
+                report.add_issue(height, "Transactions root mismatch".to_string());
+            }
+            
+            let calculated_fees = block.transactions.iter()
+                .try_fold(0u64, |acc, tx| acc.checked_add(tx.fee_qor));
+            if calculated_fees != Some(block.header.total_fees) {
+                report.add_issue(height, format!(
+                    "Fee total mismatch: header {}, transactions {:?}",
+                    block.header.total_fees, calculated_fees
+                ));
+            }
+            
+            for tx in &block.transactions {
+                let tx_hash = tx.hash();
+                if !matches!(self.get_transaction(&tx_hash), Ok(Some(_))) {
+                    report.add_issue(height, format!("Transaction {} missing from transaction index", tx_hash));
+                }
+            }
+            
+            previous_hash = Some(block_hash);
+        }
+        
+        // Latest block metadata must point at the tip we just walked to
+        if let Some(latest_hash) = &self.cache.latest_block_hash {
+            if previous_hash.as_ref() != Some(latest_hash) {
+                report.add_issue(latest_height, "Latest block metadata does not match block at tip height".to_string());
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    /// Re-execute the chain from genesis into the empty `replay` storage.
+    ///
+    /// Blocks are streamed one at a time. Each block's link, transactions root,
+    /// fee total and committed state root are checked, then it is executed the
+    /// way the validator executes it. Headers from before state roots have
+    /// nothing to check against, so every account such a block touched is
+    /// compared with this storage's checkpoint at its height instead. Finally
+    /// the re-executed state must match the stored state at the tip. Stops at
+    /// the first divergence.
+    pub fn verify_chain(&self, genesis: &GenesisConfig, replay: &mut BlockchainStorage) -> Result<ChainVerification> {
+        if replay.get_latest_block_info().0.is_some() {
+            return Err(QoraNetError::StorageError("Chain verification requires an empty replay storage".to_string()));
+        }
+        let pruned_below = self.pruned_below()?;
+        if pruned_below > 0 {
+            return Err(QoraNetError::StorageError(format!(
+                "Blocks below height {} are pruned; the chain can't be re-executed from genesis",
+                pruned_below
+            )));
+        }
+        
+        let latest_height = self.cache.latest_block_height;
+        let mut verification = ChainVerification { latest_height, blocks_verified: 0, divergence: None };
+        let mut previous_hash = genesis.genesis_hash();
+        
+        for (expected_height, block) in (0..=latest_height).zip(self.iter_blocks_range(0, latest_height)) {
+            let block = match block {
+                Ok(block) => block,
+                Err(e) => return Ok(verification.diverged(expected_height, format!("Unreadable block: {}", e))),
+            };
+            // Missing heights are skipped by the iterator, so a gap shows up as a height mismatch
+            if block.header.height != expected_height {
+                return Ok(verification.diverged(expected_height, "Block missing".to_string()));
+            }
+            if let Some(reason) = replay.replay_block(&block, &previous_hash, genesis)? {
+                return Ok(verification.diverged(expected_height, reason));
+            }
+            if !block.header.commits_state_root() {
+                if let Some(reason) = self.compare_replayed_accounts(replay, &block, genesis)? {
+                    return Ok(verification.diverged(expected_height, reason));
+                }
+            }
+            
+            verification.blocks_verified += 1;
+            previous_hash = block.hash();
+        }
+        
+        if verification.blocks_verified != latest_height + 1 {
+            let height = verification.blocks_verified;
+            return Ok(verification.diverged(height, "Block missing".to_string()));
+        }
+        let (replayed_root, stored_root) = (replay.state_root()?, self.state_root()?);
+        if replayed_root != stored_root {
+            return Ok(verification.diverged(latest_height, format!(
+                "State after the tip diverged: stored root {}, re-executed root {}",
+                stored_root, replayed_root
+            )));
+        }
+        Ok(verification)
+    }
+    
+    /// Apply `block` on top of this (replay) state; `Some(reason)` if it doesn't re-execute.
+    ///
+    /// Mirrors block production: transactions that no longer apply are left
+    /// without effect, then the producer is rewarded.
+    fn replay_block(&mut self, block: &Block, previous_hash: &Hash, genesis: &GenesisConfig) -> Result<Option<String>> {
+        if block.header.previous_hash != *previous_hash {
+            return Ok(Some("Previous hash does not link to the replayed chain".to_string()));
+        }
+        let root = Block::transactions_root_at(&block.transactions, block.header.height, genesis.domain_separated_root_height);
+        if root != block.header.transactions_root {
+            return Ok(Some("Transactions root mismatch".to_string()));
+        }
+        let calculated_fees = block.transactions.iter()
+            .try_fold(0u64, |acc, tx| acc.checked_add(tx.fee_qor));
+        if calculated_fees != Some(block.header.total_fees) {
+            return Ok(Some(format!(
+                "Fee total mismatch: header {}, transactions {:?}",
+                block.header.total_fees, calculated_fees
+            )));
+        }
+        
+        // Genesis has no parent state to commit to
+        if block.header.height > 0 && block.header.commits_state_root() {
+            let state_root = self.state_root()?;
+            if state_root != block.header.state_root {
+                return Ok(Some(format!(
+                    "State root mismatch: header commits {}, re-executed state before the block is {}",
+                    block.header.state_root, state_root
+                )));
+            }
+        }
+        
+        self.store_block(block)?;
+        if block.header.height == 0 {
+            self.apply_genesis_allocations(genesis)?;
+            return Ok(None);
+        }
+        
+        let failed = self.apply_block_transactions(block)?;
+        let epoch = block.header.height / crate::consensus::DEFAULT_EPOCH_LENGTH;
+        self.reward_block(&block.header.validator, block.collected_fees(&failed), epoch, genesis)?;
+        Ok(None)
+    }
+    
+    /// Compare every account `block` touched in `replay` with this storage's state at its height
+    fn compare_replayed_accounts(
+        &self,
+        replay: &BlockchainStorage,
+        block: &Block,
+        genesis: &GenesisConfig,
+    ) -> Result<Option<String>> {
+        let mut touched: Vec<&Address> = block.transactions.iter()
+            .flat_map(|tx| tx.data.participants().into_iter().chain(std::iter::once(&tx.signer)))
+            .chain(std::iter::once(&block.header.validator))
+            .collect();
+        if block.header.height == 0 {
+            touched.extend(genesis.allocations.iter().map(|allocation| &allocation.address));
+        }
+        touched.sort_by(|a, b| a.0.cmp(&b.0));
+        touched.dedup();
+        
+        for address in touched {
+            let replayed = replay.get_account(address)?;
+            let stored = self.get_account_at_height(address, block.header.height)?;
+            // Timestamps differ between runs; only the executed state has to match
+            let state = |account: &Option<AccountState>| account.as_ref()
+                .map_or((0, 0, TokenBalance::new()), |a| (a.balance.amount, a.nonce, a.token_balances.clone()));
+            let (replayed, stored) = (state(&replayed), state(&stored));
+            if replayed != stored {
+                return Ok(Some(format!(
+                    "Account {} diverged: stored balance {} nonce {}, re-executed balance {} nonce {}",
+                    address, stored.0, stored.1, replayed.0, replayed.1
+                )));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Flush cache to disk.
+    ///
+    /// Memtables are persisted before the cache is dropped, so writes made
+    /// before the flush are what later reads see.
+    pub fn flush(&mut self) -> Result<()> {
+        for name in ALL_COLUMN_FAMILIES {
+            let cf = self.db.cf_handle(name)
+                .ok_or_else(|| QoraNetError::StorageError(format!("Column family {} not found", name)))?;
+            self.db.flush_cf(cf)
+                .map_err(|e| QoraNetError::StorageError(format!("Failed to flush {}: {}", name, e)))?;
+        }
+        
+        // Invalidate cache to force reload from disk
+        self.cache.accounts().clear();
+        self.load_latest_block_info()?;
+        Ok(())
+    }
+}
+
+fn accrued_rewards_key(account: &Address) -> String {
+    format!("{}{}", ACCRUED_REWARDS_PREFIX, hex::encode(account.as_bytes()))
+}
+
+/// Prefix of slashing records in the validators column family
+const SLASHING_PREFIX: &[u8] = b"slashed:";
+
+fn slashing_key(validator: &Address) -> Vec<u8> {
+    let mut key = SLASHING_PREFIX.to_vec();
+    key.extend_from_slice(validator.as_bytes());
+    key
+}
+
+/// Prefix of smoothed app scores in the applications column family
+const APP_SCORE_PREFIX: &[u8] = b"score:";
+
+/// Keyed by hosting validator as well, so one validator's reports can't move another's app
+fn app_score_key(validator: &Address, app_key: &str) -> Vec<u8> {
+    let mut key = APP_SCORE_PREFIX.to_vec();
+    key.extend_from_slice(validator.as_bytes());
+    key.extend_from_slice(app_key.as_bytes());
+    key
+}
+
+/// Fee breakdowns share the tx index with locations, which are keyed by the bare hash
+const FEE_BREAKDOWN_PREFIX: &[u8] = b"fee:";
+
+fn fee_breakdown_key(tx_hash: &Hash) -> Vec<u8> {
+    let mut key = FEE_BREAKDOWN_PREFIX.to_vec();
+    key.extend_from_slice(tx_hash.as_bytes());
+    key
+}
+
+/// Failure reasons share the tx index the same way
+const TX_FAILURE_PREFIX: &[u8] = b"failed:";
+
+fn tx_failure_key(tx_hash: &Hash) -> Vec<u8> {
+    let mut key = TX_FAILURE_PREFIX.to_vec();
+    key.extend_from_slice(tx_hash.as_bytes());
+    key
+}
+
+/// Heights of pruned blocks in the metadata column family, by block hash
+const PRUNED_BLOCK_PREFIX: &[u8] = b"pruned_block:";
+
+fn pruned_block_key(block_hash: &Hash) -> Vec<u8> {
+    let mut key = PRUNED_BLOCK_PREFIX.to_vec();
+    key.extend_from_slice(block_hash.as_bytes());
+    key
+}
+
+/// Prefix of the undo journal in the metadata column family
+const UNDO_PREFIX: &[u8] = b"undo:";
+
+fn undo_height_prefix(height: BlockHeight) -> Vec<u8> {
+    let mut prefix = UNDO_PREFIX.to_vec();
+    prefix.extend_from_slice(&height.to_be_bytes());
+    prefix
+}
+
+/// Undo key: big-endian height so heights sort in order, then the column family and key written
+fn undo_key(height: BlockHeight, cf_name: &str, key: &[u8]) -> Vec<u8> {
+    let mut undo = undo_height_prefix(height);
+    undo.extend_from_slice(cf_name.as_bytes());
+    undo.push(b':');
+    undo.extend_from_slice(key);
+    undo
+}
+
+fn parse_undo_key(undo: &[u8]) -> Result<(BlockHeight, &str, &[u8])> {
+    let corrupt = || QoraNetError::StorageError("Corrupt undo journal key".to_string());
+    let rest = undo.strip_prefix(UNDO_PREFIX).ok_or_else(corrupt)?;
+    let height: [u8; 8] = rest.get(..8).ok_or_else(corrupt)?.try_into().map_err(|_| corrupt())?;
+    let rest = &rest[8..];
+    let separator = rest.iter().position(|byte| *byte == b':').ok_or_else(corrupt)?;
+    let cf_name = std::str::from_utf8(&rest[..separator]).map_err(|_| corrupt())?;
+    Ok((u64::from_be_bytes(height), cf_name, &rest[separator + 1..]))
+}
+
+/// Checkpoint key: address followed by big-endian height, so heights sort in order
+fn account_checkpoint_key(address: &Address, height: BlockHeight) -> Vec<u8> {
+    let mut key = Vec::with_capacity(40);
+    key.extend_from_slice(address.as_bytes());
+    key.extend_from_slice(&height.to_be_bytes());
+    key
+}
+
+/// An app's smoothed performance as tracked by the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppScore {
+    pub validator: Address,
+    pub app_owner: Address,
+    pub app_id: String,
+    /// Smoothed performance score in millionths
+    pub smoothed_score: u64,
+}
+
+/// Storage statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageStats {
     pub latest_block_height: BlockHeight,
     pub total_blocks: usize,
     pub total_transactions: usize,
     pub total_accounts: usize,
     pub cache_size: usize,
+    pub column_families: Vec<ColumnFamilyStats>,
+}
+
+/// RocksDB footprint of one column family
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnFamilyStats {
+    pub name: String,
+    pub estimated_keys: u64,
+    pub sst_files_bytes: u64,
+    pub memtable_bytes: u64,
+    pub block_cache_bytes: u64,
+}
+
+/// Header fields pushed to head subscribers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeadSummary {
+    pub height: BlockHeight,
+    pub hash: Hash,
+    pub timestamp: Timestamp,
+    pub validator: Address,
+    pub tx_count: usize,
+    /// Sum of transaction fees in QOR
+    pub fees_qor: u64,
+}
+
+impl HeadSummary {
+    pub fn from_block(block: &Block) -> Self {
+        Self {
+            height: block.header.height,
+            hash: block.hash(),
+            timestamp: block.header.timestamp,
+            validator: block.header.validator.clone(),
+            tx_count: block.transactions.len(),
+            fees_qor: block.transactions.iter().map(|tx| tx.fee_qor).sum(),
+        }
+    }
+}
+
+/// Change to the canonical chain tip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HeadEvent {
+    /// Block became the canonical tip
+    New(HeadSummary),
+    /// Head was rewound by a reorg
+    Removed(HeadSummary),
+    /// Summary of a reorg, sent after its `Removed` and `New` events
+    Reorg(ReorgEvent),
+}
+
+/// Canonical chain switched to another branch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReorgEvent {
+    /// Highest block shared by the old and new branch
+    pub common_ancestor_height: BlockHeight,
+    pub old_tip: HeadSummary,
+    pub new_tip: HeadSummary,
+    /// Transactions of the abandoned blocks missing from the new branch, in chain order
+    pub reverted_tx_ids: Vec<Hash>,
+}
+
+impl ReorgEvent {
+    /// Reorg from rewinding `removed` (newest first) and adopting `new_tip`; `None` if nothing was rewound
+    fn new(removed: &[Block], new_tip: &Block) -> Option<Self> {
+        let old_tip = removed.first()?;
+        let lowest_removed = removed.last()?.header.height;
+        let kept: std::collections::HashSet<Hash> = new_tip.transaction_hashes().into_iter().collect();
+        
+        let reverted_tx_ids = removed.iter().rev()
+            .flat_map(|old| old.transaction_hashes())
+            .filter(|tx_hash| !kept.contains(tx_hash))
+            .collect();
+        
+        Some(Self {
+            common_ancestor_height: lowest_removed.saturating_sub(1),
+            old_tip: HeadSummary::from_block(old_tip),
+            new_tip: HeadSummary::from_block(new_tip),
+            reverted_tx_ids,
+        })
+    }
+}
+
+/// Where a transaction was included in the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxLocation {
+    pub block_hash: Hash,
+    pub block_height: BlockHeight,
+    pub index: u32,
+    pub timestamp: Timestamp,
+}
+
+/// Position in an account's history, encoded as "height:index"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryCursor {
+    pub height: BlockHeight,
+    pub index: u32,
+}
+
+impl HistoryCursor {
+    /// Account history key: address || height (BE) || index (BE)
+    fn history_key(&self, address: &Address) -> Vec<u8> {
+        let mut key = Vec::with_capacity(44);
+        key.extend_from_slice(address.as_bytes());
+        key.extend_from_slice(&self.height.to_be_bytes());
+        key.extend_from_slice(&self.index.to_be_bytes());
+        key
+    }
+}
+
+impl std::fmt::Display for HistoryCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.height, self.index)
+    }
+}
+
+impl std::str::FromStr for HistoryCursor {
+    type Err = QoraNetError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || QoraNetError::StorageError(format!("Invalid history cursor: {}", s));
+        let (height, index) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            height: height.parse().map_err(|_| invalid())?,
+            index: index.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Direction of a transfer relative to an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Sent,
+    Received,
+    #[serde(rename = "self")]
+    SelfTransfer,
+}
+
+impl TransferDirection {
+    /// Direction for transfers; `None` for other transaction types
+    pub fn for_address(data: &TransactionData, address: &Address) -> Option<Self> {
+        match data {
+            TransactionData::Transfer { from, to, .. } => {
+                if from == address && to == address {
+                    Some(TransferDirection::SelfTransfer)
+                } else if from == address {
+                    Some(TransferDirection::Sent)
+                } else if to == address {
+                    Some(TransferDirection::Received)
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Transaction in an account's history with its block context
+#[derive(Debug, Clone)]
+pub struct AccountHistoryEntry {
+    pub transaction: Transaction,
+    pub location: TxLocation,
+    pub direction: Option<TransferDirection>,
+}
+
+/// One page of account history
+#[derive(Debug, Clone)]
+pub struct AccountHistoryPage {
+    pub entries: Vec<AccountHistoryEntry>,
+    /// Cursor for the next (older) page, if any
+    pub next_cursor: Option<HistoryCursor>,
+}
+
+/// A single discrepancy found by `verify_integrity`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub height: BlockHeight,
+    pub description: String,
+}
+
+/// Result of a storage integrity self-check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub latest_height: BlockHeight,
+    pub blocks_checked: u64,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    fn add_issue(&mut self, height: BlockHeight, description: String) {
+        self.issues.push(IntegrityIssue { height, description });
+    }
+    
+    /// True if no discrepancies were found
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Point where re-execution stopped matching the stored chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainDivergence {
+    pub height: BlockHeight,
+    pub reason: String,
+}
+
+/// Result of re-executing the stored chain from genesis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainVerification {
+    pub latest_height: BlockHeight,
+    pub blocks_verified: u64,
+    /// First divergence found; `None` if the whole chain re-executed identically
+    pub divergence: Option<ChainDivergence>,
+}
+
+impl ChainVerification {
+    fn diverged(mut self, height: BlockHeight, reason: String) -> Self {
+        self.divergence = Some(ChainDivergence { height, reason });
+        self
+    }
+    
+    /// True if every block re-executed to the stored state
+    pub fn is_valid(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn build_chain(storage: &mut BlockchainStorage, length: u64) -> Vec<Block> {
+        let validator = Address([1u8; 32]);
+        let mut blocks = vec![Block::genesis(validator.clone())];
+        storage.store_block(&blocks[0]).unwrap();
+
+        for height in 1..length {
+            let previous_hash = blocks.last().unwrap().hash();
+            let block = Block::new(previous_hash, height, validator.clone(), Vec::new(), 0, 0);
+            storage.store_block(&block).unwrap();
+            blocks.push(block);
+        }
+
+        blocks
+    }
+
+    fn transfer(from: &Address, to: &Address, nonce: u64) -> Transaction {
+        Transaction {
+            data: TransactionData::Transfer { from: from.clone(), to: to.clone(), amount: 100, memo: None },
+            nonce,
+            fee_qor: 1_000,
+            fee_usd: 0.0001,
+            priority: crate::FeePriority::Medium,
+            signature: crate::QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+            signer: from.clone(),
+            valid_until_height: None,
+        }
+    }
+
+    #[test]
+    fn test_account_history_newest_first_with_direction() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let alice = Address([2u8; 32]);
+        let bob = Address([3u8; 32]);
+        let validator = Address([1u8; 32]);
+
+        let genesis = Block::genesis(validator.clone());
+        storage.store_block(&genesis).unwrap();
+        let block1 = Block::new(genesis.hash(), 1, validator.clone(), vec![transfer(&alice, &bob, 0)], 0, 0);
+        storage.store_block(&block1).unwrap();
+        let block2 = Block::new(block1.hash(), 2, validator.clone(), vec![
+            transfer(&bob, &alice, 0),
+            transfer(&alice, &alice, 1),
+        ], 0, 0);
+        storage.store_block(&block2).unwrap();
+
+        let page = storage.get_account_history(&alice, 10, None).unwrap();
+        let positions: Vec<(u64, u32)> = page.entries.iter()
+            .map(|e| (e.location.block_height, e.location.index))
+            .collect();
+        assert_eq!(positions, vec![(2, 1), (2, 0), (1, 0)]);
+
+        let directions: Vec<_> = page.entries.iter().map(|e| e.direction).collect();
+        assert_eq!(directions, vec![
+            Some(TransferDirection::SelfTransfer),
+            Some(TransferDirection::Received),
+            Some(TransferDirection::Sent),
+        ]);
+        assert_eq!(page.entries[0].location.timestamp, block2.header.timestamp);
+        assert!(page.next_cursor.is_none());
+
+        let bob_page = storage.get_account_history(&bob, 10, None).unwrap();
+        assert_eq!(bob_page.entries.len(), 2);
+        assert_eq!(bob_page.entries[0].direction, Some(TransferDirection::Sent));
+    }
+
+    #[test]
+    fn test_account_history_pagination_without_gaps() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let alice = Address([2u8; 32]);
+        let bob = Address([3u8; 32]);
+        let validator = Address([1u8; 32]);
+
+        let mut previous = Block::genesis(validator.clone());
+        storage.store_block(&previous).unwrap();
+        let mut nonce = 0;
+        for height in 1..=4 {
+            let txs = (0..3).map(|_| { nonce += 1; transfer(&alice, &bob, nonce) }).collect();
+            let block = Block::new(previous.hash(), height, validator.clone(), txs, 0, 0);
+            storage.store_block(&block).unwrap();
+            previous = block;
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = storage.get_account_history(&alice, 5, cursor).unwrap();
+            seen.extend(page.entries.iter().map(|e| e.transaction.nonce));
+            match page.next_cursor {
+                Some(next) => {
+                    // Cursor survives a string round trip (as used over RPC)
+                    cursor = Some(next.to_string().parse().unwrap());
+                },
+                None => break,
+            }
+        }
+
+        let expected: Vec<u64> = (1..=12).rev().collect();
+        assert_eq!(seen, expected);
+    }
+
+    fn signed(data: TransactionData, signer: &Address, nonce: u64) -> Transaction {
+        Transaction { data, ..transfer(signer, signer, nonce) }
+    }
+
+    /// The pre-index lookup: deserialize every stored transaction and filter
+    fn scan_account_transactions(storage: &BlockchainStorage, address: &Address) -> Vec<Hash> {
+        let cf_transactions = storage.db.cf_handle(CF_TRANSACTIONS).unwrap();
+        let mut hashes: Vec<Hash> = storage.db.iterator_cf(cf_transactions, IteratorMode::Start)
+            .map(|item| bincode::deserialize::<Transaction>(&item.unwrap().1).unwrap())
+            .filter(|tx| tx.data.involves(address))
+            .map(|tx| tx.hash())
+            .collect();
+        hashes.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        hashes
+    }
+
+    #[test]
+    fn test_indexed_account_transactions_match_scan() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let alice = Address([2u8; 32]);
+        let bob = Address([3u8; 32]);
+        let validator = Address([1u8; 32]);
+        let app_id = crate::AppId::new("indexer-1").unwrap();
+
+        let blocks = vec![
+            vec![transfer(&alice, &bob, 0), transfer(&bob, &bob, 0)],
+            vec![
+                signed(TransactionData::ProvideLiquidity { provider: alice.clone(), lp_tokens: Vec::new() }, &alice, 1),
+                signed(TransactionData::RegisterApp {
+                    owner: bob.clone(),
+                    app_id: app_id.clone(),
+                    app_type: crate::transaction::AppType::IndexingService,
+                    resource_requirements: crate::transaction::ResourceRequirements {
+                        min_cpu_cores: 2,
+                        min_memory_gb: 4,
+                        min_disk_gb: 100,
+                        min_bandwidth_mbps: 100,
+                    },
+                }, &bob, 1),
+            ],
+            vec![
+                signed(TransactionData::ReportMetrics {
+                    validator: validator.clone(),
+                    app_owner: bob.clone(),
+                    app_id,
+                    metrics: crate::AppMetrics::new(),
+                }, &validator, 0),
+                signed(TransactionData::ClaimRewards {
+                    claimant: alice.clone(),
+                    lp_rewards: 10,
+                    app_rewards: 0,
+                }, &alice, 2),
+            ],
+        ];
+        let mut previous = Block::genesis(validator.clone());
+        storage.store_block(&previous).unwrap();
+        for (height, txs) in blocks.into_iter().enumerate() {
+            let block = Block::new(previous.hash(), height as u64 + 1, validator.clone(), txs, 0, 0);
+            storage.store_block(&block).unwrap();
+            previous = block;
+        }
+
+        for address in [&alice, &bob, &validator] {
+            let mut indexed: Vec<Hash> = storage.get_account_transactions(address, 100).unwrap()
+                .iter().map(|tx| tx.hash()).collect();
+            indexed.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+            assert_eq!(indexed, scan_account_transactions(&storage, address));
+        }
+        assert_eq!(storage.get_account_transactions(&alice, 100).unwrap().len(), 3);
+        assert_eq!(storage.get_account_transactions(&validator, 100).unwrap().len(), 1);
+
+        // Newest first, honouring the limit
+        let latest = storage.get_account_transactions(&alice, 2).unwrap();
+        assert_eq!(latest.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_account_transactions_read_only_the_index() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let alice = Address([2u8; 32]);
+        let bob = Address([3u8; 32]);
+        let indexed = transfer(&alice, &bob, 0);
+
+        let genesis = Block::genesis(Address([1u8; 32]));
+        storage.store_block(&genesis).unwrap();
+        let block = Block::new(genesis.hash(), 1, Address([1u8; 32]), vec![indexed.clone()], 0, 0);
+        storage.store_block(&block).unwrap();
+
+        // A transaction row without index entries, plus an undecodable one, is never visited
+        let cf_transactions = storage.db.cf_handle(CF_TRANSACTIONS).unwrap();
+        let unindexed = transfer(&alice, &bob, 1);
+        storage.db.put_cf(cf_transactions, unindexed.hash().as_bytes(), bincode::serialize(&unindexed).unwrap()).unwrap();
+        storage.db.put_cf(cf_transactions, [0xffu8; 32], b"garbage").unwrap();
+
+        let found = storage.get_account_transactions(&alice, 10).unwrap();
+        assert_eq!(found.iter().map(|tx| tx.hash()).collect::<Vec<_>>(), vec![indexed.hash()]);
+    }
+
+    #[test]
+    fn test_open_indexes_metrics_reports_for_validator() {
+        let dir = TempDir::new().unwrap();
+        let validator = Address([1u8; 32]);
+        let report = signed(TransactionData::ReportMetrics {
+            validator: validator.clone(),
+            app_owner: Address([3u8; 32]),
+            app_id: crate::AppId::new("indexer-1").unwrap(),
+            metrics: crate::AppMetrics::new(),
+        }, &validator, 0);
+        {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            let genesis = Block::genesis(validator.clone());
+            storage.store_block(&genesis).unwrap();
+            let block = Block::new(genesis.hash(), 1, validator.clone(), vec![report.clone()], 0, 0);
+            storage.store_block(&block).unwrap();
+
+            // Simulate a version 5 database, where reports were indexed under the app owner only
+            let cf_history = storage.db.cf_handle(CF_ACCOUNT_HISTORY).unwrap();
+            storage.db.delete_cf(cf_history, HistoryCursor { height: 1, index: 0 }.history_key(&validator)).unwrap();
+            storage.update_metadata(SCHEMA_VERSION_KEY, &5u32.to_le_bytes()).unwrap();
+        }
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let found = storage.get_account_transactions(&validator, 10).unwrap();
+        assert_eq!(found.iter().map(|tx| tx.hash()).collect::<Vec<_>>(), vec![report.hash()]);
+    }
+
+    #[test]
+    fn test_block_writes_invisible_until_batch_commits() {
+        let dir = TempDir::new().unwrap();
+        let alice = Address([2u8; 32]);
+        let validator = Address([1u8; 32]);
+        let tx = transfer(&alice, &Address([3u8; 32]), 0);
+        let genesis = Block::genesis(validator.clone());
+        let block = Block::new(genesis.hash(), 1, validator.clone(), vec![tx.clone()], 0, 0);
+        {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.store_block(&genesis).unwrap();
+
+            // Interrupted before commit: the batch is built but never written
+            let mut batch = WriteBatch::default();
+            storage.block_write_batch(&mut batch, &block, &block.hash(), false).unwrap();
+            assert!(batch.len() > 4);
+            assert!(storage.get_block(&block.hash()).unwrap().is_none());
+            assert!(storage.get_block_hash_by_height(1).unwrap().is_none());
+            assert!(storage.get_transaction(&tx.hash()).unwrap().is_none());
+            assert!(storage.get_account_transactions(&alice, 10).unwrap().is_empty());
+        }
+
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        assert_eq!(storage.get_latest_block_info(), (Some(genesis.hash()), 0));
+
+        storage.store_block(&block).unwrap();
+        drop(storage);
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        assert_eq!(storage.get_latest_block_info(), (Some(block.hash()), 1));
+        assert_eq!(
+            storage.get_metadata("latest_block_hash").unwrap(),
+            Some(block.hash().as_bytes().to_vec())
+        );
+        assert_eq!(storage.get_block_by_height(1).unwrap().unwrap().hash(), block.hash());
+        assert_eq!(storage.get_transaction_location(&tx.hash()).unwrap().unwrap().block_height, 1);
+    }
+
+    #[test]
+    fn test_flush_keeps_written_accounts_readable() {
+        let dir = TempDir::new().unwrap();
+        let alice = Address([2u8; 32]);
+        {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.apply_balance_delta(&alice, 750).unwrap();
+            storage.flush().unwrap();
+            assert_eq!(storage.get_storage_stats().unwrap().cache_size, 0);
+            assert_eq!(storage.get_account(&alice).unwrap().unwrap().balance.amount, 750);
+        }
+
+        // Persisted, not just buffered
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        assert_eq!(storage.get_account(&alice).unwrap().unwrap().balance.amount, 750);
+    }
+
+    #[test]
+    fn test_pending_balance_applies_mempool_effects() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        storage.apply_balance_delta(&alice, 5_000).unwrap();
+
+        let mut pool = TransactionPool::new();
+        assert_eq!(storage.pending_balance(&alice, &pool).unwrap(), 5_000);
+
+        // Each transfer is 100 plus a 1_000 fee
+        pool.insert(transfer(&alice, &bob, 0), 0).unwrap();
+        pool.insert(transfer(&alice, &bob, 1), 0).unwrap();
+        assert_eq!(storage.pending_balance(&alice, &pool).unwrap(), 2_800);
+        assert_eq!(storage.pending_balance(&bob, &pool).unwrap(), 200);
+        assert_eq!(storage.get_account(&alice).unwrap().unwrap().balance.amount, 5_000);
+
+        // More pending spend than the confirmed balance floors at zero
+        for nonce in 2..6 {
+            pool.insert(transfer(&alice, &bob, nonce), 0).unwrap();
+        }
+        assert_eq!(storage.pending_balance(&alice, &pool).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_pending_balance_counts_replacement_once() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        storage.apply_balance_delta(&alice, 2_500).unwrap();
+
+        let mut pool = TransactionPool::new();
+        pool.insert(transfer(&alice, &bob, 0), 0).unwrap();
+        assert_eq!(storage.pending_balance(&alice, &pool).unwrap(), 1_400);
+
+        // The replacement's spend takes the place of the original's
+        let replacement = Transaction { fee_qor: 1_200, ..transfer(&alice, &bob, 0) };
+        assert_eq!(storage.pending_balance_with(&alice, &pool, &replacement).unwrap(), 1_200);
+        assert_eq!(storage.pending_balance_with(&bob, &pool, &replacement).unwrap(), 100);
+
+        pool.insert(replacement, 0).unwrap();
+        assert_eq!(storage.pending_balance(&alice, &pool).unwrap(), 1_200);
+
+        // A follow-up is covered by what the replacement left over
+        let follow_up = transfer(&alice, &bob, 1);
+        assert_eq!(storage.pending_balance_with(&alice, &pool, &follow_up).unwrap(), 100);
+        let overdraft = Transaction { fee_qor: 1_101, ..transfer(&alice, &bob, 1) };
+        assert!(storage.pending_balance_with(&alice, &pool, &overdraft).unwrap() < 0);
+    }
+
+    #[test]
+    fn test_pending_balance_matches_applied_block() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        storage.apply_balance_delta(&alice, 2_199).unwrap();
+
+        let mut pool = TransactionPool::new();
+        let (first, second) = (transfer(&alice, &bob, 0), transfer(&alice, &bob, 1));
+        pool.insert(first.clone(), 0).unwrap();
+        // The pending view refuses the second transfer, and so does the chain
+        assert_eq!(storage.pending_balance_with(&alice, &pool, &second).unwrap(), -1);
+        let expected = (storage.pending_balance(&alice, &pool).unwrap(), storage.pending_balance(&bob, &pool).unwrap());
+
+        let block = Block::new(Hash::zero(), 1, Address([1u8; 32]), vec![first, second.clone()], 0, 0);
+        storage.store_block(&block).unwrap();
+        assert_eq!(storage.apply_block_transactions(&block).unwrap(), vec![second.hash()]);
+        let confirmed = |address: &Address| storage.get_account(address).unwrap().unwrap().balance.amount;
+        assert_eq!((confirmed(&alice), confirmed(&bob)), expected);
+        assert_eq!(expected, (1_099, 100));
+    }
+
+    #[test]
+    fn test_apply_balance_delta_rejects_overdraft() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let alice = Address([2u8; 32]);
+
+        assert_eq!(storage.apply_balance_delta(&alice, 500).unwrap().amount, 500);
+        assert_eq!(storage.apply_balance_delta(&alice, -200).unwrap().amount, 300);
+
+        let result = storage.apply_balance_delta(&alice, -301);
+        assert!(matches!(result, Err(QoraNetError::InsufficientLiquidity { .. })));
+        assert_eq!(storage.get_account(&alice).unwrap().unwrap().balance.amount, 300);
+    }
+
+    #[test]
+    fn test_block_transactions_advance_nonces() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        storage.apply_balance_delta(&alice, 2_000).unwrap();
+
+        let applied = transfer(&alice, &bob, 0);
+        let gapped = transfer(&alice, &bob, 5);
+        let unfunded = transfer(&bob, &alice, 0);
+        let mut block = Block::new(Hash::zero(), 1, Address([1u8; 32]), vec![applied, gapped.clone(), unfunded.clone()], 0, 0);
+        block.header.timestamp = 1_000;
+        storage.store_block(&block).unwrap();
+
+        let failed = storage.apply_block_transactions(&block).unwrap();
+        assert_eq!(failed, vec![gapped.hash(), unfunded.hash()]);
+        let alice_state = storage.get_account(&alice).unwrap().unwrap();
+        // 100 sent plus the 1_000 fee
+        assert_eq!((alice_state.nonce, alice_state.balance.amount), (1, 900));
+        // Failed transactions leave the signer's nonce and balance alone, fee included
+        let bob_state = storage.get_account(&bob).unwrap().unwrap();
+        assert_eq!((bob_state.nonce, bob_state.balance.amount), (0, 100));
+    }
+
+    #[test]
+    fn test_equivocation_evidence_slashes_once() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let cheater = ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng);
+        let reporter = ed25519_dalek::Keypair::generate(&mut rand::rngs::OsRng);
+        let conflicting = |fees| {
+            let mut block = Block::new(Hash::zero(), 7, Address::from_pubkey(&cheater.public), Vec::new(), 0, 0);
+            block.header.total_fees = fees;
+            block.sign(&cheater);
+            block.signed_header().unwrap()
+        };
+        let proof = EquivocationProof { first: conflicting(0), second: conflicting(1) };
+
+        let report = Transaction::equivocation_report(proof.clone(), 0, &reporter);
+        storage.apply_transaction(&report, 12, 1_000).unwrap();
+        let offender = Address::from_pubkey(&cheater.public);
+        assert_eq!(storage.slashing_height(&offender).unwrap(), Some(12));
+        assert_eq!(storage.slashed_validators().unwrap(), vec![(offender, 12)]);
+
+        // The same offence cannot be reported twice
+        let repeat = Transaction::equivocation_report(proof, 1, &reporter);
+        assert!(storage.apply_transaction(&repeat, 13, 1_001).is_err());
+        let reporter_state = storage.get_account(&Address::from_pubkey(&reporter.public)).unwrap().unwrap();
+        assert_eq!(reporter_state.nonce, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_credits_sum_exactly() {
+        let dir = TempDir::new().unwrap();
+        let storage = std::sync::Arc::new(tokio::sync::RwLock::new(
+            BlockchainStorage::new(dir.path()).unwrap()
+        ));
+        let alice = Address([2u8; 32]);
+
+        let handles: Vec<_> = (1..=200u64).map(|i| {
+            let storage = storage.clone();
+            let alice = alice.clone();
+            tokio::spawn(async move {
+                storage.write().await.apply_balance_delta(&alice, i as i128)
+            })
+        }).collect();
+
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let storage = storage.read().await;
+        let final_balance = storage.get_account(&alice).unwrap().unwrap().balance.amount;
+        assert_eq!(final_balance, (1..=200u64).sum::<u64>());
+    }
+
+    #[tokio::test]
+    async fn test_same_nonce_consumed_once() {
+        let dir = TempDir::new().unwrap();
+        let storage = std::sync::Arc::new(tokio::sync::RwLock::new(
+            BlockchainStorage::new(dir.path()).unwrap()
+        ));
+        let alice = Address([2u8; 32]);
+
+        let handles: Vec<_> = (0..2).map(|_| {
+            let storage = storage.clone();
+            let alice = alice.clone();
+            tokio::spawn(async move {
+                storage.write().await.try_consume_nonce(&alice, 0)
+            })
+        }).collect();
+
+        let mut consumed = 0;
+        for handle in handles {
+            if handle.await.unwrap().is_ok() {
+                consumed += 1;
+            }
+        }
+        assert_eq!(consumed, 1);
+
+        let mut storage = storage.write().await;
+        assert_eq!(storage.get_account(&alice).unwrap().unwrap().nonce, 1);
+        assert!(storage.try_consume_nonce(&alice, 1).is_ok());
+    }
+
+    #[test]
+    fn test_open_refuses_newer_schema_version() {
+        let dir = TempDir::new().unwrap();
+        {
+            let storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.update_metadata(SCHEMA_VERSION_KEY, &(SCHEMA_VERSION + 1).to_le_bytes()).unwrap();
+        }
+
+        match BlockchainStorage::new(dir.path()) {
+            Err(QoraNetError::StorageError(message)) => assert!(message.contains("newer")),
+            other => panic!("expected schema error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_open_refuses_unknown_column_family() {
+        let dir = TempDir::new().unwrap();
+        {
+            let mut opts = Options::default();
+            opts.create_if_missing(true);
+            opts.create_missing_column_families(true);
+            DB::open_cf(&opts, dir.path(), vec![CF_BLOCKS, CF_METADATA, "from_the_future"]).unwrap();
+        }
+
+        assert!(matches!(BlockchainStorage::new(dir.path()), Err(QoraNetError::StorageError(_))));
+    }
+
+    #[test]
+    fn test_open_migrates_unversioned_database() {
+        let dir = TempDir::new().unwrap();
+        let alice = Address([2u8; 32]);
+        let tx = transfer(&alice, &Address([3u8; 32]), 0);
+        {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            let genesis = Block::genesis(Address([1u8; 32]));
+            storage.store_block(&genesis).unwrap();
+            let block = Block::new(genesis.hash(), 1, Address([1u8; 32]), vec![tx.clone()], 0, 0);
+            storage.store_block(&block).unwrap();
+
+            // Simulate a version 1 database: no indexes, no version key
+            let cf_metadata = storage.db.cf_handle(CF_METADATA).unwrap();
+            storage.db.delete_cf(cf_metadata, SCHEMA_VERSION_KEY.as_bytes()).unwrap();
+            for key in [BLOCK_COUNT_KEY, TRANSACTION_COUNT_KEY, ACCOUNT_COUNT_KEY] {
+                storage.db.delete_cf(cf_metadata, key.as_bytes()).unwrap();
+            }
+            let cf_tx_index = storage.db.cf_handle(CF_TX_INDEX).unwrap();
+            storage.db.delete_cf(cf_tx_index, tx.hash().as_bytes()).unwrap();
+        }
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let location = storage.get_transaction_location(&tx.hash()).unwrap().unwrap();
+        assert_eq!(location.block_height, 1);
+        assert_eq!(
+            storage.get_metadata(SCHEMA_VERSION_KEY).unwrap(),
+            Some(SCHEMA_VERSION.to_le_bytes().to_vec())
+        );
+        // Counters are rebuilt once from the stored keys
+        let stats = storage.get_storage_stats().unwrap();
+        assert_eq!((stats.total_blocks, stats.total_transactions), (2, 1));
+    }
+
+    #[test]
+    fn test_multi_token_account_round_trips() {
+        let dir = TempDir::new().unwrap();
+        let alice = Address([2u8; 32]);
+        let usdc = Address([0xaa; 32]);
+        let dai = Address([0xbb; 32]);
+        let root = {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.update_account_balance(&alice, &Address::native_qor(), Balance::new(500)).unwrap();
+            storage.update_account_balance(&alice, &usdc, Balance::new(42)).unwrap();
+            storage.update_account_balance(&alice, &dai, Balance::new(7)).unwrap();
+            storage.update_account_balance(&alice, &dai, Balance::new(0)).unwrap();
+            storage.accounts_root().unwrap()
+        };
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let account = storage.get_account(&alice).unwrap().unwrap();
+        assert_eq!(account.balance.amount, 500);
+        assert_eq!(account.get_token_balance(&Address::native_qor()), 500);
+        assert_eq!(account.get_token_balance(&usdc), 42);
+        assert_eq!(account.get_token_balance(&dai), 0);
+        assert_eq!(account.token_balances.get_all_balances(), vec![(usdc, 42)]);
+        assert_eq!(storage.accounts_root().unwrap(), root);
+    }
+
+    #[test]
+    fn test_open_migrates_v3_accounts() {
+        #[derive(Serialize)]
+        struct V3 {
+            address: Address,
+            balance: Balance,
+            nonce: u64,
+            frozen: bool,
+            created_at: u64,
+            last_updated: u64,
+        }
+
+        let dir = TempDir::new().unwrap();
+        let alice = Address([2u8; 32]);
+        {
+            let storage = BlockchainStorage::new(dir.path()).unwrap();
+            let old = V3 { address: alice.clone(), balance: Balance::new(900), nonce: 4, frozen: true, created_at: 1, last_updated: 2 };
+            let cf_accounts = storage.db.cf_handle(CF_ACCOUNTS).unwrap();
+            storage.db.put_cf(cf_accounts, alice.as_bytes(), bincode::serialize(&old).unwrap()).unwrap();
+            storage.update_metadata(SCHEMA_VERSION_KEY, &3u32.to_le_bytes()).unwrap();
+        }
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let account = storage.get_account(&alice).unwrap().unwrap();
+        assert_eq!((account.balance.amount, account.nonce, account.frozen), (900, 4, true));
+        assert!(account.token_balances.get_all_balances().is_empty());
+        assert_eq!(
+            storage.get_metadata(SCHEMA_VERSION_KEY).unwrap(),
+            Some(SCHEMA_VERSION.to_le_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_open_migrates_unsigned_v7_blocks() {
+        #[derive(Serialize)]
+        struct V7<'a> {
+            header: &'a BlockHeader,
+            transactions: &'a Vec<Transaction>,
+        }
+
+        let dir = TempDir::new().unwrap();
+        let blocks = {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            let blocks = build_chain(&mut storage, 3);
+            let cf_blocks = storage.db.cf_handle(CF_BLOCKS).unwrap();
+            for block in &blocks {
+                let old = V7 { header: &block.header, transactions: &block.transactions };
+                storage.db.put_cf(cf_blocks, block.hash().as_bytes(), bincode::serialize(&old).unwrap()).unwrap();
+            }
+            storage.update_metadata(SCHEMA_VERSION_KEY, &7u32.to_le_bytes()).unwrap();
+            blocks
+        };
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        for block in &blocks {
+            let migrated = storage.get_block_by_height(block.header.height).unwrap().unwrap();
+            assert_eq!(migrated.hash(), block.hash());
+            assert!(migrated.signature.is_none());
+        }
+        assert_eq!(
+            storage.get_metadata(SCHEMA_VERSION_KEY).unwrap(),
+            Some(SCHEMA_VERSION.to_le_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_open_migrates_and_rekeys_pre_expiry_v3_transactions() {
+        #[derive(Serialize)]
+        struct TxV3<'a> {
+            data: &'a TransactionData,
+            nonce: u64,
+            fee_qor: u64,
+            fee_usd: f64,
+            priority: crate::FeePriority,
+            signature: crate::QoraSignature,
+            signer: &'a Address,
+        }
+        #[derive(Serialize)]
+        struct V3<'a> {
+            header: &'a BlockHeader,
+            transactions: Vec<TxV3<'a>>,
+        }
+        let old_tx = |tx: &'_ Transaction| TxV3 {
+            data: &tx.data, nonce: tx.nonce, fee_qor: tx.fee_qor, fee_usd: tx.fee_usd,
+            priority: tx.priority.clone(), signature: tx.signature, signer: &tx.signer,
+        };
+
+        let dir = TempDir::new().unwrap();
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        let block = Block::new(Hash::zero(), 0, Address([1u8; 32]), vec![transfer(&alice, &bob, 0), transfer(&alice, &bob, 1)], 0, 0);
+        {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.store_block(&block).unwrap();
+            let cf_blocks = storage.db.cf_handle(CF_BLOCKS).unwrap();
+            let old = V3 { header: &block.header, transactions: block.transactions.iter().map(old_tx).collect() };
+            storage.db.put_cf(cf_blocks, block.hash().as_bytes(), bincode::serialize(&old).unwrap()).unwrap();
+            let cf_transactions = storage.db.cf_handle(CF_TRANSACTIONS).unwrap();
+            for tx in &block.transactions {
+                storage.db.delete_cf(cf_transactions, tx.hash().as_bytes()).unwrap();
+                storage.db.put_cf(cf_transactions, tx.legacy_hash().as_bytes(), bincode::serialize(&old_tx(tx)).unwrap()).unwrap();
+            }
+            storage.update_metadata(SCHEMA_VERSION_KEY, &3u32.to_le_bytes()).unwrap();
+        }
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let migrated = storage.get_block_by_height(0).unwrap().unwrap();
+        assert_eq!(migrated.hash(), block.hash());
+        assert_eq!(migrated.transactions.len(), 2);
+        for tx in &block.transactions {
+            let stored = storage.get_transaction(&tx.hash()).unwrap().unwrap();
+            assert_eq!((stored.nonce, stored.valid_until_height), (tx.nonce, None));
+            // Rekeyed from the id that covered unsigned fields
+            assert!(storage.get_transaction(&tx.legacy_hash()).unwrap().is_none());
+            assert_eq!(storage.get_transaction_location(&tx.hash()).unwrap().unwrap().block_height, 0);
+        }
+    }
+
+    #[test]
+    fn test_open_migrates_pre_memo_transfers() {
+        #[derive(Serialize)]
+        enum DataV0<'a> {
+            Transfer { from: &'a Address, to: &'a Address, amount: u64 },
+        }
+        #[derive(Serialize)]
+        struct TxV0<'a> {
+            data: DataV0<'a>,
+            nonce: u64,
+            fee_qor: u64,
+            fee_usd: f64,
+            priority: crate::FeePriority,
+            signature: crate::QoraSignature,
+            signer: &'a Address,
+        }
+        #[derive(Serialize)]
+        struct V0<'a> {
+            header: &'a BlockHeader,
+            transactions: Vec<TxV0<'a>>,
+        }
+        let old_tx = |tx: &'_ Transaction| match &tx.data {
+            TransactionData::Transfer { from, to, amount, .. } => TxV0 {
+                data: DataV0::Transfer { from, to, amount: *amount },
+                nonce: tx.nonce, fee_qor: tx.fee_qor, fee_usd: tx.fee_usd,
+                priority: tx.priority.clone(), signature: tx.signature, signer: &tx.signer,
+            },
+            _ => unreachable!(),
+        };
+
+        let dir = TempDir::new().unwrap();
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        let block = Block::new(Hash::zero(), 0, Address([1u8; 32]), vec![transfer(&alice, &bob, 0), transfer(&alice, &bob, 1)], 0, 0);
+        {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.store_block(&block).unwrap();
+            let cf_blocks = storage.db.cf_handle(CF_BLOCKS).unwrap();
+            let old = V0 { header: &block.header, transactions: block.transactions.iter().map(old_tx).collect() };
+            storage.db.put_cf(cf_blocks, block.hash().as_bytes(), bincode::serialize(&old).unwrap()).unwrap();
+            let cf_transactions = storage.db.cf_handle(CF_TRANSACTIONS).unwrap();
+            for tx in &block.transactions {
+                storage.db.delete_cf(cf_transactions, tx.hash().as_bytes()).unwrap();
+                storage.db.put_cf(cf_transactions, tx.legacy_hash().as_bytes(), bincode::serialize(&old_tx(tx)).unwrap()).unwrap();
+            }
+            storage.update_metadata(SCHEMA_VERSION_KEY, &3u32.to_le_bytes()).unwrap();
+        }
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let migrated = storage.get_block_by_height(0).unwrap().unwrap();
+        assert_eq!(migrated.hash(), block.hash());
+        assert_eq!(Block::calculate_transactions_root(&migrated.transactions), block.header.transactions_root);
+        for tx in &block.transactions {
+            let stored = storage.get_transaction(&tx.hash()).unwrap().unwrap();
+            assert_eq!(stored.hash(), tx.hash());
+            assert!(stored.data.memo().is_none());
+        }
+    }
+
+    #[test]
+    fn test_open_stores_empty_memos_as_none() {
+        let dir = TempDir::new().unwrap();
+        let tx = transfer(&Address([2u8; 32]), &Address([3u8; 32]), 0);
+        let block = Block::new(Hash::zero(), 0, Address([1u8; 32]), vec![tx.clone()], 0, 0);
+        {
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.store_block(&block).unwrap();
+
+            // A version 10 node stored the empty memo, which then hashed like none
+            let mut empty = tx.clone();
+            if let TransactionData::Transfer { memo, .. } = &mut empty.data {
+                *memo = Some(Vec::new());
+            }
+            let old_block = Block { transactions: vec![empty.clone()], ..block.clone() };
+            let cf_blocks = storage.db.cf_handle(CF_BLOCKS).unwrap();
+            storage.db.put_cf(cf_blocks, block.hash().as_bytes(), bincode::serialize(&old_block).unwrap()).unwrap();
+            let cf_transactions = storage.db.cf_handle(CF_TRANSACTIONS).unwrap();
+            storage.db.put_cf(cf_transactions, tx.hash().as_bytes(), bincode::serialize(&empty).unwrap()).unwrap();
+            storage.update_metadata(SCHEMA_VERSION_KEY, &10u32.to_le_bytes()).unwrap();
+        }
+
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let stored = storage.get_transaction(&tx.hash()).unwrap().unwrap();
+        assert_eq!(stored.hash(), tx.hash());
+        let migrated = storage.get_block_by_height(0).unwrap().unwrap();
+        assert_eq!(Block::calculate_transactions_root(&migrated.transactions), block.header.transactions_root);
+    }
+
+    #[test]
+    fn test_iter_blocks_range_matches_eager() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        build_chain(&mut storage, 10);
+
+        let eager = storage.get_blocks_range(2, 7).unwrap();
+        let lazy: Vec<Block> = storage.iter_blocks_range(2, 7).map(|b| b.unwrap()).collect();
+        let eager_hashes: Vec<Hash> = eager.iter().map(|b| b.hash()).collect();
+        let lazy_hashes: Vec<Hash> = lazy.iter().map(|b| b.hash()).collect();
+        assert_eq!(lazy_hashes, eager_hashes);
+        assert_eq!(lazy.len(), 6);
+
+        // Nothing is fetched until the iterator is driven, so an enormous range is fine
+        let first_three: Vec<BlockHeight> = storage.iter_blocks_range(0, u64::MAX)
+            .take(3)
+            .map(|b| b.unwrap().header.height)
+            .collect();
+        assert_eq!(first_three, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_verify_integrity_healthy_chain() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        build_chain(&mut storage, 5);
+
+        let report = storage.verify_integrity().unwrap();
+        assert!(report.is_clean(), "unexpected issues: {:?}", report.issues);
+        assert_eq!(report.blocks_checked, 5);
+    }
+
+    #[test]
+    fn test_verify_integrity_reports_all_discrepancies() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let blocks = build_chain(&mut storage, 5);
+
+        let cf_blocks = storage.db.cf_handle(CF_BLOCKS).unwrap();
+
+        // Height 2: index points at a block that doesn't exist
+        storage.db.put_cf(cf_blocks, b"height:2", Hash::new(b"missing").as_bytes()).unwrap();
+
+        // Height 3: fee total in the header doesn't match its transactions
+        let mut bad_block = blocks[3].clone();
+        bad_block.header.total_fees = 42;
+        let bad_hash = bad_block.hash();
+        storage.db.put_cf(cf_blocks, bad_hash.as_bytes(), bincode::serialize(&bad_block).unwrap()).unwrap();
+        storage.db.put_cf(cf_blocks, b"height:3", bad_hash.as_bytes()).unwrap();
+
+        let report = storage.verify_integrity().unwrap();
+        assert!(!report.is_clean());
+
+        let heights: Vec<BlockHeight> = report.issues.iter().map(|issue| issue.height).collect();
+        assert!(heights.contains(&2));
+        assert!(heights.contains(&3));
+        // Block 4 still links to the original block 3, not the tampered one
+        assert!(heights.contains(&4));
+        assert_eq!(report.blocks_checked, 4);
+    }
+    
+    /// Chain built the way a validator applies it: transfers, then the block reward
+    fn build_executed_chain(storage: &mut BlockchainStorage, genesis: &GenesisConfig, length: u64) -> Vec<Block> {
+        let mut blocks = vec![genesis.genesis_block(Address([1u8; 32]))];
+        storage.store_block(&blocks[0]).unwrap();
+        storage.apply_genesis_allocations(genesis).unwrap();
+        extend_executed_chain(storage, genesis, &mut blocks, length, true);
+        blocks
+    }
+    
+    /// Produce blocks up to `length` the way the validator does, each with one
+    /// transfer from alice to bob; `commit_roots` false builds pre-state-root headers
+    fn extend_executed_chain(
+        storage: &mut BlockchainStorage,
+        genesis: &GenesisConfig,
+        blocks: &mut Vec<Block>,
+        length: u64,
+        commit_roots: bool,
+    ) {
+        let validator = Address([1u8; 32]);
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        for height in blocks.len() as u64..length {
+            let previous_hash = blocks.last().unwrap().hash();
+            let nonce = storage.get_account(&alice).unwrap().map_or(0, |account| account.nonce);
+            let mut block = Block::new(previous_hash, height, validator.clone(), vec![transfer(&alice, &bob, nonce)], 0, 0)
+                .with_state_root(storage.state_root().unwrap());
+            if !commit_roots {
+                block.header.version = 1;
+                block.header.state_root = Hash::zero();
+            }
+            storage.store_block(&block).unwrap();
+            let failed = storage.apply_block_transactions(&block).unwrap();
+            let epoch = height / crate::consensus::DEFAULT_EPOCH_LENGTH;
+            storage.reward_block(&validator, block.collected_fees(&failed), epoch, genesis).unwrap();
+            blocks.push(block);
+        }
+    }
+    
+    fn executed_genesis() -> GenesisConfig {
+        GenesisConfig {
+            allocations: vec![crate::consensus::GenesisAllocation { address: Address([2u8; 32]), amount: 1_000_000 }],
+            ..GenesisConfig::default()
+        }
+    }
+    
+    #[test]
+    fn test_verify_chain_accepts_executed_chain() {
+        let (dir, replay_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = executed_genesis();
+        build_executed_chain(&mut storage, &genesis, 6);
+        
+        let mut replay = BlockchainStorage::new(replay_dir.path()).unwrap();
+        let verification = storage.verify_chain(&genesis, &mut replay).unwrap();
+        assert!(verification.is_valid(), "unexpected divergence: {:?}", verification.divergence);
+        assert_eq!(verification.blocks_verified, 6);
+        assert_eq!(replay.get_account(&Address([3u8; 32])).unwrap().unwrap().balance.amount, 500);
+    }
+    
+    #[test]
+    fn test_verify_chain_reports_tampered_block_height() {
+        let (dir, replay_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = executed_genesis();
+        let blocks = build_executed_chain(&mut storage, &genesis, 6);
+        
+        // Height 3 now carries a different transfer than the one its header commits to
+        let mut bad_block = blocks[3].clone();
+        if let TransactionData::Transfer { amount, .. } = &mut bad_block.transactions[0].data {
+            *amount = 50;
+        }
+        let bad_hash = bad_block.hash();
+        let cf_blocks = storage.db.cf_handle(CF_BLOCKS).unwrap();
+        storage.db.put_cf(cf_blocks, bad_hash.as_bytes(), bincode::serialize(&bad_block).unwrap()).unwrap();
+        storage.db.put_cf(cf_blocks, b"height:3", bad_hash.as_bytes()).unwrap();
+        
+        let mut replay = BlockchainStorage::new(replay_dir.path()).unwrap();
+        let verification = storage.verify_chain(&genesis, &mut replay).unwrap();
+        let divergence = verification.divergence.expect("tampered block detected");
+        assert_eq!(divergence.height, 3);
+        assert_eq!(verification.blocks_verified, 3);
+    }
+    
+    #[test]
+    fn test_verify_chain_reports_state_divergence() {
+        let (dir, replay_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = executed_genesis();
+        let mut blocks = build_executed_chain(&mut storage, &genesis, 4);
+        
+        // The producer credited bob outside any block, so block 4 commits to a state the chain can't reach
+        let bob = Address([3u8; 32]);
+        let mut inflated = storage.get_account(&bob).unwrap().unwrap();
+        inflated.balance.amount += 1;
+        storage.store_account(&inflated).unwrap();
+        extend_executed_chain(&mut storage, &genesis, &mut blocks, 6, true);
+        
+        let mut replay = BlockchainStorage::new(replay_dir.path()).unwrap();
+        let verification = storage.verify_chain(&genesis, &mut replay).unwrap();
+        let divergence = verification.divergence.unwrap();
+        assert_eq!(divergence.height, 4);
+        assert!(divergence.reason.contains("State root mismatch"), "{}", divergence.reason);
+    }
+    
+    #[test]
+    fn test_verify_chain_keeps_failed_transactions_without_effect() {
+        let (dir, replay_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = executed_genesis();
+        let mut blocks = build_executed_chain(&mut storage, &genesis, 3);
+        
+        // A replayed nonce no longer applies; the validator includes it without effect
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        let block = Block::new(blocks[2].hash(), 3, Address([1u8; 32]), vec![transfer(&alice, &bob, 0)], 0, 0)
+            .with_state_root(storage.state_root().unwrap());
+        storage.store_block(&block).unwrap();
+        let failed = storage.apply_block_transactions(&block).unwrap();
+        assert_eq!(failed.len(), 1);
+        storage.reward_block(&Address([1u8; 32]), block.collected_fees(&failed), 0, &genesis).unwrap();
+        blocks.push(block);
+        extend_executed_chain(&mut storage, &genesis, &mut blocks, 5, true);
+        
+        let mut replay = BlockchainStorage::new(replay_dir.path()).unwrap();
+        let verification = storage.verify_chain(&genesis, &mut replay).unwrap();
+        assert!(verification.is_valid(), "unexpected divergence: {:?}", verification.divergence);
+    }
+    
+    #[test]
+    fn test_verify_chain_checks_legacy_blocks_against_checkpoints() {
+        let (dir, replay_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = executed_genesis();
+        let mut blocks = vec![genesis.genesis_block(Address([1u8; 32]))];
+        storage.store_block(&blocks[0]).unwrap();
+        storage.apply_genesis_allocations(&genesis).unwrap();
+        extend_executed_chain(&mut storage, &genesis, &mut blocks, 6, false);
+        
+        // Stored state at height 4 credits bob more than the chain paid him
+        let bob = Address([3u8; 32]);
+        let mut inflated = storage.get_account_at_height(&bob, 4).unwrap().unwrap();
+        inflated.balance.amount += 1;
+        let cf_checkpoints = storage.db.cf_handle(CF_ACCOUNT_CHECKPOINTS).unwrap();
+        storage.db.put_cf(cf_checkpoints, account_checkpoint_key(&bob, 4), bincode::serialize(&inflated).unwrap()).unwrap();
+        
+        let mut replay = BlockchainStorage::new(replay_dir.path()).unwrap();
+        let verification = storage.verify_chain(&genesis, &mut replay).unwrap();
+        assert_eq!(verification.divergence.unwrap().height, 4);
+    }
+
+    fn claim(claimant: &Address, nonce: u64, amount: u64) -> Transaction {
+        Transaction {
+            data: TransactionData::ClaimRewards { claimant: claimant.clone(), lp_rewards: amount, app_rewards: 0 },
+            signer: claimant.clone(),
+            ..transfer(claimant, claimant, nonce)
+        }
+    }
+
+    #[test]
+    fn test_prune_blocks_keeps_index_and_accounts() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        storage.set_pruning_config(PruningConfig { retention_blocks: 3, finality_depth: 4 });
+        let genesis = executed_genesis();
+        let blocks = build_executed_chain(&mut storage, &genesis, 12);
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        let pruned_tx = blocks[2].transactions[0].hash();
+        
+        // Tip 11 with depth 4: height 7 is the highest final block
+        assert_eq!(storage.finalized_height(), Some(7));
+        assert!(storage.prune_blocks(9).is_err());
+        assert_eq!(storage.prune_blocks(5).unwrap(), 5);
+        
+        for height in 0..5 {
+            assert!(storage.get_block_by_height(height).unwrap().is_none());
+            assert_eq!(storage.get_block_hash_by_height(height).unwrap(), Some(blocks[height as usize].hash()));
+            assert_eq!(storage.pruned_block_height(&blocks[height as usize].hash()).unwrap(), Some(height));
+        }
+        assert!(storage.get_block_by_height(5).unwrap().is_some());
+        // Only blocks the index places below the pruned height count as pruned
+        assert_eq!(storage.pruned_block_height(&blocks[5].hash()).unwrap(), None);
+        assert_eq!(storage.pruned_block_height(&Hash::new(b"never stored")).unwrap(), None);
+        assert!(storage.get_transaction(&pruned_tx).unwrap().is_none());
+        assert_eq!(storage.get_account(&bob).unwrap().unwrap().balance.amount, 1_100);
+        assert_eq!(storage.get_account(&alice).unwrap().unwrap().nonce, 11);
+        
+        // History only lists kept transactions; stats and integrity account for the pruned range
+        assert_eq!(storage.get_account_transactions(&bob, 100).unwrap().len(), 7);
+        assert_eq!(storage.get_storage_stats().unwrap().total_blocks, 7);
+        assert!(storage.verify_integrity().unwrap().is_clean());
+        
+        // The retention window stops at the finalized height
+        assert_eq!(storage.prune_to_retention().unwrap(), 3);
+        assert_eq!(storage.pruned_below().unwrap(), 8);
+        assert!(storage.get_block_by_height(8).unwrap().is_some());
+        
+        // Finality follows the chain's genesis, not the default one
+        let shallow = GenesisConfig { finality_depth: 2, ..GenesisConfig::default() };
+        storage.set_pruning_config(PruningConfig::for_genesis(&shallow));
+        assert_eq!(storage.finalized_height(), Some(9));
+    }
+
+    #[test]
+    fn test_send_limit_enforced_over_rolling_window() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let (alice, bob, custodian) = (Address([2u8; 32]), Address([3u8; 32]), Address([9u8; 32]));
+        let genesis = GenesisConfig { spending_limit_authority: Some(custodian.clone()), ..GenesisConfig::default() };
+        storage.apply_genesis_allocations(&genesis).unwrap();
+        storage.apply_balance_delta(&alice, 10_000).unwrap();
+
+        // Only a transaction signed by the custody key may set limits
+        let set_limit = |signer: &Address, authority: &Address, nonce: u64| Transaction {
+            data: TransactionData::SetSendLimit { authority: authority.clone(), account: alice.clone(), limit: Some(250) },
+            ..transfer(signer, &bob, nonce)
+        };
+        assert!(storage.apply_transaction(&set_limit(&alice, &alice, 0), 1, 0).is_err());
+        assert!(storage.apply_transaction(&set_limit(&alice, &custodian, 0), 1, 0).is_err());
+        storage.apply_transaction(&set_limit(&custodian, &custodian, 0), 1, 0).unwrap();
+
+        let now = 1_000_000;
+        storage.apply_transaction(&transfer(&alice, &bob, 0), 2, now).unwrap();
+        storage.apply_transaction(&transfer(&alice, &bob, 1), 3, now + 60).unwrap();
+
+        match storage.apply_transaction(&transfer(&alice, &bob, 2), 4, now + 120) {
+            Err(QoraNetError::SpendingLimitExceeded { limit, spent, requested }) => {
+                assert_eq!((limit, spent, requested), (250, 200, 100));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        // A rejected transfer leaves the nonce and balances alone
+        let account = storage.get_account(&alice).unwrap().unwrap();
+        assert_eq!((account.nonce, account.balance.amount), (2, 9_800));
+
+        // Once the first send leaves the window there is room again
+        storage.apply_transaction(&transfer(&alice, &bob, 2), 5, now + SPENDING_LIMIT_WINDOW_SECS).unwrap();
+        assert_eq!(storage.get_account(&bob).unwrap().unwrap().balance.amount, 300);
+        assert_eq!(storage.get_account(&alice).unwrap().unwrap().recent_sends.len(), 2);
+    }
+
+    #[test]
+    fn test_claims_never_exceed_funded_pool() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let config = RewardPoolConfig::default();
+
+        // Epoch 0 collects half of 10 blocks' fees
+        for _ in 0..10 {
+            assert_eq!(storage.fund_reward_pool(0, 1_000, 2_500, &config).unwrap(), 500);
+        }
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+        storage.accrue_rewards(&alice, 3_000).unwrap();
+        storage.accrue_rewards(&bob, 2_500).unwrap();
+        assert!(storage.apply_claim_rewards(&claim(&alice, 0, 1)).is_err());
+
+        // The first block of epoch 1 finalizes epoch 0
+        storage.fund_reward_pool(1, 1_000, 0, &config).unwrap();
+        assert_eq!(storage.reward_pool().unwrap().available(), 5_000);
+
+        // The pool could pay it, but no one may claim beyond their entitlement
+        assert!(storage.apply_claim_rewards(&claim(&alice, 0, 3_001)).is_err());
+        assert_eq!(storage.apply_claim_rewards(&claim(&alice, 0, 3_000)).unwrap(), 3_000);
+        assert!(storage.apply_claim_rewards(&claim(&bob, 0, 2_001)).is_err());
+        assert_eq!(storage.apply_claim_rewards(&claim(&bob, 0, 2_000)).unwrap(), 2_000);
+        assert!(storage.apply_claim_rewards(&claim(&alice, 1, 1)).is_err());
+        assert_eq!(storage.accrued_rewards(&alice).unwrap(), 0);
+        assert_eq!(storage.accrued_rewards(&bob).unwrap(), 500);
+
+        let paid = storage.get_account(&alice).unwrap().unwrap().balance.amount
+            + storage.get_account(&bob).unwrap().unwrap().balance.amount;
+        assert_eq!(paid, 5_000);
+    }
+
+    #[test]
+    fn test_block_fees_fund_pool_past_supply_cap() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = GenesisConfig { block_reward: 10_000, max_qor_supply: 10_000, ..GenesisConfig::default() };
+        let producer = Address([1u8; 32]);
+
+        // Half of the fees go to the pool; the producer keeps the rest and the minted reward
+        assert_eq!(storage.reward_block(&producer, 4_000, 0, &genesis).unwrap(), 12_000);
+        // Nothing more is minted at the cap, but fees still fund the pool
+        assert_eq!(storage.reward_block(&producer, 40_000, 0, &genesis).unwrap(), 20_000);
+        assert_eq!(storage.get_account(&producer).unwrap().unwrap().balance.amount, 32_000);
+        assert_eq!(storage.total_qor_supply().unwrap(), 10_000);
+
+        storage.reward_block(&producer, 0, 1, &genesis).unwrap();
+        assert_eq!(storage.reward_pool().unwrap().available(), 22_000);
+    }
+
+    #[test]
+    fn test_closed_epoch_pays_top_apps_by_smoothed_score() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = GenesisConfig { block_reward: 10_000, max_rewarded_apps: 1, ..GenesisConfig::default() };
+        let (steady, spiky) = (Address([1u8; 32]), Address([2u8; 32]));
+        let report = |validator: &Address, app: &str, requests: u64, nonce: u64| signed(TransactionData::ReportMetrics {
+            validator: validator.clone(),
+            app_owner: Address([9u8; 32]),
+            app_id: crate::AppId::new(app).unwrap(),
+            metrics: crate::AppMetrics { requests_served: requests, ..crate::AppMetrics::new() },
+        }, validator, nonce);
+
+        // The spiky app's last report beats the steady one's, but not its smoothed score
+        for nonce in 0..3 {
+            storage.apply_transaction(&report(&spiky, "spiky", 0, nonce), 1, 1).unwrap();
+        }
+        for nonce in 0..3 {
+            storage.apply_transaction(&report(&steady, "steady", 600, nonce), 1, 1).unwrap();
+        }
+        storage.apply_transaction(&report(&spiky, "spiky", 1_000, 3), 1, 1).unwrap();
+        assert_eq!(storage.app_scores().unwrap().len(), 2);
+
+        // Reports for another validator's app are refused
+        assert!(storage.apply_transaction(&signed(report(&steady, "steady", 0, 3).data, &spiky, 4), 1, 1).is_err());
+
+        storage.reward_block(&steady, 4_000, 0, &genesis).unwrap();
+        assert_eq!(storage.accrued_rewards(&steady).unwrap(), 0);
+        storage.reward_block(&steady, 0, 1, &genesis).unwrap();
+        assert_eq!(storage.accrued_rewards(&steady).unwrap(), 2_000);
+        assert_eq!(storage.accrued_rewards(&spiky).unwrap(), 0);
+    }
+
+    fn fixed_account(byte: u8, amount: u64) -> AccountState {
+        AccountState {
+            balance: Balance::new(amount),
+            created_at: 1,
+            last_updated: 1,
+            ..AccountState::new(Address([byte; 32]))
+        }
+    }
+
+    #[test]
+    fn test_account_cache_evicts_least_recently_used() {
+        let mut cache = StorageCache::new();
+        *cache.accounts() = AccountLru::new(3);
+        
+        // Updated-at order is the reverse of access order, so oldest-updated eviction would pick 3
+        let account = |byte: u8| AccountState { last_updated: 100 - byte as u64, ..fixed_account(byte, 0) };
+        for byte in 1..=3 {
+            cache.cache_account(account(byte));
+        }
+        assert!(cache.get_cached_account(&Address([1u8; 32])).is_some());
+        cache.cache_account(account(4));
+        
+        assert!(cache.get_cached_account(&Address([2u8; 32])).is_none());
+        for byte in [1, 3, 4] {
+            assert!(cache.get_cached_account(&Address([byte; 32])).is_some(), "account {} evicted", byte);
+        }
+        assert_eq!(cache.accounts().len(), 3);
+        
+        // Re-caching an entry refreshes it without evicting anything
+        cache.cache_account(account(1));
+        assert_eq!(cache.accounts().len(), 3);
+        
+        // Invalidated entries leave no stale recency behind
+        cache.invalidate_account(&Address([3u8; 32]));
+        assert!(cache.get_cached_account(&Address([3u8; 32])).is_none());
+        cache.cache_account(account(5));
+        cache.cache_account(account(6));
+        // 4 is now least recently used: 1 was re-cached after it, 5 and 6 came later
+        assert!(cache.get_cached_account(&Address([4u8; 32])).is_none());
+        let lru = cache.accounts();
+        assert_eq!(lru.len(), 3);
+        assert_eq!(lru.recency.len(), 3);
+    }
+
+    #[test]
+    fn test_iter_accounts_in_address_order_only() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        storage.store_block(&Block::genesis(Address([1u8; 32]))).unwrap();
+        for byte in [9u8, 3, 7] {
+            storage.store_account(&fixed_account(byte, byte as u64 * 10)).unwrap();
+        }
+
+        // Stray non-account key in the same column family
+        let cf_accounts = storage.db.cf_handle(CF_ACCOUNTS).unwrap();
+        storage.db.put_cf(cf_accounts, b"height:1", b"junk").unwrap();
+
+        let accounts: Vec<AccountState> = storage.iter_accounts().collect::<Result<_>>().unwrap();
+        let addresses: Vec<Address> = accounts.iter().map(|account| account.address.clone()).collect();
+        assert_eq!(addresses, vec![Address([3u8; 32]), Address([7u8; 32]), Address([9u8; 32])]);
+        assert_eq!(accounts[0].balance.amount, 30);
+    }
+
+    #[test]
+    fn test_accounts_root_independent_of_write_order() {
+        let roots: Vec<Hash> = [[1u8, 2, 3], [3, 1, 2]].iter().map(|order| {
+            let dir = TempDir::new().unwrap();
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            for byte in order {
+                storage.store_account(&fixed_account(*byte, 100)).unwrap();
+            }
+            storage.accounts_root().unwrap()
+        }).collect();
+        assert_eq!(roots[0], roots[1]);
+    }
+
+    #[test]
+    fn test_accounts_root_ignores_local_timestamps() {
+        let roots: Vec<Hash> = [1u64, 2].iter().map(|clock| {
+            let dir = TempDir::new().unwrap();
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            let account = AccountState { created_at: *clock, last_updated: *clock, ..fixed_account(1, 100) };
+            storage.store_account(&account).unwrap();
+            assert_eq!(storage.export_snapshot().unwrap().state_root, storage.state_root().unwrap());
+            storage.accounts_root().unwrap()
+        }).collect();
+        assert_eq!(roots[0], roots[1]);
+    }
+
+    fn balances(storage: &BlockchainStorage) -> Vec<(Address, u64)> {
+        storage.iter_accounts()
+            .map(|account| account.map(|account| (account.address, account.balance.amount)))
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_credits_independent_of_output_order() {
+        let credits = vec![
+            (Address([5u8; 32]), 500),
+            (Address([1u8; 32]), 100),
+            (Address([9u8; 32]), 900),
+            (Address([1u8; 32]), 50),
+        ];
+        let mut shuffled = credits.clone();
+        shuffled.reverse();
+        shuffled.swap(0, 2);
+
+        let results: Vec<Vec<(Address, u64)>> = [credits, shuffled].iter().map(|credits| {
+            let dir = TempDir::new().unwrap();
+            let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+            storage.apply_credits(credits).unwrap();
+            balances(&storage)
+        }).collect();
+
+        assert_eq!(results[0], results[1]);
+        assert_eq!(results[0], vec![
+            (Address([1u8; 32]), 150),
+            (Address([5u8; 32]), 500),
+            (Address([9u8; 32]), 900),
+        ]);
+    }
+
+    #[test]
+    fn test_failing_credit_leaves_no_partial_state() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        storage.store_account(&fixed_account(9, u64::MAX)).unwrap();
+
+        // The overflowing recipient sorts last, after one that would otherwise be credited
+        let credits = [(Address([9u8; 32]), 1), (Address([1u8; 32]), 100)];
+        assert!(storage.apply_credits(&credits).is_err());
+        assert!(storage.get_account(&Address([1u8; 32])).unwrap().is_none());
+    }
+
+    fn exporting_storage(dir: &TempDir) -> BlockchainStorage {
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        for byte in [4u8, 2, 8] {
+            storage.store_account(&fixed_account(byte, byte as u64 * 1_000)).unwrap();
+        }
+        storage
+    }
+
+    #[test]
+    fn test_valid_snapshot_imports() {
+        let source_dir = TempDir::new().unwrap();
+        let snapshot = exporting_storage(&source_dir).export_snapshot().unwrap();
+        assert_eq!(snapshot.accounts.len(), 3);
+
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        storage.import_snapshot(&snapshot, Some(&snapshot.state_root)).unwrap();
+
+        assert_eq!(storage.accounts_root().unwrap(), snapshot.state_root);
+        assert_eq!(storage.get_account(&Address([8u8; 32])).unwrap().unwrap().balance.amount, 8_000);
+    }
+
+    #[test]
+    fn test_tampered_snapshot_rejected() {
+        let source_dir = TempDir::new().unwrap();
+        let snapshot = exporting_storage(&source_dir).export_snapshot().unwrap();
+
+        let mut tampered = snapshot.clone();
+        tampered.accounts[0].balance = Balance::new(1_000_000);
+
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let err = storage.import_snapshot(&tampered, None).unwrap_err();
+        assert!(err.to_string().contains("state root mismatch"), "{}", err);
+        assert!(storage.iter_accounts().next().is_none());
+
+        // Consistent with itself, but not with the header the node trusts
+        let mut recomputed = tampered.clone();
+        recomputed.state_root = BlockchainStorage::compute_accounts_root(&recomputed.accounts).unwrap();
+        assert!(storage.import_snapshot(&recomputed, Some(&snapshot.state_root)).is_err());
+        assert!(storage.iter_accounts().next().is_none());
+
+        // Reordered accounts would hash differently than the exporter's state
+        let mut reordered = snapshot.clone();
+        reordered.accounts.swap(0, 1);
+        assert!(storage.import_snapshot(&reordered, None).is_err());
+    }
+
+    #[test]
+    fn test_block_rewards_stop_at_supply_cap() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let validator = Address([1u8; 32]);
+        let genesis = GenesisConfig {
+            max_qor_supply: 10_000,
+            allocations: vec![crate::consensus::GenesisAllocation { address: Address([2u8; 32]), amount: 4_000 }],
+            ..GenesisConfig::default()
+        };
+
+        storage.apply_genesis_allocations(&genesis).unwrap();
+        assert_eq!(storage.total_qor_supply().unwrap(), 4_000);
+
+        // 2_500 per block: two full rewards, then a clamped one, then nothing
+        let minted: Vec<u64> = (0..5)
+            .map(|_| storage.mint_block_reward(&validator, 2_500, genesis.max_qor_supply).unwrap())
+            .collect();
+        assert_eq!(minted, vec![2_500, 2_500, 1_000, 0, 0]);
+
+        assert_eq!(storage.total_qor_supply().unwrap(), genesis.max_qor_supply);
+        assert_eq!(storage.get_account(&validator).unwrap().unwrap().balance.amount, 6_000);
+    }
+
+    #[test]
+    fn test_genesis_allocations_over_cap_rejected() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let genesis = GenesisConfig {
+            max_qor_supply: 1_000,
+            allocations: vec![crate::consensus::GenesisAllocation { address: Address([2u8; 32]), amount: 1_001 }],
+            ..GenesisConfig::default()
+        };
+
+        assert!(storage.apply_genesis_allocations(&genesis).is_err());
+        assert_eq!(storage.total_qor_supply().unwrap(), 0);
+        assert!(storage.get_account(&Address([2u8; 32])).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_account_diff_across_transfer() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let alice = Address([2u8; 32]);
+        let bob = Address([3u8; 32]);
+        let blocks = build_chain(&mut storage, 2);
+
+        // Height 1: alice is funded
+        storage.apply_balance_delta(&alice, 1_000).unwrap();
+
+        // Height 2: alice sends 300 to bob and pays a 10 fee
+        let block2 = Block::new(blocks[1].hash(), 2, Address([1u8; 32]), vec![transfer(&alice, &bob, 0)], 0, 0);
+        storage.store_block(&block2).unwrap();
+        storage.apply_balance_delta(&alice, -310).unwrap();
+        storage.apply_balance_delta(&bob, 300).unwrap();
+        storage.increment_account_nonce(&alice).unwrap();
+
+        let diff = storage.account_diff_between(&alice, 1, 2).unwrap();
+        assert_eq!(diff.balance_delta, -310);
+        assert_eq!(diff.nonce_delta, 1);
+        assert_eq!(diff.frozen_change, None);
+
+        // Bob didn't exist at height 1
+        let diff = storage.account_diff_between(&bob, 1, 2).unwrap();
+        assert_eq!(diff.balance_delta, 300);
+        assert_eq!(diff.nonce_delta, 0);
+
+        // Reversed order reports the inverse, unchanged range is empty
+        assert_eq!(storage.account_diff_between(&alice, 2, 1).unwrap().balance_delta, 310);
+        assert!(storage.account_diff_between(&alice, 2, 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_account_diff_reports_frozen_change() {
+        let before = AccountState::new(Address([2u8; 32]));
+        let mut after = before.clone();
+        after.frozen = true;
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.frozen_change, Some((false, true)));
+        assert_eq!(diff.balance_delta, 0);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_storage_stats_estimates_populated() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        build_chain(&mut storage, 20);
+        for name in ALL_COLUMN_FAMILIES {
+            storage.db.flush_cf(storage.db.cf_handle(name).unwrap()).unwrap();
+        }
+
+        let stats = storage.get_storage_stats().unwrap();
+        assert_eq!(stats.column_families.len(), ALL_COLUMN_FAMILIES.len());
+
+        let blocks = stats.column_families.iter().find(|cf| cf.name == CF_BLOCKS).unwrap();
+        assert!(blocks.estimated_keys > 0);
+        assert!(blocks.sst_files_bytes > 0);
+        assert_eq!(stats.total_blocks, 20);
+    }
+
+    #[test]
+    fn test_storage_stats_counters_track_writes() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let blocks = build_chain(&mut storage, 3);
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+
+        let txs = vec![transfer(&alice, &bob, 0), transfer(&alice, &bob, 1)];
+        let block = Block::new(blocks[2].hash(), 3, Address([1u8; 32]), txs, 0, 0);
+        storage.store_block(&block).unwrap();
+        // Storing the same tip again adds nothing
+        storage.store_block(&block).unwrap();
+
+        storage.store_account(&fixed_account(2, 100)).unwrap();
+        storage.store_account(&fixed_account(3, 100)).unwrap();
+        // Updates to existing accounts, cached or not, are not new accounts
+        storage.store_account(&fixed_account(2, 50)).unwrap();
+        storage.flush().unwrap();
+        storage.apply_balance_delta(&Address([3u8; 32]), 25).unwrap();
+
+        let stats = storage.get_storage_stats().unwrap();
+        assert_eq!(stats.total_blocks, 4);
+        assert_eq!(stats.total_transactions, 2);
+        assert_eq!(stats.total_accounts, 2);
+
+        // Counters survive a reopen
+        drop(storage);
+        let storage = BlockchainStorage::new(dir.path()).unwrap();
+        let stats = storage.get_storage_stats().unwrap();
+        assert_eq!((stats.total_blocks, stats.total_transactions, stats.total_accounts), (4, 2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_head_feed_reports_new_and_removed_heads() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let mut heads = storage.subscribe_heads();
+        let blocks = build_chain(&mut storage, 3);
+
+        for block in &blocks {
+            assert_eq!(heads.recv().await.unwrap(), HeadEvent::New(HeadSummary::from_block(block)));
+        }
+
+        // Competing block at height 1 rewinds heights 2 and 1
+        let fork = Block::new(blocks[0].hash(), 1, Address([9u8; 32]), Vec::new(), 0, 0);
+        storage.store_block(&fork).unwrap();
+
+        assert_eq!(heads.recv().await.unwrap(), HeadEvent::Removed(HeadSummary::from_block(&blocks[2])));
+        assert_eq!(heads.recv().await.unwrap(), HeadEvent::Removed(HeadSummary::from_block(&blocks[1])));
+        assert_eq!(heads.recv().await.unwrap(), HeadEvent::New(HeadSummary::from_block(&fork)));
+        assert_eq!(storage.get_latest_block_info(), (Some(fork.hash()), 1));
+        assert!(storage.get_block_by_height(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reorg_rolls_state_back_to_fork_point() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let (alice, bob, validator) = (Address([2u8; 32]), Address([3u8; 32]), Address([1u8; 32]));
+        let blocks = build_chain(&mut storage, 1);
+        storage.apply_balance_delta(&alice, 2_000).unwrap();
+        let (accounts, supply) = (storage.get_counter(ACCOUNT_COUNT_KEY).unwrap(), storage.total_qor_supply().unwrap());
+
+        let block = Block::new(blocks[0].hash(), 1, validator.clone(), vec![transfer(&alice, &bob, 0)], 0, 0);
+        storage.store_block(&block).unwrap();
+        assert!(storage.apply_block_transactions(&block).unwrap().is_empty());
+        storage.mint_block_reward(&validator, 500, u64::MAX).unwrap();
+        assert_eq!(storage.get_account(&alice).unwrap().unwrap().balance.amount, 900);
+        let tx_hash = block.transactions[0].hash();
+        assert!(storage.get_fee_breakdown(&tx_hash).unwrap().is_some());
+
+        // A competing block 1 undoes everything applied on top of genesis
+        let fork = Block::new(blocks[0].hash(), 1, Address([9u8; 32]), Vec::new(), 0, 0);
+        storage.store_block(&fork).unwrap();
+        let restored = storage.get_account(&alice).unwrap().unwrap();
+        assert_eq!((restored.balance.amount, restored.nonce), (2_000, 0));
+        assert!(storage.get_account(&bob).unwrap().is_none());
+        assert!(storage.get_account(&validator).unwrap().is_none());
+        assert_eq!(storage.total_qor_supply().unwrap(), supply);
+        assert_eq!(storage.get_counter(ACCOUNT_COUNT_KEY).unwrap(), accounts);
+        assert_eq!(storage.get_account_at_height(&alice, 1).unwrap().unwrap().balance.amount, 2_000);
+        assert!(storage.get_account_transactions(&alice, 10).unwrap().is_empty());
+        assert!(storage.get_fee_breakdown(&tx_hash).unwrap().is_none());
+
+        // Heights applied before the journal existed can't be replaced
+        storage.update_metadata(UNDO_FROM_KEY, &2u64.to_le_bytes()).unwrap();
+        let deeper = Block::new(blocks[0].hash(), 1, Address([8u8; 32]), Vec::new(), 0, 0);
+        assert!(storage.store_block(&deeper).is_err());
+        assert_eq!(storage.get_latest_block_info(), (Some(fork.hash()), 1));
+    }
+
+    #[tokio::test]
+    async fn test_reverted_transactions_readmitted_to_pool() {
+        use crate::transaction::DEFAULT_MAX_READMITTED;
+        use crate::{FeeOracle, FeePriority, GlobalFeeOracle};
+        use ed25519_dalek::Keypair;
+
+        let oracle = GlobalFeeOracle::from_oracle(FeeOracle::new().with_fallback_price(0.25));
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let alice = Address::from_pubkey(&keypair.public);
+        let signed = |nonce: u64| {
+            let data = TransactionData::Transfer { from: alice.clone(), to: Address([3u8; 32]), amount: 10, memo: None };
+            Transaction::new(data, nonce, FeePriority::Medium, &keypair, &oracle)
+        };
+        let (shared, dropped) = (signed(0).await.unwrap(), signed(1).await.unwrap());
+
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let blocks = build_chain(&mut storage, 2);
+        storage.store_block(&Block::new(blocks[1].hash(), 2, Address([1u8; 32]), vec![shared.clone(), dropped.clone()], 0, 0)).unwrap();
+
+        let mut heads = storage.subscribe_heads();
+        storage.store_block(&Block::new(blocks[1].hash(), 2, Address([9u8; 32]), vec![shared.clone()], 0, 0)).unwrap();
+        let reorg = loop {
+            if let HeadEvent::Reorg(reorg) = heads.recv().await.unwrap() {
+                break reorg;
+            }
+        };
+
+        let reverted = storage.reverted_transactions(&reorg).unwrap();
+        let mut pool = TransactionPool::new();
+        assert_eq!(pool.readmit_reverted(reverted.clone(), &oracle, 0, |_| AccountHoldings::default()).await, 0);
+        assert_eq!(pool.readmit_reverted(reverted, &oracle, DEFAULT_MAX_READMITTED, |_| AccountHoldings::default()).await, 1);
+
+        assert_eq!(pool.pending_count(), 1);
+        assert!(pool.remove_transaction(&dropped.hash()).is_some());
+        assert!(pool.remove_transaction(&shared.hash()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reorg_event_lists_reverted_transactions() {
+        let dir = TempDir::new().unwrap();
+        let mut storage = BlockchainStorage::new(dir.path()).unwrap();
+        let blocks = build_chain(&mut storage, 2);
+        let (alice, bob) = (Address([2u8; 32]), Address([3u8; 32]));
+
+        let (shared, dropped_a, dropped_b) = (transfer(&alice, &bob, 0), transfer(&alice, &bob, 1), transfer(&bob, &alice, 0));
+        let old_2 = Block::new(blocks[1].hash(), 2, Address([1u8; 32]), vec![shared.clone(), dropped_a.clone()], 0, 0);
+        storage.store_block(&old_2).unwrap();
+        let old_3 = Block::new(old_2.hash(), 3, Address([1u8; 32]), vec![dropped_b.clone()], 0, 0);
+        storage.store_block(&old_3).unwrap();
+
+        let mut heads = storage.subscribe_heads();
+        let fork = Block::new(blocks[1].hash(), 2, Address([9u8; 32]), vec![shared], 0, 0);
+        storage.store_block(&fork).unwrap();
+
+        let reorg = loop {
+            if let HeadEvent::Reorg(reorg) = heads.recv().await.unwrap() {
+                break reorg;
+            }
+        };
+        assert_eq!(reorg.common_ancestor_height, 1);
+        assert_eq!(reorg.old_tip, HeadSummary::from_block(&old_3));
+        assert_eq!(reorg.new_tip, HeadSummary::from_block(&fork));
+        assert_eq!(reorg.reverted_tx_ids, vec![dropped_a.hash(), dropped_b.hash()]);
+        
+        // A removed head yields only what the new branch left out
+        let removed = storage.removed_head_transactions(&HeadSummary::from_block(&old_2)).unwrap();
+        assert_eq!(removed.iter().map(|tx| tx.hash()).collect::<Vec<_>>(), vec![dropped_a.hash()]);
+    }
 }
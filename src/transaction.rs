@@ -1,16 +1,17 @@
-/// Add transaction to pool
-    pub async fn add_transaction(&mut self, transaction: Transaction, fee_oracle: &GlobalFeeOracle) -> Result<()> {
-        // Validate transaction
-        transaction.validate(fee_oracle).await?;
-        
-        let tx_hash = transaction.hash();
-        let signer = transaction.signer.clone();
-        
-        // Add to pending
-        self.pending.insert(tx_hash.clone(), transactionuse crate::{Address, Hash, QoraSignature, Result, QoraNetError, LPToken, AppMetrics, Balance, TransactionType, FeePriority, GlobalFeeOracle};
+use crate::{Address, Hash, QoraSignature, Result, QoraNetError, LPToken, AppMetrics, Balance, TransactionType, FeePriority, GlobalFeeOracle, MempoolStats, BlockHeight, AccountHoldings};
+use crate::consensus::{EquivocationProof, GenesisConfig};
 use serde::{Deserialize, Serialize};
 use ed25519_dalek::{Keypair, Signer};
 
+/// Maximum length of a transfer memo in bytes
+pub const MAX_MEMO_BYTES: usize = 128;
+
+/// Maximum length of an app id in bytes
+pub const MAX_APP_ID_LEN: usize = 64;
+
+/// Prefix of every transaction signing message, so a signature can't be reused for another protocol
+pub const TRANSACTION_SIGNING_DOMAIN: &[u8] = b"QORANET_TX_V1";
+
 /// Transaction types in QoraNet
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TransactionData {
@@ -19,6 +20,8 @@ pub enum TransactionData {
         from: Address,
         to: Address,
         amount: u64,
+        /// Optional reference (e.g. exchange deposit tag)
+        memo: Option<Vec<u8>>,
     },
     /// Provide liquidity to DEX pool
     ProvideLiquidity {
@@ -28,7 +31,7 @@ pub enum TransactionData {
     /// Register application for hosting
     RegisterApp {
         owner: Address,
-        app_id: String,
+        app_id: AppId,
         app_type: AppType,
         resource_requirements: ResourceRequirements,
     },
@@ -36,7 +39,7 @@ pub enum TransactionData {
     ReportMetrics {
         validator: Address,
         app_owner: Address,
-        app_id: String,
+        app_id: AppId,
         metrics: AppMetrics,
     },
     /// Claim rewards for liquidity provision and app hosting
@@ -45,6 +48,198 @@ pub enum TransactionData {
         lp_rewards: u64,
         app_rewards: u64,
     },
+    /// Evidence that a validator signed two blocks at one height; slashes it once included
+    ReportEquivocation {
+        reporter: Address,
+        proof: EquivocationProof,
+    },
+    /// Set or clear an account's daily send limit; only the chain's spending limit authority may
+    SetSendLimit {
+        authority: Address,
+        account: Address,
+        limit: Option<u64>,
+    },
+}
+
+/// Fieldless transaction type, used to enable or disable types per chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TransactionKind {
+    Transfer,
+    ProvideLiquidity,
+    RegisterApp,
+    ReportMetrics,
+    ClaimRewards,
+    SmartContract,
+    ReportEquivocation,
+    SetSendLimit,
+}
+
+impl TransactionKind {
+    /// Kinds a chain may enable; `SetSendLimit` is instead enabled by the chain having a spending limit authority
+    pub const ALL: [TransactionKind; 7] = [
+        TransactionKind::Transfer,
+        TransactionKind::ProvideLiquidity,
+        TransactionKind::RegisterApp,
+        TransactionKind::ReportMetrics,
+        TransactionKind::ClaimRewards,
+        TransactionKind::SmartContract,
+        TransactionKind::ReportEquivocation,
+    ];
+    
+    pub fn of(tx_type: &TransactionType) -> Self {
+        match tx_type {
+            TransactionType::Transfer => TransactionKind::Transfer,
+            TransactionType::ProvideLiquidity => TransactionKind::ProvideLiquidity,
+            TransactionType::RegisterApp => TransactionKind::RegisterApp,
+            TransactionType::ReportMetrics => TransactionKind::ReportMetrics,
+            TransactionType::ClaimRewards => TransactionKind::ClaimRewards,
+            TransactionType::SmartContract { .. } => TransactionKind::SmartContract,
+            TransactionType::ReportEquivocation => TransactionKind::ReportEquivocation,
+            TransactionType::SetSendLimit => TransactionKind::SetSendLimit,
+        }
+    }
+}
+
+impl TransactionData {
+    /// Fee schedule type of this transaction
+    pub fn transaction_type(&self) -> TransactionType {
+        match self {
+            TransactionData::Transfer { .. } => TransactionType::Transfer,
+            TransactionData::ProvideLiquidity { .. } => TransactionType::ProvideLiquidity,
+            TransactionData::RegisterApp { .. } => TransactionType::RegisterApp,
+            TransactionData::ReportMetrics { .. } => TransactionType::ReportMetrics,
+            TransactionData::ClaimRewards { .. } => TransactionType::ClaimRewards,
+            TransactionData::ReportEquivocation { .. } => TransactionType::ReportEquivocation,
+            TransactionData::SetSendLimit { .. } => TransactionType::SetSendLimit,
+        }
+    }
+    
+    /// Fieldless kind of this transaction, for filters and per-chain enablement
+    pub fn kind(&self) -> TransactionKind {
+        TransactionKind::of(&self.transaction_type())
+    }
+    
+    /// Accounts affected by this transaction
+    pub fn participants(&self) -> Vec<&Address> {
+        match self {
+            TransactionData::Transfer { from, to, .. } => {
+                if from == to { vec![from] } else { vec![from, to] }
+            },
+            TransactionData::ProvideLiquidity { provider, .. } => vec![provider],
+            TransactionData::RegisterApp { owner, .. } => vec![owner],
+            TransactionData::ReportMetrics { validator, app_owner, .. } => {
+                if validator == app_owner { vec![validator] } else { vec![validator, app_owner] }
+            },
+            TransactionData::ClaimRewards { claimant, .. } => vec![claimant],
+            TransactionData::ReportEquivocation { reporter, .. } => vec![reporter],
+            TransactionData::SetSendLimit { authority, account, .. } => {
+                if authority == account { vec![authority] } else { vec![authority, account] }
+            },
+        }
+    }
+    
+    /// Check if the transaction involves this address
+    pub fn involves(&self, address: &Address) -> bool {
+        self.participants().contains(&address)
+    }
+    
+    /// Memo attached to a transfer, if any
+    pub fn memo(&self) -> Option<&[u8]> {
+        match self {
+            TransactionData::Transfer { memo: Some(memo), .. } if !memo.is_empty() => Some(memo),
+            _ => None,
+        }
+    }
+    
+    /// Owner-namespaced key of the app this transaction concerns, if any
+    pub fn app_key(&self) -> Option<String> {
+        match self {
+            TransactionData::RegisterApp { owner, app_id, .. } => Some(app_id.namespaced(owner)),
+            TransactionData::ReportMetrics { app_owner, app_id, .. } => Some(app_id.namespaced(app_owner)),
+            _ => None,
+        }
+    }
+    
+    /// Bytes used for signing and hashing.
+    ///
+    /// Transfers without a memo encode exactly as they did before the memo
+    /// field existed, so their hashes and signatures are unchanged. Any memo,
+    /// even an empty one, is encoded with its option tag, so an empty memo
+    /// never shares an id with no memo.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        match self {
+            TransactionData::Transfer { from, to, amount, memo: None } => {
+                // Variant index 0 followed by the original fields
+                bincode::serialize(&(0u32, from, to, amount)).unwrap()
+            },
+            _ => bincode::serialize(self).unwrap(),
+        }
+    }
+}
+
+/// Human-readable application id chosen by the app's owner.
+///
+/// 1 to `MAX_APP_ID_LEN` ASCII letters, digits, `-`, `_` or `.`. Ids are only
+/// unique per owner, so anything keyed by app uses `namespaced` rather than
+/// the bare id. Serializes as the plain string.
+///
+/// Decoding doesn't check the format: transactions stored before it was
+/// enforced must stay readable. New transactions are checked in `validate`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AppId(String);
+
+impl AppId {
+    pub fn new(id: impl Into<String>) -> Result<Self> {
+        let id = Self(id.into());
+        id.check_format()?;
+        Ok(id)
+    }
+    
+    /// Check the id is in the format new transactions must use
+    pub fn check_format(&self) -> Result<()> {
+        let id = &self.0;
+        if id.is_empty() || id.len() > MAX_APP_ID_LEN {
+            return Err(QoraNetError::InvalidTransaction(format!(
+                "App ID must be 1 to {} characters, got {}", MAX_APP_ID_LEN, id.len()
+            )));
+        }
+        if let Some(c) = id.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))) {
+            return Err(QoraNetError::InvalidTransaction(format!(
+                "App ID {:?} contains invalid character {:?}", id, c
+            )));
+        }
+        Ok(())
+    }
+    
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    
+    /// Internal key `<owner hex>:<app id>`, unique across owners
+    pub fn namespaced(&self, owner: &Address) -> String {
+        format!("{}:{}", hex::encode(owner.0), self.0)
+    }
+}
+
+impl TryFrom<String> for AppId {
+    type Error = QoraNetError;
+    
+    fn try_from(id: String) -> Result<Self> {
+        Self::new(id)
+    }
+}
+
+impl From<AppId> for String {
+    fn from(id: AppId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for AppId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
 /// Types of applications that can be hosted
@@ -66,19 +261,37 @@ pub struct ResourceRequirements {
     pub min_bandwidth_mbps: u32,
 }
 
+/// Signature verification mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureVerification {
+    /// Reject weak (small-order) keys and non-canonical signatures
+    #[default]
+    Strict,
+    /// Plain ed25519 verification, only for re-checking historical data
+    Legacy,
+}
+
 /// Complete transaction with signature
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub data: TransactionData,
     pub nonce: u64,
     pub fee_qor: u64,        // Fee amount in QOR tokens
-    pub fee_usd: f64,        // Fee amount in USD (for validation)
+    pub fee_usd: f64,        // Fee amount in USD (informational, not signed)
     pub priority: FeePriority, // Transaction priority
     pub signature: QoraSignature,
     pub signer: Address,
+    /// Last block height this transaction may be included at, if it expires
+    #[serde(default)]
+    pub valid_until_height: Option<BlockHeight>,
 }
 
 impl Transaction {
+    /// Fee `(qor, usd)` that `new` would charge for this data and priority, without signing
+    pub async fn estimate(data: &TransactionData, priority: FeePriority, fee_oracle: &GlobalFeeOracle) -> (u64, f64) {
+        fee_oracle.preview_fee(&data.transaction_type(), priority).await
+    }
+    
     /// Create a new transaction with automatic fee calculation
     pub async fn new(
         data: TransactionData, 
@@ -89,19 +302,8 @@ impl Transaction {
     ) -> Result<Self> {
         let signer = Address::from_pubkey(&keypair.public);
         
-        // Determine transaction type
-        let tx_type = match &data {
-            TransactionData::Transfer { .. } => TransactionType::Transfer,
-            TransactionData::ProvideLiquidity { .. } => TransactionType::ProvideLiquidity,
-            TransactionData::RegisterApp { .. } => TransactionType::RegisterApp,
-            TransactionData::ReportMetrics { .. } => TransactionType::ReportMetrics,
-            TransactionData::ClaimRewards { .. } => TransactionType::ClaimRewards,
-        };
-        
         // Calculate fee
-        let fee_qor = fee_oracle.calculate_fee(&tx_type, priority.clone()).await;
-        let fee_estimate = fee_oracle.get_fee_estimate(&tx_type).await;
-        let fee_usd = fee_estimate.get_usd_fee(priority.clone());
+        let (fee_qor, fee_usd) = Self::estimate(&data, priority.clone(), fee_oracle).await;
         
         let mut tx = Self {
             data,
@@ -111,6 +313,7 @@ impl Transaction {
             priority,
             signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(), // Placeholder
             signer,
+            valid_until_height: None,
         };
         
         // Sign the transaction
@@ -120,6 +323,43 @@ impl Transaction {
         Ok(tx)
     }
     
+    /// Create a transaction with the cheapest priority expected to confirm within `target_secs`
+    pub async fn new_for_target(
+        data: TransactionData,
+        nonce: u64,
+        target_secs: u64,
+        mempool_stats: &MempoolStats,
+        keypair: &Keypair,
+        fee_oracle: &GlobalFeeOracle
+    ) -> Result<Self> {
+        let priority = GlobalFeeOracle::priority_for_target(target_secs, mempool_stats);
+        Self::new(data, nonce, priority, keypair, fee_oracle).await
+    }
+    
+    /// Fee-free equivocation report from `keypair`, for a block producer to include
+    pub fn equivocation_report(proof: EquivocationProof, nonce: u64, keypair: &Keypair) -> Self {
+        let reporter = Address::from_pubkey(&keypair.public);
+        let mut tx = Self {
+            data: TransactionData::ReportEquivocation { reporter: reporter.clone(), proof },
+            nonce,
+            fee_qor: 0,
+            fee_usd: 0.0,
+            priority: FeePriority::Urgent,
+            signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(), // Placeholder
+            signer: reporter,
+            valid_until_height: None,
+        };
+        tx.signature = keypair.sign(&tx.signing_message());
+        tx
+    }
+    
+    /// Set an expiry height and re-sign
+    pub fn with_valid_until(mut self, height: BlockHeight, keypair: &Keypair) -> Self {
+        self.valid_until_height = Some(height);
+        self.signature = keypair.sign(&self.signing_message());
+        self
+    }
+    
     /// Create transaction with custom fee (must still be valid)
     pub async fn new_with_fee(
         data: TransactionData,
@@ -131,17 +371,8 @@ impl Transaction {
     ) -> Result<Self> {
         let signer = Address::from_pubkey(&keypair.public);
         
-        // Determine transaction type and validate fee
-        let tx_type = match &data {
-            TransactionData::Transfer { .. } => TransactionType::Transfer,
-            TransactionData::ProvideLiquidity { .. } => TransactionType::ProvideLiquidity,
-            TransactionData::RegisterApp { .. } => TransactionType::RegisterApp,
-            TransactionData::ReportMetrics { .. } => TransactionType::ReportMetrics,
-            TransactionData::ClaimRewards { .. } => TransactionType::ClaimRewards,
-        };
-        
         // Validate fee
-        fee_oracle.validate_fee(fee_qor, &tx_type).await?;
+        fee_oracle.validate_fee(fee_qor, &data.transaction_type()).await?;
         
         let qor_price = fee_oracle.get_qor_price().await;
         let fee_usd = crate::qor_to_usd(fee_qor, qor_price);
@@ -154,6 +385,7 @@ impl Transaction {
             priority,
             signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(), // Placeholder
             signer,
+            valid_until_height: None,
         };
         
         // Sign the transaction
@@ -163,20 +395,31 @@ impl Transaction {
         Ok(tx)
     }
     
-    /// Get the message that should be signed
+    /// Get the message that should be signed.
+    ///
+    /// `fee_usd` is left out: it is derived from `fee_qor` and the price, and
+    /// its float bits can differ by an ULP between nodes computing the same fee.
+    /// The expiry height is appended only when set.
     pub fn signing_message(&self) -> Vec<u8> {
-        let mut message = Vec::new();
-        message.extend_from_slice(&bincode::serialize(&self.data).unwrap());
+        let mut message = TRANSACTION_SIGNING_DOMAIN.to_vec();
+        message.extend_from_slice(&self.data.canonical_bytes());
         message.extend_from_slice(&self.nonce.to_le_bytes());
         message.extend_from_slice(&self.fee_qor.to_le_bytes());
-        message.extend_from_slice(&self.fee_usd.to_le_bytes());
         message.extend_from_slice(&bincode::serialize(&self.priority).unwrap());
         message.extend_from_slice(&self.signer.as_bytes());
+        if let Some(valid_until) = self.valid_until_height {
+            message.extend_from_slice(&valid_until.to_le_bytes());
+        }
         message
     }
     
-    /// Verify transaction signature
+    /// Verify transaction signature (strict mode)
     pub fn verify_signature(&self) -> Result<()> {
+        self.verify_signature_with(SignatureVerification::Strict)
+    }
+    
+    /// Verify transaction signature with an explicit verification mode
+    pub fn verify_signature_with(&self, mode: SignatureVerification) -> Result<()> {
         use ed25519_dalek::{PublicKey, Verifier};
         
         let pubkey = PublicKey::from_bytes(&self.signer.0)
@@ -184,40 +427,125 @@ impl Transaction {
             
         let message = self.signing_message();
         
-        pubkey.verify(&message, &self.signature)
-            .map_err(|e| QoraNetError::InvalidTransaction(format!("Invalid signature: {}", e)))?;
+        let verified = match mode {
+            // Rejects small-order public keys and R values, so a signature can't be
+            // tweaked into a second valid one (which would change the tx hash)
+            SignatureVerification::Strict => pubkey.verify_strict(&message, &self.signature),
+            SignatureVerification::Legacy => pubkey.verify(&message, &self.signature),
+        };
+        
+        verified.map_err(|e| QoraNetError::InvalidTransaction(format!("Invalid signature: {}", e)))?;
             
         Ok(())
     }
     
-    /// Get transaction hash
+    /// Get transaction hash.
+    ///
+    /// Covers only what the signer committed to plus the signature, so no
+    /// unsigned field (such as `fee_usd`) can change a transaction's id.
     pub fn hash(&self) -> Hash {
-        let serialized = bincode::serialize(self).unwrap();
+        let mut message = self.signing_message();
+        message.extend_from_slice(&self.signature.to_bytes());
+        Hash::new(&message)
+    }
+    
+    /// Id the transaction had before ids covered only signed fields
+    pub fn legacy_hash(&self) -> Hash {
+        // Same layout as serializing the whole struct, but with canonical data bytes
+        let mut serialized = self.data.canonical_bytes();
+        serialized.extend_from_slice(&bincode::serialize(&(
+            self.nonce,
+            self.fee_qor,
+            self.fee_usd,
+            &self.priority,
+            &self.signature,
+            &self.signer,
+        )).unwrap());
+        // Only set expiries change the layout, keeping older hashes stable
+        if let Some(valid_until) = self.valid_until_height {
+            serialized.extend_from_slice(&valid_until.to_le_bytes());
+        }
         Hash::new(&serialized)
     }
     
-    /// Validate transaction logic
-    pub async fn validate(&self, fee_oracle: &GlobalFeeOracle) -> Result<()> {
+    /// Reject transaction types the chain hasn't enabled at `height`
+    pub fn check_type_enabled(&self, chain: &GenesisConfig, height: BlockHeight) -> Result<()> {
+        let kind = self.data.kind();
+        if !chain.tx_type_enabled(kind, height) {
+            return Err(QoraNetError::InvalidTransaction("transaction type not enabled".to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Change to `address`'s balance once its block applies this transaction:
+    /// the fee and outgoing transfers are debits, incoming transfers credits.
+    /// A reward claim's payout isn't counted; it depends on what has accrued.
+    pub fn balance_effect(&self, address: &Address) -> i128 {
+        let mut effect = 0i128;
+        if &self.signer == address {
+            effect -= self.fee_qor as i128;
+        }
+        if let TransactionData::Transfer { from, to, amount, .. } = &self.data {
+            if from == address {
+                effect -= *amount as i128;
+            }
+            if to == address {
+                effect += *amount as i128;
+            }
+        }
+        effect
+    }
+    
+    /// Whether the transaction can no longer be included at `height`
+    pub fn is_expired_at(&self, height: BlockHeight) -> bool {
+        self.valid_until_height.map_or(false, |valid_until| height > valid_until)
+    }
+    
+    /// Reject inclusion after the transaction's expiry height
+    pub fn check_not_expired(&self, height: BlockHeight) -> Result<()> {
+        if self.is_expired_at(height) {
+            return Err(QoraNetError::InvalidTransaction(
+                format!("Transaction expired at height {}", self.valid_until_height.unwrap_or_default())
+            ));
+        }
+        Ok(())
+    }
+    
+    /// Validate transaction logic for inclusion at `height` on `chain`
+    pub async fn validate(&self, fee_oracle: &GlobalFeeOracle, chain: &GenesisConfig, height: BlockHeight) -> Result<()> {
+        self.validate_for(fee_oracle, chain, height, &AccountHoldings::default()).await
+    }
+    
+    /// Validate as `validate`, accepting the fee discount the signer's `holdings` earn
+    pub async fn validate_for(
+        &self,
+        fee_oracle: &GlobalFeeOracle,
+        chain: &GenesisConfig,
+        height: BlockHeight,
+        holdings: &AccountHoldings,
+    ) -> Result<()> {
+        self.check_type_enabled(chain, height)?;
+        self.check_not_expired(height)?;
+        
         // Verify signature first
         self.verify_signature()?;
         
         // Validate fee
-        let tx_type = match &self.data {
-            TransactionData::Transfer { .. } => TransactionType::Transfer,
-            TransactionData::ProvideLiquidity { .. } => TransactionType::ProvideLiquidity,
-            TransactionData::RegisterApp { .. } => TransactionType::RegisterApp,
-            TransactionData::ReportMetrics { .. } => TransactionType::ReportMetrics,
-            TransactionData::ClaimRewards { .. } => TransactionType::ClaimRewards,
-        };
+        let tx_type = self.data.transaction_type();
         
-        fee_oracle.validate_fee(self.fee_qor, &tx_type).await?;
+        fee_oracle.validate_fee_for(self.fee_qor, &tx_type, holdings).await?;
         
         // Validate transaction-specific logic
         match &self.data {
-            TransactionData::Transfer { amount, .. } => {
+            TransactionData::Transfer { amount, memo, .. } => {
                 if *amount == 0 {
                     return Err(QoraNetError::InvalidTransaction("Transfer amount cannot be zero".to_string()));
                 }
+                if memo.as_ref().map_or(false, |memo| memo.len() > MAX_MEMO_BYTES) {
+                    return Err(QoraNetError::InvalidTransaction(
+                        format!("Memo exceeds {} bytes", MAX_MEMO_BYTES)
+                    ));
+                }
             },
             TransactionData::ProvideLiquidity { lp_tokens, .. } => {
                 if lp_tokens.is_empty() {
@@ -230,14 +558,13 @@ impl Transaction {
                 }
             },
             TransactionData::RegisterApp { app_id, resource_requirements, .. } => {
-                if app_id.is_empty() {
-                    return Err(QoraNetError::InvalidTransaction("App ID cannot be empty".to_string()));
-                }
+                app_id.check_format()?;
                 if resource_requirements.min_cpu_cores == 0 {
                     return Err(QoraNetError::InvalidTransaction("Minimum CPU cores must be > 0".to_string()));
                 }
             },
-            TransactionData::ReportMetrics { metrics, .. } => {
+            TransactionData::ReportMetrics { app_id, metrics, .. } => {
+                app_id.check_format()?;
                 if metrics.cpu_usage > 100.0 {
                     return Err(QoraNetError::InvalidTransaction("CPU usage cannot exceed 100%".to_string()));
                 }
@@ -247,17 +574,114 @@ impl Transaction {
                     return Err(QoraNetError::InvalidTransaction("Cannot claim zero rewards".to_string()));
                 }
             },
+            TransactionData::ReportEquivocation { proof, .. } => proof.verify()?,
+            // Whether the signer is the authority depends on chain state, checked when applied
+            TransactionData::SetSendLimit { .. } => {},
         }
         
         Ok(())
     }
 }
 
+/// Outputs of a multi-recipient credit in canonical order.
+///
+/// Amounts for the same recipient are merged and recipients are sorted by
+/// address, so listings that differ only in order produce the same outputs.
+/// Errors if a merged amount overflows.
+pub fn canonical_outputs(outputs: &[(Address, u64)]) -> Result<Vec<(Address, u64)>> {
+    let mut merged: std::collections::BTreeMap<[u8; 32], u64> = std::collections::BTreeMap::new();
+    for (recipient, amount) in outputs {
+        let total = merged.entry(recipient.0).or_insert(0);
+        *total = total.checked_add(*amount)
+            .ok_or_else(|| QoraNetError::InvalidTransaction(format!("Output amount overflows for {}", recipient)))?;
+    }
+    Ok(merged.into_iter().map(|(recipient, amount)| (Address(recipient), amount)).collect())
+}
+
+/// Default time a signer's nonce gap may persist before peers are asked for the missing transactions (seconds)
+pub const DEFAULT_NONCE_GAP_GRACE_SECS: u64 = 10;
+
+/// Default maximum time a transaction stuck behind a nonce gap is retained (seconds)
+pub const DEFAULT_GAPPED_RETENTION_SECS: u64 = 300;
+
+/// Default maximum number of reverted transactions re-admitted after one reorg
+pub const DEFAULT_MAX_READMITTED: usize = 1_000;
+
+/// Default maximum number of pending transactions
+pub const DEFAULT_MAX_POOL_SIZE: usize = 10_000;
+
+/// Default maximum number of pending transactions from one signer
+pub const DEFAULT_MAX_PER_SIGNER: usize = 64;
+
+/// Default fee increase, in percent, for a transaction to replace a pending one with the same nonce
+pub const DEFAULT_MIN_REPLACEMENT_BUMP_PCT: u64 = 10;
+
+/// Size and replacement limits for the transaction pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionPoolConfig {
+    /// Once reached, a new transaction must outbid the lowest fee to get in
+    pub max_pool_size: usize,
+    /// Further transactions from a signer at this limit are rejected
+    pub max_per_signer: usize,
+    /// How much more a transaction must pay to replace a pending one from the same signer and nonce
+    pub min_replacement_bump_pct: u64,
+}
+
+impl Default for TransactionPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_pool_size: DEFAULT_MAX_POOL_SIZE,
+            max_per_signer: DEFAULT_MAX_PER_SIGNER,
+            min_replacement_bump_pct: DEFAULT_MIN_REPLACEMENT_BUMP_PCT,
+        }
+    }
+}
+
+/// Eviction order: lowest fee first, and the latest arrival first among equal fees
+type EvictionKey = (u64, std::cmp::Reverse<u64>);
+
+/// Missing nonces for a signer, `from_nonce..to_nonce` (exclusive end)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceGap {
+    pub signer: Address,
+    pub from_nonce: u64,
+    pub to_nonce: u64,
+}
+
+impl NonceGap {
+    /// Network request asking peers for the missing transactions
+    pub fn to_request(&self) -> crate::network::NetworkMessage {
+        crate::network::NetworkMessage::MissingTransactionsRequest {
+            signer: self.signer.clone(),
+            from_nonce: self.from_nonce,
+            to_nonce: self.to_nonce,
+        }
+    }
+}
+
 /// Transaction pool for pending transactions
 #[derive(Debug)]
 pub struct TransactionPool {
     pending: std::collections::HashMap<Hash, Transaction>,
     by_signer: std::collections::HashMap<Address, Vec<Hash>>,
+    /// Arrival time of each pending transaction
+    received_at: std::collections::HashMap<Hash, u64>,
+    /// Next nonce expected on chain, for signers whose account state is known
+    next_nonces: std::collections::HashMap<Address, u64>,
+    /// Last time a resync was requested for a signer
+    resync_requested: std::collections::HashMap<Address, u64>,
+    nonce_gap_grace_secs: u64,
+    gapped_retention_secs: u64,
+    config: TransactionPoolConfig,
+    /// Pending transactions in eviction order
+    eviction_order: std::collections::BTreeMap<EvictionKey, Hash>,
+    eviction_keys: std::collections::HashMap<Hash, EvictionKey>,
+    /// Insertion counter, to order transactions with equal fees
+    next_sequence: u64,
+    /// Chain the pooled transactions are validated for
+    chain: GenesisConfig,
+    /// Height of the chain tip; transactions are validated for the block after it
+    chain_height: BlockHeight,
 }
 
 impl TransactionPool {
@@ -265,32 +689,150 @@ impl TransactionPool {
         Self {
             pending: std::collections::HashMap::new(),
             by_signer: std::collections::HashMap::new(),
+            received_at: std::collections::HashMap::new(),
+            next_nonces: std::collections::HashMap::new(),
+            resync_requested: std::collections::HashMap::new(),
+            nonce_gap_grace_secs: DEFAULT_NONCE_GAP_GRACE_SECS,
+            gapped_retention_secs: DEFAULT_GAPPED_RETENTION_SECS,
+            config: TransactionPoolConfig::default(),
+            eviction_order: std::collections::BTreeMap::new(),
+            eviction_keys: std::collections::HashMap::new(),
+            next_sequence: 0,
+            chain: GenesisConfig::default(),
+            chain_height: 0,
         }
     }
+
+    /// Validate transactions for `chain` instead of the default config
+    pub fn with_chain(mut self, chain: GenesisConfig) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Record the height of the chain tip, so transactions are validated for the block after it
+    pub fn set_chain_height(&mut self, height: BlockHeight) {
+        self.chain_height = height;
+    }
+
+    /// Configure pool size limits
+    pub fn with_config(mut self, config: TransactionPoolConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Configure how long a gap may persist before resync, and how long gapped transactions are kept
+    pub fn with_nonce_gap_limits(mut self, grace_secs: u64, retention_secs: u64) -> Self {
+        self.nonce_gap_grace_secs = grace_secs;
+        self.gapped_retention_secs = retention_secs.max(grace_secs);
+        self
+    }
     
-    /// Add transaction to pool
+    /// Add transaction to pool.
+    ///
+    /// A transaction with the same signer and nonce as a pending one replaces it
+    /// if its fee is at least `min_replacement_bump_pct` higher, and fails with
+    /// `ReplacementUnderpriced` otherwise. When the pool is full the lowest-fee
+    /// transaction is evicted to make room, provided the new one pays more;
+    /// otherwise this fails with `PoolFull`.
     pub async fn add_transaction(&mut self, transaction: Transaction, fee_oracle: &GlobalFeeOracle) -> Result<()> {
-        // Validate transaction
-        transaction.validate(fee_oracle).await?;
-        
+        self.add_transaction_for(transaction, fee_oracle, &AccountHoldings::default()).await
+    }
+
+    /// Add a transaction whose signer has `holdings`, so a discounted fee is accepted
+    pub async fn add_transaction_for(
+        &mut self,
+        transaction: Transaction,
+        fee_oracle: &GlobalFeeOracle,
+        holdings: &AccountHoldings,
+    ) -> Result<()> {
+        // Validate transaction for the next block
+        transaction.validate_for(fee_oracle, &self.chain, self.chain_height + 1, holdings).await?;
+
+        self.insert(transaction, chrono::Utc::now().timestamp() as u64)
+    }
+
+    /// Insert an already validated transaction, enforcing the size limits
+    pub(crate) fn insert(&mut self, transaction: Transaction, now: u64) -> Result<()> {
         let tx_hash = transaction.hash();
         let signer = transaction.signer.clone();
-        
+
+        if self.pending.contains_key(&tx_hash) {
+            return Ok(());
+        }
+
+        if let Some(replaced) = self.pending_with_nonce(&signer, transaction.nonce) {
+            let old_fee = self.pending[&replaced].fee_qor;
+            let required = self.replacement_fee(old_fee);
+            if transaction.fee_qor < required {
+                return Err(QoraNetError::ReplacementUnderpriced {
+                    nonce: transaction.nonce,
+                    offered: transaction.fee_qor,
+                    required,
+                });
+            }
+            tracing::debug!("Replacing {} (fee {}) with {} (fee {})", replaced, old_fee, tx_hash, transaction.fee_qor);
+            self.remove_transaction(&replaced);
+        }
+
+        let signer_count = self.by_signer.get(&signer).map_or(0, Vec::len);
+        if signer_count >= self.config.max_per_signer {
+            return Err(QoraNetError::InvalidTransaction(format!(
+                "Signer {} already has {} pending transactions", signer, signer_count
+            )));
+        }
+
+        if self.pending.len() >= self.config.max_pool_size {
+            let lowest = self.eviction_order.iter().next().map(|(key, hash)| (key.0, hash.clone()));
+            match lowest {
+                Some((lowest_fee, lowest_hash)) if transaction.fee_qor > lowest_fee => {
+                    tracing::debug!("Transaction pool full, evicting {} (fee {})", lowest_hash, lowest_fee);
+                    self.remove_transaction(&lowest_hash);
+                }
+                _ => return Err(QoraNetError::PoolFull {
+                    offered: transaction.fee_qor,
+                    lowest: lowest.map_or(0, |(fee, _)| fee),
+                }),
+            }
+        }
+
+        let eviction_key = (transaction.fee_qor, std::cmp::Reverse(self.next_sequence));
+        self.next_sequence += 1;
+        self.eviction_order.insert(eviction_key, tx_hash.clone());
+        self.eviction_keys.insert(tx_hash.clone(), eviction_key);
+
         // Add to pending
         self.pending.insert(tx_hash.clone(), transaction);
+        self.received_at.insert(tx_hash.clone(), now);
         
         // Add to by_signer index
         self.by_signer
             .entry(signer)
             .or_insert_with(Vec::new)
             .push(tx_hash);
-            
         Ok(())
     }
     
+    /// Hash of the pending transaction from `signer` with `nonce`, if any
+    fn pending_with_nonce(&self, signer: &Address, nonce: u64) -> Option<Hash> {
+        self.by_signer.get(signer)?
+            .iter()
+            .find(|hash| self.pending.get(*hash).is_some_and(|tx| tx.nonce == nonce))
+            .cloned()
+    }
+
+    /// Lowest fee that may replace a pending transaction paying `old_fee`; always more than `old_fee`
+    fn replacement_fee(&self, old_fee: u64) -> u64 {
+        let bump = (old_fee as u128 * self.config.min_replacement_bump_pct as u128).div_ceil(100) as u64;
+        old_fee.saturating_add(bump.max(1))
+    }
+
     /// Remove transaction from pool
     pub fn remove_transaction(&mut self, tx_hash: &Hash) -> Option<Transaction> {
         if let Some(transaction) = self.pending.remove(tx_hash) {
+            self.received_at.remove(tx_hash);
+            if let Some(eviction_key) = self.eviction_keys.remove(tx_hash) {
+                self.eviction_order.remove(&eviction_key);
+            }
             // Remove from by_signer index
             if let Some(tx_hashes) = self.by_signer.get_mut(&transaction.signer) {
                 tx_hashes.retain(|h| h != tx_hash);
@@ -303,35 +845,1045 @@ impl TransactionPool {
             None
         }
     }
-    
-    /// Get transactions for block creation (sorted by fee priority)
-    pub fn get_transactions_for_block(&self, max_count: usize) -> Vec<Transaction> {
-        let mut transactions: Vec<Transaction> = self.pending.values().cloned().collect();
+
+    /// Put transactions from blocks rewound by a reorg back into the pool.
+    ///
+    /// Each one is validated again, its fee against the discount `holdings`
+    /// reports for the signer; ones already pending or below the signer's
+    /// known next nonce are skipped, and at most `limit` are re-admitted.
+    /// Returns how many were.
+    pub async fn readmit_reverted(
+        &mut self,
+        transactions: Vec<Transaction>,
+        fee_oracle: &GlobalFeeOracle,
+        limit: usize,
+        holdings: impl Fn(&Address) -> AccountHoldings,
+    ) -> usize {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut readmitted = 0;
         
-        // Sort by priority (Urgent > High > Medium > Low) then by fee amount
-        transactions.sort_by(|a, b| {
-            // First sort by priority
-            let priority_order = |p: &FeePriority| match p {
-                FeePriority::Urgent => 4,
-                FeePriority::High => 3, 
-                FeePriority::Medium => 2,
-                FeePriority::Low => 1,
-            };
+        for transaction in transactions {
+            if readmitted >= limit {
+                break;
+            }
+            if self.pending.contains_key(&transaction.hash()) {
+                continue;
+            }
+            if self.next_nonces.get(&transaction.signer).map_or(false, |next| transaction.nonce < *next) {
+                continue;
+            }
+            let holdings = holdings(&transaction.signer);
+            if let Err(e) = transaction.validate_for(fee_oracle, &self.chain, self.chain_height + 1, &holdings).await {
+                tracing::debug!("Dropping reverted transaction {}: {}", transaction.hash(), e);
+                continue;
+            }
             
-            let priority_cmp = priority_order(&b.priority).cmp(&priority_order(&a.priority));
-            if priority_cmp != std::cmp::Ordering::Equal {
-                return priority_cmp;
+            if let Err(e) = self.insert(transaction, now) {
+                tracing::debug!("Reverted transaction not re-admitted: {}", e);
+                continue;
+            }
+            readmitted += 1;
+        }
+        
+        readmitted
+    }
+    
+    /// Drop transactions that can't be included in the block after `chain_height`
+    pub fn evict_expired(&mut self, chain_height: BlockHeight) -> usize {
+        let expired: Vec<Hash> = self.pending.values()
+            .filter(|tx| tx.is_expired_at(chain_height + 1))
+            .map(|tx| tx.hash())
+            .collect();
+        for tx_hash in &expired {
+            self.remove_transaction(tx_hash);
+        }
+        expired.len()
+    }
+
+    /// Record the next nonce a signer's account expects; drops pending transactions it supersedes
+    pub fn set_next_nonce(&mut self, signer: &Address, next_nonce: u64) {
+        self.next_nonces.insert(signer.clone(), next_nonce);
+
+        let stale: Vec<Hash> = self.signer_transactions(signer)
+            .filter(|tx| tx.nonce < next_nonce)
+            .map(|tx| tx.hash())
+            .collect();
+        for tx_hash in stale {
+            self.remove_transaction(&tx_hash);
+        }
+    }
+
+    /// Pending transactions of a signer
+    fn signer_transactions<'a>(&'a self, signer: &Address) -> impl Iterator<Item = &'a Transaction> + 'a {
+        self.by_signer
+            .get(signer)
+            .into_iter()
+            .flatten()
+            .filter_map(move |tx_hash| self.pending.get(tx_hash))
+    }
+
+    /// First gap in a signer's pending nonces, if its next nonce is known
+    fn nonce_gap(&self, signer: &Address) -> Option<NonceGap> {
+        let mut expected = *self.next_nonces.get(signer)?;
+
+        let mut nonces: Vec<u64> = self.signer_transactions(signer).map(|tx| tx.nonce).collect();
+        nonces.sort_unstable();
+        nonces.dedup();
+
+        for nonce in nonces {
+            if nonce == expected {
+                expected += 1;
+            } else if nonce > expected {
+                return Some(NonceGap {
+                    signer: signer.clone(),
+                    from_nonce: expected,
+                    to_nonce: nonce,
+                });
+            }
+        }
+        None
+    }
+
+    /// Find nonce gaps that have persisted past the grace period.
+    ///
+    /// Transactions stuck behind a gap longer than the retention limit are
+    /// evicted first. Each returned gap should be sent to peers (see
+    /// `NonceGap::to_request`); a signer is asked about again only after
+    /// another grace period.
+    pub fn detect_nonce_gaps(&mut self, now: u64) -> Vec<NonceGap> {
+        let signers: Vec<Address> = self.by_signer.keys().cloned().collect();
+        let mut gaps = Vec::new();
+
+        for signer in signers {
+            let gap = match self.nonce_gap(&signer) {
+                Some(gap) => gap,
+                None => {
+                    self.resync_requested.remove(&signer);
+                    continue;
+                }
+            };
+
+            let gapped: Vec<(Hash, u64)> = self.signer_transactions(&signer)
+                .filter(|tx| tx.nonce >= gap.to_nonce)
+                .map(|tx| {
+                    let tx_hash = tx.hash();
+                    let received = self.received_at.get(&tx_hash).copied().unwrap_or(now);
+                    (tx_hash, received)
+                })
+                .collect();
+
+            // Bound how long transactions wait on a gap that never fills
+            let mut oldest_remaining = None;
+            for (tx_hash, received) in gapped {
+                if now.saturating_sub(received) > self.gapped_retention_secs {
+                    self.remove_transaction(&tx_hash);
+                } else {
+                    oldest_remaining = Some(oldest_remaining.map_or(received, |oldest: u64| oldest.min(received)));
+                }
+            }
+
+            let oldest = match oldest_remaining {
+                Some(oldest) => oldest,
+                None => {
+                    self.resync_requested.remove(&signer);
+                    continue;
+                }
+            };
+            if now.saturating_sub(oldest) < self.nonce_gap_grace_secs {
+                continue;
+            }
+            if let Some(last) = self.resync_requested.get(&signer) {
+                if now.saturating_sub(*last) < self.nonce_gap_grace_secs {
+                    continue;
+                }
             }
+
+            self.resync_requested.insert(signer, now);
+            gaps.push(gap);
+        }
+
+        gaps
+    }
+    
+    /// Get transactions for block creation (best fee priority first)
+    ///
+    /// Uses the next nonces recorded with `set_next_nonce`; see
+    /// `get_transactions_for_block_from`.
+    pub fn get_transactions_for_block(&self, max_count: usize, height: BlockHeight) -> Vec<Transaction> {
+        self.get_transactions_for_block_from(max_count, height, |_| None)
+    }
+    
+    /// Get transactions for block creation, given each signer's account nonce.
+    ///
+    /// Each signer's transactions are taken in nonce order, starting from
+    /// `account_nonce(signer)` (falling back to the nonce recorded with
+    /// `set_next_nonce`, then to the signer's lowest pending nonce) and
+    /// stopping at the first gap or at a transaction expired at `height`, so a
+    /// nonce is only included after every lower one. Across signers, the next transaction of each is picked by priority
+    /// (Urgent > High > Medium > Low), then higher `fee_qor`, then lower hash,
+    /// so every validator builds the same block from the same pool.
+    pub fn get_transactions_for_block_from(
+        &self,
+        max_count: usize,
+        height: BlockHeight,
+        account_nonce: impl Fn(&Address) -> Option<u64>,
+    ) -> Vec<Transaction> {
+        let priority_order = |p: &FeePriority| match p {
+            FeePriority::Urgent => 4u8,
+            FeePriority::High => 3,
+            FeePriority::Medium => 2,
+            FeePriority::Low => 1,
+        };
+        
+        // Contiguous run of each signer's executable transactions
+        let mut runs: Vec<Vec<&Transaction>> = Vec::new();
+        for signer in self.by_signer.keys() {
+            let mut transactions: Vec<&Transaction> = self.signer_transactions(signer).collect();
+            // Of two transactions with the same nonce, the better-paying one is kept
+            transactions.sort_by(|a, b| a.nonce.cmp(&b.nonce).then_with(|| b.fee_qor.cmp(&a.fee_qor)));
+            transactions.dedup_by_key(|tx| tx.nonce);
             
-            // Then sort by fee amount (higher fees first)
-            b.fee_qor.cmp(&a.fee_qor)
-        });
+            let start = account_nonce(signer)
+                .or_else(|| self.next_nonces.get(signer).copied())
+                .or_else(|| transactions.first().map(|tx| tx.nonce));
+            let mut expected = match start {
+                Some(start) => start,
+                None => continue,
+            };
+            let run: Vec<&Transaction> = transactions.into_iter()
+                // Already applied
+                .skip_while(|tx| tx.nonce < expected)
+                .take_while(|tx| {
+                    let includable = tx.nonce == expected && !tx.is_expired_at(height);
+                    expected += 1;
+                    includable
+                })
+                .collect();
+            if !run.is_empty() {
+                runs.push(run);
+            }
+        }
+        
+        // Repeatedly take the best next transaction among the signers
+        let head_key = |tx: &Transaction, run: usize| {
+            (priority_order(&tx.priority), tx.fee_qor, std::cmp::Reverse(tx.hash().0), run)
+        };
+        let mut heads: std::collections::BinaryHeap<_> = runs.iter().enumerate()
+            .map(|(run, transactions)| head_key(transactions[0], run))
+            .collect();
+        let mut positions = vec![0usize; runs.len()];
+        let mut selected = Vec::new();
         
-        transactions.into_iter().take(max_count).collect()
+        while selected.len() < max_count {
+            let (_, _, _, run) = match heads.pop() {
+                Some(head) => head,
+                None => break,
+            };
+            selected.push(runs[run][positions[run]].clone());
+            positions[run] += 1;
+            if let Some(next) = runs[run].get(positions[run]) {
+                heads.push(head_key(next, run));
+            }
+        }
+        
+        selected
+    }
+    
+    /// Net balance change of `address` if every pending transaction were applied
+    pub fn pending_balance_delta(&self, address: &Address) -> i128 {
+        self.pending.values().map(|tx| tx.balance_effect(address)).sum()
+    }
+
+    /// Net balance change of `address` if `candidate` were added too; the pending
+    /// transaction it would replace (same signer and nonce) no longer counts
+    pub fn pending_balance_delta_with(&self, address: &Address, candidate: &Transaction) -> i128 {
+        let replaced = self.pending_with_nonce(&candidate.signer, candidate.nonce)
+            .map_or(0, |hash| self.pending[&hash].balance_effect(address));
+        self.pending_balance_delta(address) - replaced + candidate.balance_effect(address)
     }
     
     /// Get pending transaction count
     pub fn pending_count(&self) -> usize {
         self.pending.len()
     }
+
+    /// Congestion snapshot for picking a fee priority
+    pub fn stats(&self, max_block_transactions: usize, block_time_secs: u64) -> MempoolStats {
+        let mut stats = MempoolStats { max_block_transactions, block_time_secs, ..MempoolStats::default() };
+        for tx in self.pending.values() {
+            match tx.priority {
+                FeePriority::Low => stats.pending_low += 1,
+                FeePriority::Medium => stats.pending_medium += 1,
+                FeePriority::High => stats.pending_high += 1,
+                FeePriority::Urgent => stats.pending_urgent += 1,
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    fn unsigned_claim(signer: Address) -> Transaction {
+        Transaction {
+            data: TransactionData::ClaimRewards {
+                claimant: signer.clone(),
+                lp_rewards: 10,
+                app_rewards: 0,
+            },
+            nonce: 0,
+            fee_qor: 1_000,
+            fee_usd: 0.0001,
+            priority: FeePriority::Medium,
+            signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+            signer,
+            valid_until_height: None,
+        }
+    }
+
+    /// Transfer layout before memos were added
+    #[derive(Serialize)]
+    enum LegacyTransactionData {
+        Transfer { from: Address, to: Address, amount: u64 },
+    }
+
+    #[derive(Serialize)]
+    struct LegacyTransaction {
+        data: LegacyTransactionData,
+        nonce: u64,
+        fee_qor: u64,
+        fee_usd: f64,
+        priority: FeePriority,
+        signature: QoraSignature,
+        signer: Address,
+    }
+
+    fn transfer(memo: Option<Vec<u8>>) -> Transaction {
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        let mut tx = Transaction {
+            data: TransactionData::Transfer {
+                from: signer.clone(),
+                to: Address([5u8; 32]),
+                amount: 250,
+                memo,
+            },
+            nonce: 3,
+            fee_qor: 1_000,
+            fee_usd: 0.0001,
+            priority: FeePriority::Low,
+            signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+            signer,
+            valid_until_height: None,
+        };
+        tx.signature = keypair.sign(&tx.signing_message());
+        tx
+    }
+
+    #[test]
+    fn test_memo_less_transfer_hash_unchanged() {
+        let tx = transfer(None);
+        let (from, to, amount) = match &tx.data {
+            TransactionData::Transfer { from, to, amount, .. } => (from.clone(), to.clone(), *amount),
+            _ => unreachable!(),
+        };
+
+        let legacy = LegacyTransaction {
+            data: LegacyTransactionData::Transfer { from, to, amount },
+            nonce: tx.nonce,
+            fee_qor: tx.fee_qor,
+            fee_usd: tx.fee_usd,
+            priority: tx.priority.clone(),
+            signature: tx.signature,
+            signer: tx.signer.clone(),
+        };
+
+        // Ids now cover only signed fields; the id a memo-less transfer was
+        // stored under before that is still its legacy hash, so it can be rekeyed
+        assert_eq!(tx.legacy_hash(), Hash::new(&bincode::serialize(&legacy).unwrap()));
+        let mut signed = tx.signing_message();
+        signed.extend_from_slice(&tx.signature.to_bytes());
+        assert_eq!(tx.hash(), Hash::new(&signed));
+
+        // The signed message, and with it the current id, encodes a memo-less
+        // transfer exactly as before memos existed
+        assert_eq!(tx.data.canonical_bytes(), bincode::serialize(&legacy.data).unwrap());
+
+        // An empty memo is a different transaction, which the original signature doesn't cover
+        let mut empty = tx.clone();
+        if let TransactionData::Transfer { memo, .. } = &mut empty.data {
+            *memo = Some(Vec::new());
+        }
+        assert_ne!(empty.hash(), tx.hash());
+        assert!(empty.verify_signature().is_err());
+    }
+
+    #[test]
+    fn test_memo_transfer_round_trips() {
+        let tx = transfer(Some(b"deposit-tag-42".to_vec()));
+        assert!(tx.verify_signature().is_ok());
+
+        let decoded: Transaction = bincode::deserialize(&bincode::serialize(&tx).unwrap()).unwrap();
+        assert_eq!(decoded.data.memo(), Some(&b"deposit-tag-42"[..]));
+        assert_eq!(decoded.hash(), tx.hash());
+        assert!(decoded.verify_signature().is_ok());
+
+        // Memo is covered by the signature
+        let mut tampered = tx.clone();
+        if let TransactionData::Transfer { memo, .. } = &mut tampered.data {
+            *memo = Some(b"deposit-tag-43".to_vec());
+        }
+        assert!(tampered.verify_signature().is_err());
+        assert_ne!(tampered.hash(), tx.hash());
+    }
+
+    #[test]
+    fn test_strict_verification_accepts_honest_signature() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let mut tx = unsigned_claim(Address::from_pubkey(&keypair.public));
+        tx.signature = keypair.sign(&tx.signing_message());
+
+        assert!(tx.verify_signature().is_ok());
+        assert!(tx.verify_signature_with(SignatureVerification::Legacy).is_ok());
+    }
+
+    #[test]
+    fn test_strict_verification_rejects_weak_key_forgery() {
+        // Identity point as public key: (R = identity, s = 0) satisfies the
+        // verification equation for *any* message, so signatures are malleable
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+
+        let mut tx = unsigned_claim(Address(identity));
+        let mut forged = [0u8; 64];
+        forged[..32].copy_from_slice(&identity);
+        tx.signature = QoraSignature::from_bytes(&forged).unwrap();
+
+        // Non-strict verification accepts the forgery
+        assert!(tx.verify_signature_with(SignatureVerification::Legacy).is_ok());
+
+        // A second, distinct transaction verifies with the same signature
+        let mut other = tx.clone();
+        other.nonce = 1;
+        assert!(other.verify_signature_with(SignatureVerification::Legacy).is_ok());
+        assert_ne!(tx.hash(), other.hash());
+
+        // Strict verification rejects both
+        assert!(matches!(tx.verify_signature(), Err(QoraNetError::InvalidTransaction(_))));
+        assert!(other.verify_signature().is_err());
+    }
+
+    fn claim_with_nonce(signer: &Address, nonce: u64) -> Transaction {
+        let mut tx = unsigned_claim(signer.clone());
+        tx.nonce = nonce;
+        tx
+    }
+
+    #[test]
+    fn test_nonce_gap_triggers_resync_request() {
+        let mut pool = TransactionPool::new().with_nonce_gap_limits(10, 300);
+        let signer = Address([9u8; 32]);
+        pool.set_next_nonce(&signer, 3);
+
+        pool.insert(claim_with_nonce(&signer, 5), 1_000).unwrap();
+
+        // Not executable and no request until the gap persists
+        assert!(pool.get_transactions_for_block(10, 1).is_empty());
+        assert!(pool.detect_nonce_gaps(1_005).is_empty());
+
+        let gaps = pool.detect_nonce_gaps(1_010);
+        assert_eq!(gaps, vec![NonceGap { signer: signer.clone(), from_nonce: 3, to_nonce: 5 }]);
+        assert!(matches!(
+            gaps[0].to_request(),
+            crate::network::NetworkMessage::MissingTransactionsRequest { from_nonce: 3, to_nonce: 5, .. }
+        ));
+
+        // Not re-requested until another grace period has passed
+        assert!(pool.detect_nonce_gaps(1_012).is_empty());
+        assert_eq!(pool.detect_nonce_gaps(1_020).len(), 1);
+    }
+
+    #[test]
+    fn test_filled_gap_connects_transactions() {
+        let mut pool = TransactionPool::new().with_nonce_gap_limits(10, 300);
+        let signer = Address([9u8; 32]);
+        pool.set_next_nonce(&signer, 3);
+
+        pool.insert(claim_with_nonce(&signer, 5), 1_000).unwrap();
+        assert_eq!(pool.detect_nonce_gaps(1_010).len(), 1);
+
+        // Missing transactions arrive from peers
+        pool.insert(claim_with_nonce(&signer, 3), 1_011).unwrap();
+        pool.insert(claim_with_nonce(&signer, 4), 1_011).unwrap();
+
+        assert!(pool.detect_nonce_gaps(1_030).is_empty());
+        let mut nonces: Vec<u64> = pool.get_transactions_for_block(10, 1).iter().map(|tx| tx.nonce).collect();
+        nonces.sort_unstable();
+        assert_eq!(nonces, vec![3, 4, 5]);
+
+        // Included transactions are superseded once the account nonce advances
+        pool.set_next_nonce(&signer, 5);
+        assert_eq!(pool.pending_count(), 1);
+    }
+
+    fn claim_with_priority(signer: &Address, nonce: u64, priority: FeePriority) -> Transaction {
+        let mut tx = claim_with_nonce(signer, nonce);
+        tx.priority = priority;
+        tx
+    }
+
+    /// Nonces of `signer` in the order they were selected
+    fn selected_nonces(selected: &[Transaction], signer: &Address) -> Vec<u64> {
+        selected.iter().filter(|tx| &tx.signer == signer).map(|tx| tx.nonce).collect()
+    }
+
+    #[test]
+    fn test_block_selection_keeps_nonces_contiguous() {
+        let mut pool = TransactionPool::new();
+        let alice = Address([1u8; 32]);
+        let bob = Address([2u8; 32]);
+
+        // Alice's later nonces pay for a higher priority than her first
+        pool.insert(claim_with_priority(&alice, 2, FeePriority::Urgent), 0).unwrap();
+        pool.insert(claim_with_priority(&bob, 1, FeePriority::High), 0).unwrap();
+        pool.insert(claim_with_priority(&alice, 0, FeePriority::Low), 0).unwrap();
+        pool.insert(claim_with_priority(&bob, 0, FeePriority::Medium), 0).unwrap();
+        pool.insert(claim_with_priority(&alice, 1, FeePriority::Urgent), 0).unwrap();
+
+        let selected = pool.get_transactions_for_block(10, 1);
+        assert_eq!(selected.len(), 5);
+        assert_eq!(selected_nonces(&selected, &alice), vec![0, 1, 2]);
+        assert_eq!(selected_nonces(&selected, &bob), vec![0, 1]);
+        // Bob's Medium head beats Alice's Low head
+        assert_eq!(selected[0].signer, bob);
+
+        // A short block still never skips a nonce
+        let selected = pool.get_transactions_for_block(2, 1);
+        assert_eq!(selected_nonces(&selected, &bob), vec![0, 1]);
+        assert!(selected_nonces(&selected, &alice).is_empty());
+    }
+
+    #[test]
+    fn test_block_selection_orders_by_priority_fee_then_hash() {
+        let mut pool = TransactionPool::new();
+        let with_fee = |seed: u8, priority: FeePriority, fee_qor: u64| {
+            let mut tx = claim_with_priority(&Address([seed; 32]), 0, priority);
+            tx.fee_qor = fee_qor;
+            tx
+        };
+        let low_rich = with_fee(1, FeePriority::Low, 5_000);
+        let urgent_cheap = with_fee(2, FeePriority::Urgent, 100);
+        let high = with_fee(3, FeePriority::High, 2_000);
+        let high_richer = with_fee(4, FeePriority::High, 3_000);
+        let medium_a = with_fee(5, FeePriority::Medium, 1_000);
+        let medium_b = with_fee(6, FeePriority::Medium, 1_000);
+        for tx in [&low_rich, &urgent_cheap, &high, &high_richer, &medium_a, &medium_b] {
+            pool.insert(tx.clone(), 0).unwrap();
+        }
+
+        let (first_medium, second_medium) = if medium_a.hash().0 < medium_b.hash().0 {
+            (&medium_a, &medium_b)
+        } else {
+            (&medium_b, &medium_a)
+        };
+        let expected: Vec<Hash> = [&urgent_cheap, &high_richer, &high, first_medium, second_medium, &low_rich]
+            .iter().map(|tx| tx.hash()).collect();
+        let selected: Vec<Hash> = pool.get_transactions_for_block(10, 1).iter().map(|tx| tx.hash()).collect();
+        assert_eq!(selected, expected);
+    }
+
+    #[test]
+    fn test_block_selection_starts_at_account_nonce() {
+        let mut pool = TransactionPool::new();
+        let alice = Address([1u8; 32]);
+        let bob = Address([2u8; 32]);
+        let carol = Address([3u8; 32]);
+        for nonce in [0, 1, 2] {
+            pool.insert(claim_with_nonce(&alice, nonce), 0).unwrap();
+        }
+        pool.insert(claim_with_nonce(&bob, 0), 0).unwrap();
+        pool.insert(claim_with_nonce(&bob, 2), 0).unwrap();
+        pool.insert(claim_with_nonce(&carol, 4), 0).unwrap();
+
+        let account_nonces: std::collections::HashMap<Address, u64> =
+            [(alice.clone(), 1), (bob.clone(), 0), (carol.clone(), 3)].into_iter().collect();
+        let selected = pool.get_transactions_for_block_from(10, 1, |signer| account_nonces.get(signer).copied());
+
+        // Alice's nonce 0 is already applied; Bob is stuck behind his missing nonce 1
+        assert_eq!(selected_nonces(&selected, &alice), vec![1, 2]);
+        assert_eq!(selected_nonces(&selected, &bob), vec![0]);
+        assert!(selected_nonces(&selected, &carol).is_empty());
+    }
+
+    #[test]
+    fn test_gapped_transactions_evicted_after_retention() {
+        let mut pool = TransactionPool::new().with_nonce_gap_limits(10, 60);
+        let signer = Address([9u8; 32]);
+        pool.set_next_nonce(&signer, 0);
+
+        pool.insert(claim_with_nonce(&signer, 0), 1_000).unwrap();
+        pool.insert(claim_with_nonce(&signer, 2), 1_000).unwrap();
+
+        assert!(pool.detect_nonce_gaps(1_061).is_empty());
+        assert_eq!(pool.pending_count(), 1);
+        assert_eq!(pool.get_transactions_for_block(10, 1)[0].nonce, 0);
+    }
+
+    fn register_app(signer: Address) -> Transaction {
+        Transaction {
+            data: TransactionData::RegisterApp {
+                owner: signer.clone(),
+                app_id: AppId::new("indexer-1").unwrap(),
+                app_type: AppType::IndexingService,
+                resource_requirements: ResourceRequirements {
+                    min_cpu_cores: 2,
+                    min_memory_gb: 4,
+                    min_disk_gb: 100,
+                    min_bandwidth_mbps: 100,
+                },
+            },
+            ..unsigned_claim(signer)
+        }
+    }
+
+    #[test]
+    fn test_app_id_validation() {
+        assert!(AppId::new("indexer-1.v2_eu").is_ok());
+        assert!(AppId::new("").is_err());
+        assert!(AppId::new("a".repeat(MAX_APP_ID_LEN + 1)).is_err());
+        assert!(AppId::new("owner:app").is_err());
+        assert!(AppId::new("my app").is_err());
+
+        // Ids stored before the format was enforced still decode, but fail the check
+        let encoded = bincode::serialize(&"bad id".to_string()).unwrap();
+        let legacy = bincode::deserialize::<AppId>(&encoded).unwrap();
+        assert_eq!(legacy.as_str(), "bad id");
+        assert!(legacy.check_format().is_err());
+    }
+
+    #[test]
+    fn test_app_id_encodes_as_plain_string() {
+        // Keeps hashes of existing RegisterApp / ReportMetrics transactions unchanged
+        let id = AppId::new("indexer-1").unwrap();
+        assert_eq!(bincode::serialize(&id).unwrap(), bincode::serialize(&"indexer-1".to_string()).unwrap());
+        assert_eq!(id.to_string(), "indexer-1");
+    }
+
+    #[test]
+    fn test_same_app_id_namespaced_per_owner() {
+        let alice = register_app(Address([1u8; 32]));
+        let bob = register_app(Address([2u8; 32]));
+
+        let (alice_key, bob_key) = (alice.data.app_key().unwrap(), bob.data.app_key().unwrap());
+        assert_ne!(alice_key, bob_key);
+        assert!(alice_key.ends_with(":indexer-1"));
+
+        let report = TransactionData::ReportMetrics {
+            validator: Address([9u8; 32]),
+            app_owner: Address([1u8; 32]),
+            app_id: AppId::new("indexer-1").unwrap(),
+            metrics: crate::AppMetrics::new(),
+        };
+        assert_eq!(report.app_key().unwrap(), alice_key);
+        assert!(transfer(None).data.app_key().is_none());
+    }
+
+    #[test]
+    fn test_kind_matches_every_variant() {
+        let owner = Address([1u8; 32]);
+        let samples = vec![
+            transfer(None).data,
+            TransactionData::ProvideLiquidity { provider: owner.clone(), lp_tokens: Vec::new() },
+            register_app(owner.clone()).data,
+            TransactionData::ReportMetrics {
+                validator: owner.clone(),
+                app_owner: owner.clone(),
+                app_id: AppId::new("indexer-1").unwrap(),
+                metrics: crate::AppMetrics::new(),
+            },
+            unsigned_claim(owner.clone()).data,
+            TransactionData::SetSendLimit { authority: owner.clone(), account: Address([2u8; 32]), limit: Some(1) },
+            TransactionData::ReportEquivocation {
+                reporter: owner.clone(),
+                proof: crate::consensus::EquivocationProof {
+                    first: crate::consensus::SignedHeader {
+                        header: crate::consensus::Block::genesis(owner.clone()).header,
+                        signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+                    },
+                    second: crate::consensus::SignedHeader {
+                        header: crate::consensus::Block::genesis(owner).header,
+                        signature: QoraSignature::from_bytes(&[0u8; 64]).unwrap(),
+                    },
+                },
+            },
+        ];
+
+        for data in samples {
+            // Exhaustive on purpose: a new variant must be added here and to `kind`
+            let expected = match &data {
+                TransactionData::Transfer { .. } => TransactionKind::Transfer,
+                TransactionData::ProvideLiquidity { .. } => TransactionKind::ProvideLiquidity,
+                TransactionData::RegisterApp { .. } => TransactionKind::RegisterApp,
+                TransactionData::ReportMetrics { .. } => TransactionKind::ReportMetrics,
+                TransactionData::ClaimRewards { .. } => TransactionKind::ClaimRewards,
+                TransactionData::ReportEquivocation { .. } => TransactionKind::ReportEquivocation,
+                TransactionData::SetSendLimit { .. } => TransactionKind::SetSendLimit,
+            };
+            assert_eq!(data.kind(), expected);
+            assert_eq!(TransactionKind::of(&data.transaction_type()), expected);
+        }
+    }
+
+    #[test]
+    fn test_disabled_tx_type_rejected() {
+        let mut chain = GenesisConfig::default();
+        chain.enabled_tx_types.remove(&TransactionKind::RegisterApp);
+
+        let err = register_app(Address([1u8; 32])).check_type_enabled(&chain, 10).unwrap_err();
+        assert!(err.to_string().contains("transaction type not enabled"));
+        assert!(transfer(None).check_type_enabled(&chain, 10).is_ok());
+
+        // Default config enables everything
+        assert!(register_app(Address([1u8; 32])).check_type_enabled(&GenesisConfig::default(), 0).is_ok());
+    }
+
+    #[test]
+    fn test_tx_type_enabled_at_activation_height() {
+        let mut chain = GenesisConfig::default();
+        chain.enabled_tx_types.remove(&TransactionKind::RegisterApp);
+        chain.tx_type_activation_heights.insert(TransactionKind::RegisterApp, 1_000);
+
+        let tx = register_app(Address([1u8; 32]));
+        assert!(tx.check_type_enabled(&chain, 999).is_err());
+        assert!(tx.check_type_enabled(&chain, 1_000).is_ok());
+        assert!(tx.check_type_enabled(&chain, 1_001).is_ok());
+    }
+
+    #[test]
+    fn test_send_limit_updates_need_an_authority() {
+        let mut tx = register_app(Address([1u8; 32]));
+        tx.data = TransactionData::SetSendLimit { authority: Address([1u8; 32]), account: Address([2u8; 32]), limit: None };
+
+        let mut chain = GenesisConfig::default();
+        assert!(tx.check_type_enabled(&chain, 0).is_err());
+        chain.spending_limit_authority = Some(Address([1u8; 32]));
+        assert!(tx.check_type_enabled(&chain, 0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pool_gates_types_by_its_chain_and_height() {
+        let oracle = GlobalFeeOracle::from_oracle(crate::FeeOracle::new().with_fallback_price(0.25));
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        let data = TransactionData::Transfer { from: signer, to: Address([5u8; 32]), amount: 10, memo: None };
+        let tx = Transaction::new(data, 0, FeePriority::Medium, &keypair, &oracle).await.unwrap();
+
+        let mut chain = GenesisConfig::default();
+        chain.enabled_tx_types.remove(&TransactionKind::Transfer);
+        chain.tx_type_activation_heights.insert(TransactionKind::Transfer, 100);
+        let mut pool = TransactionPool::new().with_chain(chain);
+
+        // The tip at 98 means the next block is 99, still before activation
+        pool.set_chain_height(98);
+        assert!(pool.add_transaction(tx.clone(), &oracle).await.is_err());
+        pool.set_chain_height(99);
+        assert!(pool.add_transaction(tx, &oracle).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_matches_built_transaction() {
+        let oracle = GlobalFeeOracle::from_oracle(crate::FeeOracle::new().with_fallback_price(0.25));
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+
+        for priority in [FeePriority::Low, FeePriority::Medium, FeePriority::High] {
+            let data = TransactionData::Transfer { from: signer.clone(), to: Address([5u8; 32]), amount: 10, memo: None };
+            let (fee_qor, fee_usd) = Transaction::estimate(&data, priority.clone(), &oracle).await;
+
+            let tx = Transaction::new(data, 0, priority, &keypair, &oracle).await.unwrap();
+            assert_eq!(tx.fee_qor, fee_qor);
+            assert_eq!(tx.fee_usd, fee_usd);
+        }
+    }
+
+    #[test]
+    fn test_signature_is_domain_separated() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let mut tx = transfer(None);
+        tx.signer = Address::from_pubkey(&keypair.public);
+
+        let message = tx.signing_message();
+        assert!(message.starts_with(TRANSACTION_SIGNING_DOMAIN));
+
+        // A signature over the untagged bytes, as another protocol might produce
+        tx.signature = keypair.sign(&message[TRANSACTION_SIGNING_DOMAIN.len()..]);
+        assert!(tx.verify_signature().is_err());
+
+        tx.signature = keypair.sign(&message);
+        assert!(tx.verify_signature().is_ok());
+    }
+
+    #[test]
+    fn test_signing_message_ignores_fee_usd_representation() {
+        let mut a = transfer(None);
+        let mut b = a.clone();
+        // Same logical fee, one ULP apart
+        a.fee_usd = 0.1 + 0.2;
+        b.fee_usd = 0.3;
+        assert_ne!(a.fee_usd.to_bits(), b.fee_usd.to_bits());
+
+        assert_eq!(a.signing_message(), b.signing_message());
+        assert!(a.verify_signature().is_ok());
+        assert!(b.verify_signature().is_ok());
+
+        // The unsigned USD fee doesn't change the id either
+        assert_eq!(a.hash(), b.hash());
+        b.fee_usd = 42.0;
+        assert_eq!(a.hash(), b.hash());
+
+        // The QOR fee is still covered by the signature
+        b.fee_qor += 1;
+        assert!(b.verify_signature().is_err());
+        assert_ne!(a.hash(), b.hash());
+    }
+
+    #[tokio::test]
+    async fn test_new_for_target_uses_congestion() {
+        let oracle = GlobalFeeOracle::from_oracle(crate::FeeOracle::new().with_fallback_price(0.25));
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        let data = || TransactionData::Transfer { from: signer.clone(), to: Address([5u8; 32]), amount: 10, memo: None };
+
+        let mut pool = TransactionPool::new();
+        let quiet = Transaction::new_for_target(data(), 0, 30, &pool.stats(100, 10), &keypair, &oracle).await.unwrap();
+        assert!(matches!(quiet.priority, FeePriority::Low));
+
+        for nonce in 0..300 {
+            let mut tx = transfer(None);
+            tx.nonce = nonce;
+            tx.priority = FeePriority::High;
+            pool.insert(tx, 0).unwrap();
+        }
+        let stats = pool.stats(100, 10);
+        assert_eq!(stats.pending_high, 300);
+        let rushed = Transaction::new_for_target(data(), 1, 30, &stats, &keypair, &oracle).await.unwrap();
+        assert!(matches!(rushed.priority, FeePriority::Urgent));
+        assert!(rushed.fee_qor > quiet.fee_qor);
+    }
+
+    async fn expiring_transfer(valid_until: BlockHeight) -> Transaction {
+        let oracle = GlobalFeeOracle::from_oracle(crate::FeeOracle::new().with_fallback_price(0.25));
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        let data = TransactionData::Transfer { from: signer, to: Address([5u8; 32]), amount: 10, memo: None };
+        Transaction::new(data, 0, FeePriority::Medium, &keypair, &oracle).await.unwrap()
+            .with_valid_until(valid_until, &keypair)
+    }
+
+    #[tokio::test]
+    async fn test_transaction_expiry_enforced_at_inclusion() {
+        use crate::consensus::Block;
+
+        let tx = expiring_transfer(10).await;
+        assert!(tx.verify_signature().is_ok());
+
+        // The expiry is signed
+        let mut extended = tx.clone();
+        extended.valid_until_height = Some(20);
+        assert!(extended.verify_signature().is_err());
+
+        let oracle = GlobalFeeOracle::from_oracle(crate::FeeOracle::new().with_fallback_price(0.25));
+        let chain = GenesisConfig::default();
+        assert!(tx.validate(&oracle, &chain, 10).await.is_ok());
+        assert!(tx.validate(&oracle, &chain, 11).await.is_err());
+
+        let in_time = Block::new(Hash::zero(), 10, Address([1u8; 32]), vec![tx.clone()], 0, 0);
+        assert!(in_time.validate(10, &Hash::zero()).is_ok());
+        let too_late = Block::new(Hash::zero(), 11, Address([1u8; 32]), vec![tx], 0, 0);
+        assert!(too_late.validate(11, &Hash::zero()).is_err());
+    }
+
+    #[test]
+    fn test_canonical_outputs_sorted_and_merged() {
+        let outputs = [(Address([7u8; 32]), 1), (Address([2u8; 32]), 2), (Address([7u8; 32]), 3)];
+        let canonical = canonical_outputs(&outputs).unwrap();
+        assert_eq!(canonical, vec![(Address([2u8; 32]), 2), (Address([7u8; 32]), 4)]);
+
+        let mut reordered = outputs.to_vec();
+        reordered.rotate_left(1);
+        assert_eq!(canonical_outputs(&reordered).unwrap(), canonical);
+
+        assert!(canonical_outputs(&[(Address([1u8; 32]), u64::MAX), (Address([1u8; 32]), 1)]).is_err());
+    }
+
+    fn transfer_with_fee(fee_qor: u64) -> Transaction {
+        let mut tx = transfer(None);
+        tx.fee_qor = fee_qor;
+        tx
+    }
+
+    #[test]
+    fn test_full_pool_evicts_lowest_fee() {
+        let mut pool = TransactionPool::new().with_config(TransactionPoolConfig { max_pool_size: 3, max_per_signer: 8, ..Default::default() });
+        let cheapest = transfer_with_fee(1_000);
+        pool.insert(cheapest.clone(), 0).unwrap();
+        pool.insert(transfer_with_fee(2_000), 0).unwrap();
+        pool.insert(transfer_with_fee(3_000), 0).unwrap();
+
+        let better = transfer_with_fee(1_500);
+        pool.insert(better.clone(), 0).unwrap();
+        assert_eq!(pool.pending_count(), 3);
+        assert!(!pool.pending.contains_key(&cheapest.hash()));
+        assert!(pool.pending.contains_key(&better.hash()));
+
+        // Nothing left that a 1_500 fee can displace
+        match pool.insert(transfer_with_fee(1_500), 0) {
+            Err(QoraNetError::PoolFull { offered, lowest }) => assert_eq!((offered, lowest), (1_500, 1_500)),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(pool.pending_count(), 3);
+
+        // Removing a transaction keeps the eviction index in step
+        pool.remove_transaction(&better.hash());
+        pool.insert(transfer_with_fee(1_000), 0).unwrap();
+        assert_eq!(pool.eviction_order.len(), pool.pending_count());
+    }
+
+    #[test]
+    fn test_per_signer_limit() {
+        let mut pool = TransactionPool::new().with_config(TransactionPoolConfig { max_pool_size: 100, max_per_signer: 2, ..Default::default() });
+        let signer = Address([3u8; 32]);
+        pool.insert(claim_with_nonce(&signer, 0), 0).unwrap();
+        pool.insert(claim_with_nonce(&signer, 1), 0).unwrap();
+        assert!(pool.insert(claim_with_nonce(&signer, 2), 0).is_err());
+        // Re-inserting a pending transaction is still a no-op
+        assert!(pool.insert(claim_with_nonce(&signer, 1), 0).is_ok());
+        assert_eq!(pool.pending_count(), 2);
+    }
+
+    fn claim_with_fee(signer: &Address, nonce: u64, fee_qor: u64) -> Transaction {
+        let mut tx = claim_with_nonce(signer, nonce);
+        tx.fee_qor = fee_qor;
+        tx
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let mut pool = TransactionPool::new();
+        let signer = Address([4u8; 32]);
+        let original = claim_with_fee(&signer, 0, 1_000);
+        pool.insert(original.clone(), 0).unwrap();
+        pool.insert(claim_with_fee(&signer, 1, 1_000), 0).unwrap();
+
+        let bumped = claim_with_fee(&signer, 0, 1_100);
+        pool.insert(bumped.clone(), 0).unwrap();
+
+        assert_eq!(pool.pending_count(), 2);
+        assert!(!pool.pending.contains_key(&original.hash()));
+        assert!(pool.pending.contains_key(&bumped.hash()));
+        // No trace of the replaced hash in the indexes
+        let indexed = &pool.by_signer[&signer];
+        assert_eq!(indexed.len(), 2);
+        assert!(!indexed.contains(&original.hash()));
+        assert!(!pool.received_at.contains_key(&original.hash()));
+        assert_eq!(pool.eviction_order.len(), pool.pending_count());
+    }
+
+    #[test]
+    fn test_replacement_with_insufficient_bump_rejected() {
+        let mut pool = TransactionPool::new();
+        let signer = Address([4u8; 32]);
+        let original = claim_with_fee(&signer, 0, 1_000);
+        pool.insert(original.clone(), 0).unwrap();
+
+        match pool.insert(claim_with_fee(&signer, 0, 1_099), 0) {
+            Err(QoraNetError::ReplacementUnderpriced { nonce, offered, required }) => {
+                assert_eq!((nonce, offered, required), (0, 1_099, 1_100));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(pool.pending_count(), 1);
+        assert!(pool.pending.contains_key(&original.hash()));
+
+        // Even a zero bump must raise the fee
+        let mut pool = TransactionPool::new().with_config(TransactionPoolConfig {
+            min_replacement_bump_pct: 0,
+            ..Default::default()
+        });
+        pool.insert(claim_with_fee(&signer, 0, 1_000), 0).unwrap();
+        let same_fee = claim_with_priority(&signer, 0, FeePriority::High);
+        assert!(pool.insert(same_fee, 0).is_err());
+        assert!(pool.insert(claim_with_fee(&signer, 0, 1_001), 0).is_ok());
+        assert_eq!(pool.pending_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_transaction_rejects_invalid_fee() {
+        let oracle = GlobalFeeOracle::from_oracle(crate::FeeOracle::new().with_fallback_price(0.25));
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        let data = || TransactionData::Transfer { from: signer.clone(), to: Address([5u8; 32]), amount: 10, memo: None };
+        let mut pool = TransactionPool::new();
+
+        let mut underpaid = Transaction::new(data(), 0, FeePriority::Low, &keypair, &oracle).await.unwrap();
+        underpaid.fee_qor = 1;
+        underpaid.signature = keypair.sign(&underpaid.signing_message());
+        assert!(pool.add_transaction(underpaid, &oracle).await.is_err());
+        assert_eq!(pool.pending_count(), 0);
+
+        let paid = Transaction::new(data(), 0, FeePriority::Low, &keypair, &oracle).await.unwrap();
+        pool.add_transaction(paid, &oracle).await.unwrap();
+        assert_eq!(pool.pending_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pool_accepts_fee_discounted_for_signer_holdings() {
+        let policy = crate::FeeDiscountPolicy::none().with_qor_tier(1_000, 5_000);
+        let oracle = GlobalFeeOracle::from_oracle(crate::FeeOracle::new().with_fallback_price(0.25).with_discount_policy(policy));
+        let keypair = Keypair::generate(&mut OsRng);
+        let signer = Address::from_pubkey(&keypair.public);
+        let holder = AccountHoldings { qor_balance: 1_000, lp_value: 0 };
+
+        // Transfers already pay the minimum; a claim's fee has room to be discounted
+        let data = TransactionData::ClaimRewards { claimant: signer.clone(), lp_rewards: 10, app_rewards: 0 };
+        let mut discounted = Transaction::new(data, 0, FeePriority::Low, &keypair, &oracle).await.unwrap();
+        discounted.fee_qor = oracle.calculate_fee_for(&TransactionType::ClaimRewards, FeePriority::Low, &holder).await;
+        discounted.signature = keypair.sign(&discounted.signing_message());
+
+        let mut pool = TransactionPool::new();
+        assert!(pool.add_transaction(discounted.clone(), &oracle).await.is_err());
+        pool.add_transaction_for(discounted, &oracle, &holder).await.unwrap();
+        assert_eq!(pool.pending_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pool_evicts_expired_transactions() {
+        let mut pool = TransactionPool::new();
+        let expiring = expiring_transfer(10).await;
+        pool.insert(expiring.clone(), 0).unwrap();
+        pool.insert(transfer(None), 0).unwrap();
+
+        // Still includable in block 10
+        assert_eq!(pool.evict_expired(9), 0);
+        assert_eq!(pool.pending_count(), 2);
+
+        // Once the chain reaches the expiry height it can't be included anymore
+        assert_eq!(pool.evict_expired(10), 1);
+        assert_eq!(pool.pending_count(), 1);
+        assert!(pool.remove_transaction(&expiring.hash()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_transactions_not_selected_for_block() {
+        let mut pool = TransactionPool::new();
+        let expiring = expiring_transfer(10).await;
+        pool.insert(expiring.clone(), 0).unwrap();
+
+        assert_eq!(pool.get_transactions_for_block(10, 10).len(), 1);
+        // Left in the pool, it would otherwise make every block at height 11 invalid
+        assert!(pool.get_transactions_for_block(10, 11).is_empty());
+    }
 }
@@ -0,0 +1,144 @@
+//! Wallet helpers for displaying and parsing token amounts
+
+use crate::{QoraNetError, Result};
+use primitive_types::U256;
+
+/// Format a raw token amount with `decimals` places, trimming trailing zeros
+pub fn format_token_balance(amount: U256, decimals: u8) -> String {
+    let divisor = U256::from(10).pow(U256::from(decimals));
+    let integer_part = amount / divisor;
+    let fractional_part = amount % divisor;
+
+    if fractional_part.is_zero() {
+        return integer_part.to_string();
+    }
+
+    let frac_str = format!("{:0width$}", fractional_part, width = decimals as usize);
+    let trimmed = frac_str.trim_end_matches('0');
+
+    if trimmed.is_empty() {
+        integer_part.to_string()
+    } else {
+        format!("{}.{}", integer_part, trimmed)
+    }
+}
+
+/// Format a raw token amount rounded half-up to at most `display_decimals` places.
+///
+/// For display only; trailing zeros are trimmed as in `format_token_balance`,
+/// which still gives the exact value.
+pub fn format_token_balance_rounded(amount: U256, decimals: u8, display_decimals: u8) -> String {
+    if display_decimals >= decimals {
+        return format_token_balance(amount, decimals);
+    }
+
+    let unit = U256::from(10).pow(U256::from(decimals - display_decimals));
+    let mut rounded = amount / unit;
+    // Compare the remainder against half a unit without overflowing near U256::MAX
+    if amount % unit >= unit - unit / 2 {
+        rounded = rounded.saturating_add(U256::one());
+    }
+    format_token_balance(rounded, display_decimals)
+}
+
+/// Insert thousands separators into the integer part of a formatted amount ("1234.5" -> "1,234.5")
+pub fn group_thousands(formatted: &str) -> String {
+    let (integer, fractional) = match formatted.split_once('.') {
+        Some((integer, fractional)) => (integer, Some(fractional)),
+        None => (formatted, None),
+    };
+
+    let mut grouped = String::with_capacity(formatted.len() + integer.len() / 3);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fractional) = fractional {
+        grouped.push('.');
+        grouped.push_str(fractional);
+    }
+    grouped
+}
+
+/// Parse a human-readable amount (e.g. "1000.50") into raw token units
+pub fn parse_token_amount(amount: &str, decimals: u8) -> Result<U256> {
+    let invalid = || QoraNetError::TokenError(format!("Invalid token amount: {}", amount));
+
+    let (integer_str, fractional_str) = match amount.trim().split_once('.') {
+        Some((integer, fractional)) => (integer, fractional),
+        None => (amount.trim(), ""),
+    };
+
+    if integer_str.is_empty() && fractional_str.is_empty() {
+        return Err(invalid());
+    }
+    if !integer_str.chars().all(|c| c.is_ascii_digit()) || !fractional_str.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    if fractional_str.len() > decimals as usize {
+        return Err(QoraNetError::TokenError(format!(
+            "Amount {} has more than {} decimal places", amount, decimals
+        )));
+    }
+
+    let integer = if integer_str.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(integer_str).map_err(|_| invalid())?
+    };
+    let padded = format!("{:0<width$}", fractional_str, width = decimals as usize);
+    let fractional = if padded.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(&padded).map_err(|_| invalid())?
+    };
+
+    integer.checked_mul(U256::from(10).pow(U256::from(decimals)))
+        .and_then(|scaled| scaled.checked_add(fractional))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_round_trip() {
+        let amount = parse_token_amount("1000.50", 6).unwrap();
+        assert_eq!(amount, U256::from(1_000_500_000u64));
+        assert_eq!(format_token_balance(amount, 6), "1000.5");
+
+        assert_eq!(parse_token_amount("42", 9).unwrap(), U256::from(42_000_000_000u64));
+        assert_eq!(parse_token_amount(".5", 2).unwrap(), U256::from(50));
+    }
+
+    #[test]
+    fn test_rounded_display_of_18_decimal_balance() {
+        let amount = parse_token_amount("1234567.123456789012345678", 18).unwrap();
+        assert_eq!(format_token_balance_rounded(amount, 18, 4), "1234567.1235");
+        assert_eq!(group_thousands(&format_token_balance_rounded(amount, 18, 4)), "1,234,567.1235");
+        // The exact value is still available
+        assert_eq!(format_token_balance(amount, 18), "1234567.123456789012345678");
+
+        // Exactly half rounds up, carrying into the integer part
+        let half = parse_token_amount("999.99995", 18).unwrap();
+        assert_eq!(group_thousands(&format_token_balance_rounded(half, 18, 4)), "1,000");
+        let below_half = parse_token_amount("999.999949999", 18).unwrap();
+        assert_eq!(format_token_balance_rounded(below_half, 18, 4), "999.9999");
+
+        // Nothing to round when showing all decimals
+        assert_eq!(format_token_balance_rounded(amount, 18, 18), format_token_balance(amount, 18));
+        assert_eq!(group_thousands("123"), "123");
+        assert_eq!(group_thousands("0.5"), "0.5");
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_amounts() {
+        assert!(parse_token_amount("1.1234567", 6).is_err());
+        assert!(parse_token_amount("abc", 6).is_err());
+        assert!(parse_token_amount("-1", 6).is_err());
+        assert!(parse_token_amount(".", 6).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Compile-time check that the curated top-level API stays importable.
+//!
+//! Mirrors the import paths used by `examples/erc20_demo.rs`; if a re-export
+//! moves, this test stops compiling.
+
+use qoranet::{
+    QoraNet,
+    TransactionType,
+    Transaction,
+    TransactionData,
+    qrc20::QRC20Transaction,
+    evm::{EVMTransaction, QoraNetEVM},
+    BridgeRequest,
+    BridgeTransaction,
+    QRC20Event,
+    QRC20Registry,
+    wallet,
+};
+use primitive_types::{H160, U256};
+
+#[test]
+fn test_reexported_paths_resolve() {
+    let _: Option<TransactionType> = Some(TransactionType::Transfer);
+    let _: Option<Transaction> = None;
+    let _: Option<TransactionData> = None;
+    let _: Option<QRC20Transaction> = None;
+    let _: Option<EVMTransaction> = None;
+    let _: Option<QoraNetEVM> = None;
+    let _: Option<BridgeTransaction> = None;
+    let _: Option<QRC20Event> = None;
+
+    let registry = QRC20Registry::new();
+    assert_eq!(registry.token_count(), 0);
+}
+
+#[test]
+fn test_facade_bridge_request() {
+    let mut qoranet = QoraNet::new();
+    let charlie = qoranet.create_account(U256::from(200) * U256::from(10).pow(9.into()));
+
+    let bridge_tx = BridgeRequest::FromEthereum {
+        eth_token: H160::from_low_u64_be(0xa0b86a33e6ba),
+        amount: U256::from(5000) * U256::from(10).pow(6.into()),
+        token_name: "USD Coin".to_string(),
+        token_symbol: "USDC".to_string(),
+        decimals: 6,
+    };
+
+    let receipt = qoranet.process_bridge_transaction(charlie, bridge_tx, 150000).unwrap();
+    let bridged_usdc = receipt.contract_address.unwrap();
+    assert!(!qoranet.get_token_balance(charlie, Some(bridged_usdc)).is_zero());
+}
+
+#[test]
+fn test_wallet_helpers_reexported() {
+    let amount = wallet::parse_token_amount("1000.50", 6).unwrap();
+    assert_eq!(amount, U256::from(1_000_500_000u64));
+    assert_eq!(wallet::format_token_balance(amount, 6), "1000.5");
+}